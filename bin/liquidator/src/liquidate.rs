@@ -33,6 +33,16 @@ pub struct Config {
     pub max_cu_per_transaction: u32,
 }
 
+/// Value of a perp base position in native settle token, comparable across markets
+fn perp_notional(base_lots: i64, base_lot_size: i64, price: I80F48) -> I80F48 {
+    I80F48::from(base_lots.abs()) * I80F48::from(base_lot_size) * price
+}
+
+/// Orders (market, base lots, price, notional) entries by descending notional
+fn sort_largest_notional_first(positions: &mut [(PerpMarketIndex, i64, I80F48, I80F48)]) {
+    positions.sort_by_key(|p| Reverse(p.3));
+}
+
 struct LiquidateHelper<'a> {
     client: &'a MangoClient,
     account_fetcher: &'a chain_data::AccountFetcher,
@@ -168,11 +178,12 @@ impl<'a> LiquidateHelper<'a> {
                     return Ok(None);
                 }
                 let price = self.client.perp_oracle_price(pp.market_index).await?;
+                let base_lot_size = self.client.context.perp(pp.market_index).base_lot_size;
                 Ok(Some((
                     pp.market_index,
                     base_lots,
                     price,
-                    I80F48::from(base_lots.abs()) * price,
+                    perp_notional(base_lots, base_lot_size, price),
                 )))
             })
             .try_collect()
@@ -181,14 +192,14 @@ impl<'a> LiquidateHelper<'a> {
             .into_iter()
             .flatten()
             .collect::<Vec<_>>();
-        perp_base_positions.sort_by(|a, b| a.3.cmp(&b.3));
+        sort_largest_notional_first(&mut perp_base_positions);
 
         if perp_base_positions.is_empty() {
             return Ok(None);
         }
 
-        // Liquidate the highest-value perp base position
-        let (perp_market_index, base_lots, price, _) = perp_base_positions.last().unwrap();
+        // Liquidate the highest-value perp base position: it usually improves health the most
+        let (perp_market_index, base_lots, price, _) = perp_base_positions.first().unwrap();
         let perp = self.client.context.perp(*perp_market_index);
 
         let (side, side_signum) = if *base_lots > 0 {
@@ -758,3 +769,32 @@ pub async fn maybe_liquidate_account(
 
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_largest_notional_first() {
+        // market 1 has more lots, but market 2's lots are worth more
+        let position = |market: PerpMarketIndex, base_lots: i64, base_lot_size: i64, price: f64| {
+            let price = I80F48::from_num(price);
+            (
+                market,
+                base_lots,
+                price,
+                perp_notional(base_lots, base_lot_size, price),
+            )
+        };
+        let mut positions = vec![
+            position(0, 5, 10, 1.0),
+            position(1, 100, 1, 2.0),
+            position(2, -30, 100, 0.5),
+        ];
+        sort_largest_notional_first(&mut positions);
+
+        let order = positions.iter().map(|p| p.0).collect::<Vec<_>>();
+        assert_eq!(order, vec![2, 1, 0]);
+        assert_eq!(positions[0].3, I80F48::from(1500));
+    }
+}
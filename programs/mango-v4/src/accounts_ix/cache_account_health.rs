@@ -0,0 +1,14 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CacheAccountHealth<'info> {
+    #[account(
+        constraint = group.load()?.is_ix_enabled(IxGate::CacheAccountHealth) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+
+    #[account(mut, has_one = group)]
+    pub account: AccountLoader<'info, MangoAccountFixed>,
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct GroupAcceptAdmin<'info> {
+    #[account(
+        mut,
+        has_one = pending_admin,
+    )]
+    pub group: AccountLoader<'info, Group>,
+    pub pending_admin: Signer<'info>,
+}
@@ -0,0 +1,11 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GroupStatus<'info> {
+    #[account(
+        constraint = group.load()?.is_ix_enabled(IxGate::GroupStatus) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+}
@@ -0,0 +1,40 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+/// Takes the loss of a bankrupt perp position from profitable accounts
+///
+/// remaining_accounts are the health accounts of the liqee and all counterparties,
+/// followed by the num_counterparties counterparty MangoAccounts, ranked by descending pnl.
+#[derive(Accounts)]
+pub struct PerpAdl<'info> {
+    #[account(
+        has_one = admin,
+        has_one = insurance_vault,
+        constraint = group.load()?.is_ix_enabled(IxGate::PerpAdl) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = group,
+        has_one = oracle,
+        constraint = perp_market.load()?.is_adl_enabled() @ MangoError::AdlDisabled
+    )]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+
+    /// CHECK: Oracle can have different account types, constrained by address in perp_market
+    pub oracle: UncheckedAccount<'info>,
+
+    // This account MUST have a loss
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqee.load()?.is_operational() @ MangoError::AccountIsFrozen
+    )]
+    pub liqee: AccountLoader<'info, MangoAccountFixed>,
+
+    pub insurance_vault: Account<'info, TokenAccount>,
+}
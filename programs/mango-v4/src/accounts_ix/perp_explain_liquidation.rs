@@ -0,0 +1,26 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct PerpExplainLiquidation<'info> {
+    #[account(
+        constraint = group.load()?.is_ix_enabled(IxGate::PerpExplainLiquidation) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+
+    #[account(has_one = group)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+
+    #[account(has_one = group)]
+    pub liqor: AccountLoader<'info, MangoAccountFixed>,
+
+    #[account(has_one = group)]
+    pub liqee: AccountLoader<'info, MangoAccountFixed>,
+
+    #[account(
+        has_one = group,
+        constraint = settle_bank.load()?.token_index == perp_market.load()?.settle_token_index @ MangoError::InvalidBank
+    )]
+    pub settle_bank: AccountLoader<'info, Bank>,
+}
@@ -1,7 +1,7 @@
 use crate::error::*;
 use crate::state::*;
 use anchor_lang::prelude::*;
-use anchor_spl::token::TokenAccount;
+use anchor_spl::token::{self, Token, TokenAccount};
 
 #[derive(Accounts)]
 pub struct PerpLiqBaseOrPositivePnl<'info> {
@@ -32,6 +32,9 @@ pub struct PerpLiqBaseOrPositivePnl<'info> {
     )]
     pub liqee: AccountLoader<'info, MangoAccountFixed>,
 
+    /// The liqor pays for taken over pnl in this token, unless perp_liq_base_or_positive_pnl_v3
+    /// gets a liqor_quote_token_index. Then that token's bank and oracle must be among the
+    /// health accounts.
     #[account(
         mut,
         has_one = group,
@@ -49,3 +52,86 @@ pub struct PerpLiqBaseOrPositivePnl<'info> {
     #[account(address = settle_bank.load()?.oracle)]
     pub settle_oracle: UncheckedAccount<'info>,
 }
+
+/// Accounts of PerpLiqBaseOrPositivePnl, plus the group insurance fund that can cover
+/// the liqee's bad debt before the base position gets liquidated
+#[derive(Accounts)]
+pub struct PerpLiqBaseOrPositivePnlV3<'info> {
+    #[account(
+        has_one = insurance_vault,
+        constraint = group.load()?.is_ix_enabled(IxGate::PerpLiqBaseOrPositivePnl) @ MangoError::IxIsDisabled
+    )]
+    pub group: AccountLoader<'info, Group>,
+
+    #[account(mut, has_one = group, has_one = oracle)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+
+    /// CHECK: Oracle can have different account types, constrained by address in perp_market
+    pub oracle: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqor.load()?.is_operational() @ MangoError::AccountIsFrozen
+        // liqor_owner is checked at #1
+    )]
+    pub liqor: AccountLoader<'info, MangoAccountFixed>,
+    pub liqor_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqee.load()?.is_operational() @ MangoError::AccountIsFrozen
+    )]
+    pub liqee: AccountLoader<'info, MangoAccountFixed>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = settle_bank.load()?.token_index == perp_market.load()?.settle_token_index @ MangoError::InvalidBank
+    )]
+    pub settle_bank: AccountLoader<'info, Bank>,
+
+    #[account(
+        mut,
+        address = settle_bank.load()?.vault
+    )]
+    pub settle_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Oracle can have different account types
+    #[account(address = settle_bank.load()?.oracle)]
+    pub settle_oracle: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> PerpLiqBaseOrPositivePnlV3<'info> {
+    pub fn liq_base_or_positive_pnl_accounts(&self) -> PerpLiqBaseOrPositivePnl<'info> {
+        PerpLiqBaseOrPositivePnl {
+            group: self.group.clone(),
+            perp_market: self.perp_market.clone(),
+            oracle: self.oracle.clone(),
+            liqor: self.liqor.clone(),
+            liqor_owner: self.liqor_owner.clone(),
+            liqee: self.liqee.clone(),
+            settle_bank: self.settle_bank.clone(),
+            settle_vault: self.settle_vault.clone(),
+            settle_oracle: self.settle_oracle.clone(),
+        }
+    }
+
+    pub fn insurance_to_settle_transfer_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let program = self.token_program.to_account_info();
+        let accounts = token::Transfer {
+            from: self.insurance_vault.to_account_info(),
+            to: self.settle_vault.to_account_info(),
+            authority: self.group.to_account_info(),
+        };
+        CpiContext::new(program, accounts)
+    }
+}
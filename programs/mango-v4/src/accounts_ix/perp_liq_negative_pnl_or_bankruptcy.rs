@@ -142,4 +142,16 @@ impl<'info> PerpLiqNegativePnlOrBankruptcyV2<'info> {
         };
         CpiContext::new(program, accounts)
     }
+
+    pub fn settle_to_insurance_transfer_ctx(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, token::Transfer<'info>> {
+        let program = self.token_program.to_account_info();
+        let accounts = token::Transfer {
+            from: self.settle_vault.to_account_info(),
+            to: self.insurance_vault.to_account_info(),
+            authority: self.group.to_account_info(),
+        };
+        CpiContext::new(program, accounts)
+    }
 }
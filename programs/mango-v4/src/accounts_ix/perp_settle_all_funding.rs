@@ -0,0 +1,21 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// Settles funding on all of an account's perp positions
+///
+/// The perp markets of all active perp positions are passed in remaining_accounts.
+#[derive(Accounts)]
+pub struct PerpSettleAllFunding<'info> {
+    #[account(
+        constraint = group.load()?.is_ix_enabled(IxGate::PerpSettleAllFunding) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = account.load()?.is_operational() @ MangoError::AccountIsFrozen
+    )]
+    pub account: AccountLoader<'info, MangoAccountFixed>,
+}
@@ -18,7 +18,7 @@ pub struct PerpSettlePnl<'info> {
     pub settler: AccountLoader<'info, MangoAccountFixed>,
     pub settler_owner: Signer<'info>,
 
-    #[account(has_one = group, has_one = oracle)]
+    #[account(mut, has_one = group, has_one = oracle)]
     pub perp_market: AccountLoader<'info, PerpMarket>,
 
     // This account MUST be profitable
@@ -11,7 +11,7 @@ pub struct TokenConditionalSwapCreate<'info> {
         mut,
         has_one = group,
         constraint = account.load()?.is_operational() @ MangoError::AccountIsFrozen,
-        constraint = account.load()?.is_owner_or_delegate(authority.key()),
+        constraint = account.load()?.has_permission(authority.key(), DelegatePermission::Trade),
     )]
     pub account: AccountLoader<'info, MangoAccountFixed>,
     pub authority: Signer<'info>,
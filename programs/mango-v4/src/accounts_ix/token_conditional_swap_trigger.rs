@@ -20,7 +20,7 @@ pub struct TokenConditionalSwapTrigger<'info> {
         mut,
         has_one = group,
         constraint = liqor.load()?.is_operational() @ MangoError::AccountIsFrozen,
-        constraint = liqor.load()?.is_owner_or_delegate(liqor_authority.key()),
+        constraint = liqor.load()?.has_permission(liqor_authority.key(), DelegatePermission::Liquidate),
     )]
     pub liqor: AccountLoader<'info, MangoAccountFixed>,
     pub liqor_authority: Signer<'info>,
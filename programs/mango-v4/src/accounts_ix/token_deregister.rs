@@ -7,6 +7,7 @@ use anchor_spl::token::{Token, TokenAccount};
 #[derive(Accounts)]
 pub struct TokenDeregister<'info> {
     #[account(
+        mut,
         has_one = admin,
         constraint = group.load()?.is_ix_enabled(IxGate::TokenDeregister) @ MangoError::IxIsDisabled,
         constraint = group.load()?.is_testing(),
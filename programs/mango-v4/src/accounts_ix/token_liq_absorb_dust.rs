@@ -0,0 +1,27 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct TokenLiqAbsorbDust<'info> {
+    #[account(
+        constraint = group.load()?.is_ix_enabled(IxGate::TokenLiqAbsorbDust) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqor.load()?.is_operational() @ MangoError::AccountIsFrozen
+        // liqor_owner is checked at #1
+    )]
+    pub liqor: AccountLoader<'info, MangoAccountFixed>,
+    pub liqor_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqee.load()?.is_operational() @ MangoError::AccountIsFrozen
+    )]
+    pub liqee: AccountLoader<'info, MangoAccountFixed>,
+}
@@ -0,0 +1,30 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// Like TokenLiqWithToken, but signed by the group admin to allow staler oracles
+#[derive(Accounts)]
+pub struct TokenLiqWithTokenRelaxedStaleness<'info> {
+    #[account(
+        has_one = admin,
+        constraint = group.load()?.is_ix_enabled(IxGate::TokenLiqWithTokenRelaxedStaleness) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqor.load()?.is_operational() @ MangoError::AccountIsFrozen
+        // liqor_owner is checked at #1
+    )]
+    pub liqor: AccountLoader<'info, MangoAccountFixed>,
+    pub liqor_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqee.load()?.is_operational() @ MangoError::AccountIsFrozen
+    )]
+    pub liqee: AccountLoader<'info, MangoAccountFixed>,
+}
@@ -10,6 +10,7 @@ const FIRST_BANK_NUM: u32 = 0;
 #[instruction(token_index: TokenIndex)]
 pub struct TokenRegister<'info> {
     #[account(
+        mut,
         has_one = admin,
         constraint = group.load()?.is_ix_enabled(IxGate::TokenRegister) @ MangoError::IxIsDisabled,
     )]
@@ -151,6 +151,34 @@ pub enum MangoError {
     InvalidSequenceNumber,
     #[msg("invalid health")]
     InvalidHealth,
+    #[msg("liquidation price is not within tolerance of the oracle price")]
+    LiquidationPriceOutOfTolerance,
+    #[msg("liquidation reward does not cover the liqor's minimum net profit")]
+    LiquidationNotProfitable,
+    #[msg("the delegate can't be the account owner or the account itself")]
+    InvalidDelegate,
+    #[msg("the init asset weight is below the group's floor")]
+    InitAssetWeightBelowFloor,
+    #[msg("the liqor is not whitelisted for perp positions this large")]
+    LiqorNotWhitelistedForLargePosition,
+    #[msg("the token's weights were changed too recently")]
+    WeightChangeTooSoon,
+    #[msg("the perp market's liquidation volume for this slot is exhausted")]
+    LiquidationThrottled,
+    #[msg("max_base_transfer has the wrong sign for the liqee's base position")]
+    LiquidationWrongDirection,
+    #[msg("the liqor_owner is neither the owner nor a delegate allowed to liquidate for the liqor account")]
+    UnauthorizedLiqor,
+    #[msg("auto-deleveraging is not enabled for this perp market")]
+    AdlDisabled,
+    #[msg("the token is not on the account's token allowlist")]
+    TokenNotAllowedForAccount,
+    #[msg("the liquidation transfers too few base lots without closing the liqee position")]
+    LiquidationTooSmall,
+    #[msg("the delegate doesn't have the permission for this action")]
+    DelegatePermissionMissing,
+    #[msg("math overflow")]
+    MathOverflow,
 }
 
 impl MangoError {
@@ -10,6 +10,17 @@ pub struct MangoAccountData {
     pub equity: Equity,
 }
 
+#[event]
+pub struct MangoAccountDataV2 {
+    pub init_health: I80F48,
+    pub maint_health: I80F48,
+    pub equity: Equity,
+    /// Lifetime fee totals, in native quote
+    pub perp_trading_fees_paid: i64,
+    pub perp_funding_paid: i64,
+    pub perp_liquidation_fees_paid: i64,
+}
+
 #[derive(AnchorDeserialize, AnchorSerialize, Debug)]
 pub struct Equity {
     pub tokens: Vec<TokenEquity>,
@@ -27,3 +38,35 @@ pub struct PerpEquity {
     pub perp_market_index: PerpMarketIndex,
     value: I80F48, // in native quote
 }
+
+#[event]
+pub struct GroupStatusData {
+    pub group: Pubkey,
+    pub admin: Pubkey,
+    pub security_admin: Pubkey,
+    /// Bitfield of disabled instructions, see IxGate
+    pub ix_gate: u128,
+    pub token_count: u16,
+}
+
+#[event]
+pub struct PerpLiquidationExplanation {
+    pub perp_market_index: PerpMarketIndex,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub oracle_price: I80F48,
+    /// Price the base position would be transferred at
+    pub price: I80F48,
+    pub price_per_lot: I80F48,
+    pub health_per_lot: I80F48,
+    pub base_transfer_for_zero: i64,
+    /// See LiquidationConstraint
+    pub binding_constraint: u8,
+    pub base_transfer_liqee: i64,
+    pub quote_transfer_liqee: I80F48,
+    pub quote_transfer_liqor: I80F48,
+    pub quote_platform_fee: I80F48,
+    pub pnl_transfer: I80F48,
+    pub liqee_health_before: I80F48,
+    pub liqee_health_after: I80F48,
+}
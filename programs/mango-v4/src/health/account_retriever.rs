@@ -4,7 +4,7 @@ use anchor_lang::ZeroCopy;
 use fixed::types::I80F48;
 use serum_dex::state::OpenOrders;
 
-use std::cell::Ref;
+use std::cell::{Ref, RefCell};
 use std::collections::HashMap;
 
 use crate::accounts_zerocopy::*;
@@ -12,8 +12,8 @@ use crate::error::*;
 use crate::serum3_cpi;
 use crate::state::pyth_mainnet_sol_oracle;
 use crate::state::pyth_mainnet_usdc_oracle;
-use crate::state::OracleAccountInfos;
 use crate::state::{Bank, MangoAccountRef, PerpMarket, PerpMarketIndex, TokenIndex};
+use crate::state::{OracleAccountInfos, OracleState};
 
 /// This trait abstracts how to find accounts needed for the health computation.
 ///
@@ -34,7 +34,19 @@ pub trait AccountRetriever {
         group: &Pubkey,
         active_token_position_index: usize,
         token_index: TokenIndex,
-    ) -> Result<(&Bank, I80F48)>;
+    ) -> Result<(&Bank, I80F48)> {
+        let (bank, state) =
+            self.bank_and_oracle_state(group, active_token_position_index, token_index)?;
+        Ok((bank, state.price))
+    }
+
+    /// Like bank_and_oracle(), but returns the full state of the oracle that was used
+    fn bank_and_oracle_state(
+        &self,
+        group: &Pubkey,
+        active_token_position_index: usize,
+        token_index: TokenIndex,
+    ) -> Result<(&Bank, OracleState)>;
 
     fn serum_oo(&self, active_serum_oo_index: usize, key: &Pubkey) -> Result<&OpenOrders>;
 
@@ -43,7 +55,22 @@ pub trait AccountRetriever {
         group: &Pubkey,
         active_perp_position_index: usize,
         perp_market_index: PerpMarketIndex,
-    ) -> Result<(&PerpMarket, I80F48)>;
+    ) -> Result<(&PerpMarket, I80F48)> {
+        let (perp_market, state) = self.perp_market_and_oracle_state(
+            group,
+            active_perp_position_index,
+            perp_market_index,
+        )?;
+        Ok((perp_market, state.price))
+    }
+
+    /// Like perp_market_and_oracle_price(), but returns the full oracle state
+    fn perp_market_and_oracle_state(
+        &self,
+        group: &Pubkey,
+        active_perp_position_index: usize,
+        perp_market_index: PerpMarketIndex,
+    ) -> Result<(&PerpMarket, OracleState)>;
 }
 
 /// Assumes the account infos needed for the health computation follow a strict order.
@@ -187,10 +214,14 @@ impl<T: KeyedAccountReader> FixedOrderAccountRetriever<T> {
         Ok(market)
     }
 
-    fn oracle_price_perp(&self, account_index: usize, perp_market: &PerpMarket) -> Result<I80F48> {
+    fn oracle_state_perp(
+        &self,
+        account_index: usize,
+        perp_market: &PerpMarket,
+    ) -> Result<OracleState> {
         let oracle = &self.ais[account_index];
         let oracle_acc_infos = OracleAccountInfos::from_reader(oracle);
-        perp_market.oracle_price(&oracle_acc_infos, self.now)
+        perp_market.oracle_state(&oracle_acc_infos, self.now)
     }
 
     #[inline(always)]
@@ -223,19 +254,19 @@ impl<T: KeyedAccountReader> AccountRetriever for FixedOrderAccountRetriever<T> {
         Ok(result)
     }
 
-    fn bank_and_oracle(
+    fn bank_and_oracle_state(
         &self,
         group: &Pubkey,
         active_token_position_index: usize,
         token_index: TokenIndex,
-    ) -> Result<(&Bank, I80F48)> {
+    ) -> Result<(&Bank, OracleState)> {
         let (bank_account_index, bank) =
             self.bank(group, active_token_position_index, token_index)?;
 
         let oracle_index = self.n_banks + bank_account_index;
         let oracle_acc_infos = &self.create_oracle_infos(oracle_index, &bank.fallback_oracle);
-        let oracle_price_result = bank.oracle_price(oracle_acc_infos, self.now);
-        let oracle_price = oracle_price_result.with_context(|| {
+        let oracle_state_result = bank.oracle_state(oracle_acc_infos, self.now);
+        let oracle_state = oracle_state_result.with_context(|| {
             format!(
                 "getting oracle for bank with health account index {} and token index {}, passed account {}",
                 bank_account_index,
@@ -244,15 +275,15 @@ impl<T: KeyedAccountReader> AccountRetriever for FixedOrderAccountRetriever<T> {
             )
         })?;
 
-        Ok((bank, oracle_price))
+        Ok((bank, oracle_state))
     }
 
-    fn perp_market_and_oracle_price(
+    fn perp_market_and_oracle_state(
         &self,
         group: &Pubkey,
         active_perp_position_index: usize,
         perp_market_index: PerpMarketIndex,
-    ) -> Result<(&PerpMarket, I80F48)> {
+    ) -> Result<(&PerpMarket, OracleState)> {
         let perp_index = self.begin_perp + active_perp_position_index;
         let perp_market = self
             .perp_market(group, perp_index, perp_market_index)
@@ -266,7 +297,7 @@ impl<T: KeyedAccountReader> AccountRetriever for FixedOrderAccountRetriever<T> {
             })?;
 
         let oracle_index = perp_index + self.n_perps;
-        let oracle_price = self.oracle_price_perp(oracle_index, perp_market).with_context(|| {
+        let oracle_state = self.oracle_state_perp(oracle_index, perp_market).with_context(|| {
             format!(
                 "getting oracle for perp market with health account index {} and perp market index {}, passed account {}",
                 oracle_index,
@@ -274,7 +305,7 @@ impl<T: KeyedAccountReader> AccountRetriever for FixedOrderAccountRetriever<T> {
                 self.ais[oracle_index].key(),
             )
         })?;
-        Ok((perp_market, oracle_price))
+        Ok((perp_market, oracle_state))
     }
 
     fn serum_oo(&self, active_serum_oo_index: usize, key: &Pubkey) -> Result<&OpenOrders> {
@@ -366,13 +397,21 @@ impl<'a, 'info> ScannedBanksAndOracles<'a, 'info> {
     }
 
     pub fn scanned_bank_and_oracle(&self, token_index: TokenIndex) -> Result<(&Bank, I80F48)> {
+        let (bank, state) = self.scanned_bank_and_oracle_state(token_index)?;
+        Ok((bank, state.price))
+    }
+
+    pub fn scanned_bank_and_oracle_state(
+        &self,
+        token_index: TokenIndex,
+    ) -> Result<(&Bank, OracleState)> {
         let index = self.bank_index(token_index)?;
         // The account was already loaded successfully during construction
         let bank = self.banks[index].load_fully_unchecked::<Bank>()?;
         let oracle_acc_infos = self.create_oracle_infos(index, &bank.fallback_oracle);
-        let price = bank.oracle_price(&oracle_acc_infos, self.staleness_slot)?;
+        let state = bank.oracle_state(&oracle_acc_infos, self.staleness_slot)?;
 
-        Ok((bank, price))
+        Ok((bank, state))
     }
 
     #[inline(always)]
@@ -413,6 +452,20 @@ pub struct ScanningAccountRetriever<'a, 'info> {
     perp_oracles: Vec<AccountInfoRef<'a, 'info>>,
     serum3_oos: Vec<AccountInfoRef<'a, 'info>>,
     perp_index_map: HashMap<PerpMarketIndex, usize>,
+    oracle_cache: RefCell<OracleStateCache>,
+}
+
+/// Oracle states a ScanningAccountRetriever parsed for health computations, by token
+/// and perp market index.
+///
+/// Oracles don't change during an instruction, so a retriever created later in the same
+/// instruction can reuse the states instead of parsing the oracle accounts again, see
+/// ScanningAccountRetriever::with_oracle_cache(). Never keep it across instructions: the
+/// states were only checked for staleness against the current clock.
+#[derive(Clone, Default)]
+pub struct OracleStateCache {
+    banks: HashMap<TokenIndex, OracleState>,
+    perps: HashMap<PerpMarketIndex, OracleState>,
 }
 
 /// Returns None if `ai` doesn't have the owner or discriminator for T.
@@ -523,9 +576,23 @@ impl<'a, 'info> ScanningAccountRetriever<'a, 'info> {
             perp_oracles: AccountInfoRef::borrow_slice(&ais[perp_oracles_start..serum3_start])?,
             serum3_oos: AccountInfoRef::borrow_slice(&ais[serum3_start..fallback_oracles_start])?,
             perp_index_map,
+            oracle_cache: RefCell::new(OracleStateCache::default()),
         })
     }
 
+    /// Reuses the oracle states parsed by an earlier retriever in the same instruction
+    pub fn with_oracle_cache(self, oracle_cache: OracleStateCache) -> Self {
+        Self {
+            oracle_cache: RefCell::new(oracle_cache),
+            ..self
+        }
+    }
+
+    /// The oracle states parsed so far, to pass to with_oracle_cache()
+    pub fn into_oracle_cache(self) -> OracleStateCache {
+        self.oracle_cache.into_inner()
+    }
+
     #[inline]
     fn perp_market_index(&self, perp_market_index: PerpMarketIndex) -> Result<usize> {
         Ok(*self
@@ -552,14 +619,36 @@ impl<'a, 'info> ScanningAccountRetriever<'a, 'info> {
         &self,
         perp_market_index: PerpMarketIndex,
     ) -> Result<(&PerpMarket, I80F48)> {
+        let (perp_market, state) = self.scanned_perp_market_and_oracle_state(perp_market_index)?;
+        Ok((perp_market, state.price))
+    }
+
+    pub fn scanned_perp_market_and_oracle_state(
+        &self,
+        perp_market_index: PerpMarketIndex,
+    ) -> Result<(&PerpMarket, OracleState)> {
         let index = self.perp_market_index(perp_market_index)?;
         // The account was already loaded successfully during construction
         let perp_market = self.perp_markets[index].load_fully_unchecked::<PerpMarket>()?;
+        let cached = self
+            .oracle_cache
+            .borrow()
+            .perps
+            .get(&perp_market_index)
+            .copied();
+        if let Some(state) = cached {
+            return Ok((perp_market, state));
+        }
+
         let oracle_acc = &self.perp_oracles[index];
         let oracle_acc_infos = OracleAccountInfos::from_reader(oracle_acc);
-        let price =
-            perp_market.oracle_price(&oracle_acc_infos, self.banks_and_oracles.staleness_slot)?;
-        Ok((perp_market, price))
+        let state =
+            perp_market.oracle_state(&oracle_acc_infos, self.banks_and_oracles.staleness_slot)?;
+        self.oracle_cache
+            .borrow_mut()
+            .perps
+            .insert(perp_market_index, state);
+        Ok((perp_market, state))
     }
 
     pub fn scanned_serum_oo(&self, key: &Pubkey) -> Result<&OpenOrders> {
@@ -581,22 +670,37 @@ impl<'a, 'info> AccountRetriever for ScanningAccountRetriever<'a, 'info> {
         Ok(self.banks_and_oracles.index_map.keys().copied().collect())
     }
 
-    fn bank_and_oracle(
+    fn bank_and_oracle_state(
         &self,
         _group: &Pubkey,
         _account_index: usize,
         token_index: TokenIndex,
-    ) -> Result<(&Bank, I80F48)> {
-        self.scanned_bank_and_oracle(token_index)
+    ) -> Result<(&Bank, OracleState)> {
+        let cached = self.oracle_cache.borrow().banks.get(&token_index).copied();
+        if let Some(state) = cached {
+            let index = self.banks_and_oracles.bank_index(token_index)?;
+            // The account was already loaded successfully during construction
+            let bank = self.banks_and_oracles.banks[index].load_fully_unchecked::<Bank>()?;
+            return Ok((bank, state));
+        }
+
+        let (bank, state) = self
+            .banks_and_oracles
+            .scanned_bank_and_oracle_state(token_index)?;
+        self.oracle_cache
+            .borrow_mut()
+            .banks
+            .insert(token_index, state);
+        Ok((bank, state))
     }
 
-    fn perp_market_and_oracle_price(
+    fn perp_market_and_oracle_state(
         &self,
         _group: &Pubkey,
         _account_index: usize,
         perp_market_index: PerpMarketIndex,
-    ) -> Result<(&PerpMarket, I80F48)> {
-        self.scanned_perp_market_and_oracle(perp_market_index)
+    ) -> Result<(&PerpMarket, OracleState)> {
+        self.scanned_perp_market_and_oracle_state(perp_market_index)
     }
 
     fn serum_oo(&self, _account_index: usize, key: &Pubkey) -> Result<&OpenOrders> {
@@ -729,6 +833,114 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    fn test_account_retrievers_reject_wrong_oracle() {
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 1, 1.0, 0.2, 0.1);
+        let (mut bank2, mut oracle2) = mock_bank_and_oracle(group, 4, 5.0, 0.5, 0.3);
+        let mut perp1 = mock_perp_market(group, oracle2.pubkey, 5.0, 9, (0.2, 0.1), (0.05, 0.02));
+
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+        account.ensure_token_position(1).unwrap();
+        account.ensure_token_position(4).unwrap();
+        account.ensure_perp_position(9, 1).unwrap();
+
+        // oracles are passed in the wrong order: each bank and the perp market get an
+        // oracle that isn't theirs
+        let oracle1_account_info = oracle1.as_account_info();
+        let oracle2_account_info = oracle2.as_account_info();
+        let ais = vec![
+            bank1.as_account_info(),
+            bank2.as_account_info(),
+            oracle2_account_info.clone(),
+            oracle1_account_info.clone(),
+            perp1.as_account_info(),
+            oracle1_account_info,
+        ];
+
+        let retriever = new_fixed_order_account_retriever(&ais, &account.borrow(), (0, 0)).unwrap();
+        assert!(retriever.bank_and_oracle(&group, 0, 1).is_err());
+        assert!(retriever.bank_and_oracle(&group, 1, 4).is_err());
+        assert!(retriever
+            .perp_market_and_oracle_price(&group, 0, 9)
+            .is_err());
+
+        let mut retriever =
+            ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+        assert!(retriever.banks_mut_and_oracles(1, 4).is_err());
+        assert!(retriever.scanned_bank_and_oracle(4).is_err());
+        assert!(retriever
+            .perp_market_and_oracle_price(&group, 0, 9)
+            .is_err());
+    }
+
+    #[test]
+    fn test_scanning_account_retriever_oracle_cache() {
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 1, 1.0, 0.2, 0.1);
+        let mut perp1 = mock_perp_market(group, oracle1.pubkey, 1.0, 9, (0.2, 0.1), (0.05, 0.02));
+        // same account with a different price, as if the oracle had changed
+        let mut oracle1_changed = oracle1.clone();
+        oracle1_changed.data().price = I80F48::from(3);
+
+        let oracle1_account_info = oracle1.as_account_info();
+        let ais = vec![
+            bank1.as_account_info(),
+            oracle1_account_info.clone(),
+            perp1.as_account_info(),
+            oracle1_account_info,
+        ];
+        let retriever = ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+        assert_eq!(
+            retriever.bank_and_oracle(&group, 0, 1).unwrap().1,
+            I80F48::ONE
+        );
+        assert_eq!(
+            retriever
+                .perp_market_and_oracle_price(&group, 0, 9)
+                .unwrap()
+                .1,
+            I80F48::ONE
+        );
+        let oracle_cache = retriever.into_oracle_cache();
+        drop(ais);
+
+        let oracle1_changed_account_info = oracle1_changed.as_account_info();
+        let ais = vec![
+            bank1.as_account_info(),
+            oracle1_changed_account_info.clone(),
+            perp1.as_account_info(),
+            oracle1_changed_account_info,
+        ];
+
+        // without the cache, the oracles are parsed again
+        let retriever = ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+        assert_eq!(
+            retriever.bank_and_oracle(&group, 0, 1).unwrap().1,
+            I80F48::from(3)
+        );
+        drop(retriever);
+
+        // with it, the earlier states are reused
+        let retriever = ScanningAccountRetriever::new_with_staleness(&ais, &group, None)
+            .unwrap()
+            .with_oracle_cache(oracle_cache);
+        assert_eq!(
+            retriever.bank_and_oracle(&group, 0, 1).unwrap().1,
+            I80F48::ONE
+        );
+        assert_eq!(
+            retriever
+                .perp_market_and_oracle_price(&group, 0, 9)
+                .unwrap()
+                .1,
+            I80F48::ONE
+        );
+    }
+
     #[test]
     fn test_fixed_account_retriever_with_skips() {
         let group = Pubkey::new_unique();
@@ -19,11 +19,11 @@ use anchor_lang::prelude::*;
 use fixed::types::I80F48;
 
 use crate::error::*;
-use crate::i80f48::LowPrecisionDivision;
+use crate::i80f48::{ClampToInt, LowPrecisionDivision};
 use crate::serum3_cpi::{OpenOrdersAmounts, OpenOrdersSlim};
 use crate::state::{
     Bank, MangoAccountRef, PerpMarket, PerpMarketIndex, PerpPosition, Serum3MarketIndex,
-    Serum3Orders, TokenIndex,
+    Serum3Orders, TokenIndex, MS_PER_SLOT, YEAR_I80F48,
 };
 
 use super::*;
@@ -36,6 +36,9 @@ pub struct Prices {
 
     /// A "stable" price, provided by StablePriceModel
     pub stable: I80F48, // native/native
+
+    /// The slot the oracle price was last updated in
+    pub last_update_slot: u64,
 }
 
 impl Prices {
@@ -44,6 +47,8 @@ impl Prices {
         Self {
             oracle: price,
             stable: price,
+            // like stub oracles, never stale
+            last_update_slot: u64::MAX,
         }
     }
 
@@ -435,6 +440,7 @@ pub(crate) struct Serum3Reserved {
 pub struct PerpInfo {
     pub perp_market_index: PerpMarketIndex,
     pub settle_token_index: TokenIndex,
+    pub underlying_id: u16,
     pub maint_base_asset_weight: I80F48,
     pub init_base_asset_weight: I80F48,
     pub maint_base_liab_weight: I80F48,
@@ -448,6 +454,8 @@ pub struct PerpInfo {
     // in health-reference-token native units, no asset/liab factor needed
     pub quote: I80F48,
     pub base_prices: Prices,
+    /// Replaces base_prices for maint health, see PerpMarket::use_ema_for_trigger
+    pub maint_trigger_price: Option<I80F48>,
     pub has_open_orders: bool,
     pub has_open_fills: bool,
 }
@@ -457,6 +465,7 @@ impl PerpInfo {
         perp_position: &PerpPosition,
         perp_market: &PerpMarket,
         base_prices: Prices,
+        maint_trigger_price: Option<I80F48>,
     ) -> Result<Self> {
         let base_lots = perp_position.base_position_lots() + perp_position.taker_base_lots;
 
@@ -467,6 +476,7 @@ impl PerpInfo {
         Ok(Self {
             perp_market_index: perp_market.perp_market_index,
             settle_token_index: perp_market.settle_token_index,
+            underlying_id: perp_market.underlying_id,
             init_base_asset_weight: perp_market.init_base_asset_weight,
             init_base_liab_weight: perp_market.init_base_liab_weight,
             maint_base_asset_weight: perp_market.maint_base_asset_weight,
@@ -479,6 +489,7 @@ impl PerpInfo {
             asks_base_lots: perp_position.asks_base_lots,
             quote: quote_current,
             base_prices,
+            maint_trigger_price,
             has_open_orders: perp_position.has_open_orders(),
             has_open_fills: perp_position.has_open_taker_fills(),
         })
@@ -540,6 +551,15 @@ impl PerpInfo {
     /// Generally: hupnl <= uhupnl <= upnl
     #[inline(always)]
     pub fn unweighted_health_unsettled_pnl(&self, health_type: HealthType) -> I80F48 {
+        let base_prices = match (health_type, self.maint_trigger_price) {
+            (HealthType::Maint, Some(price)) => Prices {
+                oracle: price,
+                stable: price,
+                last_update_slot: self.base_prices.last_update_slot,
+            },
+            _ => self.base_prices.clone(),
+        };
+
         let order_execution_case = |orders_base_lots: i64, order_price: I80F48| {
             let net_base_native =
                 I80F48::from((self.base_lots + orders_base_lots) * self.base_lot_size);
@@ -554,9 +574,9 @@ impl PerpInfo {
                 (HealthType::Maint, false) => self.maint_base_asset_weight,
             };
             let base_price = if net_base_native.is_negative() {
-                self.base_prices.liab(health_type)
+                base_prices.liab(health_type)
             } else {
-                self.base_prices.asset(health_type)
+                base_prices.asset(health_type)
             };
 
             // Total value of the order-execution adjusted base position
@@ -570,10 +590,8 @@ impl PerpInfo {
         };
 
         // What is worse: Executing all bids at oracle_price.liab, or executing all asks at oracle_price.asset?
-        let bids_case =
-            order_execution_case(self.bids_base_lots, self.base_prices.liab(health_type));
-        let asks_case =
-            order_execution_case(-self.asks_base_lots, self.base_prices.asset(health_type));
+        let bids_case = order_execution_case(self.bids_base_lots, base_prices.liab(health_type));
+        let asks_case = order_execution_case(-self.asks_base_lots, base_prices.asset(health_type));
         let worst_case = bids_case.min(asks_case);
 
         self.quote + worst_case
@@ -604,6 +622,73 @@ pub struct HealthCache {
 }
 
 impl HealthCache {
+    /// The oldest last update slot of all oracles the cache's prices came from
+    ///
+    /// Allows callers to check the cache's freshness. u64::MAX if no oracles were used.
+    pub fn oracle_slot(&self) -> u64 {
+        let token_slots = self.token_infos.iter().map(|ti| ti.prices.last_update_slot);
+        let perp_slots = self
+            .perp_infos
+            .iter()
+            .map(|pi| pi.base_prices.last_update_slot);
+        token_slots.chain(perp_slots).min().unwrap_or(u64::MAX)
+    }
+
+    /// Health after changing the account's balance of `token_index` by `native_delta`,
+    /// without modifying the cache
+    ///
+    /// Allows previewing deposits, withdraws and borrows. Doesn't include loan origination
+    /// fees or the weight scaling change from the bank's new deposit or borrow totals.
+    pub fn health_after_token_change(
+        &self,
+        token_index: TokenIndex,
+        native_delta: I80F48,
+        health_type: HealthType,
+    ) -> Result<I80F48> {
+        let entry_index = self.token_info_index(token_index)?;
+        let mut cache = self.clone();
+        cache.token_infos[entry_index].balance_spot += native_delta;
+        Ok(cache.health(health_type))
+    }
+
+    /// Slots until interest on the account's borrows pushes maint health below zero,
+    /// assuming prices and all other balances stay unchanged
+    ///
+    /// `rate_info` has the annual borrow rate of the account's tokens, see Bank::borrow_apr().
+    /// Rates for tokens without a position are ignored. Interest accrues by time and not by
+    /// slot, so the time is converted with MS_PER_SLOT. It's projected linearly and deposit
+    /// interest is ignored, so the estimate errs on the early side.
+    ///
+    /// Returns None if the account is already liquidatable or its health doesn't decline.
+    pub fn slots_until_liquidatable(&self, rate_info: &[(TokenIndex, I80F48)]) -> Option<u64> {
+        let health = self.health(HealthType::Maint);
+        if health < 0 {
+            return None;
+        }
+
+        let mut cache = self.clone();
+        for &(token_index, rate) in rate_info {
+            let Ok(entry_index) = self.token_info_index(token_index) else {
+                continue;
+            };
+            let balance = self.token_infos[entry_index].balance_spot;
+            if balance < 0 {
+                cache.token_infos[entry_index].balance_spot += balance * rate;
+            }
+        }
+        let yearly_decline = health - cache.health(HealthType::Maint);
+        if yearly_decline <= 0 {
+            return None;
+        }
+
+        let slots_per_year = YEAR_I80F48 * I80F48::from(1000) / I80F48::from(MS_PER_SLOT);
+        let slots = (health / yearly_decline)
+            .checked_mul(slots_per_year)
+            .map(|s| s.clamp_to_u64())
+            .unwrap_or(u64::MAX);
+        Some(slots)
+    }
+
     pub fn health(&self, health_type: HealthType) -> I80F48 {
         let token_balances = self.effective_token_balances(health_type);
         let mut health = I80F48::ZERO;
@@ -632,6 +717,20 @@ impl HealthCache {
         }
     }
 
+    /// The positive side of `health()`: the sum of all weighted asset contributions
+    ///
+    /// `weighted_assets(t) - weighted_liabilities(t) == health(t)`.
+    pub fn weighted_assets(&self, health_type: HealthType) -> I80F48 {
+        self.health_assets_and_liabs_stable_liabs(health_type).0
+    }
+
+    /// The negative side of `health()`, as a positive number
+    ///
+    /// Liabilities are held stable, see health_assets_and_liabs().
+    pub fn weighted_liabilities(&self, health_type: HealthType) -> I80F48 {
+        self.health_assets_and_liabs_stable_liabs(health_type).1
+    }
+
     pub fn health_assets_and_liabs_stable_assets(
         &self,
         health_type: HealthType,
@@ -807,10 +906,52 @@ impl HealthCache {
         liabs / equity.max(I80F48::from_num(0.001))
     }
 
+    /// Computes the account's gross leverage as ratio of assets / (assets - liabs)
+    ///
+    /// This is the position size per unit of equity that traders usually refer to: an account
+    /// with only deposits is at 1x, buying a token or perp base worth twice the equity with
+    /// borrowed funds brings it to 3x. It's always leverage() + 1. Like leverage(), it's only
+    /// meant for display.
+    ///
+    /// Returns I80F48::MAX if the equity isn't positive.
+    pub fn current_leverage(&self) -> I80F48 {
+        let (assets, liabs) = self.assets_and_liabs();
+        let equity = assets - liabs;
+        if equity <= 0 {
+            return I80F48::MAX;
+        }
+        assets / equity
+    }
+
+    /// Net base exposure across all perp markets with the given underlying_id, in
+    /// health-reference-token native units.
+    ///
+    /// Long positions count positive and short positions negative, valued at the oracle
+    /// price, so offsetting positions in related markets cancel. Open orders are ignored.
+    /// Markets without an underlying_id (zero) are never included.
+    pub fn net_delta_for_underlying(&self, underlying_id: u16) -> Result<I80F48> {
+        let mut net_delta = I80F48::ZERO;
+        if underlying_id == 0 {
+            return Ok(net_delta);
+        }
+        for perp_info in self.perp_infos.iter() {
+            if perp_info.underlying_id != underlying_id {
+                continue;
+            }
+            let settle_price = self.token_info(perp_info.settle_token_index)?.prices.oracle;
+            net_delta += I80F48::from(perp_info.base_lots * perp_info.base_lot_size)
+                * perp_info.base_prices.oracle
+                * settle_price;
+        }
+        Ok(net_delta)
+    }
+
     pub fn token_info(&self, token_index: TokenIndex) -> Result<&TokenInfo> {
         Ok(&self.token_infos[self.token_info_index(token_index)?])
     }
 
+    /// A linear scan is fine here: token_infos only has entries for the account's
+    /// active token positions, not for every token in the group.
     pub fn token_info_index(&self, token_index: TokenIndex) -> Result<usize> {
         self.token_infos
             .iter()
@@ -914,7 +1055,12 @@ impl HealthCache {
             .iter_mut()
             .find(|m| m.perp_market_index == perp_market.perp_market_index)
             .ok_or_else(|| error_msg!("perp market {} not found", perp_market.perp_market_index))?;
-        *perp_entry = PerpInfo::new(perp_position, perp_market, perp_entry.base_prices.clone())?;
+        *perp_entry = PerpInfo::new(
+            perp_position,
+            perp_market,
+            perp_entry.base_prices.clone(),
+            perp_entry.maint_trigger_price,
+        )?;
         Ok(())
     }
 
@@ -1295,7 +1441,7 @@ fn new_health_cache_impl(
         }
 
         let bank_oracle_result =
-            retriever.bank_and_oracle(&account.fixed.group, i, position.token_index);
+            retriever.bank_and_oracle_state(&account.fixed.group, i, position.token_index);
 
         // Allow skipping of bad-oracle banks if the account has a nonnegative balance
         if allow_skipping_banks
@@ -1305,12 +1451,19 @@ fn new_health_cache_impl(
             // Ignore the asset because the oracle is bad, decreasing total health
             continue;
         }
-        let (bank, oracle_price) = bank_oracle_result?;
+        let (bank, oracle_state) = bank_oracle_result?;
 
-        let native = position.native(bank);
+        let native = if position.indexed_position.is_negative()
+            && bank.projects_borrow_interest_in_health()
+        {
+            position.indexed_position * bank.projected_borrow_index(now_ts)?
+        } else {
+            position.native(bank)
+        };
         let prices = Prices {
-            oracle: oracle_price,
+            oracle: oracle_state.price,
             stable: bank.stable_price(),
+            last_update_slot: oracle_state.last_update_slot,
         };
         // Use the liab price for computing weight scaling, because it's pessimistic and
         // causes the most unfavorable scaling.
@@ -1377,7 +1530,7 @@ fn new_health_cache_impl(
     // health contribution from perp accounts
     let mut perp_infos = Vec::with_capacity(account.active_perp_positions().count());
     for (i, perp_position) in account.active_perp_positions().enumerate() {
-        let (perp_market, oracle_price) = retriever.perp_market_and_oracle_price(
+        let (perp_market, oracle_state) = retriever.perp_market_and_oracle_state(
             &account.fixed.group,
             i,
             perp_position.market_index,
@@ -1392,9 +1545,11 @@ fn new_health_cache_impl(
             perp_position,
             perp_market,
             Prices {
-                oracle: oracle_price,
+                oracle: oracle_state.price,
                 stable: perp_market.stable_price(),
+                last_update_slot: oracle_state.last_update_slot,
             },
+            perp_market.maint_trigger_price(now_ts),
         )?);
     }
 
@@ -1521,6 +1676,313 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_health_oracle_slot() {
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 0, 1.0, 0.2, 0.1);
+        let (mut bank2, mut oracle2) = mock_bank_and_oracle(group, 4, 5.0, 0.5, 0.3);
+        let (_bank3, mut oracle3) = mock_bank_and_oracle(group, 5, 5.0, 0.5, 0.3);
+        account.ensure_token_position(0).unwrap();
+        account.ensure_token_position(4).unwrap();
+
+        let mut perp1 = mock_perp_market(group, oracle3.pubkey, 5.0, 9, (0.2, 0.1), (0.05, 0.02));
+        account.ensure_perp_position(9, 0).unwrap();
+
+        oracle1.data().last_update_slot = 1000;
+        oracle2.data().last_update_slot = 900;
+        oracle3.data().last_update_slot = 950;
+
+        let mut oracle_slot = |oracle2: &mut TestAccount<StubOracle>| {
+            let ais = vec![
+                bank1.as_account_info(),
+                bank2.as_account_info(),
+                oracle1.as_account_info(),
+                oracle2.as_account_info(),
+                perp1.as_account_info(),
+                oracle3.as_account_info(),
+            ];
+            let retriever =
+                ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+            new_health_cache(&account.borrow(), &retriever, 0)
+                .unwrap()
+                .oracle_slot()
+        };
+
+        // the stale token oracle determines the slot
+        assert_eq!(oracle_slot(&mut oracle2), 900);
+
+        // then the perp oracle
+        oracle2.data().last_update_slot = 1100;
+        assert_eq!(oracle_slot(&mut oracle2), 950);
+
+        // stub oracles without a slot are never stale
+        oracle2.data().last_update_slot = 0;
+        assert_eq!(oracle_slot(&mut oracle2), 950);
+
+        let empty_cache = HealthCache {
+            token_infos: vec![],
+            serum3_infos: vec![],
+            perp_infos: vec![],
+            being_liquidated: false,
+        };
+        assert_eq!(empty_cache.oracle_slot(), u64::MAX);
+    }
+
+    #[test]
+    fn test_health_after_token_change() {
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 0, 1.0, 0.2, 0.1);
+        let (mut bank2, mut oracle2) = mock_bank_and_oracle(group, 4, 5.0, 0.5, 0.3);
+        bank1
+            .data()
+            .deposit(
+                account.ensure_token_position(0).unwrap().0,
+                I80F48::from(100),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+        bank2
+            .data()
+            .withdraw_without_fee(
+                account.ensure_token_position(4).unwrap().0,
+                I80F48::from(10),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+
+        let mut perp1 = mock_perp_market(group, oracle2.pubkey, 5.0, 9, (0.2, 0.1), (0.05, 0.02));
+        account.ensure_perp_position(9, 0).unwrap().0.record_trade(
+            perp1.data(),
+            3,
+            -I80F48::from(310u16),
+        );
+
+        let mut health_cache = |account: &MangoAccountValue,
+                                bank1: &mut TestAccount<Bank>,
+                                bank2: &mut TestAccount<Bank>| {
+            let oracle2_ai = oracle2.as_account_info();
+            let ais = vec![
+                bank1.as_account_info(),
+                bank2.as_account_info(),
+                oracle1.as_account_info(),
+                oracle2_ai.clone(),
+                perp1.as_account_info(),
+                oracle2_ai,
+            ];
+            let retriever =
+                ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+            new_health_cache(&account.borrow(), &retriever, 0).unwrap()
+        };
+
+        for (token_index, delta) in [(0, -50), (0, -250), (0, 30), (4, 10), (4, -20)] {
+            let delta = I80F48::from(delta);
+            let before = health_cache(&account, &mut bank1, &mut bank2);
+            let init_before = before.health(HealthType::Init);
+            let preview_init = before
+                .health_after_token_change(token_index, delta, HealthType::Init)
+                .unwrap();
+            let preview_maint = before
+                .health_after_token_change(token_index, delta, HealthType::Maint)
+                .unwrap();
+            assert_eq!(before.health(HealthType::Init), init_before);
+
+            let bank = if token_index == 0 {
+                &mut bank1
+            } else {
+                &mut bank2
+            };
+            let position = account.token_position_mut(token_index).unwrap().0;
+            bank.data()
+                .change_without_fee(position, delta, DUMMY_NOW_TS)
+                .unwrap();
+
+            let after = health_cache(&account, &mut bank1, &mut bank2);
+            assert!(health_eq(
+                preview_init,
+                after.health(HealthType::Init).to_num()
+            ));
+            assert!(health_eq(
+                preview_maint,
+                after.health(HealthType::Maint).to_num()
+            ));
+
+            // undo the change for the next case
+            let bank = if token_index == 0 {
+                &mut bank1
+            } else {
+                &mut bank2
+            };
+            let position = account.token_position_mut(token_index).unwrap().0;
+            bank.data()
+                .change_without_fee(position, -delta, DUMMY_NOW_TS)
+                .unwrap();
+        }
+
+        assert!(health_cache(&account, &mut bank1, &mut bank2)
+            .health_after_token_change(1, I80F48::ONE, HealthType::Init)
+            .is_err());
+    }
+
+    #[test]
+    fn test_slots_until_liquidatable() {
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 0, 1.0, 0.2, 0.1);
+        let (mut bank2, mut oracle2) = mock_bank_and_oracle(group, 4, 5.0, 0.5, 0.3);
+        bank1
+            .data()
+            .deposit(
+                account.ensure_token_position(0).unwrap().0,
+                I80F48::from(100),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+        bank2
+            .data()
+            .withdraw_without_fee(
+                account.ensure_token_position(4).unwrap().0,
+                I80F48::from(10),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+
+        let ais = vec![
+            bank1.as_account_info(),
+            bank2.as_account_info(),
+            oracle1.as_account_info(),
+            oracle2.as_account_info(),
+        ];
+        let retriever = ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+        let health_cache = new_health_cache(&account.borrow(), &retriever, 0).unwrap();
+
+        // maint health is 100 * 0.9 - 10 * 5 * 1.3 = 25, a 50% rate makes it
+        // decline by 10 * 0.5 * 5 * 1.3 = 32.5 per year
+        assert!(health_eq(health_cache.health(HealthType::Maint), 25.0));
+        let rates = |r0: f64, r4: f64| [(0, I80F48::from_num(r0)), (4, I80F48::from_num(r4))];
+        let slots = health_cache
+            .slots_until_liquidatable(&rates(0.1, 0.5))
+            .unwrap();
+        let slots_per_year = YEAR_I80F48.to_num::<f64>() * 1000.0 / MS_PER_SLOT as f64;
+        let expected = 25.0 / 32.5 * slots_per_year;
+        assert!((slots as f64 - expected).abs() <= 1.0);
+
+        // doubling the rate halves the time
+        let slots_double = health_cache
+            .slots_until_liquidatable(&rates(0.1, 1.0))
+            .unwrap();
+        assert!((slots_double as f64 - expected / 2.0).abs() <= 1.0);
+
+        // no interest on the borrow: never liquidatable, deposit rates don't matter
+        assert_eq!(
+            health_cache.slots_until_liquidatable(&rates(0.1, 0.0)),
+            None
+        );
+        assert_eq!(health_cache.slots_until_liquidatable(&[]), None);
+
+        // tokens the account doesn't have are ignored
+        assert_eq!(
+            health_cache.slots_until_liquidatable(&[(1, I80F48::ONE)]),
+            None
+        );
+
+        // already liquidatable
+        let mut underwater = health_cache.clone();
+        underwater.token_infos[1].balance_spot -= I80F48::from(10);
+        assert!(underwater.health(HealthType::Maint) < 0);
+        assert_eq!(underwater.slots_until_liquidatable(&rates(0.1, 0.5)), None);
+    }
+
+    #[test]
+    fn test_health_perp_ema_trigger() {
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 0, 1.0, 0.2, 0.1);
+        let (_bank2, mut oracle2) = mock_bank_and_oracle(group, 4, 5.0, 0.5, 0.3);
+        bank1
+            .data()
+            .deposit(
+                account.ensure_token_position(0).unwrap().0,
+                I80F48::from(50),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+
+        let mut perp1 = mock_perp_market(group, oracle2.pubkey, 5.0, 9, (0.2, 0.1), (0.05, 0.02));
+        perp1.data().ema_interval_seconds = 100;
+        perp1.data().update_ema_price(0, 5.0);
+        let perpaccount = account.ensure_perp_position(9, 0).unwrap().0;
+        perpaccount.record_trade(perp1.data(), 3, -I80F48::from(150));
+
+        let mut health_at = |perp1: &mut TestAccount<PerpMarket>,
+                             oracle2: &mut TestAccount<StubOracle>,
+                             health_type: HealthType,
+                             now_ts: u64| {
+            let ais = vec![
+                bank1.as_account_info(),
+                oracle1.as_account_info(),
+                perp1.as_account_info(),
+                oracle2.as_account_info(),
+            ];
+            let retriever =
+                ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+            new_health_cache(&account.borrow(), &retriever, now_ts)
+                .unwrap()
+                .health(health_type)
+        };
+        let mut health =
+            |perp1: &mut TestAccount<PerpMarket>,
+             oracle2: &mut TestAccount<StubOracle>,
+             health_type: HealthType| { health_at(perp1, oracle2, health_type, 0) };
+
+        // maint: (50 + (-150 + 3 * 10 * price * 0.9)) * weight
+        assert!(health_eq(
+            health(&mut perp1, &mut oracle2, HealthType::Maint),
+            (50.0 - 150.0 + 30.0 * 5.0 * 0.9) * 0.9
+        ));
+
+        // a one-tick drop of the oracle price triggers liquidation at spot
+        oracle2.data().price = I80F48::from_num(2.0);
+        let spike_health = (50.0 - 150.0 + 30.0 * 2.0 * 0.9) * 1.1;
+        assert!(spike_health < 0.0);
+        assert!(health_eq(
+            health(&mut perp1, &mut oracle2, HealthType::Maint),
+            spike_health
+        ));
+
+        // but not when the trigger uses the ema price
+        perp1.data().use_ema_for_trigger = 1;
+        assert!(health_eq(
+            health(&mut perp1, &mut oracle2, HealthType::Maint),
+            (50.0 - 150.0 + 30.0 * 5.0 * 0.9) * 0.9
+        ));
+
+        // liquidation end health, used for liquidation transfers, stays at spot
+        assert!(health_eq(
+            health(&mut perp1, &mut oracle2, HealthType::LiquidationEnd),
+            (50.0 - 150.0 + 30.0 * 2.0 * 0.8) * 1.2
+        ));
+
+        // an ema that wasn't updated within its interval is ignored
+        assert!(health_eq(
+            health_at(&mut perp1, &mut oracle2, HealthType::Maint, 101),
+            spike_health
+        ));
+    }
+
     #[derive(Default)]
     struct BankSettings {
         deposits: u64,
@@ -1644,6 +2106,21 @@ mod tests {
             compute_health(&account.borrow(), HealthType::Init, &retriever, 0).unwrap(),
             testcase.expected_health
         ));
+
+        let health_cache = new_health_cache(&account.borrow(), &retriever, 0).unwrap();
+        for health_type in [
+            HealthType::Init,
+            HealthType::Maint,
+            HealthType::LiquidationEnd,
+        ] {
+            let assets = health_cache.weighted_assets(health_type);
+            let liabs = health_cache.weighted_liabilities(health_type);
+            assert!(assets >= 0 && liabs >= 0);
+            assert!(health_eq(
+                assets - liabs,
+                health_cache.health(health_type).to_num::<f64>()
+            ));
+        }
     }
 
     // Check some specific health constellations
@@ -2104,4 +2581,83 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_health_projected_borrow_interest() {
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 0, 1.0, 0.2, 0.1);
+        let (mut bank2, mut oracle2) = mock_bank_and_oracle(group, 4, 5.0, 0.5, 0.3);
+        {
+            // flat 100% APR
+            let b = bank2.data();
+            b.zero_util_rate = I80F48::ONE;
+            b.util0 = I80F48::from_num(0.5);
+            b.rate0 = I80F48::ONE;
+            b.util1 = I80F48::from_num(0.75);
+            b.rate1 = I80F48::ONE;
+            b.max_rate = I80F48::ONE;
+            b.interest_curve_scaling = 1.0;
+        }
+        bank1
+            .data()
+            .deposit(
+                account.ensure_token_position(0).unwrap().0,
+                I80F48::from(1000),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+        bank2
+            .data()
+            .withdraw_without_fee(
+                account.ensure_token_position(4).unwrap().0,
+                I80F48::from(100),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+
+        let mut health_at = |bank2: &mut TestAccount<Bank>, now_ts: u64| {
+            let ais = vec![
+                bank1.as_account_info(),
+                bank2.as_account_info(),
+                oracle1.as_account_info(),
+                oracle2.as_account_info(),
+            ];
+            let retriever =
+                ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+            compute_health(&account.borrow(), HealthType::Init, &retriever, now_ts).unwrap()
+        };
+
+        let start_health = health_at(&mut bank2, 0);
+        assert!(health_eq(start_health, 1000.0 * 0.8 - 100.0 * 5.0 * 1.5));
+
+        let day = 24 * 60 * 60;
+        for now_ts in [day, 2 * day, 10 * day] {
+            // without projection, accrued interest is invisible until the index update
+            bank2.data().project_borrow_interest_in_health = 0;
+            assert_eq!(health_at(&mut bank2, now_ts), start_health);
+
+            bank2.data().project_borrow_interest_in_health = 1;
+            let projected = health_at(&mut bank2, now_ts);
+            assert!(projected < start_health);
+
+            // apply the interest like the index update would and compare
+            let mut applied_bank2 = bank2.clone();
+            {
+                let b = applied_bank2.data();
+                let diff_ts = I80F48::from(now_ts - b.index_last_updated);
+                let (deposit_index, borrow_index, _, _, _) = b
+                    .compute_index(b.indexed_deposits, b.indexed_borrows, diff_ts)
+                    .unwrap();
+                b.deposit_index = deposit_index;
+                b.borrow_index = borrow_index;
+                b.index_last_updated = now_ts;
+            }
+            let applied = health_at(&mut applied_bank2, now_ts);
+            assert!((projected - applied).abs() < 0.000001);
+        }
+    }
 }
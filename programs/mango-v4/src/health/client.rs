@@ -6,10 +6,32 @@ use fixed::types::I80F48;
 
 use crate::error::*;
 use crate::state::Side as PerpOrderSide;
-use crate::state::{Bank, MangoAccountValue, PerpMarketIndex};
+use crate::state::{Bank, MangoAccountValue, PerpMarket, PerpMarketIndex, TokenIndex};
 
 use super::*;
 
+/// The asset whose price moves in a LiquidationScenario
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LiquidationScenarioAsset {
+    Token(TokenIndex),
+    Perp(PerpMarketIndex),
+}
+
+/// A price move of a single asset that brings the account's maint health to zero
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LiquidationScenario {
+    pub asset: LiquidationScenarioAsset,
+    /// The asset's price gets multiplied by this: below 1 for price drops, above 1 for rises
+    pub price_factor: I80F48,
+}
+
+impl LiquidationScenario {
+    /// Size of the price move as a fraction of the current price
+    pub fn price_change(&self) -> I80F48 {
+        (self.price_factor - I80F48::ONE).abs()
+    }
+}
+
 impl HealthCache {
     pub fn is_liquidatable(&self) -> bool {
         if self.being_liquidated {
@@ -480,6 +502,385 @@ impl HealthCache {
         )
     }
 
+    /// Return a copy of the cache where the prices of `asset` are multiplied by `factor`
+    fn cache_after_price_change(
+        &self,
+        asset: LiquidationScenarioAsset,
+        factor: I80F48,
+    ) -> Result<Self> {
+        let mut cache = self.clone();
+        let prices = match asset {
+            LiquidationScenarioAsset::Token(token_index) => {
+                let index = self.token_info_index(token_index)?;
+                &mut cache.token_infos[index].prices
+            }
+            LiquidationScenarioAsset::Perp(perp_market_index) => {
+                let index = self.perp_info_index(perp_market_index)?;
+                &mut cache.perp_infos[index].base_prices
+            }
+        };
+        prices.oracle *= factor;
+        prices.stable *= factor;
+        Ok(cache)
+    }
+
+    /// Find the single asset price move that makes the account liquidatable soonest.
+    ///
+    /// Each token and perp market price is moved on its own, down to zero and up to 100x,
+    /// and the scenario with the smallest relative price change is returned. Returns None
+    /// if no single price move in that range can bring maint health below zero.
+    pub fn worst_case_liquidation_scenario(&self) -> Result<Option<LiquidationScenario>> {
+        let health_type = HealthType::Maint;
+        let health = self.health(health_type);
+        require_msg!(
+            health >= 0,
+            "account is already liquidatable, maint health {}",
+            health
+        );
+
+        let assets = self
+            .token_infos
+            .iter()
+            .map(|ti| LiquidationScenarioAsset::Token(ti.token_index))
+            .chain(
+                self.perp_infos
+                    .iter()
+                    .map(|pi| LiquidationScenarioAsset::Perp(pi.perp_market_index)),
+            );
+
+        let min_step = I80F48::from_num(0.000001);
+        let max_factor = I80F48::from(100);
+        let mut worst: Option<LiquidationScenario> = None;
+        for asset in assets {
+            let health_fn = |factor: I80F48| -> Result<I80F48> {
+                Ok(self
+                    .cache_after_price_change(asset, factor)?
+                    .health(health_type))
+            };
+
+            for limit in [I80F48::ZERO, max_factor] {
+                let limit_health = health_fn(limit)?;
+                if limit_health >= 0 {
+                    continue;
+                }
+                let price_factor = binary_search(
+                    limit,
+                    limit_health,
+                    I80F48::ONE,
+                    I80F48::ZERO,
+                    min_step,
+                    health_fn,
+                )?;
+                let scenario = LiquidationScenario {
+                    asset,
+                    price_factor,
+                };
+                if worst.map_or(true, |w| scenario.price_change() < w.price_change()) {
+                    worst = Some(scenario);
+                }
+            }
+        }
+
+        Ok(worst)
+    }
+
+    /// The maint health below which liquidating the account's base position in `perp_market`
+    /// earns a liqor at least `reference_cost` (in native settle token) in liquidation fees.
+    ///
+    /// The liqor's reward is its part of the base liquidation fee on the lots needed to bring
+    /// liquidation end health to zero. That number of lots grows as maint health drops,
+    /// assuming the gap between maint and liquidation end health stays the same.
+    ///
+    /// Returns I80F48::MIN if the liquidation never pays for the cost.
+    pub fn effective_liquidation_threshold(
+        &self,
+        perp_market: &PerpMarket,
+        reference_cost: I80F48,
+    ) -> Result<I80F48> {
+        let perp_info = self.perp_info(perp_market.perp_market_index)?;
+        let base_lots = perp_info.base_lots;
+        let price_per_lot = I80F48::from(perp_market.base_lot_size) * perp_info.base_prices.oracle;
+
+        // Liquidation end health gained for each lot of base reduction, like uhupnl_per_lot
+        // in perp_liq_base_or_positive_pnl
+        let all_fees = perp_market.base_liquidation_fee + perp_market.platform_liquidation_fee;
+        let health_per_lot = if base_lots > 0 {
+            price_per_lot * (I80F48::ONE - all_fees - perp_market.init_base_asset_weight)
+        } else {
+            price_per_lot * (perp_market.init_base_liab_weight - I80F48::ONE - all_fees)
+        };
+        let reward_per_lot = price_per_lot
+            * perp_market.base_liquidation_fee_to_liqor(perp_market.base_liquidation_fee);
+        if base_lots == 0 || health_per_lot <= 0 || reward_per_lot <= 0 {
+            return Ok(I80F48::MIN);
+        }
+
+        let lots_for_cost = match reference_cost.max(I80F48::ZERO).checked_div(reward_per_lot) {
+            Some(lots) if lots <= I80F48::from(base_lots.abs()) => lots,
+            _ => return Ok(I80F48::MIN),
+        };
+
+        let gap = self.health(HealthType::Maint) - self.health(HealthType::LiquidationEnd);
+        Ok((gap - lots_for_cost * health_per_lot).min(I80F48::ZERO))
+    }
+
+    /// Estimates fully liquidating the account's base position in `perp_market` with repeated
+    /// perp_liq_base_or_positive_pnl calls that are each limited to `max_base_transfer_per_call`
+    /// lots.
+    ///
+    /// Returns the account's total base lot change, its total quote change and the number of calls.
+    ///
+    /// This is a rough planning aid and not a simulation of the instruction:
+    /// - only this market is considered, as if the liqor didn't liquidate other markets or tokens
+    /// - base is transferred at the oracle price, not at a liqor-supplied price
+    /// - no pnl is taken over (max_pnl_transfer = 0)
+    /// - min_liquidation_base_lots, the overshoot cap and the market's liquidation throttle
+    ///   are ignored
+    ///
+    /// Since the account's state only depends on the total base reduced, the total is the same
+    /// no matter how it is split into calls.
+    pub fn estimate_full_liquidation(
+        &self,
+        perp_market: &PerpMarket,
+        max_base_transfer_per_call: i64,
+    ) -> Result<(i64, I80F48, u32)> {
+        let max_lots_per_call = max_base_transfer_per_call.saturating_abs();
+        require_msg!(
+            max_lots_per_call > 0,
+            "max_base_transfer_per_call must not be zero"
+        );
+
+        let oracle_price = self
+            .perp_info(perp_market.perp_market_index)?
+            .base_prices
+            .oracle;
+        let (base_transfer, quote_transfer) =
+            self.base_transfer_for_zero_health(perp_market, oracle_price)?;
+
+        let lots = base_transfer.abs();
+        let num_calls = if lots == 0 {
+            0
+        } else {
+            (lots - 1) / max_lots_per_call + 1
+        };
+        Ok((
+            base_transfer,
+            quote_transfer,
+            num_calls.try_into().unwrap_or(u32::MAX),
+        ))
+    }
+
+    /// The signed max_base_transfer a liqor should pass to perp_liq_base_or_positive_pnl to
+    /// bring the account's health to zero in a single call, when the base is transferred at
+    /// `oracle_price`.
+    ///
+    /// Has the same limits as estimate_full_liquidation(). The result never exceeds the
+    /// account's base position.
+    pub fn recommended_max_base_transfer(
+        &self,
+        perp_market: &PerpMarket,
+        oracle_price: I80F48,
+    ) -> Result<i64> {
+        // the liqee's base change has the opposite sign of max_base_transfer
+        let (base_transfer, _) = self.base_transfer_for_zero_health(perp_market, oracle_price)?;
+        Ok(-base_transfer)
+    }
+
+    /// Whether reducing the account's base position in this market to the LiquidationEnd
+    /// health zero target at oracle price also brings its init health to zero or above.
+    ///
+    /// Liquidators can prefer such markets: afterwards the account is fully healthy again and
+    /// no further liquidation calls are needed. Has the same limits as
+    /// estimate_full_liquidation().
+    pub fn perp_liquidation_fully_recovers(&self, perp_market: &PerpMarket) -> Result<bool> {
+        let perp_info_index = self.perp_info_index(perp_market.perp_market_index)?;
+        let oracle_price = self.perp_infos[perp_info_index].base_prices.oracle;
+        let (base_transfer, quote_transfer) =
+            self.base_transfer_for_zero_health(perp_market, oracle_price)?;
+
+        let mut cache = self.clone();
+        let perp_info = &mut cache.perp_infos[perp_info_index];
+        perp_info.base_lots += base_transfer;
+        perp_info.quote += quote_transfer;
+        Ok(cache.health(HealthType::Init) >= 0)
+    }
+
+    /// Lists the markets among `perp_markets` where reducing the account's base position at
+    /// oracle price improves its LiquidationEnd health, with the account's base lot change
+    /// for each.
+    ///
+    /// The sizing is the same as in estimate_full_liquidation(), with each market considered
+    /// on its own, raised to the market's min_liquidation_base_lots where needed. It's meant
+    /// for planning after check_liquidatable() found the account liquidatable: if the
+    /// account's health is already non-negative the list is empty.
+    pub fn liquidatable_perp_markets(
+        &self,
+        perp_markets: &[&PerpMarket],
+    ) -> Result<Vec<(PerpMarketIndex, i64)>> {
+        let mut result = vec![];
+        for perp_market in perp_markets {
+            if let Some(base_transfer) = self.liquidatable_base_transfer(perp_market)? {
+                result.push((perp_market.perp_market_index, base_transfer));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Whether the account has negative maint health, but none of the base liquidations from
+    /// liquidatable_perp_markets() would pay the liqor at least `min_liqor_reward`.
+    ///
+    /// No liquidator with that profit threshold will touch such accounts, so their risk stays
+    /// with the protocol until someone steps in manually. The reward is the liqor's share of
+    /// the base liquidation fee including the dust boost, minus the quote rounding buffer.
+    /// Positive pnl takeover isn't considered. Accounts without any perp base liquidation
+    /// are not flagged: whether they get liquidated doesn't depend on perp liquidators.
+    pub fn perp_liquidation_uneconomical(
+        &self,
+        perp_markets: &[&PerpMarket],
+        min_liqor_reward: I80F48,
+    ) -> Result<bool> {
+        if self.health(HealthType::Maint) >= 0 {
+            return Ok(false);
+        }
+        let mut any_liquidatable = false;
+        for perp_market in perp_markets {
+            let Some(base_transfer) = self.liquidatable_base_transfer(perp_market)? else {
+                continue;
+            };
+            any_liquidatable = true;
+
+            let perp_info = self.perp_info(perp_market.perp_market_index)?;
+            let price_per_lot =
+                I80F48::from(perp_market.base_lot_size) * perp_info.base_prices.oracle;
+            let base_reduction = base_transfer.abs();
+            let dust_lots = perp_market.liquidation_dust_lots(perp_info.base_lots, base_reduction);
+            let liqor_reward = I80F48::from(base_reduction)
+                * price_per_lot
+                * perp_market.base_liquidation_fee_to_liqor(perp_market.base_liquidation_fee)
+                + I80F48::from(dust_lots)
+                    * price_per_lot
+                    * perp_market.dust_liquidation_fee_boost(perp_market.base_liquidation_fee)
+                - I80F48::from_num(perp_market.liquidation_quote_rounding_buffer);
+            if liqor_reward >= min_liqor_reward {
+                return Ok(false);
+            }
+        }
+        Ok(any_liquidatable)
+    }
+
+    /// Returns the account's base lot and quote changes of the smallest base reduction at the
+    /// oracle price that brings its init health to at least `target_buffer`, or of closing the
+    /// whole base position if that is not enough.
+    ///
+    /// Since only whole lots are transferred, the init health afterwards is at least
+    /// `target_buffer` and exceeds it by less than the health gained from a single lot.
+    /// Has the same limits as estimate_full_liquidation().
+    pub fn base_transfer_for_init_health_target(
+        &self,
+        perp_market: &PerpMarket,
+        target_buffer: I80F48,
+    ) -> Result<(i64, I80F48)> {
+        let oracle_price = self
+            .perp_info(perp_market.perp_market_index)?
+            .base_prices
+            .oracle;
+        self.base_transfer_for_health_target(
+            perp_market,
+            oracle_price,
+            HealthType::Init,
+            target_buffer,
+        )
+    }
+
+    /// The account's base lot change for liquidatable_perp_markets(), or None if reducing the
+    /// base position in `perp_market` doesn't improve LiquidationEnd health
+    fn liquidatable_base_transfer(&self, perp_market: &PerpMarket) -> Result<Option<i64>> {
+        let perp_info_index = self.perp_info_index(perp_market.perp_market_index)?;
+        let perp_info = &self.perp_infos[perp_info_index];
+        let base_lots = perp_info.base_lots;
+        let oracle_price = perp_info.base_prices.oracle;
+        let (base_transfer, quote_transfer) =
+            self.base_transfer_for_zero_health(perp_market, oracle_price)?;
+        if base_transfer == 0 {
+            return Ok(None);
+        }
+
+        let mut cache = self.clone();
+        let perp_info = &mut cache.perp_infos[perp_info_index];
+        perp_info.base_lots += base_transfer;
+        perp_info.quote += quote_transfer;
+        if cache.health(HealthType::LiquidationEnd) <= self.health(HealthType::LiquidationEnd) {
+            return Ok(None);
+        }
+
+        let min_lots = perp_market.min_liquidation_base_lots.min(base_lots.abs());
+        Ok(Some(
+            base_transfer.signum() * base_transfer.abs().max(min_lots),
+        ))
+    }
+
+    /// Returns the account's base lot and quote changes of the smallest base reduction at
+    /// `price` that brings its LiquidationEnd health to zero, or of closing the whole base
+    /// position if that is not enough.
+    fn base_transfer_for_zero_health(
+        &self,
+        perp_market: &PerpMarket,
+        price: I80F48,
+    ) -> Result<(i64, I80F48)> {
+        self.base_transfer_for_health_target(
+            perp_market,
+            price,
+            HealthType::LiquidationEnd,
+            I80F48::ZERO,
+        )
+    }
+
+    fn base_transfer_for_health_target(
+        &self,
+        perp_market: &PerpMarket,
+        price: I80F48,
+        health_type: HealthType,
+        target: I80F48,
+    ) -> Result<(i64, I80F48)> {
+        let perp_info_index = self.perp_info_index(perp_market.perp_market_index)?;
+        let base_lots = self.perp_infos[perp_info_index].base_lots;
+
+        let direction: i64 = if base_lots > 0 { -1 } else { 1 };
+        let base_fee_factor_all = I80F48::ONE
+            + I80F48::from(direction)
+                * (perp_market.base_liquidation_fee + perp_market.platform_liquidation_fee);
+        let price_per_lot = I80F48::from(perp_market.base_lot_size) * price;
+
+        let quote_transfer =
+            |lots: i64| -I80F48::from(direction * lots) * price_per_lot * base_fee_factor_all;
+        let health_after = |lots: i64| {
+            let mut cache = self.clone();
+            let perp_info = &mut cache.perp_infos[perp_info_index];
+            perp_info.base_lots += direction * lots;
+            perp_info.quote += quote_transfer(lots);
+            cache.health(health_type)
+        };
+
+        // Health increases with each lot of base reduction, find the fewest lots that reach
+        // the target
+        let mut low = 0;
+        let mut high = base_lots.abs();
+        if self.health(health_type) >= target {
+            high = 0;
+        } else if health_after(high) >= target {
+            while high - low > 1 {
+                let mid = low + (high - low) / 2;
+                if health_after(mid) >= target {
+                    high = mid;
+                } else {
+                    low = mid;
+                }
+            }
+        }
+
+        Ok((direction * high, quote_transfer(high)))
+    }
+
     pub fn max_borrow_for_health_ratio(
         &self,
         account: &MangoAccountValue,
@@ -492,6 +893,15 @@ impl HealthCache {
     }
 }
 
+/// Quote the liqor must already hold to cover `quote_transfer`, the change to its
+/// quote position (like quote_transfer_liqor in perp_liq_base_or_positive_pnl), without
+/// borrowing.
+///
+/// Taking over a long position costs quote, taking over a short one pays it out.
+pub fn liqor_quote_required(quote_transfer: I80F48) -> I80F48 {
+    (-quote_transfer).max(I80F48::ZERO)
+}
+
 fn scan_right_until_less_than(
     start: I80F48,
     target: I80F48,
@@ -693,6 +1103,7 @@ mod tests {
         PerpInfo {
             perp_market_index: 0,
             settle_token_index: 0,
+            underlying_id: 0,
             maint_base_asset_weight: I80F48::from_num(1.0 - x),
             init_base_asset_weight: I80F48::from_num(1.0 - x),
             maint_base_liab_weight: I80F48::from_num(1.0 + x),
@@ -705,6 +1116,7 @@ mod tests {
             asks_base_lots: 0,
             quote: I80F48::ZERO,
             base_prices: Prices::new_single_price(I80F48::from_num(price)),
+            maint_trigger_price: None,
             has_open_orders: false,
             has_open_fills: false,
         }
@@ -1585,6 +1997,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_worst_case_liquidation_scenario() {
+        // Three tokens, the second one dropping is the biggest risk:
+        // health = 100 * 2 * 0.8 + 10 * 5 * 0.5 - 150 = 35
+        let token_cache = HealthCache {
+            token_infos: vec![
+                TokenInfo {
+                    token_index: 0,
+                    balance_spot: I80F48::from(-150),
+                    ..default_token_info(0.0, 1.0)
+                },
+                TokenInfo {
+                    token_index: 1,
+                    balance_spot: I80F48::from(100),
+                    ..default_token_info(0.2, 2.0)
+                },
+                TokenInfo {
+                    token_index: 2,
+                    balance_spot: I80F48::from(10),
+                    ..default_token_info(0.5, 5.0)
+                },
+            ],
+            serum3_infos: vec![],
+            perp_infos: vec![],
+            being_liquidated: false,
+        };
+        assert!(health_eq(token_cache.health(HealthType::Maint), 35.0));
+
+        // token 1 down: 160 * f + 25 - 150 = 0 at f = 0.78125
+        // token 0 up: 185 - 150 * f = 0 at f = 1.2333, a bigger move
+        // token 2 can't bring health to zero on its own
+        let scenario = token_cache
+            .worst_case_liquidation_scenario()
+            .unwrap()
+            .unwrap();
+        assert_eq!(scenario.asset, LiquidationScenarioAsset::Token(1));
+        assert!((scenario.price_factor - I80F48::from_num(0.78125)).abs() < 0.001);
+        let after = token_cache
+            .cache_after_price_change(scenario.asset, scenario.price_factor)
+            .unwrap();
+        assert!(after.health(HealthType::Maint).abs() < 0.2);
+
+        // With fewer token 1 deposits, the token 0 price increase is the worst case
+        let mut cache = token_cache.clone();
+        cache.token_infos[1].balance_spot = I80F48::from(80);
+        // health = 128 + 25 - 150 = 3
+        // token 1 down: 128 * f - 125 = 0 at f = 0.977
+        // token 0 up: 153 - 150 * f = 0 at f = 1.02
+        let scenario = cache.worst_case_liquidation_scenario().unwrap().unwrap();
+        assert_eq!(scenario.asset, LiquidationScenarioAsset::Token(0));
+        assert!((scenario.price_factor - I80F48::from_num(1.02)).abs() < 0.001);
+
+        // Changing which deposit backs the borrow changes the worst case
+        cache.token_infos[1].balance_spot = I80F48::ZERO;
+        cache.token_infos[2].balance_spot = I80F48::from(80);
+        // health = 200 - 150 = 50
+        // token 2 down: 200 * f - 150 = 0 at f = 0.75
+        // token 0 up: 200 - 150 * f = 0 at f = 1.333
+        let scenario = cache.worst_case_liquidation_scenario().unwrap().unwrap();
+        assert_eq!(scenario.asset, LiquidationScenarioAsset::Token(2));
+        assert!((scenario.price_factor - I80F48::from_num(0.75)).abs() < 0.001);
+        cache.token_infos[2].balance_spot = I80F48::ZERO;
+        cache.token_infos[0].balance_spot = I80F48::from(-10);
+        cache.token_infos[1].balance_spot = I80F48::from(100);
+        cache.token_infos[1].maint_asset_weight = I80F48::from(1);
+        // health = 200 - 10 = 190
+        // token 1 down: 200 * f - 10 = 0 at f = 0.05
+        // token 0 up: 200 - 10 * f = 0 at f = 20
+        let scenario = cache.worst_case_liquidation_scenario().unwrap().unwrap();
+        assert_eq!(scenario.asset, LiquidationScenarioAsset::Token(1));
+        assert!((scenario.price_factor - I80F48::from_num(0.05)).abs() < 0.001);
+
+        // A perp position settling in token 0
+        // health = 20 + 10 * 10 * 0.8 - 80 = 20, perp down: 80 * f - 60 = 0 at f = 0.75
+        let perp_cache = HealthCache {
+            token_infos: vec![TokenInfo {
+                token_index: 0,
+                balance_spot: I80F48::from(20),
+                ..default_token_info(0.0, 1.0)
+            }],
+            serum3_infos: vec![],
+            perp_infos: vec![PerpInfo {
+                perp_market_index: 3,
+                base_lots: 10,
+                quote: I80F48::from(-80),
+                ..default_perp_info(0.2, 10.0)
+            }],
+            being_liquidated: false,
+        };
+        assert!(health_eq(perp_cache.health(HealthType::Maint), 20.0));
+        let scenario = perp_cache
+            .worst_case_liquidation_scenario()
+            .unwrap()
+            .unwrap();
+        assert_eq!(scenario.asset, LiquidationScenarioAsset::Perp(3));
+        assert!((scenario.price_factor - I80F48::from_num(0.75)).abs() < 0.002);
+
+        // Deposits only: no price move leads to liquidation
+        let mut cache = perp_cache.clone();
+        cache.perp_infos.clear();
+        assert!(cache.worst_case_liquidation_scenario().unwrap().is_none());
+
+        // Already liquidatable
+        cache.token_infos[0].balance_spot = I80F48::from(-1);
+        assert!(cache.worst_case_liquidation_scenario().is_err());
+    }
+
     #[test]
     fn test_assets_and_borrows() {
         let health_cache = HealthCache {
@@ -1733,4 +2252,237 @@ mod tests {
 
         assert!(leverage_eq(&health_cache, 2.0));
     }
+
+    #[test]
+    fn test_current_leverage() {
+        let cache =
+            |balance0: f64, balance1: f64, perp_base_lots: i64, perp_quote: f64| HealthCache {
+                token_infos: vec![
+                    TokenInfo {
+                        token_index: 0,
+                        balance_spot: I80F48::from_num(balance0),
+                        ..default_token_info(0.0, 1.0)
+                    },
+                    TokenInfo {
+                        token_index: 1,
+                        balance_spot: I80F48::from_num(balance1),
+                        ..default_token_info(0.2, 2.0)
+                    },
+                ],
+                serum3_infos: vec![],
+                perp_infos: vec![PerpInfo {
+                    perp_market_index: 0,
+                    base_lots: perp_base_lots,
+                    quote: I80F48::from_num(perp_quote),
+                    ..default_perp_info(0.1, 1.0)
+                }],
+                being_liquidated: false,
+            };
+        let lev = |h: &HealthCache| h.current_leverage().to_num::<f64>();
+
+        // only deposits: 1x
+        assert!((lev(&cache(1.0, 0.0, 0, 0.0)) - 1.0).abs() < 0.0001);
+        assert!((lev(&cache(1.0, 2.0, 0, 0.0)) - 1.0).abs() < 0.0001);
+        assert!(
+            (cache(1.0, 0.0, 0, 0.0).current_leverage()
+                - cache(1.0, 0.0, 0, 0.0).leverage()
+                - I80F48::ONE)
+                .abs()
+                < 0.0001
+        );
+
+        // 3x: perp base worth twice the equity, in both directions
+        assert!((lev(&cache(1.0, 0.0, 2, -2.0)) - 3.0).abs() < 0.0001);
+        assert!((lev(&cache(1.0, 0.0, -2, 2.0)) - 3.0).abs() < 0.0001);
+
+        // 3x: spot token worth twice the equity, bought with borrowed funds
+        assert!((lev(&cache(-2.0, 1.5, 0, 0.0)) - 3.0).abs() < 0.0001);
+
+        // near the max: with an init asset weight of 0.8, init health reaches zero at 5x
+        let max_cache = cache(-4.0, 2.5, 0, 0.0);
+        assert!(max_cache.health(HealthType::Init).abs() < 0.0001);
+        assert!((lev(&max_cache) - 5.0).abs() < 0.0001);
+        let over_cache = cache(-4.2, 2.6, 0, 0.0);
+        assert!(over_cache.health(HealthType::Init) < 0);
+        assert!(lev(&over_cache) > 5.0);
+
+        // no equity left
+        assert_eq!(cache(-1.0, 0.5, 0, 0.0).current_leverage(), I80F48::MAX);
+        assert_eq!(cache(-2.0, 0.5, 0, 0.0).current_leverage(), I80F48::MAX);
+    }
+
+    #[test]
+    fn test_net_delta_for_underlying() {
+        let perp = |perp_market_index: PerpMarketIndex,
+                    underlying_id: u16,
+                    base_lots: i64,
+                    price: f64| PerpInfo {
+            perp_market_index,
+            underlying_id,
+            base_lots,
+            base_lot_size: 10,
+            ..default_perp_info(0.1, price)
+        };
+        let cache = HealthCache {
+            token_infos: vec![TokenInfo {
+                token_index: 0,
+                ..default_token_info(0.0, 2.0)
+            }],
+            serum3_infos: vec![],
+            perp_infos: vec![
+                // two markets on the same underlying, e.g. a perpetual and a dated future
+                perp(0, 1, 3, 100.0),
+                perp(1, 1, -2, 101.0),
+                // a market on a different underlying
+                perp(2, 2, 5, 7.0),
+                // a market without an underlying_id
+                perp(3, 0, 4, 100.0),
+            ],
+            being_liquidated: false,
+        };
+        let delta = |id: u16| cache.net_delta_for_underlying(id).unwrap().to_num::<f64>();
+
+        // (30 * 100 - 20 * 101) * 2
+        assert!((delta(1) - 1960.0).abs() < 0.0001);
+        // 50 * 7 * 2, not netted with anything
+        assert!((delta(2) - 700.0).abs() < 0.0001);
+        assert_eq!(delta(0), 0.0);
+        assert_eq!(delta(3), 0.0);
+
+        // fully offsetting positions net out
+        let mut offsetting = cache.clone();
+        offsetting.perp_infos[1].base_lots = -3;
+        offsetting.perp_infos[1].base_prices = Prices::new_single_price(I80F48::from(100));
+        assert_eq!(offsetting.net_delta_for_underlying(1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_liquidatable_perp_markets() {
+        let group = Pubkey::new_unique();
+        let (mut settle_bank, mut settle_oracle) = mock_bank_and_oracle(group, 0, 1.0, 0.0, 0.0);
+        let (_, mut oracle1) = mock_bank_and_oracle(group, 4, 1.0, 0.0, 0.0);
+        let (_, mut oracle2) = mock_bank_and_oracle(group, 5, 1.0, 0.0, 0.0);
+        let (_, mut oracle3) = mock_bank_and_oracle(group, 6, 1.0, 0.0, 0.0);
+        let mut pm1 = mock_perp_market(group, oracle1.pubkey, 1.0, 9, (0.5, 0.25), (0.0, 0.0));
+        let mut pm2 = mock_perp_market(group, oracle2.pubkey, 1.0, 10, (0.5, 0.25), (0.0, 0.0));
+        // base is valued at the oracle price, so reducing it doesn't change health
+        let mut pm3 = mock_perp_market(group, oracle3.pubkey, 1.0, 11, (0.0, 0.0), (0.0, 0.0));
+        for pm in [&mut pm1, &mut pm2, &mut pm3] {
+            pm.data().base_lot_size = 1;
+        }
+
+        let liqee_buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut liqee = MangoAccountValue::from_bytes(&liqee_buffer).unwrap();
+        liqee.ensure_token_position(0).unwrap();
+        settle_bank
+            .data()
+            .change_without_fee(
+                liqee.token_position_mut(0).unwrap().0,
+                I80F48::from_num(7.0),
+                0,
+            )
+            .unwrap();
+        // each of the first two markets contributes -5 liquidation end health, 0.5 per lot
+        for (pm, base_lots) in [(&mut pm1, 10), (&mut pm2, -10), (&mut pm3, 10)] {
+            let index = pm.data().perp_market_index;
+            liqee.ensure_perp_position(index, 0).unwrap();
+            liqee.perp_position_mut(index).unwrap().record_trade(
+                pm.data(),
+                base_lots,
+                I80F48::from(-base_lots),
+            );
+        }
+
+        let mut healthy = liqee.clone();
+        settle_bank
+            .data()
+            .change_without_fee(
+                healthy.token_position_mut(0).unwrap().0,
+                I80F48::from_num(10.0),
+                0,
+            )
+            .unwrap();
+
+        let markets = [*pm1.data(), *pm2.data(), *pm3.data()];
+        let markets = markets.iter().collect::<Vec<_>>();
+        let mut pm2_min = *pm2.data();
+        pm2_min.min_liquidation_base_lots = 8;
+
+        let ais = vec![
+            settle_bank.as_account_info(),
+            settle_oracle.as_account_info(),
+            pm1.as_account_info(),
+            pm2.as_account_info(),
+            pm3.as_account_info(),
+            oracle1.as_account_info(),
+            oracle2.as_account_info(),
+            oracle3.as_account_info(),
+        ];
+        let retriever = ScanningAccountRetriever::new(&ais, &group).unwrap();
+        let cache = new_health_cache(&liqee.borrow(), &retriever, 0).unwrap();
+        assert!(health_eq(cache.health(HealthType::LiquidationEnd), -3.0));
+
+        // 6 lots bring health to zero in either of the first two markets
+        assert_eq!(
+            cache.liquidatable_perp_markets(&markets).unwrap(),
+            vec![(9, -6), (10, 6)]
+        );
+
+        // the sizing respects min_liquidation_base_lots
+        assert_eq!(
+            cache
+                .liquidatable_perp_markets(&[markets[0], &pm2_min, markets[2]])
+                .unwrap(),
+            vec![(9, -6), (10, 8)]
+        );
+
+        // a healthy account has nothing to liquidate
+        let cache = new_health_cache(&healthy.borrow(), &retriever, 0).unwrap();
+        assert!(cache
+            .liquidatable_perp_markets(&markets)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_effective_liquidation_threshold() {
+        let cache = |base_lots: i64| HealthCache {
+            token_infos: vec![default_token_info(0.0, 1.0)],
+            serum3_infos: vec![],
+            perp_infos: vec![PerpInfo {
+                base_lots,
+                ..default_perp_info(0.1, 100.0)
+            }],
+            being_liquidated: false,
+        };
+        let market = |fee: f64| {
+            let mut pm = PerpMarket::default_for_tests();
+            pm.base_lot_size = 1;
+            pm.init_base_asset_weight = I80F48::from_num(0.9);
+            pm.init_base_liab_weight = I80F48::from_num(1.1);
+            pm.base_liquidation_fee = I80F48::from_num(fee);
+            pm.platform_liquidation_fee = I80F48::ZERO;
+            pm.liquidation_fee_insurance_fraction = 0.0;
+            pm
+        };
+        let threshold = |base_lots: i64, fee: f64, cost: f64| {
+            cache(base_lots)
+                .effective_liquidation_threshold(&market(fee), I80F48::from_num(cost))
+                .unwrap()
+        };
+
+        // 2 reward and 8 health per lot: 5 lots pay for the cost, needing a health of -40
+        assert!(health_eq(threshold(10, 0.02, 10.0), -40.0));
+        // 5 reward and 5 health per lot: 2 lots are enough
+        assert!(health_eq(threshold(10, 0.05, 10.0), -10.0));
+        // shorts: 2 reward and 8 health per lot as well
+        assert!(health_eq(threshold(-10, 0.02, 10.0), -40.0));
+        // no cost: liquidating is worthwhile as soon as it's possible
+        assert_eq!(threshold(10, 0.02, 0.0), 0);
+
+        // the whole position earns only 20
+        assert_eq!(threshold(10, 0.02, 30.0), I80F48::MIN);
+        assert_eq!(threshold(10, 0.0, 10.0), I80F48::MIN);
+        assert_eq!(threshold(0, 0.02, 10.0), I80F48::MIN);
+    }
 }
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+use crate::logs::{emit_stack, AccountCompactTokenPositionsLog};
+use crate::state::*;
+
+/// Moves the account's active token positions into the front slots, filling the gaps that
+/// deactivated positions left behind. See MangoAccount::compact_token_positions().
+///
+/// Positions are always looked up by token index and keep their relative order, so serum3,
+/// perp and token conditional swap references as well as health account lists stay valid.
+pub fn account_compact_token_positions(ctx: Context<AccountCompactTokenPositions>) -> Result<()> {
+    let mut account = ctx.accounts.account.load_full_mut()?;
+
+    let moved = account.compact_token_positions();
+    if moved == 0 {
+        return Ok(());
+    }
+
+    msg!("moved {} token positions", moved);
+    emit_stack(AccountCompactTokenPositionsLog {
+        mango_group: ctx.accounts.group.key(),
+        mango_account: ctx.accounts.account.key(),
+        token_indexes: account
+            .all_token_positions()
+            .map(|position| position.token_index)
+            .collect(),
+    });
+
+    Ok(())
+}
@@ -20,6 +20,7 @@ pub fn account_edit(
         MangoError::SomeError
     );
 
+    let account_key = ctx.accounts.account.key();
     let mut account = ctx.accounts.account.load_full_mut()?;
 
     if let Some(name) = name_opt {
@@ -27,6 +28,7 @@ pub fn account_edit(
     }
 
     if let Some(delegate) = delegate_opt {
+        account.fixed.check_delegate(&account_key, &delegate)?;
         account.fixed.delegate = delegate;
     }
 
@@ -34,6 +36,9 @@ pub fn account_edit(
         (Some(temporary_delegate), Some(temporary_delegate_expiry)) => {
             let now_ts: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
             require_gt!(now_ts + ONE_WEEK_SECONDS, temporary_delegate_expiry);
+            account
+                .fixed
+                .check_delegate(&account_key, &temporary_delegate)?;
             account.fixed.temporary_delegate = temporary_delegate;
             account.fixed.temporary_delegate_expiry = temporary_delegate_expiry;
         }
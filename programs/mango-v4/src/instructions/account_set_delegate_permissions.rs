@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+use crate::state::*;
+
+// Restricts what the delegate and the temporary delegate may do, as DelegatePermission bits.
+// Zero leaves a delegate unrestricted. The owner always has every permission.
+pub fn account_set_delegate_permissions(
+    ctx: Context<AccountSetDelegatePermissions>,
+    delegate_permissions: u8,
+    temporary_delegate_permissions: u8,
+) -> Result<()> {
+    let all_permissions = DelegatePermission::Liquidate.bit()
+        | DelegatePermission::Trade.bit()
+        | DelegatePermission::Withdraw.bit();
+    require_eq!(delegate_permissions & !all_permissions, 0);
+    require_eq!(temporary_delegate_permissions & !all_permissions, 0);
+
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    msg!(
+        "Delegate permissions: old - {:#05b}, new - {:#05b}",
+        account.fixed.delegate_permissions,
+        delegate_permissions
+    );
+    msg!(
+        "Temporary delegate permissions: old - {:#05b}, new - {:#05b}",
+        account.fixed.temporary_delegate_permissions,
+        temporary_delegate_permissions
+    );
+    account.fixed.delegate_permissions = delegate_permissions;
+    account.fixed.temporary_delegate_permissions = temporary_delegate_permissions;
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+use crate::error::MangoError;
+
+pub fn account_set_owner(
+    ctx: Context<AccountSetOwner>,
+    new_owner: Pubkey,
+    clear_delegates: bool,
+) -> Result<()> {
+    require_keys_neq!(new_owner, Pubkey::default());
+
+    let account_key = ctx.accounts.account.key();
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    require!(
+        !account.fixed.being_liquidated(),
+        MangoError::BeingLiquidated
+    );
+
+    msg!("Owner old {:?}, new {:?}", account.fixed.owner, new_owner);
+    account.fixed.owner = new_owner;
+
+    if clear_delegates {
+        account.fixed.delegate = Pubkey::default();
+        account.fixed.temporary_delegate = Pubkey::default();
+        account.fixed.temporary_delegate_expiry = 0;
+    }
+
+    // the new owner must not already be a delegate
+    let delegate = account.fixed.delegate;
+    let temporary_delegate = account.fixed.temporary_delegate;
+    account.fixed.check_delegate(&account_key, &delegate)?;
+    account
+        .fixed
+        .check_delegate(&account_key, &temporary_delegate)?;
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+use crate::state::*;
+
+// Restricts the tokens an account may deposit into or borrow. Existing positions in other
+// tokens are not affected and can still be withdrawn, repaid or liquidated.
+pub fn account_set_token_allowlist(
+    ctx: Context<AccountSetTokenAllowlist>,
+    token_indexes: Vec<TokenIndex>,
+) -> Result<()> {
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    msg!(
+        "Token allowlist: old - {:?}, new - {:?}",
+        account.fixed.token_allowlist(),
+        token_indexes
+    );
+    account.fixed.set_token_allowlist(&token_indexes)?;
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+use crate::error::Contextable;
+use crate::health::{new_health_cache, HealthType, ScanningAccountRetriever};
+use crate::state::*;
+use crate::util::clock_now;
+
+/// Stores the account's init and maint health for the current slot on the account.
+///
+/// Permissionless, meant for keepers. Readers use MangoAccountFixed::cached_health().
+pub fn cache_account_health(ctx: Context<CacheAccountHealth>) -> Result<()> {
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    let (now_ts, now_slot) = clock_now();
+
+    let retriever =
+        ScanningAccountRetriever::new(ctx.remaining_accounts, &ctx.accounts.group.key())
+            .context("create account retriever")?;
+    let health_cache = new_health_cache(&account.borrow(), &retriever, now_ts)
+        .context("cache_account_health health cache")?;
+
+    account.fixed.set_cached_health(
+        health_cache.health(HealthType::Init),
+        health_cache.health(HealthType::Maint),
+        now_slot,
+    );
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use crate::accounts_ix::*;
-use crate::{error::MangoError, events::MangoAccountData, health::*, state::*};
+use crate::{error::MangoError, events::MangoAccountDataV2, health::*, state::*};
 use anchor_lang::prelude::*;
 
 pub fn compute_account_data(ctx: Context<ComputeAccountData>) -> Result<()> {
@@ -21,10 +21,13 @@ pub fn compute_account_data(ctx: Context<ComputeAccountData>) -> Result<()> {
     let equity = compute_equity(&account.borrow(), &account_retriever)?;
 
     // Potentially too big for the stack!
-    emit!(MangoAccountData {
+    emit!(MangoAccountDataV2 {
         init_health,
         maint_health,
         equity,
+        perp_trading_fees_paid: account.fixed.perp_trading_fees_paid,
+        perp_funding_paid: account.perp_funding_paid(),
+        perp_liquidation_fees_paid: account.fixed.perp_liquidation_fees_paid,
     });
 
     Ok(())
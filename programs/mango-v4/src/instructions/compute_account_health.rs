@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use solana_program::program::set_return_data;
+
+use crate::accounts_ix::*;
+use crate::error::Contextable;
+use crate::health::{new_health_cache, HealthType, ScanningAccountRetriever};
+use crate::util::clock_now;
+
+/// Computes the account's init and maint health with the program's own health code,
+/// for use with simulateTransaction.
+///
+/// The return data is the init health followed by the maint health, each as the
+/// little-endian bits of an I80F48 (16 bytes).
+pub fn compute_account_health(ctx: Context<ComputeAccountHealth>) -> Result<()> {
+    let account = ctx.accounts.account.load_full()?;
+    let (now_ts, _) = clock_now();
+
+    let retriever =
+        ScanningAccountRetriever::new(ctx.remaining_accounts, &ctx.accounts.group.key())
+            .context("create account retriever")?;
+    let health_cache = new_health_cache(&account.borrow(), &retriever, now_ts)
+        .context("compute_account_health health cache")?;
+
+    let init_health = health_cache.health(HealthType::Init);
+    let maint_health = health_cache.health(HealthType::Maint);
+
+    let mut data = [0u8; 32];
+    data[..16].copy_from_slice(&init_health.to_bits().to_le_bytes());
+    data[16..].copy_from_slice(&maint_health.to_bits().to_le_bytes());
+    set_return_data(&data);
+
+    Ok(())
+}
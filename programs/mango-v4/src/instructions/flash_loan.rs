@@ -35,7 +35,9 @@ pub fn flash_loan_begin<'key, 'accounts, 'remaining, 'info>(
 
     // account constraint #1
     require!(
-        account.fixed.is_owner_or_delegate(*owner_pk),
+        account
+            .fixed
+            .has_permission(*owner_pk, DelegatePermission::Trade),
         MangoError::SomeError
     );
 
@@ -275,7 +277,9 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
 
     // account constraint #1
     require!(
-        account.fixed.is_owner_or_delegate(ctx.accounts.owner.key()),
+        account
+            .fixed
+            .has_permission(ctx.accounts.owner.key(), DelegatePermission::Trade),
         MangoError::SomeError
     );
 
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+
+pub fn group_accept_admin(ctx: Context<GroupAcceptAdmin>) -> Result<()> {
+    let mut group = ctx.accounts.group.load_mut()?;
+    require_keys_neq!(group.pending_admin, Pubkey::default());
+
+    msg!("Admin old {:?}, new {:?}", group.admin, group.pending_admin);
+    group.admin = group.pending_admin;
+    group.pending_admin = Pubkey::default();
+
+    Ok(())
+}
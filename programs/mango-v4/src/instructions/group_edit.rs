@@ -20,14 +20,22 @@ pub fn group_edit(
     buyback_fees_expiry_interval_opt: Option<u64>,
     allowed_fast_listings_per_interval_opt: Option<u16>,
     collateral_fee_interval_opt: Option<u64>,
+    max_liquidation_overshoot_health_opt: Option<u64>,
+    max_dust_absorb_quote_opt: Option<u64>,
+    init_asset_weight_floor_opt: Option<f32>,
+    liqor_fee_waiver_min_mngo_opt: Option<u64>,
+    min_weight_change_interval_slots_opt: Option<u64>,
+    protocol_liquidator_opt: Option<Pubkey>,
+    max_liquidation_fee_opt: Option<f32>,
+    sweep_bankrupt_perp_pnl_opt: Option<bool>,
+    liqor_fee_waiver_registrar_opt: Option<Pubkey>,
 ) -> Result<()> {
     let mut group = ctx.accounts.group.load_mut()?;
 
-    if let Some(admin) = admin_opt {
-        require_keys_neq!(admin, Pubkey::default());
-        msg!("Admin old {:?}, new {:?}", group.admin, admin);
-        group.admin = admin;
-    }
+    require_msg!(
+        admin_opt.is_none(),
+        "the admin can only be changed with group_set_pending_admin"
+    );
 
     if let Some(fast_listing_admin) = fast_listing_admin_opt {
         msg!(
@@ -126,5 +134,90 @@ pub fn group_edit(
         group.collateral_fee_interval = collateral_fee_interval;
     }
 
+    if let Some(max_liquidation_overshoot_health) = max_liquidation_overshoot_health_opt {
+        msg!(
+            "Max liquidation overshoot health old {:?}, new {:?}",
+            group.max_liquidation_overshoot_health,
+            max_liquidation_overshoot_health
+        );
+        group.max_liquidation_overshoot_health = max_liquidation_overshoot_health;
+    }
+
+    if let Some(max_dust_absorb_quote) = max_dust_absorb_quote_opt {
+        msg!(
+            "Max dust absorb quote old {:?}, new {:?}",
+            group.max_dust_absorb_quote,
+            max_dust_absorb_quote
+        );
+        group.max_dust_absorb_quote = max_dust_absorb_quote;
+    }
+
+    if let Some(init_asset_weight_floor) = init_asset_weight_floor_opt {
+        msg!(
+            "Init asset weight floor old {:?}, new {:?}",
+            group.init_asset_weight_floor,
+            init_asset_weight_floor
+        );
+        require_gte!(init_asset_weight_floor, 0.0);
+        require_gte!(1.0, init_asset_weight_floor);
+        group.init_asset_weight_floor = init_asset_weight_floor;
+    }
+
+    if let Some(liqor_fee_waiver_min_mngo) = liqor_fee_waiver_min_mngo_opt {
+        msg!(
+            "Liqor fee waiver min mngo old {:?}, new {:?}",
+            group.liqor_fee_waiver_min_mngo,
+            liqor_fee_waiver_min_mngo
+        );
+        group.liqor_fee_waiver_min_mngo = liqor_fee_waiver_min_mngo;
+    }
+
+    if let Some(min_weight_change_interval_slots) = min_weight_change_interval_slots_opt {
+        msg!(
+            "Min weight change interval slots old {:?}, new {:?}",
+            group.min_weight_change_interval_slots,
+            min_weight_change_interval_slots
+        );
+        group.min_weight_change_interval_slots = min_weight_change_interval_slots;
+    }
+
+    if let Some(protocol_liquidator) = protocol_liquidator_opt {
+        msg!(
+            "Protocol liquidator old {:?}, new {:?}",
+            group.protocol_liquidator,
+            protocol_liquidator
+        );
+        group.protocol_liquidator = protocol_liquidator;
+    }
+
+    if let Some(max_liquidation_fee) = max_liquidation_fee_opt {
+        msg!(
+            "Max liquidation fee old {:?}, new {:?}",
+            group.max_liquidation_fee,
+            max_liquidation_fee
+        );
+        require_gte!(max_liquidation_fee, 0.0);
+        require_gte!(1.0, max_liquidation_fee);
+        group.max_liquidation_fee = max_liquidation_fee;
+    }
+
+    if let Some(sweep_bankrupt_perp_pnl) = sweep_bankrupt_perp_pnl_opt {
+        msg!(
+            "Sweep bankrupt perp pnl old {:?}, new {:?}",
+            group.sweep_bankrupt_perp_pnl,
+            sweep_bankrupt_perp_pnl
+        );
+        group.sweep_bankrupt_perp_pnl = u8::from(sweep_bankrupt_perp_pnl);
+    }
+
+    if let Some(liqor_fee_waiver_registrar) = liqor_fee_waiver_registrar_opt {
+        msg!(
+            "Liqor fee waiver registrar old {:?}, new {:?}",
+            group.liqor_fee_waiver_registrar,
+            liqor_fee_waiver_registrar
+        );
+        group.liqor_fee_waiver_registrar = liqor_fee_waiver_registrar;
+    }
+
     Ok(())
 }
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+
+// The new admin only takes over once it signs group_accept_admin, so that a
+// mistyped key can't lock the group.
+pub fn group_set_pending_admin(
+    ctx: Context<GroupSetPendingAdmin>,
+    pending_admin: Pubkey,
+) -> Result<()> {
+    let mut group = ctx.accounts.group.load_mut()?;
+    require_keys_neq!(pending_admin, group.admin);
+
+    msg!(
+        "Pending admin old {:?}, new {:?}",
+        group.pending_admin,
+        pending_admin
+    );
+    group.pending_admin = pending_admin;
+
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use crate::accounts_ix::*;
+use crate::events::GroupStatusData;
+use anchor_lang::prelude::*;
+
+/// Emits the group's pause state and token count so clients can check it without loading the group account.
+pub fn group_status(ctx: Context<GroupStatus>) -> Result<()> {
+    let group = ctx.accounts.group.load()?;
+
+    emit!(GroupStatusData {
+        group: ctx.accounts.group.key(),
+        admin: group.admin,
+        security_admin: group.security_admin,
+        ix_gate: group.ix_gate,
+        token_count: group.token_count,
+    });
+
+    Ok(())
+}
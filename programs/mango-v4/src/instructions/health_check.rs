@@ -9,13 +9,36 @@ use crate::health::{
 use crate::state::*;
 use crate::util::clock_now;
 
+/// Fails if the account's health is below `min_value`.
+///
+/// For the Init and Maint kinds it reuses the health stored on the account by an earlier
+/// cache_account_health or health_region_end in the same slot, see
+/// MangoAccountFixed::cached_health(). That skips the health computation when the check
+/// follows a health region or another check in the same transaction. Any change to the
+/// account's positions invalidates the stored health.
 pub fn health_check(
     ctx: Context<HealthCheck>,
     min_value: f64,
     health_check_kind: HealthCheckKind,
 ) -> Result<()> {
-    let account = ctx.accounts.account.load_full_mut()?;
     let (now_ts, now_slot) = clock_now();
+    let min_value = I80F48::from_num(min_value);
+
+    let cached_health = ctx.accounts.account.load()?.cached_health(now_slot);
+    if let Some((cached_init_health, cached_maint_health)) = cached_health {
+        let cached_value = match health_check_kind {
+            HealthCheckKind::Maint => Some(cached_maint_health),
+            HealthCheckKind::Init => Some(cached_init_health),
+            _ => None,
+        };
+        if let Some(value) = cached_value {
+            require_gte!(I80F48::from(value), min_value, MangoError::InvalidHealth);
+            return Ok(());
+        }
+    }
+
+    // Loaded immutably to keep the cached health valid for later instructions
+    let account = ctx.accounts.account.load_full()?;
 
     let group_pk = &ctx.accounts.group.key();
 
@@ -29,7 +52,6 @@ pub fn health_check(
     )
     .context("health_check health cache")?;
 
-    let min_value = I80F48::from_num(min_value);
     let actual_value = match health_check_kind {
         HealthCheckKind::Maint => health_cache.health(HealthType::Maint),
         HealthCheckKind::Init => health_cache.health(HealthType::Init),
@@ -2,6 +2,7 @@ use crate::accounts_ix::*;
 use crate::error::*;
 use crate::health::*;
 use crate::state::*;
+use crate::util::clock_now;
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::sysvar::instructions as tx_instructions;
 use anchor_lang::Discriminator;
@@ -11,6 +12,12 @@ pub fn health_region_begin<'key, 'accounts, 'remaining, 'info>(
     ctx: Context<'key, 'accounts, 'remaining, 'info, HealthRegionBegin<'info>>,
 ) -> Result<()> {
     // The instructions that may be called inside a HealthRegion
+    //
+    // These only change order book and open orders state, where skipping the intermediate
+    // health checks is safe because HealthRegionEnd checks the final state. Settlement and
+    // liquidation instructions are deliberately not included: they move funds between accounts
+    // based on the current health, so health must be computed fresh in each of them. For the
+    // same reason they don't use the health cached by cache_account_health or health_region_end.
     let allowed_inner_ix = [
         crate::instruction::PerpCancelAllOrders::discriminator(),
         crate::instruction::PerpCancelAllOrdersBySide::discriminator(),
@@ -113,8 +120,16 @@ pub fn health_region_end<'key, 'accounts, 'remaining, 'info>(
     let health_cache = new_health_cache(&account.borrow(), &account_retriever, now_ts)?;
 
     let pre_init_health = I80F48::from(account.fixed.health_region_begin_init_health);
-    account.check_health_post(&health_cache, pre_init_health)?;
+    let post_init_health = account.check_health_post(&health_cache, pre_init_health)?;
     account.fixed.health_region_begin_init_health = 0;
 
+    // Later instructions in the slot, like health_check, can reuse the health
+    let (_, now_slot) = clock_now();
+    account.fixed.set_cached_health(
+        post_init_health,
+        health_cache.health(HealthType::Maint),
+        now_slot,
+    );
+
     Ok(())
 }
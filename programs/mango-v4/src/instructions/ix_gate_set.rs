@@ -99,6 +99,19 @@ pub fn ix_gate_set(ctx: Context<IxGateSet>, ix_gate: u128) -> Result<()> {
     log_if_changed(&group, ix_gate, IxGate::SequenceCheck);
     log_if_changed(&group, ix_gate, IxGate::HealthCheck);
     log_if_changed(&group, ix_gate, IxGate::GroupChangeInsuranceFund);
+    log_if_changed(&group, ix_gate, IxGate::AccountSetOwner);
+    log_if_changed(&group, ix_gate, IxGate::PerpSettleAllFunding);
+    log_if_changed(&group, ix_gate, IxGate::TokenLiqWithTokenRelaxedStaleness);
+    log_if_changed(&group, ix_gate, IxGate::TokenLiqAbsorbDust);
+    log_if_changed(&group, ix_gate, IxGate::PerpAdl);
+    log_if_changed(&group, ix_gate, IxGate::AccountSetTokenAllowlist);
+    log_if_changed(&group, ix_gate, IxGate::ComputeAccountHealth);
+    log_if_changed(&group, ix_gate, IxGate::CacheAccountHealth);
+    log_if_changed(&group, ix_gate, IxGate::RecoverFromBeingLiquidated);
+    log_if_changed(&group, ix_gate, IxGate::AccountSetDelegatePermissions);
+    log_if_changed(&group, ix_gate, IxGate::GroupStatus);
+    log_if_changed(&group, ix_gate, IxGate::PerpExplainLiquidation);
+    log_if_changed(&group, ix_gate, IxGate::AccountCompactTokenPositions);
 
     group.ix_gate = ix_gate;
 
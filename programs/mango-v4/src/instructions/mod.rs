@@ -1,8 +1,12 @@
 pub use account_buyback_fees_with_mngo::*;
 pub use account_close::*;
+pub use account_compact_token_positions::*;
 pub use account_create::*;
 pub use account_edit::*;
 pub use account_expand::*;
+pub use account_set_delegate_permissions::*;
+pub use account_set_owner::*;
+pub use account_set_token_allowlist::*;
 pub use account_size_migration::*;
 pub use account_toggle_freeze::*;
 pub use admin_perp_withdraw_fees::*;
@@ -10,16 +14,22 @@ pub use admin_token_withdraw_fees::*;
 pub use alt_extend::*;
 pub use alt_set::*;
 pub use benchmark::*;
+pub use cache_account_health::*;
 pub use compute_account_data::*;
+pub use compute_account_health::*;
 pub use flash_loan::*;
+pub use group_accept_admin::*;
 pub use group_change_insurance_fund::*;
 pub use group_close::*;
 pub use group_create::*;
 pub use group_edit::*;
+pub use group_set_pending_admin::*;
+pub use group_status::*;
 pub use group_withdraw_insurance_fund::*;
 pub use health_check::*;
 pub use health_region::*;
 pub use ix_gate_set::*;
+pub use perp_adl::*;
 pub use perp_cancel_all_orders::*;
 pub use perp_cancel_all_orders_by_side::*;
 pub use perp_cancel_order::*;
@@ -34,9 +44,11 @@ pub use perp_liq_base_or_positive_pnl::*;
 pub use perp_liq_force_cancel_orders::*;
 pub use perp_liq_negative_pnl_or_bankruptcy::*;
 pub use perp_place_order::*;
+pub use perp_settle_all_funding::*;
 pub use perp_settle_fees::*;
 pub use perp_settle_pnl::*;
 pub use perp_update_funding::*;
+pub use recover_from_being_liquidated::*;
 pub use sequence_check::*;
 pub use serum3_cancel_all_orders::*;
 pub use serum3_cancel_order::*;
@@ -63,6 +75,7 @@ pub use token_deregister::*;
 pub use token_edit::*;
 pub use token_force_close_borrows_with_token::*;
 pub use token_force_withdraw::*;
+pub use token_liq_absorb_dust::*;
 pub use token_liq_bankruptcy::*;
 pub use token_liq_with_token::*;
 pub use token_register::*;
@@ -72,9 +85,13 @@ pub use token_withdraw::*;
 
 mod account_buyback_fees_with_mngo;
 mod account_close;
+mod account_compact_token_positions;
 mod account_create;
 mod account_edit;
 mod account_expand;
+mod account_set_delegate_permissions;
+mod account_set_owner;
+mod account_set_token_allowlist;
 mod account_size_migration;
 mod account_toggle_freeze;
 mod admin_perp_withdraw_fees;
@@ -82,16 +99,22 @@ mod admin_token_withdraw_fees;
 mod alt_extend;
 mod alt_set;
 mod benchmark;
+mod cache_account_health;
 mod compute_account_data;
+mod compute_account_health;
 mod flash_loan;
+mod group_accept_admin;
 mod group_change_insurance_fund;
 mod group_close;
 mod group_create;
 mod group_edit;
+mod group_set_pending_admin;
+mod group_status;
 mod group_withdraw_insurance_fund;
 mod health_check;
 mod health_region;
 mod ix_gate_set;
+mod perp_adl;
 mod perp_cancel_all_orders;
 mod perp_cancel_all_orders_by_side;
 mod perp_cancel_order;
@@ -106,9 +129,11 @@ mod perp_liq_base_or_positive_pnl;
 mod perp_liq_force_cancel_orders;
 mod perp_liq_negative_pnl_or_bankruptcy;
 mod perp_place_order;
+mod perp_settle_all_funding;
 mod perp_settle_fees;
 mod perp_settle_pnl;
 mod perp_update_funding;
+mod recover_from_being_liquidated;
 mod sequence_check;
 mod serum3_cancel_all_orders;
 mod serum3_cancel_order;
@@ -135,6 +160,7 @@ mod token_deregister;
 mod token_edit;
 mod token_force_close_borrows_with_token;
 mod token_force_withdraw;
+mod token_liq_absorb_dust;
 mod token_liq_bankruptcy;
 mod token_liq_with_token;
 mod token_register;
@@ -0,0 +1,276 @@
+use anchor_lang::prelude::*;
+
+use fixed::types::I80F48;
+
+use crate::accounts_ix::*;
+use crate::accounts_zerocopy::AccountInfoRef;
+use crate::error::*;
+use crate::health::*;
+use crate::logs::{emit_perp_balances, emit_stack, PerpAdlCounterpartyLog, PerpAdlLog};
+use crate::state::*;
+
+/// Auto-deleveraging: the last resort for perp losses that the insurance fund couldn't cover.
+///
+/// On markets with adl enabled, perp_liq_negative_pnl_or_bankruptcy doesn't socialize the
+/// remaining loss and leaves it on the liqee. This takes it from the passed counterparties
+/// instead, in order:
+/// - while the liqee still has a base position in the market, counterparties on the opposite
+///   side close their base position against it at the oracle price
+/// - once the liqee's base position is closed, each counterparty gives up pnl (valued at the
+///   oracle price) until the deficit is gone
+///
+/// Every counterparty must keep non-negative maint health.
+pub fn perp_adl(ctx: Context<PerpAdl>, num_counterparties: u8) -> Result<()> {
+    let mango_group = ctx.accounts.group.key();
+    let liqee_key = ctx.accounts.liqee.key();
+
+    let now_slot = Clock::get()?.slot;
+    let now_ts = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+    let num_counterparties = num_counterparties as usize;
+    require_gte!(ctx.remaining_accounts.len(), num_counterparties);
+    let (health_ais, counterparty_ais) = ctx
+        .remaining_accounts
+        .split_at(ctx.remaining_accounts.len() - num_counterparties);
+
+    let mut liqee = ctx.accounts.liqee.load_full_mut()?;
+
+    // The health accounts cover the liqee and all counterparties. The caches are built
+    // before the perp market is borrowed mutably.
+    let (mut liqee_health_cache, mut counterparty_health_caches) = {
+        let retriever = ScanningAccountRetriever::new(health_ais, &mango_group)
+            .context("create account retriever")?;
+        let liqee_health_cache = new_health_cache(&liqee.borrow(), &retriever, now_ts)?;
+        let counterparty_health_caches = counterparty_ais
+            .iter()
+            .map(|ai| {
+                let loader: AccountLoader<MangoAccountFixed> = AccountLoader::try_from(ai)?;
+                let counterparty = loader.load_full()?;
+                require_keys_eq!(counterparty.fixed.group, mango_group);
+                new_health_cache(&counterparty.borrow(), &retriever, now_ts)
+                    .with_context(|| format!("create health cache for counterparty {}", ai.key))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        (liqee_health_cache, counterparty_health_caches)
+    };
+
+    let mut perp_market = ctx.accounts.perp_market.load_mut()?;
+    let perp_market_index = perp_market.perp_market_index;
+    let settle_token_index = perp_market.settle_token_index;
+    let oracle_ref = &AccountInfoRef::borrow(ctx.accounts.oracle.as_ref())?;
+    let oracle_price = perp_market.oracle_price(
+        &OracleAccountInfos::from_reader(oracle_ref),
+        Some((now_ts, now_slot)),
+    )?;
+    let price_per_lot = I80F48::from(perp_market.base_lot_size) * oracle_price;
+
+    // Like socializing the loss in perp_liq_negative_pnl_or_bankruptcy, except that the
+    // liqee may still have a base position in this market: it gets closed against the
+    // counterparties.
+    liqee_health_cache.require_after_phase1_liquidation()?;
+    require!(
+        !liqee_health_cache.has_possible_spot_liquidations(),
+        MangoError::HasLiquidatableTokenPosition
+    );
+    require!(
+        liqee_health_cache
+            .perp_infos
+            .iter()
+            .all(|p| p.base_lots == 0 || p.perp_market_index == perp_market_index),
+        MangoError::HasLiquidatablePerpBasePosition
+    );
+    require!(
+        !liqee_health_cache.has_perp_open_fills(),
+        MangoError::HasOpenPerpTakerFills
+    );
+    require!(
+        !liqee_health_cache.has_perp_positive_pnl_no_base(),
+        MangoError::HasLiquidatablePositivePerpPnl
+    );
+    require!(
+        liqee.check_liquidatable(&liqee_health_cache)? == CheckLiquidatable::Liquidatable,
+        MangoError::HealthMustBeNegative
+    );
+    require_msg!(
+        !perp_market.elligible_for_group_insurance_fund()
+            || ctx.accounts.insurance_vault.amount == 0,
+        "insurance fund must be exhausted before adl"
+    );
+
+    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+    liqee_perp_position.settle_funding(&perp_market);
+    liqee_perp_position.update_settle_limit(&perp_market, now_ts);
+
+    // Only known once the liqee's base position is closed
+    let mut deficit: Option<I80F48> = None;
+    let mut remaining_deficit = I80F48::ZERO;
+    let mut previous_pnl = I80F48::MAX;
+    let mut num_adl = 0;
+    for (i, (ai, counterparty_health_cache)) in counterparty_ais
+        .iter()
+        .zip(counterparty_health_caches.iter_mut())
+        .enumerate()
+    {
+        if deficit.is_some() && remaining_deficit <= 0 {
+            break;
+        }
+
+        require_keys_neq!(*ai.key, liqee_key);
+        require_msg!(
+            !counterparty_ais[..i]
+                .iter()
+                .any(|other| other.key == ai.key),
+            "counterparty {} was passed twice",
+            ai.key
+        );
+        let loader: AccountLoader<MangoAccountFixed> = AccountLoader::try_from(ai)?;
+        let mut counterparty = loader.load_full_mut()?;
+        require!(
+            counterparty.fixed.is_operational(),
+            MangoError::AccountIsFrozen
+        );
+
+        let counterparty_perp_position = counterparty.perp_position_mut(perp_market_index)?;
+        counterparty_perp_position.settle_funding(&perp_market);
+        let counterparty_pnl =
+            counterparty_perp_position.unsettled_pnl(&perp_market, oracle_price)?;
+        require_msg!(
+            counterparty_pnl > 0,
+            "counterparty {} has no positive pnl",
+            ai.key
+        );
+        require_msg!(
+            counterparty_pnl <= previous_pnl,
+            "counterparty {} is not ranked by descending pnl",
+            ai.key
+        );
+        previous_pnl = counterparty_pnl;
+
+        // Close opposite base positions against each other at the oracle price
+        let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+        let liqee_base_lots = liqee_perp_position.base_position_lots();
+        let counterparty_base_lots = counterparty_perp_position.base_position_lots();
+        let base_closed = if liqee_base_lots.signum() * counterparty_base_lots.signum() < 0 {
+            -counterparty_base_lots.signum()
+                * liqee_base_lots.abs().min(counterparty_base_lots.abs())
+        } else {
+            0
+        };
+        if base_closed != 0 {
+            let quote_change = -I80F48::from(base_closed) * price_per_lot;
+            counterparty_perp_position.record_trade(&mut perp_market, base_closed, quote_change);
+            liqee_perp_position.record_trade(&mut perp_market, -base_closed, -quote_change);
+        }
+
+        if deficit.is_none() && liqee_perp_position.base_position_lots() == 0 {
+            liqee_health_cache.recompute_perp_info(liqee_perp_position, &perp_market)?;
+            let d = adl_deficit(
+                &liqee_health_cache,
+                liqee_perp_position,
+                &perp_market,
+                settle_token_index,
+                oracle_price,
+            )?;
+            deficit = Some(d);
+            remaining_deficit = d;
+        }
+
+        let pnl_absorbed = if deficit.is_some() {
+            counterparty_pnl.min(remaining_deficit).max(I80F48::ZERO)
+        } else {
+            I80F48::ZERO
+        };
+        if pnl_absorbed > 0 {
+            counterparty_perp_position.record_adl(-pnl_absorbed);
+            liqee_perp_position.record_settle(-pnl_absorbed, &perp_market);
+            remaining_deficit -= pnl_absorbed;
+        }
+
+        // The adl must not make the counterparty liquidatable
+        counterparty_health_cache.recompute_perp_info(counterparty_perp_position, &perp_market)?;
+        let counterparty_maint_health = counterparty_health_cache.health(HealthType::Maint);
+        require_msg_typed!(
+            counterparty_maint_health >= 0,
+            MangoError::HealthMustBePositive,
+            "counterparty {} maint health would be {}",
+            ai.key,
+            counterparty_maint_health
+        );
+
+        emit_perp_balances(
+            mango_group,
+            *ai.key,
+            counterparty_perp_position,
+            &perp_market,
+        );
+        emit_stack(PerpAdlCounterpartyLog {
+            mango_group,
+            perp_market_index,
+            liqee: liqee_key,
+            counterparty: *ai.key,
+            base_closed,
+            pnl_absorbed: pnl_absorbed.to_bits(),
+        });
+        num_adl += 1;
+    }
+
+    let liqee_perp_position = liqee.perp_position(perp_market_index)?;
+    emit_perp_balances(mango_group, liqee_key, liqee_perp_position, &perp_market);
+
+    emit_stack(PerpAdlLog {
+        mango_group,
+        perp_market_index,
+        liqee: liqee_key,
+        oracle_price: oracle_price.to_bits(),
+        liqee_base_lots_remaining: liqee_perp_position.base_position_lots(),
+        deficit: deficit.unwrap_or(I80F48::ZERO).to_bits(),
+        remaining_deficit: remaining_deficit.to_bits(),
+        num_counterparties: num_adl,
+    });
+
+    // Covering the deficit improves health
+    liqee_health_cache.recompute_perp_info(liqee_perp_position, &perp_market)?;
+    let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+    liqee
+        .fixed
+        .maybe_recover_from_being_liquidated(liqee_liq_end_health);
+
+    Ok(())
+}
+
+/// The loss that brings the liqee below zero liquidation end health, computed like in
+/// perp_liq_negative_pnl_or_bankruptcy
+///
+/// The liqee's base position must be closed and `liqee_health_cache` up to date with it.
+fn adl_deficit(
+    liqee_health_cache: &HealthCache,
+    liqee_perp_position: &PerpPosition,
+    perp_market: &PerpMarket,
+    settle_token_index: TokenIndex,
+    oracle_price: I80F48,
+) -> Result<I80F48> {
+    let liqee_max_settle = liqee_health_cache.perp_max_settle(settle_token_index)?;
+    let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+    let settle_info_index = liqee_health_cache.token_info_index(settle_token_index)?;
+    let settle_info = &liqee_health_cache.token_infos[settle_info_index];
+    let liqee_settle_token_balance = liqee_health_cache
+        .effective_token_balances(HealthType::LiquidationEnd)[settle_info_index]
+        .spot_and_perp;
+    let max_for_health = spot_amount_given_for_health_zero(
+        liqee_liq_end_health,
+        liqee_settle_token_balance,
+        settle_info.prices.oracle * settle_info.init_asset_weight,
+        settle_info.prices.oracle * settle_info.init_liab_weight,
+    )?;
+
+    let liqee_pnl = liqee_perp_position.unsettled_pnl(perp_market, oracle_price)?;
+    require_gt!(0, liqee_pnl, MangoError::ProfitabilityMismatch);
+
+    let liqee_settleable_pnl = liqee_perp_position.apply_pnl_settle_limit(perp_market, liqee_pnl);
+    require_msg!(
+        liqee_max_settle.min(-liqee_settleable_pnl) <= 0,
+        "liqee can still settle negative pnl"
+    );
+
+    Ok((-liqee_pnl).min(max_for_health).max(I80F48::ZERO))
+}
@@ -95,7 +95,34 @@ pub fn perp_create_market(
         fees_withdrawn: 0,
         platform_liquidation_fee: I80F48::from_num(platform_liquidation_fee),
         accrued_liquidation_fees: I80F48::ZERO,
-        reserved: [0; 1848],
+        liquidation_price_tolerance: 0.0,
+        use_ema_for_trigger: 0,
+        padding5: Default::default(),
+        ema_price: 0.0,
+        ema_last_updated: 0,
+        ema_interval_seconds: 0,
+        padding6: Default::default(),
+        large_position_threshold_lots: 0,
+        large_position_whitelist: [Pubkey::default(); 4],
+        dust_threshold_lots: 0,
+        dust_liquidation_fee: I80F48::ZERO,
+        accrued_settle_fees: I80F48::ZERO,
+        settle_fee_bps: 0,
+        padding7: Default::default(),
+        max_liquidation_base_per_slot: 0,
+        liquidation_base_in_slot: 0,
+        liquidation_base_slot: 0,
+        liquidation_throttle_bypass_health: 0.0,
+        liquidation_fee_insurance_fraction: 0.0,
+        adl_enabled: 0,
+        padding8: Default::default(),
+        underlying_id: 0,
+        min_liquidation_base_lots: 0,
+        liquidation_quote_rounding_buffer: 0.0,
+        alt_settle_token_index: 0,
+        alt_settle_token_enabled: 0,
+        padding9: Default::default(),
+        reserved: [0; 1568],
     };
 
     let oracle_ref = &AccountInfoRef::borrow(ctx.accounts.oracle.as_ref())?;
@@ -40,6 +40,23 @@ pub fn perp_edit_market(
     name_opt: Option<String>,
     force_close_opt: Option<bool>,
     platform_liquidation_fee_opt: Option<f32>,
+    liquidation_price_tolerance_opt: Option<f32>,
+    base_liquidation_fee_bps_opt: Option<u32>,
+    use_ema_for_trigger_opt: Option<bool>,
+    ema_interval_seconds_opt: Option<u32>,
+    large_position_threshold_lots_opt: Option<i64>,
+    large_position_whitelist_opt: Option<Vec<Pubkey>>,
+    dust_threshold_lots_opt: Option<i64>,
+    dust_liquidation_fee_opt: Option<f32>,
+    settle_fee_bps_opt: Option<u32>,
+    max_liquidation_base_per_slot_opt: Option<i64>,
+    liquidation_throttle_bypass_health_opt: Option<f64>,
+    liquidation_fee_insurance_fraction_opt: Option<f32>,
+    adl_enabled_opt: Option<bool>,
+    underlying_id_opt: Option<u16>,
+    min_liquidation_base_lots_opt: Option<i64>,
+    liquidation_quote_rounding_buffer_opt: Option<f64>,
+    alt_settle_token_index_opt: Option<TokenIndex>,
 ) -> Result<()> {
     let group = ctx.accounts.group.load()?;
 
@@ -141,7 +158,24 @@ pub fn perp_edit_market(
             require_group_admin = true;
         }
     }
+    require!(
+        base_liquidation_fee_opt.is_none() || base_liquidation_fee_bps_opt.is_none(),
+        MangoError::SomeError
+    );
+    if let Some(base_liquidation_fee_bps) = base_liquidation_fee_bps_opt {
+        require_gte!(10_000, base_liquidation_fee_bps);
+        msg!(
+            "Base liquidation fee bps: old - {:?}, new - {:?}",
+            perp_market.liquidation_fee_bps(),
+            base_liquidation_fee_bps
+        );
+        perp_market.base_liquidation_fee =
+            PerpMarket::liquidation_fee_from_bps(base_liquidation_fee_bps);
+        require_group_admin = true;
+    }
     if let Some(base_liquidation_fee) = base_liquidation_fee_opt {
+        require_gte!(base_liquidation_fee, 0.0);
+        require_gte!(1.0, base_liquidation_fee);
         msg!(
             "Base liquidation fee: old - {:?}, new - {:?}",
             perp_market.base_liquidation_fee,
@@ -356,6 +390,191 @@ pub fn perp_edit_market(
         require_group_admin = true;
     };
 
+    if let Some(liquidation_price_tolerance) = liquidation_price_tolerance_opt {
+        require_gte!(liquidation_price_tolerance, 0.0);
+        msg!(
+            "Liquidation price tolerance: old - {:?}, new - {:?}",
+            perp_market.liquidation_price_tolerance,
+            liquidation_price_tolerance
+        );
+        perp_market.liquidation_price_tolerance = liquidation_price_tolerance;
+        require_group_admin = true;
+    };
+
+    if let Some(use_ema_for_trigger) = use_ema_for_trigger_opt {
+        msg!(
+            "Use ema for trigger: old - {:?}, new - {:?}",
+            perp_market.use_ema_for_trigger,
+            u8::from(use_ema_for_trigger)
+        );
+        perp_market.use_ema_for_trigger = u8::from(use_ema_for_trigger);
+        require_group_admin = true;
+    };
+
+    if let Some(ema_interval_seconds) = ema_interval_seconds_opt {
+        msg!(
+            "Ema interval seconds: old - {:?}, new - {:?}",
+            perp_market.ema_interval_seconds,
+            ema_interval_seconds
+        );
+        perp_market.ema_interval_seconds = ema_interval_seconds;
+        require_group_admin = true;
+    };
+
+    if let Some(large_position_threshold_lots) = large_position_threshold_lots_opt {
+        require_gte!(large_position_threshold_lots, 0);
+        msg!(
+            "Large position threshold lots: old - {:?}, new - {:?}",
+            perp_market.large_position_threshold_lots,
+            large_position_threshold_lots
+        );
+        perp_market.large_position_threshold_lots = large_position_threshold_lots;
+        require_group_admin = true;
+    };
+
+    if let Some(large_position_whitelist) = large_position_whitelist_opt {
+        let max_len = perp_market.large_position_whitelist.len();
+        require_msg!(
+            large_position_whitelist.len() <= max_len,
+            "large position whitelist can have at most {} entries",
+            max_len
+        );
+        let mut new_whitelist = [Pubkey::default(); 4];
+        new_whitelist[..large_position_whitelist.len()].copy_from_slice(&large_position_whitelist);
+        msg!(
+            "Large position whitelist: old - {:?}, new - {:?}",
+            perp_market.large_position_whitelist,
+            new_whitelist
+        );
+        perp_market.large_position_whitelist = new_whitelist;
+        require_group_admin = true;
+    };
+
+    if let Some(dust_threshold_lots) = dust_threshold_lots_opt {
+        require_gte!(dust_threshold_lots, 0);
+        msg!(
+            "Dust threshold lots: old - {:?}, new - {:?}",
+            perp_market.dust_threshold_lots,
+            dust_threshold_lots
+        );
+        perp_market.dust_threshold_lots = dust_threshold_lots;
+        require_group_admin = true;
+    };
+
+    if let Some(dust_liquidation_fee) = dust_liquidation_fee_opt {
+        require_gte!(dust_liquidation_fee, 0.0);
+        msg!(
+            "Dust liquidation fee: old - {:?}, new - {:?}",
+            perp_market.dust_liquidation_fee,
+            dust_liquidation_fee
+        );
+        perp_market.dust_liquidation_fee = I80F48::from_num(dust_liquidation_fee);
+        require_group_admin = true;
+    };
+
+    if let Some(settle_fee_bps) = settle_fee_bps_opt {
+        require_gte!(10_000, settle_fee_bps);
+        msg!(
+            "Settle fee bps: old - {:?}, new - {:?}",
+            perp_market.settle_fee_bps,
+            settle_fee_bps
+        );
+        perp_market.settle_fee_bps = settle_fee_bps;
+        require_group_admin = true;
+    };
+
+    if let Some(max_liquidation_base_per_slot) = max_liquidation_base_per_slot_opt {
+        require_gte!(max_liquidation_base_per_slot, 0);
+        msg!(
+            "Max liquidation base per slot: old - {:?}, new - {:?}",
+            perp_market.max_liquidation_base_per_slot,
+            max_liquidation_base_per_slot
+        );
+        perp_market.max_liquidation_base_per_slot = max_liquidation_base_per_slot;
+        require_group_admin = true;
+    };
+
+    if let Some(liquidation_throttle_bypass_health) = liquidation_throttle_bypass_health_opt {
+        require_gte!(liquidation_throttle_bypass_health, 0.0);
+        msg!(
+            "Liquidation throttle bypass health: old - {:?}, new - {:?}",
+            perp_market.liquidation_throttle_bypass_health,
+            liquidation_throttle_bypass_health
+        );
+        perp_market.liquidation_throttle_bypass_health = liquidation_throttle_bypass_health;
+        require_group_admin = true;
+    };
+
+    if let Some(liquidation_fee_insurance_fraction) = liquidation_fee_insurance_fraction_opt {
+        require_gte!(liquidation_fee_insurance_fraction, 0.0);
+        require_gte!(1.0, liquidation_fee_insurance_fraction);
+        msg!(
+            "Liquidation fee insurance fraction: old - {:?}, new - {:?}",
+            perp_market.liquidation_fee_insurance_fraction,
+            liquidation_fee_insurance_fraction
+        );
+        perp_market.liquidation_fee_insurance_fraction = liquidation_fee_insurance_fraction;
+        require_group_admin = true;
+    };
+
+    if let Some(adl_enabled) = adl_enabled_opt {
+        msg!(
+            "Adl enabled: old - {:?}, new - {:?}",
+            perp_market.adl_enabled,
+            u8::from(adl_enabled)
+        );
+        perp_market.adl_enabled = u8::from(adl_enabled);
+        require_group_admin = true;
+    };
+
+    if let Some(underlying_id) = underlying_id_opt {
+        msg!(
+            "Underlying id: old - {:?}, new - {:?}",
+            perp_market.underlying_id,
+            underlying_id
+        );
+        perp_market.underlying_id = underlying_id;
+        require_group_admin = true;
+    };
+
+    if let Some(min_liquidation_base_lots) = min_liquidation_base_lots_opt {
+        require_gte!(min_liquidation_base_lots, 0);
+        msg!(
+            "Min liquidation base lots: old - {:?}, new - {:?}",
+            perp_market.min_liquidation_base_lots,
+            min_liquidation_base_lots
+        );
+        perp_market.min_liquidation_base_lots = min_liquidation_base_lots;
+        require_group_admin = true;
+    };
+
+    if let Some(liquidation_quote_rounding_buffer) = liquidation_quote_rounding_buffer_opt {
+        require_gte!(liquidation_quote_rounding_buffer, 0.0);
+        msg!(
+            "Liquidation quote rounding buffer: old - {:?}, new - {:?}",
+            perp_market.liquidation_quote_rounding_buffer,
+            liquidation_quote_rounding_buffer
+        );
+        perp_market.liquidation_quote_rounding_buffer = liquidation_quote_rounding_buffer;
+        require_group_admin = true;
+    };
+
+    if let Some(alt_settle_token_index) = alt_settle_token_index_opt {
+        let enabled = alt_settle_token_index != TokenIndex::MAX;
+        require_msg!(
+            !enabled || alt_settle_token_index != perp_market.settle_token_index,
+            "the alternative settle token must differ from the settle token"
+        );
+        msg!(
+            "Alt settle token index: old - {:?}, new - {:?}",
+            perp_market.alt_settle_token_index(),
+            enabled.then_some(alt_settle_token_index)
+        );
+        perp_market.alt_settle_token_index = if enabled { alt_settle_token_index } else { 0 };
+        perp_market.alt_settle_token_enabled = u8::from(enabled);
+        require_group_admin = true;
+    };
+
     // account constraint #1
     if require_group_admin {
         require!(
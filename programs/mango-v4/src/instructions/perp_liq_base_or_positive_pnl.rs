@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token;
 
 use fixed::types::I80F48;
 
@@ -8,7 +10,11 @@ use crate::health::*;
 use crate::state::*;
 
 use crate::accounts_ix::*;
-use crate::logs::{emit_perp_balances, emit_stack, PerpLiqBaseOrPositivePnlLogV3, TokenBalanceLog};
+use crate::events::PerpLiquidationExplanation;
+use crate::logs::{
+    emit_perp_balances, emit_stack, GroupRiskDeltaLog, PerpLiqBadDebtInsuranceLog,
+    PerpLiqBaseOrPositivePnlLogV4, PerpLiqHealthLog, PerpLiqPnlTakeoverTokenLog, TokenBalanceLog,
+};
 
 /// This instruction deals with increasing health by:
 /// - reducing the liqee's base position
@@ -20,76 +26,155 @@ use crate::logs::{emit_perp_balances, emit_stack, PerpLiqBaseOrPositivePnlLogV3,
 ///
 /// Taking over pnl while health_unsettled_pnl() is negative never increases liqee health.
 /// That's why it's relegated to the separate liq_negative_pnl_or_bankruptcy instruction instead.
-pub fn perp_liq_base_or_positive_pnl(
-    ctx: Context<PerpLiqBaseOrPositivePnl>,
+///
+/// The liqor may pass `liqor_price_opt` to have the base transfer happen at their own price,
+/// for example for index markets. It must be within the market's liquidation_price_tolerance
+/// of the oracle price.
+///
+/// If `min_net_quote_profit_opt` is set, the instruction fails unless the liqor's liquidation
+/// reward (in native settle token) minus `liquidation_cost` is at least that amount.
+///
+/// With `max_health_improvement_opt` the liqee's LiquidationEnd health rises by at most that
+/// much (in native quote) in this call. This lets several liqors split a large liquidation;
+/// the liqee stays being_liquidated until the last part is done.
+///
+/// To liquidate several perp markets atomically, put one instruction per market into the
+/// same transaction. Each one computes the liqee's health from the current account state,
+/// so it accounts for the health recovered by the earlier ones, and it does nothing once
+/// the liqee is no longer liquidatable.
+///
+/// Similarly, a liqor that needs more collateral to absorb the liquidation can token_deposit
+/// into its own account earlier in the same transaction. The liqor's health is only checked
+/// at the end of this instruction, and if the liquidation fails the deposit is reverted too.
+///
+/// With `liqor_quote_token_index_opt` set to the market's alternative settle token, the liqor
+/// pays for taken over positive pnl in that token instead, see pay_pnl_takeover_in_token().
+#[allow(clippy::too_many_arguments)]
+pub fn perp_liq_base_or_positive_pnl<'info>(
+    accounts: &PerpLiqBaseOrPositivePnl<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
     mut max_base_transfer: i64,
     max_pnl_transfer: u64,
+    liqor_price_opt: Option<f64>,
+    min_net_quote_profit_opt: Option<i64>,
+    liquidation_cost: u64,
+    max_health_improvement_opt: Option<u64>,
+    liqor_quote_token_index_opt: Option<TokenIndex>,
 ) -> Result<()> {
     // Ensure max_base_transfer can be negated
     max_base_transfer = max_base_transfer.max(i64::MIN + 1);
 
-    let group_pk = &ctx.accounts.group.key();
+    let group_pk = &accounts.group.key();
     let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
 
-    require_keys_neq!(ctx.accounts.liqor.key(), ctx.accounts.liqee.key());
-    let mut liqor = ctx.accounts.liqor.load_full_mut()?;
+    require_keys_neq!(accounts.liqor.key(), accounts.liqee.key());
+    let mut liqor = accounts.liqor.load_full_mut()?;
     // account constraint #1
     require!(
         liqor
             .fixed
-            .is_owner_or_delegate(ctx.accounts.liqor_owner.key()),
-        MangoError::SomeError
-    );
-    require_msg_typed!(
-        !liqor.fixed.being_liquidated(),
-        MangoError::BeingLiquidated,
-        "liqor account"
+            .has_permission(accounts.liqor_owner.key(), DelegatePermission::Liquidate),
+        MangoError::UnauthorizedLiqor
     );
 
-    let mut liqee = ctx.accounts.liqee.load_full_mut()?;
+    let mut liqee = accounts.liqee.load_full_mut()?;
+
+    // The liqee's position for the token the liqor pays in must be part of its health cache,
+    // so it's opened before the health computation. It's closed again if nothing was paid.
+    let liqor_quote_token_opt = match liqor_quote_token_index_opt {
+        Some(token_index) => {
+            let perp_market = accounts.perp_market.load()?;
+            if token_index == perp_market.settle_token_index {
+                None
+            } else {
+                require_msg!(
+                    perp_market.alt_settle_token_index() == Some(token_index),
+                    "token index {} is not an alternative settle token of perp market {}",
+                    token_index,
+                    perp_market.perp_market_index
+                );
+                let opened = liqee.token_position(token_index).is_err();
+                let raw_index = liqee.ensure_token_position(token_index)?.1;
+                Some(LiqorQuoteToken {
+                    token_index,
+                    liqee_raw_index: raw_index,
+                    liqee_position_opened: opened,
+                })
+            }
+        }
+        None => None,
+    };
 
     // Initial liqee health check
-    let mut liqee_health_cache = {
-        let account_retriever = ScanningAccountRetriever::new(ctx.remaining_accounts, group_pk)
+    //
+    // The parsed oracle states are kept for the liqor health check at the end.
+    let (mut liqee_health_cache, oracle_cache) = {
+        let account_retriever = ScanningAccountRetriever::new(remaining_accounts, group_pk)
             .context("create account retriever")?;
-        new_health_cache(&liqee.borrow(), &account_retriever, now_ts)
-            .context("create liqee health cache")?
+        let health_cache = new_health_cache(&liqee.borrow(), &account_retriever, now_ts)
+            .context("create liqee health cache")?;
+        (health_cache, account_retriever.into_oracle_cache())
     };
     let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+    let liqee_maint_health = liqee_health_cache.health(HealthType::Maint);
+    let liqee_init_health = liqee_health_cache.health(HealthType::Init);
     liqee_health_cache.require_after_phase1_liquidation()?;
 
     if liqee.check_liquidatable(&liqee_health_cache)? != CheckLiquidatable::Liquidatable {
+        deactivate_unused_quote_token_position(
+            &mut liqee.borrow_mut(),
+            liqor_quote_token_opt.as_ref(),
+            accounts.liqee.key(),
+        )?;
         return Ok(());
     }
 
-    let mut perp_market = ctx.accounts.perp_market.load_mut()?;
+    let group = accounts.group.load()?;
+    let mut perp_market = accounts.perp_market.load_mut()?;
     let perp_market_index = perp_market.perp_market_index;
     let settle_token_index = perp_market.settle_token_index;
 
-    let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
+    emit_stack(PerpLiqHealthLog {
+        mango_group: accounts.group.key(),
+        perp_market_index,
+        liqor: accounts.liqor.key(),
+        liqee: accounts.liqee.key(),
+        init_health: liqee_init_health.to_bits(),
+        maint_health: liqee_maint_health.to_bits(),
+        liq_end_health: liqee_liq_end_health.to_bits(),
+    });
+
+    let mut settle_bank = accounts.settle_bank.load_mut()?;
 
     // Get oracle price for market. Price is validated inside
-    let oracle_ref = &AccountInfoRef::borrow(ctx.accounts.oracle.as_ref())?;
+    let oracle_ref = &AccountInfoRef::borrow(accounts.oracle.as_ref())?;
     let oracle_price = perp_market.oracle_price(
         &OracleAccountInfos::from_reader(oracle_ref),
         None, // checked in health
     )?;
 
-    // Fetch perp positions for accounts, creating for the liqor if needed
-    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
-    require!(
-        !liqee_perp_position.has_open_taker_fills(),
-        MangoError::HasOpenPerpTakerFills
-    );
-
-    let liqor_perp_position = liqor
-        .ensure_perp_position(perp_market_index, perp_market.settle_token_index)?
-        .0;
+    let params = liquidation_setup(
+        &group,
+        &perp_market,
+        &mut liqor.borrow_mut(),
+        &mut liqee.borrow_mut(),
+        remaining_accounts,
+        now_ts,
+        liqor_price_opt,
+        min_net_quote_profit_opt,
+        liquidation_cost,
+        max_health_improvement_opt,
+    )?;
+    let liquidation_price = params.liqor_price_opt.unwrap_or(oracle_price);
+    let liqor_base_lots_before = liqor.perp_position(perp_market_index)?.base_position_lots();
+    let open_interest_before = perp_market.open_interest;
 
-    // Settle funding, update limit
-    liqee_perp_position.settle_funding(&perp_market);
-    liqor_perp_position.settle_funding(&perp_market);
-    liqee_perp_position.update_settle_limit(&perp_market, now_ts);
+    let liqee_state_hash_before =
+        logged_liquidation_state_hash(&liqee.borrow(), perp_market_index, settle_token_index)?;
+    let liqor_state_hash_before =
+        logged_liquidation_state_hash(&liqor.borrow(), perp_market_index, settle_token_index)?;
+    let liqee_equity_before =
+        liquidation_equity(&liqee.borrow(), &perp_market, &settle_bank, oracle_price)?;
 
     //
     // Perform the liquidation
@@ -104,6 +189,7 @@ pub fn perp_liq_base_or_positive_pnl(
         pnl_settle_limit_transfer_oneshot,
     ) = liquidation_action(
         &mut perp_market,
+        params.base_liquidation_fee,
         &mut settle_bank,
         &mut liqor.borrow_mut(),
         &mut liqee.borrow_mut(),
@@ -112,6 +198,54 @@ pub fn perp_liq_base_or_positive_pnl(
         now_ts,
         max_base_transfer,
         max_pnl_transfer,
+        params.liqor_price_opt,
+        params.max_overshoot_health,
+        params.min_liqor_reward,
+        params.max_health_improvement,
+        params.platform_fee_waived,
+    )?;
+
+    if let Some(quote_token) = liqor_quote_token_opt.as_ref() {
+        if pnl_transfer > 0 {
+            pay_pnl_takeover_in_token(
+                accounts,
+                remaining_accounts,
+                &perp_market,
+                &mut settle_bank,
+                &mut liqor.borrow_mut(),
+                &mut liqee.borrow_mut(),
+                &mut liqee_health_cache,
+                quote_token.token_index,
+                pnl_transfer,
+                now_ts,
+            )?;
+        } else {
+            deactivate_unused_quote_token_position(
+                &mut liqee.borrow_mut(),
+                Some(quote_token),
+                accounts.liqee.key(),
+            )?;
+        }
+    }
+
+    let liqee_state_hash_after =
+        logged_liquidation_state_hash(&liqee.borrow(), perp_market_index, settle_token_index)?;
+    let liqor_state_hash_after =
+        logged_liquidation_state_hash(&liqor.borrow(), perp_market_index, settle_token_index)?;
+    let liqee_equity_after =
+        liquidation_equity(&liqee.borrow(), &perp_market, &settle_bank, oracle_price)?;
+
+    liquidation_checks(
+        &mut perp_market,
+        &accounts.liqor.key(),
+        &liqor.borrow(),
+        liqor_base_lots_before,
+        &mut liqee.borrow_mut(),
+        &liqee_health_cache,
+        liqee_liq_end_health,
+        liqee_maint_health,
+        base_transfer,
+        Clock::get()?.slot,
     )?;
 
     //
@@ -122,26 +256,32 @@ pub fn perp_liq_base_or_positive_pnl(
     let liqor_perp_position = liqor.perp_position_mut(perp_market_index)?;
 
     emit_perp_balances(
-        ctx.accounts.group.key(),
-        ctx.accounts.liqor.key(),
+        accounts.group.key(),
+        accounts.liqor.key(),
         liqor_perp_position,
         &perp_market,
     );
 
     emit_perp_balances(
-        ctx.accounts.group.key(),
-        ctx.accounts.liqee.key(),
+        accounts.group.key(),
+        accounts.liqee.key(),
         liqee_perp_position,
         &perp_market,
     );
 
+    // The liquidation is a trade at the liquidation price, updating entry and break even prices
+    let liqee_avg_entry_price = liqee_perp_position.avg_entry_price(&perp_market);
+    let liqee_break_even_price = liqee_perp_position.break_even_price(&perp_market);
+    let liqor_avg_entry_price = liqor_perp_position.avg_entry_price(&perp_market);
+    let liqor_break_even_price = liqor_perp_position.break_even_price(&perp_market);
+
     if pnl_transfer != 0 {
         let liqee_token_position = liqee.token_position(settle_token_index)?;
         let liqor_token_position = liqor.token_position(settle_token_index)?;
 
         emit_stack(TokenBalanceLog {
-            mango_group: ctx.accounts.group.key(),
-            mango_account: ctx.accounts.liqee.key(),
+            mango_group: accounts.group.key(),
+            mango_account: accounts.liqee.key(),
             token_index: settle_token_index,
             indexed_position: liqee_token_position.indexed_position.to_bits(),
             deposit_index: settle_bank.deposit_index.to_bits(),
@@ -149,8 +289,8 @@ pub fn perp_liq_base_or_positive_pnl(
         });
 
         emit_stack(TokenBalanceLog {
-            mango_group: ctx.accounts.group.key(),
-            mango_account: ctx.accounts.liqor.key(),
+            mango_group: accounts.group.key(),
+            mango_account: accounts.liqor.key(),
             token_index: settle_token_index,
             indexed_position: liqor_token_position.indexed_position.to_bits(),
             deposit_index: settle_bank.deposit_index.to_bits(),
@@ -158,634 +298,1659 @@ pub fn perp_liq_base_or_positive_pnl(
         });
     }
 
-    if base_transfer != 0 || pnl_transfer != 0 {
-        emit_stack(PerpLiqBaseOrPositivePnlLogV3 {
-            mango_group: ctx.accounts.group.key(),
-            perp_market_index: perp_market.perp_market_index,
-            liqor: ctx.accounts.liqor.key(),
-            liqee: ctx.accounts.liqee.key(),
-            base_transfer_liqee: base_transfer,
-            quote_transfer_liqee: quote_transfer_liqee.to_bits(),
-            quote_transfer_liqor: quote_transfer_liqor.to_bits(),
-            quote_platform_fee: platform_fee.to_bits(),
-            pnl_transfer: pnl_transfer.to_bits(),
-            pnl_settle_limit_transfer_recurring,
-            pnl_settle_limit_transfer_oneshot,
-            price: oracle_price.to_bits(),
+    if base_transfer != 0 {
+        emit_stack(GroupRiskDeltaLog {
+            mango_group: accounts.group.key(),
+            perp_market_index,
+            liqor: accounts.liqor.key(),
+            liqee: accounts.liqee.key(),
+            open_interest_before,
+            open_interest_after: perp_market.open_interest,
         });
     }
 
-    // Check liqee health again
-    let liqee_liq_end_health_after = liqee_health_cache.health(HealthType::LiquidationEnd);
-    liqee
-        .fixed
-        .maybe_recover_from_being_liquidated(liqee_liq_end_health_after);
-    require_gte!(liqee_liq_end_health_after, liqee_liq_end_health);
-    msg!(
-        "liqee liq end health: {} -> {}",
-        liqee_liq_end_health,
-        liqee_liq_end_health_after
-    );
+    // Logged even if nothing was transferred, so liquidation attempts can be tracked
+    emit_stack(PerpLiqBaseOrPositivePnlLogV4 {
+        mango_group: accounts.group.key(),
+        perp_market_index: perp_market.perp_market_index,
+        liqor: accounts.liqor.key(),
+        liqee: accounts.liqee.key(),
+        base_transfer_liqee: base_transfer,
+        quote_transfer_liqee: quote_transfer_liqee.to_bits(),
+        quote_transfer_liqor: quote_transfer_liqor.to_bits(),
+        quote_platform_fee: platform_fee.to_bits(),
+        pnl_transfer: pnl_transfer.to_bits(),
+        pnl_settle_limit_transfer_recurring,
+        pnl_settle_limit_transfer_oneshot,
+        price: liquidation_price.to_bits(),
+        liqee_state_hash_before,
+        liqee_state_hash_after,
+        liqor_state_hash_before,
+        liqor_state_hash_after,
+        liqee_avg_entry_price,
+        liqee_break_even_price,
+        liqor_avg_entry_price,
+        liqor_break_even_price,
+        liqee_liquidation_cost: liqee_liquidation_cost(liqee_equity_before, liqee_equity_after)
+            .to_bits(),
+        oracle_price: liqee_health_cache
+            .perp_info(perp_market_index)?
+            .base_prices
+            .oracle
+            .to_bits(),
+        liqee_init_health_before: liqee_init_health.to_bits(),
+        liqee_init_health_after: liqee_health_cache.health(HealthType::Init).to_bits(),
+    });
 
     drop(settle_bank);
     drop(perp_market);
 
-    // Check liqor's health
-    if !liqor.fixed.is_in_health_region() {
-        let account_retriever = ScanningAccountRetriever::new(ctx.remaining_accounts, group_pk)
-            .context("create account retriever end")?;
-        let liqor_health = compute_health(
-            &liqor.borrow(),
-            HealthType::Init,
-            &account_retriever,
-            now_ts,
-        )
-        .context("compute liqor health")?;
-        require!(liqor_health >= 0, MangoError::HealthMustBePositive);
+    check_liqor_health(
+        &group,
+        &accounts.liqor.key(),
+        &liqor.borrow(),
+        remaining_accounts,
+        oracle_cache,
+        now_ts,
+    )?;
+
+    Ok(())
+}
+
+/// Like perp_liq_base_or_positive_pnl, but with `settle_bad_debt_first` the group insurance
+/// fund first covers the liqee's bad debt in the market, see settle_bad_debt_from_insurance().
+///
+/// The liquidation afterwards computes the liqee's health from the updated account, so the
+/// base transfer is sized against the improved health.
+#[allow(clippy::too_many_arguments)]
+pub fn perp_liq_base_or_positive_pnl_v3<'info>(
+    ctx: Context<'_, '_, '_, 'info, PerpLiqBaseOrPositivePnlV3<'info>>,
+    max_base_transfer: i64,
+    max_pnl_transfer: u64,
+    liqor_price_opt: Option<f64>,
+    min_net_quote_profit_opt: Option<i64>,
+    liquidation_cost: u64,
+    max_health_improvement_opt: Option<u64>,
+    settle_bad_debt_first: bool,
+    liqor_quote_token_index: Option<TokenIndex>,
+) -> Result<()> {
+    if settle_bad_debt_first {
+        settle_bad_debt_from_insurance(ctx.accounts, ctx.remaining_accounts)?;
     }
 
+    perp_liq_base_or_positive_pnl(
+        &ctx.accounts.liq_base_or_positive_pnl_accounts(),
+        ctx.remaining_accounts,
+        max_base_transfer,
+        max_pnl_transfer,
+        liqor_price_opt,
+        min_net_quote_profit_opt,
+        liquidation_cost,
+        max_health_improvement_opt,
+        liqor_quote_token_index,
+    )
+}
+
+/// The token a liqor pays for taken over positive pnl in, if it's not the settle token
+struct LiqorQuoteToken {
+    token_index: TokenIndex,
+    liqee_raw_index: usize,
+    /// Whether the liqee's position was opened by this instruction
+    liqee_position_opened: bool,
+}
+
+fn deactivate_unused_quote_token_position(
+    liqee: &mut MangoAccountRefMut,
+    quote_token_opt: Option<&LiqorQuoteToken>,
+    liqee_key: Pubkey,
+) -> Result<()> {
+    let quote_token = match quote_token_opt {
+        Some(quote_token) if quote_token.liqee_position_opened => quote_token,
+        _ => return Ok(()),
+    };
+    let position = liqee.token_position_by_raw_index(quote_token.liqee_raw_index)?;
+    if position.indexed_position.is_zero() && position.in_use_count == 0 {
+        liqee.deactivate_token_position_and_log(quote_token.liqee_raw_index, liqee_key);
+    }
     Ok(())
 }
 
-pub(crate) fn liquidation_action(
-    perp_market: &mut PerpMarket,
+/// Changes the payment for taken over pnl that liquidation_action() made in the settle token
+/// into a payment of the same oracle value in `token_index`.
+///
+/// The liqor borrows the token if needed, the liqor health check at the end covers that.
+#[allow(clippy::too_many_arguments)]
+fn pay_pnl_takeover_in_token(
+    accounts: &PerpLiqBaseOrPositivePnl,
+    remaining_accounts: &[AccountInfo],
+    perp_market: &PerpMarket,
     settle_bank: &mut Bank,
     liqor: &mut MangoAccountRefMut,
     liqee: &mut MangoAccountRefMut,
     liqee_health_cache: &mut HealthCache,
-    liqee_liq_end_health: I80F48,
+    token_index: TokenIndex,
+    pnl_transfer: I80F48,
     now_ts: u64,
-    max_base_transfer: i64,
-    max_pnl_transfer: u64,
-) -> Result<(i64, I80F48, I80F48, I80F48, I80F48, i64, i64)> {
-    let liq_end_type = HealthType::LiquidationEnd;
-
-    let perp_market_index = perp_market.perp_market_index;
+) -> Result<()> {
+    let group_pk = accounts.group.key();
     let settle_token_index = perp_market.settle_token_index;
 
-    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
-    let liqor_perp_position = liqor.perp_position_mut(perp_market_index)?;
-
-    let token_balances = liqee_health_cache.effective_token_balances(liq_end_type);
-    let settle_token_balance = &token_balances[liqee_health_cache
-        .token_infos
+    // The settle bank is already borrowed and gets skipped here
+    let bank_ai = remaining_accounts
         .iter()
-        .position(|ti| ti.token_index == settle_token_index)
-        .unwrap()];
-    let settle_token_info = liqee_health_cache.token_info(settle_token_index).unwrap();
-    let perp_info = liqee_health_cache.perp_info(perp_market_index)?;
-    let settle_token_oracle_price = liqee_health_cache
+        .find(|ai| {
+            ai.load::<Bank>().map_or(false, |bank| {
+                bank.group == group_pk && bank.token_index == token_index
+            })
+        })
+        .ok_or_else(|| error_msg!("no bank for token index {} passed", token_index))?;
+    let mut bank = bank_ai.load_mut::<Bank>()?;
+
+    // Undo the settle token payment of liquidation_action()
+    let settle_token_transfer =
+        pnl_transfer * (I80F48::ONE - perp_market.positive_pnl_liquidation_fee);
+    settle_bank.deposit(
+        liqor.token_position_mut(settle_token_index)?.0,
+        settle_token_transfer,
+        now_ts,
+    )?;
+    settle_bank.withdraw_without_fee(
+        liqee.token_position_mut(settle_token_index)?.0,
+        settle_token_transfer,
+        now_ts,
+    )?;
+    liqee_health_cache.adjust_token_balance(settle_bank, -settle_token_transfer)?;
+
+    // Pay the same oracle value in the other token
+    let settle_price = liqee_health_cache
         .token_info(settle_token_index)?
         .prices
         .oracle;
-    let oracle_price = perp_info.base_prices.oracle;
-    let base_lot_size = I80F48::from(perp_market.base_lot_size);
-    let oracle_price_per_lot = base_lot_size * oracle_price;
+    let token_price = liqee_health_cache.token_info(token_index)?.prices.oracle;
+    let token_transfer = settle_token_transfer * settle_price / token_price;
+    bank.deposit(
+        liqee.token_position_mut(token_index)?.0,
+        token_transfer,
+        now_ts,
+    )?;
+    let (liqor_token_position, liqor_raw_index, _) = liqor.ensure_token_position(token_index)?;
+    let liqor_position_is_active =
+        bank.withdraw_without_fee_with_dusting(liqor_token_position, token_transfer, now_ts)?;
+    liqee_health_cache.adjust_token_balance(&bank, token_transfer)?;
+
+    emit_stack(TokenBalanceLog {
+        mango_group: group_pk,
+        mango_account: accounts.liqee.key(),
+        token_index,
+        indexed_position: liqee
+            .token_position(token_index)?
+            .indexed_position
+            .to_bits(),
+        deposit_index: bank.deposit_index.to_bits(),
+        borrow_index: bank.borrow_index.to_bits(),
+    });
+    emit_stack(TokenBalanceLog {
+        mango_group: group_pk,
+        mango_account: accounts.liqor.key(),
+        token_index,
+        indexed_position: liqor
+            .token_position(token_index)?
+            .indexed_position
+            .to_bits(),
+        deposit_index: bank.deposit_index.to_bits(),
+        borrow_index: bank.borrow_index.to_bits(),
+    });
+    if !liqor_position_is_active {
+        liqor.deactivate_token_position_and_log(liqor_raw_index, accounts.liqor.key());
+    }
 
-    let liqee_positive_settle_limit = liqee_perp_position.settle_limit(&perp_market).1;
+    emit_stack(PerpLiqPnlTakeoverTokenLog {
+        mango_group: group_pk,
+        perp_market_index: perp_market.perp_market_index,
+        liqor: accounts.liqor.key(),
+        liqee: accounts.liqee.key(),
+        token_index,
+        settle_token_transfer: settle_token_transfer.to_bits(),
+        token_transfer: token_transfer.to_bits(),
+    });
 
-    // The max settleable amount does not need to be constrained by the liqor's perp settle health,
-    // because taking over perp quote decreases liqor health: every unit of quote taken costs
-    // (1-positive_pnl_liq_fee) USDC and only gains init_overall_asset_weight in perp health.
-    let max_pnl_transfer = I80F48::from(max_pnl_transfer);
+    Ok(())
+}
 
-    // This instruction has two aspects:
-    //
-    // base reduction:
-    //    Increase perp health by reducing the base position towards 0, exchanging base for quote
-    //    at oracle price.
-    //    This step will reduce unsettled_pnl() due to fees, but increase health_unsettled_pnl().
-    //    The amount of overall health gained depends on the effective token balance (spot and
-    //    other perp market contributions) and the overall perp asset weight.
-    // pnl settlement:
-    //    Increase health by settling positive health_unsettled_pnl().
-    //    Settling pnl when health_unsettled_pnl<0 has a negative effect on health, since it
-    //    replaces unsettled pnl with (1-positive_pnl_liq_fee) spot tokens. But since the
-    //    overall perp weight is less than (1-fee), settling positive health_unsettled_pnl will
-    //    increase overall health.
-    //
-    // Base reduction increases the capacity for worthwhile pnl settlement. Also, base reduction
-    // may not improve overall health when the overall perp asset weight is zero. That means both need
-    // to be done in conjunction, where we only do enough reduction such that settlement will be able
-    // to bring health above the LiquidationEnd threshold.
+/// Deposits settle token from the group insurance fund into a liquidatable liqee's account,
+/// up to the liqee's bad debt in the perp market.
+///
+/// The bad debt is the liqee's loss in the market (negative perp pnl beyond its settle
+/// token balance), limited to what the whole account can't cover at oracle prices. Losses
+/// that the liqee's other assets can pay for are left to the normal liquidation.
+///
+/// Does nothing if the market isn't covered by the insurance fund or if the insurance
+/// token isn't the market's settle token.
+fn settle_bad_debt_from_insurance<'info>(
+    accounts: &PerpLiqBaseOrPositivePnlV3<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let group_pk = &accounts.group.key();
+    let now_slot = Clock::get()?.slot;
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
 
-    // Liquidation steps:
-    // 1. If health_unsettled_pnl is negative, reduce base until it's >=0 or total health over threshold.
-    //    Pnl settlement is not beneficial at that point anyway.
-    // 2. Pnl settlement of health_unsettled_pnl > 0 (while total health below threshold)
-    // 3. While if more settlement is possible, reduce base more to increase health_unsettled_pnl, as
-    //    long as projected health (after settlement) stays under threshold.
-    // 4. Pnl settlement of health_unsettled_pnl > 0 (while total health below threshold)
-    // 5. If perp_overall_weight>0, reduce base further while total health under threshold.
+    let mut liqee = accounts.liqee.load_full_mut()?;
+    let liqee_health_cache = {
+        let account_retriever = ScanningAccountRetriever::new(remaining_accounts, group_pk)
+            .context("create account retriever")?;
+        new_health_cache(&liqee.borrow(), &account_retriever, now_ts)
+            .context("create liqee health cache")?
+    };
+    if liqee.check_liquidatable(&liqee_health_cache)? != CheckLiquidatable::Liquidatable {
+        return Ok(());
+    }
 
-    // Take over the liqee's base in exchange for quote
-    let liqee_base_lots = liqee_perp_position.base_position_lots();
+    let perp_market = accounts.perp_market.load()?;
+    if !perp_market.elligible_for_group_insurance_fund()
+        || accounts.settle_vault.mint != accounts.insurance_vault.mint
+    {
+        msg!("the insurance fund does not cover this market");
+        return Ok(());
+    }
+    let perp_market_index = perp_market.perp_market_index;
+    let settle_token_index = perp_market.settle_token_index;
 
-    // Each lot the base position gets closer to 0, the "unweighted health unsettled pnl"
-    // increases by this amount
-    let uhupnl_per_lot;
+    let mut settle_bank = accounts.settle_bank.load_mut()?;
+    let oracle_ref = &AccountInfoRef::borrow(accounts.oracle.as_ref())?;
+    let perp_oracle_price = perp_market.oracle_price(
+        &OracleAccountInfos::from_reader(oracle_ref),
+        Some((now_ts, now_slot)),
+    )?;
+    let settle_oracle_ref = &AccountInfoRef::borrow(accounts.settle_oracle.as_ref())?;
+    let settle_token_oracle_price = settle_bank.oracle_price(
+        &OracleAccountInfos::from_reader(settle_oracle_ref),
+        Some((now_ts, now_slot)),
+    )?;
 
-    // -1 (liqee base lots decrease) or +1 (liqee base lots increase)
-    let direction: i64;
+    let market_loss = -liquidation_equity(
+        &liqee.borrow(),
+        &perp_market,
+        &settle_bank,
+        perp_oracle_price,
+    )?;
+    let (assets, liabs) = liqee_health_cache.assets_and_liabs();
+    let account_loss = (liabs - assets) / settle_token_oracle_price;
+    let bad_debt = market_loss.min(account_loss);
+    if bad_debt <= 0 {
+        return Ok(());
+    }
 
-    // Either 1+fee or 1-fee, depending on direction.
-    let base_fee_factor_liqor;
-    let base_fee_factor_all;
+    let insurance_transfer = bad_debt
+        .ceil()
+        .to_num::<u64>()
+        .min(accounts.insurance_vault.amount);
+    if insurance_transfer == 0 {
+        return Ok(());
+    }
 
-    if liqee_base_lots > 0 {
-        require_msg!(
-            max_base_transfer >= 0,
-            "max_base_transfer can't be negative when liqee's base_position is positive"
-        );
+    let (liqee_token_position, _, _) = liqee.ensure_token_position(settle_token_index)?;
+    settle_bank.deposit(
+        liqee_token_position,
+        I80F48::from(insurance_transfer),
+        now_ts,
+    )?;
 
-        // the health_unsettled_pnl gets reduced by `base * base_price * perp_init_asset_weight`
-        // and increased by `base * base_price * (1 - liq_fees)`
-        direction = -1;
-        base_fee_factor_liqor = I80F48::ONE - perp_market.base_liquidation_fee;
-        base_fee_factor_all = base_fee_factor_liqor - perp_market.platform_liquidation_fee;
-        uhupnl_per_lot =
-            oracle_price_per_lot * (-perp_market.init_base_asset_weight + base_fee_factor_all);
-    } else {
-        // liqee_base_lots <= 0
-        require_msg!(
-            max_base_transfer <= 0,
-            "max_base_transfer can't be positive when liqee's base_position is negative"
-        );
+    let group = accounts.group.load()?;
+    let group_seeds = group_seeds!(group);
+    token::transfer(
+        accounts
+            .insurance_to_settle_transfer_ctx()
+            .with_signer(&[group_seeds]),
+        insurance_transfer,
+    )?;
 
-        // health gets increased by `base * base_price * perp_init_liab_weight`
-        // and reduced by `base * base_price * (1 + liq_fees)`
-        direction = 1;
-        base_fee_factor_liqor = I80F48::ONE + perp_market.base_liquidation_fee;
-        base_fee_factor_all = base_fee_factor_liqor + perp_market.platform_liquidation_fee;
-        uhupnl_per_lot =
-            oracle_price_per_lot * (perp_market.init_base_liab_weight - base_fee_factor_all);
-    };
-    assert!(uhupnl_per_lot > 0);
+    let liqee_token_position = liqee.token_position(settle_token_index)?;
+    emit_stack(TokenBalanceLog {
+        mango_group: accounts.group.key(),
+        mango_account: accounts.liqee.key(),
+        token_index: settle_token_index,
+        indexed_position: liqee_token_position.indexed_position.to_bits(),
+        deposit_index: settle_bank.deposit_index.to_bits(),
+        borrow_index: settle_bank.borrow_index.to_bits(),
+    });
+
+    emit_stack(PerpLiqBadDebtInsuranceLog {
+        mango_group: accounts.group.key(),
+        perp_market_index,
+        liqor: accounts.liqor.key(),
+        liqee: accounts.liqee.key(),
+        bad_debt: bad_debt.to_bits(),
+        insurance_transfer,
+    });
 
-    // Amount of settle token received for each token that is settled
-    let spot_gain_per_settled = I80F48::ONE - perp_market.positive_pnl_liquidation_fee;
+    msg!(
+        "bad debt: {} covered by {} insurance",
+        bad_debt,
+        insurance_transfer
+    );
 
-    let init_overall_asset_weight = perp_market.init_overall_asset_weight;
+    Ok(())
+}
 
-    //
-    // Several steps of perp base position reduction will follow, and they'll update
-    // these variables
-    //
-    let mut base_reduction = 0;
-    let mut pnl_transfer = I80F48::ZERO;
-    let mut current_uhupnl = perp_info.unweighted_health_unsettled_pnl(liq_end_type);
-    let mut current_health = liqee_liq_end_health;
-    let mut current_settle_token = settle_token_balance.spot_and_perp;
+/// Computes what perp_liq_base_or_positive_pnl would do with the same arguments and emits
+/// a PerpLiquidationExplanation event with the intermediate values.
+///
+/// It shares the setup and the checks with the liquidation, so it fails where the
+/// liquidation would: for example on the market's liquidation throttle, the large position
+/// whitelist, min_net_quote_profit_opt or the liqor's health. Only the liqor owner's
+/// signature isn't required.
+///
+/// The liquidation happens on copies of the accounts, nothing is written back.
+pub fn perp_explain_liquidation(
+    ctx: Context<PerpExplainLiquidation>,
+    mut max_base_transfer: i64,
+    max_pnl_transfer: u64,
+    liqor_price_opt: Option<f64>,
+    min_net_quote_profit_opt: Option<i64>,
+    liquidation_cost: u64,
+    max_health_improvement_opt: Option<u64>,
+) -> Result<()> {
+    // Ensure max_base_transfer can be negated
+    max_base_transfer = max_base_transfer.max(i64::MIN + 1);
 
-    // Helper function to reduce base position in exchange for effective settle token balance changes
-    //
-    // This function does only deal with reducing the base position and getting settle token balance
-    // in exchange. This exchange does not always lead to health improvements (like when the overall
-    // weight is 0 and the uhupnl is already positive). That's why we pass an "expected" gain per lot
-    // as well, which incorporates the fact that the increase in uhupnl can get settled later and
-    // becomes health then.
-    //
-    // This means that the amount of base_lots reduced by this function is determined in anticipation
-    // of future pnl settlement. It only changes the current_* args according to the actual reduction
-    // that happened. The settlement happens later, separately, in settle_pnl() below.
-    let mut reduce_base = |step: &str,
-                           uhupnl_limit: I80F48,
-                           // How much effective settle token will be gained when taking future
-                           // upnl settlement into account.
-                           expected_settle_token_per_lot: I80F48,
-                           // Effective settle token gained directly from the operation (before settlement)
-                           actual_settle_token_per_lot: I80F48,
-                           uhupnl_per_lot: I80F48,
-                           current_uhupnl: &mut I80F48,
-                           current_settle_token: &mut I80F48,
-                           current_health: &mut I80F48| {
-        let max_settle_token_for_health = spot_amount_given_for_health_zero(
-            *current_health,
-            *current_settle_token,
-            settle_token_info.asset_weighted_price(liq_end_type),
-            settle_token_info.liab_weighted_price(liq_end_type),
-        )
-        .unwrap();
+    let group_pk = &ctx.accounts.group.key();
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
 
-        let max_settle_token = max_settle_token_for_health.min(uhupnl_limit);
+    require_keys_neq!(ctx.accounts.liqor.key(), ctx.accounts.liqee.key());
+    let mut liqor = {
+        let data = ctx.accounts.liqor.as_ref().try_borrow_data()?;
+        MangoAccountValue::from_bytes(&data[8..])?
+    };
+    let mut liqee = {
+        let data = ctx.accounts.liqee.as_ref().try_borrow_data()?;
+        MangoAccountValue::from_bytes(&data[8..])?
+    };
 
-        // How many lots to transfer?
-        let base_lots = (max_settle_token / expected_settle_token_per_lot)
-            .ceil() // overshoot to aim for init_health >= 0
-            .to_num::<i64>()
-            .min(liqee_base_lots.abs() - base_reduction)
-            .min(max_base_transfer.abs() - base_reduction)
-            .max(0);
+    let (mut liqee_health_cache, oracle_cache) = {
+        let account_retriever = ScanningAccountRetriever::new(ctx.remaining_accounts, group_pk)
+            .context("create account retriever")?;
+        let health_cache = new_health_cache(&liqee.borrow(), &account_retriever, now_ts)
+            .context("create liqee health cache")?;
+        (health_cache, account_retriever.into_oracle_cache())
+    };
+    let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+    let liqee_maint_health = liqee_health_cache.health(HealthType::Maint);
+    liqee_health_cache.require_after_phase1_liquidation()?;
+    require_msg_typed!(
+        liqee.check_liquidatable(&liqee_health_cache)? == CheckLiquidatable::Liquidatable,
+        MangoError::HealthMustBeNegative,
+        "liqee is not liquidatable"
+    );
 
-        // Note, the expected health and settle token change is just for logging
-        let expected_settle_token_gain = expected_settle_token_per_lot * I80F48::from(base_lots);
-        let new_expected_settle_token = *current_settle_token + expected_settle_token_gain;
-        let new_expected_health = *current_health
-            - settle_token_info.health_contribution(liq_end_type, *current_settle_token)
-            + settle_token_info.health_contribution(liq_end_type, new_expected_settle_token);
+    let group = ctx.accounts.group.load()?;
+    // The copies are boxed: together they don't fit into the stack frame
+    let mut perp_market = Box::new(*ctx.accounts.perp_market.load()?);
+    let mut settle_bank = Box::new(*ctx.accounts.settle_bank.load()?);
+    let perp_market_index = perp_market.perp_market_index;
 
-        let uhupnl_gain = uhupnl_per_lot * I80F48::from(base_lots);
-        let new_uhupnl = *current_uhupnl + uhupnl_gain;
+    let params = liquidation_setup(
+        &group,
+        &perp_market,
+        &mut liqor.borrow_mut(),
+        &mut liqee.borrow_mut(),
+        ctx.remaining_accounts,
+        now_ts,
+        liqor_price_opt,
+        min_net_quote_profit_opt,
+        liquidation_cost,
+        max_health_improvement_opt,
+    )?;
+    let liqor_base_lots_before = liqor.perp_position(perp_market_index)?.base_position_lots();
 
-        let new_settle_token =
-            *current_settle_token + actual_settle_token_per_lot * I80F48::from(base_lots);
-        let new_health = *current_health
-            - settle_token_info.health_contribution(liq_end_type, *current_settle_token)
-            + settle_token_info.health_contribution(liq_end_type, new_settle_token);
+    let mut explanation = LiquidationExplanation::default();
+    let (
+        base_transfer,
+        quote_transfer_liqee,
+        quote_transfer_liqor,
+        platform_fee,
+        pnl_transfer,
+        _,
+        _,
+    ) = liquidation_action_explained(
+        &mut perp_market,
+        params.base_liquidation_fee,
+        &mut settle_bank,
+        &mut liqor.borrow_mut(),
+        &mut liqee.borrow_mut(),
+        &mut liqee_health_cache,
+        liqee_liq_end_health,
+        now_ts,
+        max_base_transfer,
+        max_pnl_transfer,
+        params.liqor_price_opt,
+        params.max_overshoot_health,
+        params.min_liqor_reward,
+        params.max_health_improvement,
+        params.platform_fee_waived,
+        &mut explanation,
+    )?;
 
-        msg!(
-            "{} total: {} lots, health {} -> {}, exhealth -> {}, uhupnl: {} -> {}",
-            step,
-            base_lots,
-            // logging i64 is significantly cheaper
-            current_health.to_num::<i64>(),
-            new_health.to_num::<i64>(),
-            new_expected_health.to_num::<i64>(),
-            current_uhupnl.to_num::<i64>(),
-            new_uhupnl.to_num::<i64>(),
-        );
+    liquidation_checks(
+        &mut perp_market,
+        &ctx.accounts.liqor.key(),
+        &liqor.borrow(),
+        liqor_base_lots_before,
+        &mut liqee.borrow_mut(),
+        &liqee_health_cache,
+        liqee_liq_end_health,
+        liqee_maint_health,
+        base_transfer,
+        Clock::get()?.slot,
+    )?;
+    check_liqor_health(
+        &group,
+        &ctx.accounts.liqor.key(),
+        &liqor.borrow(),
+        ctx.remaining_accounts,
+        oracle_cache,
+        now_ts,
+    )?;
 
-        *current_settle_token = new_settle_token;
-        *current_health = new_health;
-        *current_uhupnl = new_uhupnl;
+    emit!(PerpLiquidationExplanation {
+        perp_market_index,
+        liqor: ctx.accounts.liqor.key(),
+        liqee: ctx.accounts.liqee.key(),
+        oracle_price: explanation.oracle_price,
+        price: explanation.liquidation_price,
+        price_per_lot: explanation.price_per_lot,
+        health_per_lot: explanation.health_per_lot,
+        base_transfer_for_zero: explanation.base_transfer_for_zero,
+        binding_constraint: explanation.binding_constraint as u8,
+        base_transfer_liqee: base_transfer,
+        quote_transfer_liqee,
+        quote_transfer_liqor,
+        quote_platform_fee: platform_fee,
+        pnl_transfer,
+        liqee_health_before: liqee_liq_end_health,
+        liqee_health_after: liqee_health_cache.health(HealthType::LiquidationEnd),
+    });
 
-        base_reduction += base_lots;
+    Ok(())
+}
+
+/// Parameters of liquidation_action() that perp_liq_base_or_positive_pnl and
+/// perp_explain_liquidation derive from their arguments and the group
+struct LiquidationParams {
+    /// The market's base_liquidation_fee, capped by Group::max_liquidation_fee
+    base_liquidation_fee: I80F48,
+    liqor_price_opt: Option<I80F48>,
+    max_overshoot_health: Option<I80F48>,
+    min_liqor_reward: Option<I80F48>,
+    max_health_improvement: Option<I80F48>,
+    platform_fee_waived: bool,
+}
+
+/// Validates the liqor and the liqee's perp position and prepares them for
+/// liquidation_action(): creates the liqor's perp position if needed, settles funding on
+/// both and updates the liqee's settle limit.
+///
+/// Shared by perp_liq_base_or_positive_pnl and perp_explain_liquidation.
+fn liquidation_setup(
+    group: &Group,
+    perp_market: &PerpMarket,
+    liqor: &mut MangoAccountRefMut,
+    liqee: &mut MangoAccountRefMut,
+    remaining_accounts: &[AccountInfo],
+    now_ts: u64,
+    liqor_price_opt: Option<f64>,
+    min_net_quote_profit_opt: Option<i64>,
+    liquidation_cost: u64,
+    max_health_improvement_opt: Option<u64>,
+) -> Result<LiquidationParams> {
+    require_msg_typed!(
+        !liqor.fixed.being_liquidated(),
+        MangoError::BeingLiquidated,
+        "liqor account"
+    );
+
+    let params = LiquidationParams {
+        base_liquidation_fee: group.capped_liquidation_fee(perp_market.base_liquidation_fee),
+        liqor_price_opt: liqor_price_from_num(liqor_price_opt)?,
+        max_overshoot_health: (group.max_liquidation_overshoot_health > 0)
+            .then(|| I80F48::from(group.max_liquidation_overshoot_health)),
+        min_liqor_reward: min_net_quote_profit_opt
+            .map(|min_profit| I80F48::from(min_profit) + I80F48::from(liquidation_cost)),
+        max_health_improvement: max_health_improvement_opt.map(I80F48::from),
+        platform_fee_waived: liqor_platform_fee_waived(
+            group,
+            &liqor.borrow(),
+            remaining_accounts,
+            now_ts,
+        )?,
     };
 
-    let settle_pnl = |step: &str,
-                      settle_token_per_settle: I80F48,
-                      pnl_transfer: &mut I80F48,
-                      current_uhupnl: &mut I80F48,
-                      current_settle_token: &mut I80F48,
-                      current_health: &mut I80F48| {
-        let max_settle_token_for_health = spot_amount_given_for_health_zero(
-            *current_health,
-            *current_settle_token,
-            settle_token_info.asset_weighted_price(liq_end_type),
-            settle_token_info.liab_weighted_price(liq_end_type),
-        )
-        .unwrap();
+    // Fetch perp positions for accounts, creating for the liqor if needed
+    let perp_market_index = perp_market.perp_market_index;
+    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+    require!(
+        !liqee_perp_position.has_open_taker_fills(),
+        MangoError::HasOpenPerpTakerFills
+    );
 
-        // How many units to settle?
-        let settle = (max_settle_token_for_health / settle_token_per_settle)
-            .min(max_pnl_transfer - *pnl_transfer)
-            .min(*current_uhupnl)
-            .max(I80F48::ZERO);
+    let liqor_perp_position = liqor
+        .ensure_perp_position(perp_market_index, perp_market.settle_token_index)?
+        .0;
 
-        let settle_token_gain = settle * settle_token_per_settle;
-        let new_settle_token = *current_settle_token + settle_token_gain;
-        let new_uhupnl = *current_uhupnl - settle;
-        let new_expected_health = *current_health
-            - settle_token_info.health_contribution(liq_end_type, *current_settle_token)
-            + settle_token_info.health_contribution(liq_end_type, new_settle_token);
+    // Settle funding, update limit
+    liqee_perp_position.settle_funding(perp_market);
+    liqor_perp_position.settle_funding(perp_market);
+    liqee_perp_position.update_settle_limit(perp_market, now_ts);
 
-        msg!(
-            "{}: {} settled, health {} -> {}, uhupnl: {} -> {}",
-            step,
-            settle.to_num::<i64>(),
-            current_health.to_num::<i64>(),
-            new_expected_health.to_num::<i64>(),
-            current_uhupnl.to_num::<i64>(),
-            new_uhupnl.to_num::<i64>(),
-        );
+    Ok(params)
+}
 
-        *current_settle_token = new_settle_token;
-        *current_health = new_expected_health;
-        *current_uhupnl = new_uhupnl;
+/// Checks after liquidation_action(), shared like liquidation_setup()
+///
+/// Enforces the market's large position whitelist and liquidation throttle and requires
+/// that the liqee's health didn't drop. Also lets the liqee recover from being liquidated.
+fn liquidation_checks(
+    perp_market: &mut PerpMarket,
+    liqor_key: &Pubkey,
+    liqor: &MangoAccountRef,
+    liqor_base_lots_before: i64,
+    liqee: &mut MangoAccountRefMut,
+    liqee_health_cache: &HealthCache,
+    liqee_liq_end_health: I80F48,
+    liqee_maint_health: I80F48,
+    base_transfer: i64,
+    now_slot: u64,
+) -> Result<()> {
+    perp_market.check_liqor_large_position(
+        liqor_key,
+        liqor_base_lots_before,
+        liqor
+            .perp_position(perp_market.perp_market_index)?
+            .base_position_lots(),
+    )?;
+    perp_market.record_liquidation_base(base_transfer, now_slot, liqee_maint_health)?;
 
-        *pnl_transfer += settle;
-    };
+    // Check liqee health again
+    let liqee_liq_end_health_after = liqee_health_cache.health(HealthType::LiquidationEnd);
+    liqee
+        .fixed
+        .maybe_recover_from_being_liquidated(liqee_liq_end_health_after);
+    require_gte!(liqee_liq_end_health_after, liqee_liq_end_health);
+    msg!(
+        "liqee liq end health: {} -> {}",
+        liqee_liq_end_health,
+        liqee_liq_end_health_after
+    );
+    Ok(())
+}
 
-    //
-    // Step 1: While the perp unsettled health is negative, any perp base position reduction
-    // directly increases it for the full amount.
-    //
-    if current_uhupnl < 0 {
-        reduce_base(
-            "negative",
-            -current_uhupnl,
-            // for negative values uhupnl and hupnl are the same and increases
-            // directly improve health
-            uhupnl_per_lot,
-            uhupnl_per_lot,
-            uhupnl_per_lot,
-            &mut current_uhupnl,
-            &mut current_settle_token,
-            &mut current_health,
-        );
+/// Checks the liqor's health after the liquidation, unless the protocol backstops it
+///
+/// `oracle_cache` has the oracle states parsed for the liqee's health cache.
+fn check_liqor_health(
+    group: &Group,
+    liqor_key: &Pubkey,
+    liqor: &MangoAccountRef,
+    remaining_accounts: &[AccountInfo],
+    oracle_cache: OracleStateCache,
+    now_ts: u64,
+) -> Result<()> {
+    if liqor.fixed.is_in_health_region() || group.is_protocol_liquidator(liqor_key) {
+        return Ok(());
     }
+    let account_retriever = ScanningAccountRetriever::new(remaining_accounts, &liqor.fixed.group)
+        .context("create account retriever end")?
+        .with_oracle_cache(oracle_cache);
+    let liqor_health = compute_health(liqor, HealthType::Init, &account_retriever, now_ts)
+        .context("compute liqor health")?;
+    require!(liqor_health >= 0, MangoError::HealthMustBePositive);
+    Ok(())
+}
 
-    //
-    // Step 2: pnl settlement if uhupnl > 0
-    //
-    if current_uhupnl >= 0 && spot_gain_per_settled > init_overall_asset_weight {
-        // Settlement produces direct spot (after fees) and loses perp-positive-uhupnl weighted settle token pos
-        let settle_token_per_settle = spot_gain_per_settled - init_overall_asset_weight;
-        settle_pnl(
-            "pre-settle",
-            settle_token_per_settle,
-            &mut pnl_transfer,
-            &mut current_uhupnl,
-            &mut current_settle_token,
-            &mut current_health,
-        );
-    }
+/// Converts the liqor-supplied liquidation price, rejecting NaN, infinite and
+/// out-of-range values
+fn liqor_price_from_num(liqor_price_opt: Option<f64>) -> Result<Option<I80F48>> {
+    liqor_price_opt
+        .map(|price| {
+            I80F48::checked_from_num(price).ok_or_else(|| {
+                error_msg_typed!(
+                    MangoError::LiquidationPriceOutOfTolerance,
+                    "liqor price {} is not representable",
+                    price
+                )
+            })
+        })
+        .transpose()
+}
 
-    //
-    // Step 3: If perp unsettled health is positive but below max_settle, perp base position reductions
-    // benefit account health slightly less because of the settlement liquidation fee.
-    //
-    if current_uhupnl >= 0 && pnl_transfer < max_pnl_transfer {
-        reduce_base(
-            "settleable",
-            max_pnl_transfer - pnl_transfer,
-            uhupnl_per_lot * spot_gain_per_settled,
-            uhupnl_per_lot * init_overall_asset_weight,
-            uhupnl_per_lot,
-            &mut current_uhupnl,
-            &mut current_settle_token,
-            &mut current_health,
-        );
-    }
+/// The limit that determined the size of a base reduction step
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LiquidationConstraint {
+    /// No base reduction step was attempted
+    #[default]
+    None = 0,
+    /// The lots needed to bring the liqee's health to zero
+    Health = 1,
+    /// The unsettled pnl limit of the step, like max_pnl_transfer
+    PnlLimit = 2,
+    /// The liqee's remaining base position
+    LiqeeBasePosition = 3,
+    /// The liqor's max_base_transfer
+    MaxBaseTransfer = 4,
+    /// The group's max_liquidation_overshoot_health
+    MaxOvershootHealth = 5,
+    /// The liqor's max_health_improvement
+    MaxHealthImprovement = 6,
+}
 
-    //
-    // Step 4: Again, pnl settlement if uhupnl > 0
-    //
-    if current_uhupnl >= 0 && spot_gain_per_settled > init_overall_asset_weight {
-        // Settlement produces direct spot (after fees) and loses perp-positive-uhupnl weighted settle token pos
-        let settle_token_per_settle = spot_gain_per_settled - init_overall_asset_weight;
-        settle_pnl(
-            "post-settle",
-            settle_token_per_settle,
-            &mut pnl_transfer,
-            &mut current_uhupnl,
-            &mut current_settle_token,
-            &mut current_health,
-        );
-    }
+/// Intermediate values of a liquidation_action() call
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LiquidationExplanation {
+    pub oracle_price: I80F48,
+    pub liquidation_price: I80F48,
+    pub price_per_lot: I80F48,
+    /// Increase of the unweighted health unsettled pnl per lot of base reduction
+    pub health_per_lot: I80F48,
+    /// Lots the last base reduction step would have transferred to reach zero health,
+    /// including the lots of earlier steps
+    pub base_transfer_for_zero: i64,
+    /// The limit that determined the size of the last base reduction step
+    pub binding_constraint: LiquidationConstraint,
+}
 
-    //
-    // Step 5: Above that, perp base positions only benefit account health if the pnl asset weight is positive
-    //
-    // Using -0.5 as a health target prevents rounding related issues. Any health >-1 will be enough
-    // to get the account out of being-liquidated state.
-    let health_target = I80F48::from_num(-0.5);
-    if current_health < health_target && init_overall_asset_weight > 0 {
-        let settle_token_per_lot = uhupnl_per_lot * init_overall_asset_weight;
-        reduce_base(
-            "positive",
-            I80F48::MAX,
-            settle_token_per_lot,
-            settle_token_per_lot,
-            uhupnl_per_lot,
-            &mut current_uhupnl,
-            &mut current_settle_token,
-            &mut current_health,
-        );
+/// Hash of the account state that perp_liq_base_or_positive_pnl may change: the perp
+/// position in the market and the settle token position, if there is one
+///
+/// It's logged before and after the liquidation, so auditors can check that the positions
+/// changed exactly as described by the logged transfers.
+pub fn liquidation_state_hash(
+    account: &MangoAccountRef,
+    perp_market_index: PerpMarketIndex,
+    settle_token_index: TokenIndex,
+) -> Result<[u8; 32]> {
+    let perp_position = account.perp_position(perp_market_index)?;
+    let token_bytes = account
+        .token_position(settle_token_index)
+        .map(bytemuck::bytes_of)
+        .unwrap_or(&[]);
+    Ok(hashv(&[bytemuck::bytes_of(perp_position), token_bytes]).to_bytes())
+}
+
+/// liquidation_state_hash() for the liquidation log, or all zeros if the
+/// liquidation-state-hash feature is disabled, to save the compute
+fn logged_liquidation_state_hash(
+    account: &MangoAccountRef,
+    perp_market_index: PerpMarketIndex,
+    settle_token_index: TokenIndex,
+) -> Result<[u8; 32]> {
+    if cfg!(feature = "liquidation-state-hash") {
+        liquidation_state_hash(account, perp_market_index, settle_token_index)
+    } else {
+        Ok([0u8; 32])
     }
+}
 
-    //
-    // Execute the base reduction. This is essentially a forced trade and updates the
-    // liqee and liqors entry and break even prices.
-    //
-    assert!(base_reduction <= liqee_base_lots.abs());
-    let base_transfer = direction * base_reduction;
-    let quote_transfer_base = -I80F48::from(base_transfer) * oracle_price_per_lot;
-    let quote_transfer_liqee = quote_transfer_base * base_fee_factor_all;
-    let quote_transfer_liqor = -quote_transfer_base * base_fee_factor_liqor;
-    if base_transfer != 0 {
-        msg!(
-            "transfering: {} base lots and {} quote",
-            base_transfer,
-            quote_transfer_liqee
-        );
-        liqee_perp_position.record_trade(perp_market, base_transfer, quote_transfer_liqee);
-        liqor_perp_position.record_trade(perp_market, -base_transfer, quote_transfer_liqor);
+/// Account equity that perp_liq_base_or_positive_pnl may change, in native settle token:
+/// the unsettled pnl of the perp position at `oracle_price` plus the settle token balance
+pub fn liquidation_equity(
+    account: &MangoAccountRef,
+    perp_market: &PerpMarket,
+    settle_bank: &Bank,
+    oracle_price: I80F48,
+) -> Result<I80F48> {
+    let perp_pnl = account
+        .perp_position(perp_market.perp_market_index)?
+        .unsettled_pnl(perp_market, oracle_price)?;
+    let settle_token = account
+        .token_position(perp_market.settle_token_index)
+        .map(|position| position.native(settle_bank))
+        .unwrap_or(I80F48::ZERO);
+    Ok(perp_pnl + settle_token)
+}
+
+/// The liqee's cost of being liquidated, given its liquidation_equity() before and after
+///
+/// With the oracle price as liquidation price, that's the base liquidation fees plus the
+/// fee on the taken over pnl.
+pub fn liqee_liquidation_cost(equity_before: I80F48, equity_after: I80F48) -> I80F48 {
+    equity_before - equity_after
+}
+
+/// Change of a perp market's open interest when `base_transfer` lots move from the liqor
+/// to the liqee.
+///
+/// The transfer moves risk between the accounts, but only keeps the open interest unchanged
+/// if the liqor had no position opposite to the liqee's: netting reduces it.
+pub fn liquidation_open_interest_change(
+    liqee_base_lots: i64,
+    liqor_base_lots: i64,
+    base_transfer: i64,
+) -> i64 {
+    let liqee_change = (liqee_base_lots + base_transfer).abs() - liqee_base_lots.abs();
+    let liqor_change = (liqor_base_lots - base_transfer).abs() - liqor_base_lots.abs();
+    liqee_change + liqor_change
+}
+
+/// Whether the liqor's owner has enough mngo locked in the governance stake registry to
+/// receive the platform liquidation fee, see Group::liqor_fee_waiver_min_mngo
+///
+/// The liqor passes its Voter account as one of the trailing remaining accounts.
+fn liqor_platform_fee_waived(
+    group: &Group,
+    liqor: &MangoAccountRef,
+    ais: &[AccountInfo],
+    now_ts: u64,
+) -> Result<bool> {
+    let min_mngo = group.liqor_fee_waiver_min_mngo;
+    if min_mngo == 0 || group.liqor_fee_waiver_registrar == Pubkey::default() {
+        return Ok(false);
     }
+    let Some(voter_ai) = ais.iter().find(|ai| ai.owner == &voter_stake_registry::ID) else {
+        return Ok(false);
+    };
+    let data = voter_ai.try_borrow_data()?;
+    let locked = voter_stake_registry::voter_locked_amount(
+        &data,
+        &group.liqor_fee_waiver_registrar,
+        &liqor.fixed.owner,
+        now_ts,
+    )?;
+    Ok(locked >= min_mngo)
+}
 
-    // We know that this is positive:
-    // liq a long: base_transfer < 0, quote_transfer_base > 0, base_fee_factor < 1
-    //   and -q_t_liqor >= q_t_liqee (both sides positive; take more from the liqor than we give to the liqee)
-    // liq a short: base_transfer > 0, quote_transfer_base < 0, base_fee_factor > 1
-    //   and -q_t_liqor >= q_t_liqee (both sides negative; we take more from the liqee than we give to the liqor)
-    let platform_fee = (-quote_transfer_liqor - quote_transfer_liqee).max(I80F48::ZERO);
-    perp_market.fees_accrued += platform_fee;
-    perp_market.accrued_liquidation_fees += platform_fee;
+pub(crate) fn liquidation_action(
+    perp_market: &mut PerpMarket,
+    // the perp market's base_liquidation_fee, capped by Group::max_liquidation_fee
+    base_liquidation_fee: I80F48,
+    settle_bank: &mut Bank,
+    liqor: &mut MangoAccountRefMut,
+    liqee: &mut MangoAccountRefMut,
+    liqee_health_cache: &mut HealthCache,
+    liqee_liq_end_health: I80F48,
+    now_ts: u64,
+    max_base_transfer: i64,
+    max_pnl_transfer: u64,
+    liqor_price_opt: Option<I80F48>,
+    max_overshoot_health: Option<I80F48>,
+    min_liqor_reward: Option<I80F48>,
+    max_health_improvement: Option<I80F48>,
+    platform_fee_waived: bool,
+) -> Result<(i64, I80F48, I80F48, I80F48, I80F48, i64, i64)> {
+    liquidation_action_explained(
+        perp_market,
+        base_liquidation_fee,
+        settle_bank,
+        liqor,
+        liqee,
+        liqee_health_cache,
+        liqee_liq_end_health,
+        now_ts,
+        max_base_transfer,
+        max_pnl_transfer,
+        liqor_price_opt,
+        max_overshoot_health,
+        min_liqor_reward,
+        max_health_improvement,
+        platform_fee_waived,
+        &mut LiquidationExplanation::default(),
+    )
+}
+
+/// Like liquidation_action(), but records intermediate values in `explanation`
+pub(crate) fn liquidation_action_explained(
+    perp_market: &mut PerpMarket,
+    base_liquidation_fee: I80F48,
+    settle_bank: &mut Bank,
+    liqor: &mut MangoAccountRefMut,
+    liqee: &mut MangoAccountRefMut,
+    liqee_health_cache: &mut HealthCache,
+    liqee_liq_end_health: I80F48,
+    now_ts: u64,
+    max_base_transfer: i64,
+    max_pnl_transfer: u64,
+    liqor_price_opt: Option<I80F48>,
+    max_overshoot_health: Option<I80F48>,
+    min_liqor_reward: Option<I80F48>,
+    max_health_improvement: Option<I80F48>,
+    platform_fee_waived: bool,
+    explanation: &mut LiquidationExplanation,
+) -> Result<(i64, I80F48, I80F48, I80F48, I80F48, i64, i64)> {
+    let liq_end_type = HealthType::LiquidationEnd;
 
+    let perp_market_index = perp_market.perp_market_index;
+    let settle_token_index = perp_market.settle_token_index;
+
+    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+    let liqor_perp_position = liqor.perp_position_mut(perp_market_index)?;
+
+    let token_balances = liqee_health_cache.effective_token_balances(liq_end_type);
+    let settle_token_balance = &token_balances[liqee_health_cache
+        .token_infos
+        .iter()
+        .position(|ti| ti.token_index == settle_token_index)
+        .unwrap()];
+    let settle_token_info = liqee_health_cache.token_info(settle_token_index).unwrap();
+    let perp_info = liqee_health_cache.perp_info(perp_market_index)?;
+    let settle_token_oracle_price = liqee_health_cache
+        .token_info(settle_token_index)?
+        .prices
+        .oracle;
+    let oracle_price = perp_info.base_prices.oracle;
+
+    // The base position is exchanged at the oracle price, unless the liqor supplied
+    // a price that is close enough to it
+    let liquidation_price = match liqor_price_opt {
+        Some(liqor_price) => {
+            perp_market.check_liquidation_price(liqor_price, oracle_price)?;
+            liqor_price
+        }
+        None => oracle_price,
+    };
+    let base_lot_size = I80F48::from(perp_market.base_lot_size);
+    // The liquidation price only determines the quote transfer. The liqee's health change
+    // from losing base is sized at the oracle price, like health itself.
+    let price_per_lot = base_lot_size * liquidation_price;
+    let oracle_price_per_lot = base_lot_size * oracle_price;
+    explanation.oracle_price = oracle_price;
+    explanation.liquidation_price = liquidation_price;
+    explanation.price_per_lot = price_per_lot;
+
+    let liqee_positive_settle_limit = liqee_perp_position.settle_limit(&perp_market).1;
+
+    // The max settleable amount does not need to be constrained by the liqor's perp settle health,
+    // because taking over perp quote decreases liqor health: every unit of quote taken costs
+    // (1-positive_pnl_liq_fee) USDC and only gains init_overall_asset_weight in perp health.
+    let max_pnl_transfer = I80F48::from(max_pnl_transfer);
+
+    // This instruction has two aspects:
     //
-    // Let the liqor take over positive pnl until the account health is positive,
-    // but only while the health_unsettled_pnl is positive (otherwise it would decrease liqee health!)
+    // base reduction:
+    //    Increase perp health by reducing the base position towards 0, exchanging base for quote
+    //    at oracle price (or the liqor supplied liquidation price).
+    //    This step will reduce unsettled_pnl() due to fees, but increase health_unsettled_pnl().
+    //    The amount of overall health gained depends on the effective token balance (spot and
+    //    other perp market contributions) and the overall perp asset weight.
+    // pnl settlement:
+    //    Increase health by settling positive health_unsettled_pnl().
+    //    Settling pnl when health_unsettled_pnl<0 has a negative effect on health, since it
+    //    replaces unsettled pnl with (1-positive_pnl_liq_fee) spot tokens. But since the
+    //    overall perp weight is less than (1-fee), settling positive health_unsettled_pnl will
+    //    increase overall health.
     //
-    let limit_transfer_recurring: i64;
-    let limit_transfer_oneshot: i64;
-    if pnl_transfer > 0 {
-        // Allow taking over *more* than the liqee_positive_settle_limit. In exchange, the liqor
-        // also can't settle fully immediately and just takes over a fractional chunk of the limit.
-        //
-        // If this takeover were limited by the settle limit, then we couldn't always bring the liqee
-        // base position to zero and would need to deal with that in bankruptcy. Also, the settle
-        // limit changes with the base position price, so it'd be hard to say when this liquidation
-        // step is done.
-        {
-            // take care, liqee_limit may be i64::MAX
-            let liqee_limit: i128 = liqee_positive_settle_limit.into();
-            let liqee_oneshot_positive = liqee_perp_position
-                .oneshot_settle_pnl_allowance
-                .ceil()
-                .to_num::<i128>()
-                .max(0);
-            let liqee_recurring = (liqee_limit - liqee_oneshot_positive).max(0);
-            let liqee_pnl = liqee_perp_position
-                .unsettled_pnl(perp_market, oracle_price)?
-                .ceil()
-                .to_num::<i128>()
-                .max(1);
-            let settle = pnl_transfer.floor().to_num::<i128>();
-            let total = liqee_pnl.max(settle);
-            let transfer_recurring: i64 = (liqee_recurring * settle / total).try_into().unwrap();
-            let transfer_oneshot: i64 = (liqee_oneshot_positive * settle / total)
-                .try_into()
-                .unwrap();
+    // Base reduction increases the capacity for worthwhile pnl settlement. Also, base reduction
+    // may not improve overall health when the overall perp asset weight is zero. That means both need
+    // to be done in conjunction, where we only do enough reduction such that settlement will be able
+    // to bring health above the LiquidationEnd threshold.
 
-            // never transfer more than pnl_transfer rounded up
-            // and transfer at least 1, to compensate for rounding down `settle` and int div
-            let max_transfer = pnl_transfer.ceil().to_num::<i64>();
-            limit_transfer_recurring = transfer_recurring.min(max_transfer).max(1);
-            // make is so the sum of recurring and oneshot doesn't exceed max_transfer
-            limit_transfer_oneshot = transfer_oneshot
-                .min(max_transfer - limit_transfer_recurring)
-                .max(0);
-        };
+    // Liquidation steps:
+    // 1. If health_unsettled_pnl is negative, reduce base until it's >=0 or total health over threshold.
+    //    Pnl settlement is not beneficial at that point anyway.
+    // 2. Pnl settlement of health_unsettled_pnl > 0 (while total health below threshold)
+    // 3. While if more settlement is possible, reduce base more to increase health_unsettled_pnl, as
+    //    long as projected health (after settlement) stays under threshold.
+    // 4. Pnl settlement of health_unsettled_pnl > 0 (while total health below threshold)
+    // 5. If perp_overall_weight>0, reduce base further while total health under threshold.
 
-        // The liqor pays less than the full amount to receive the positive pnl
-        let token_transfer = pnl_transfer * spot_gain_per_settled;
+    // Take over the liqee's base in exchange for quote
+    let liqee_base_lots = liqee_perp_position.base_position_lots();
 
-        liqor_perp_position.record_liquidation_pnl_takeover(
-            pnl_transfer,
-            limit_transfer_recurring,
-            limit_transfer_oneshot,
-        );
-        liqee_perp_position.record_settle(pnl_transfer, &perp_market);
+    // i64::MAX and i64::MIN mean "as much as possible" and are accepted for both directions.
+    // (i64::MIN was turned into -i64::MAX by the caller)
+    let max_base_transfer = if max_base_transfer.saturating_abs() == i64::MAX {
+        if liqee_base_lots > 0 {
+            i64::MAX
+        } else {
+            -i64::MAX
+        }
+    } else {
+        max_base_transfer
+    };
 
-        // Update the accounts' perp_spot_transfer statistics.
-        let transfer_i64 = token_transfer.round_to_zero().to_num::<i64>();
-        liqor_perp_position.perp_spot_transfers -= transfer_i64;
-        liqee_perp_position.perp_spot_transfers += transfer_i64;
-        liqor.fixed.perp_spot_transfers -= transfer_i64;
-        liqee.fixed.perp_spot_transfers += transfer_i64;
+    // Each lot the base position gets closer to 0, the "unweighted health unsettled pnl"
+    // increases by this amount
+    let uhupnl_per_lot;
 
-        // Transfer token balance
-        let liqor_token_position = liqor.token_position_mut(settle_token_index)?.0;
-        let liqee_token_position = liqee.token_position_mut(settle_token_index)?.0;
-        settle_bank.deposit(liqee_token_position, token_transfer, now_ts)?;
-        settle_bank.withdraw_without_fee(liqor_token_position, token_transfer, now_ts)?;
-        liqee_health_cache.adjust_token_balance(&settle_bank, token_transfer)?;
+    // -1 (liqee base lots decrease) or +1 (liqee base lots increase)
+    let direction: i64;
 
-        msg!(
-            "pnl {} was transferred to liqor for quote {} with settle limit {} recurring/{} oneshot",
-            pnl_transfer,
-            token_transfer,
-            limit_transfer_recurring,
-            limit_transfer_oneshot,
-        );
+    // Either 1+fee or 1-fee, depending on direction.
+    let base_fee_factor_liqor;
+    let base_fee_factor_all;
+
+    // With the waiver, the liqor also receives the platform fee
+    let platform_fee_to_liqor = if platform_fee_waived {
+        perp_market.platform_liquidation_fee
     } else {
-        limit_transfer_oneshot = 0;
-        limit_transfer_recurring = 0;
+        I80F48::ZERO
     };
 
-    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
-    liqee_health_cache.recompute_perp_info(liqee_perp_position, &perp_market)?;
+    if liqee_base_lots > 0 {
+        require_msg_typed!(
+            max_base_transfer >= 0,
+            MangoError::LiquidationWrongDirection,
+            "max_base_transfer can't be negative when liqee's base_position is positive"
+        );
 
-    Ok((
-        base_transfer,
-        quote_transfer_liqee,
-        quote_transfer_liqor,
-        platform_fee,
-        pnl_transfer,
-        limit_transfer_recurring,
-        limit_transfer_oneshot,
-    ))
-}
+        // the health_unsettled_pnl gets reduced by `base * base_price * perp_init_asset_weight`
+        // and increased by `base * base_price * (1 - liq_fees)`
+        direction = -1;
+        base_fee_factor_liqor = I80F48::ONE
+            - perp_market.base_liquidation_fee_to_liqor(base_liquidation_fee)
+            - platform_fee_to_liqor;
+        base_fee_factor_all =
+            I80F48::ONE - base_liquidation_fee - perp_market.platform_liquidation_fee;
+        uhupnl_per_lot =
+            oracle_price_per_lot * (-perp_market.init_base_asset_weight + base_fee_factor_all);
+    } else {
+        // liqee_base_lots <= 0
+        require_msg_typed!(
+            max_base_transfer <= 0,
+            MangoError::LiquidationWrongDirection,
+            "max_base_transfer can't be positive when liqee's base_position is negative"
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::health::{self, test::*};
+        // health gets increased by `base * base_price * perp_init_liab_weight`
+        // and reduced by `base * base_price * (1 + liq_fees)`
+        direction = 1;
+        base_fee_factor_liqor = I80F48::ONE
+            + perp_market.base_liquidation_fee_to_liqor(base_liquidation_fee)
+            + platform_fee_to_liqor;
+        base_fee_factor_all =
+            I80F48::ONE + base_liquidation_fee + perp_market.platform_liquidation_fee;
+        uhupnl_per_lot =
+            oracle_price_per_lot * (perp_market.init_base_liab_weight - base_fee_factor_all);
+    };
+    require_msg!(
+        uhupnl_per_lot > 0,
+        "liquidating a base lot must improve health, but changes it by {}",
+        uhupnl_per_lot
+    );
+    explanation.health_per_lot = uhupnl_per_lot;
 
-    #[derive(Clone)]
-    struct TestSetup {
-        group: Pubkey,
-        settle_bank: TestAccount<Bank>,
-        settle_oracle: TestAccount<StubOracle>,
-        other_bank: TestAccount<Bank>,
-        other_oracle: TestAccount<StubOracle>,
-        perp_market: TestAccount<PerpMarket>,
-        perp_oracle: TestAccount<StubOracle>,
-        liqee: MangoAccountValue,
-        liqor: MangoAccountValue,
-    }
+    // Amount of settle token received for each token that is settled
+    let spot_gain_per_settled = I80F48::ONE - perp_market.positive_pnl_liquidation_fee;
 
-    impl TestSetup {
-        fn new() -> Self {
-            let group = Pubkey::new_unique();
-            let (settle_bank, settle_oracle) = mock_bank_and_oracle(group, 0, 1.0, 0.0, 0.0);
-            let (other_bank, other_oracle) = mock_bank_and_oracle(group, 1, 1.0, 0.0, 0.0);
-            let (_bank2, perp_oracle) = mock_bank_and_oracle(group, 4, 1.0, 0.5, 0.3);
-            let mut perp_market =
-                mock_perp_market(group, perp_oracle.pubkey, 1.0, 9, (0.2, 0.1), (0.05, 0.02));
-            perp_market.data().base_lot_size = 1;
+    let init_overall_asset_weight = perp_market.init_overall_asset_weight;
 
-            let liqee_buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
-            let mut liqee = MangoAccountValue::from_bytes(&liqee_buffer).unwrap();
-            {
-                liqee.ensure_token_position(0).unwrap();
-                liqee.ensure_token_position(1).unwrap();
-                liqee.ensure_perp_position(9, 0).unwrap();
-            }
+    //
+    // Several steps of perp base position reduction will follow, and they'll update
+    // these variables
+    //
+    let mut base_reduction = 0;
+    let mut pnl_transfer = I80F48::ZERO;
+    let mut current_uhupnl = perp_info.unweighted_health_unsettled_pnl(liq_end_type);
+    let mut current_health = liqee_liq_end_health;
+    let mut current_settle_token = settle_token_balance.spot_and_perp;
 
-            let liqor_buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
-            let mut liqor = MangoAccountValue::from_bytes(&liqor_buffer).unwrap();
-            {
-                liqor.ensure_token_position(0).unwrap();
-                liqor.ensure_token_position(1).unwrap();
-                liqor.ensure_perp_position(9, 0).unwrap();
+    // The liqee health may not exceed this in this call, if the liqor limits the improvement
+    let max_health_for_improvement = max_health_improvement.map(|cap| liqee_liq_end_health + cap);
+    // How much settle token can be gained before reaching max_health_for_improvement?
+    let max_settle_token_for_improvement =
+        |current_health: I80F48, current_settle_token: I80F48| -> Result<Option<I80F48>> {
+            max_health_for_improvement
+                .map(|max_health| {
+                    spot_amount_given_for_health_zero(
+                        current_health - max_health,
+                        current_settle_token,
+                        settle_token_info.asset_weighted_price(liq_end_type),
+                        settle_token_info.liab_weighted_price(liq_end_type),
+                    )
+                })
+                .transpose()
+        };
+
+    // Helper function to reduce base position in exchange for effective settle token balance changes
+    //
+    // This function does only deal with reducing the base position and getting settle token balance
+    // in exchange. This exchange does not always lead to health improvements (like when the overall
+    // weight is 0 and the uhupnl is already positive). That's why we pass an "expected" gain per lot
+    // as well, which incorporates the fact that the increase in uhupnl can get settled later and
+    // becomes health then.
+    //
+    // This means that the amount of base_lots reduced by this function is determined in anticipation
+    // of future pnl settlement. It only changes the current_* args according to the actual reduction
+    // that happened. The settlement happens later, separately, in settle_pnl() below.
+    let mut reduce_base = |step: &str,
+                           uhupnl_limit: I80F48,
+                           // How much effective settle token will be gained when taking future
+                           // upnl settlement into account.
+                           expected_settle_token_per_lot: I80F48,
+                           // Effective settle token gained directly from the operation (before settlement)
+                           actual_settle_token_per_lot: I80F48,
+                           uhupnl_per_lot: I80F48,
+                           current_uhupnl: &mut I80F48,
+                           current_settle_token: &mut I80F48,
+                           current_health: &mut I80F48|
+     -> Result<()> {
+        let max_settle_token_for_health = spot_amount_given_for_health_zero(
+            *current_health,
+            *current_settle_token,
+            settle_token_info.asset_weighted_price(liq_end_type),
+            settle_token_info.liab_weighted_price(liq_end_type),
+        )?;
+
+        let max_settle_token = max_settle_token_for_health.min(uhupnl_limit);
+        let mut constraint = if max_settle_token < max_settle_token_for_health {
+            LiquidationConstraint::PnlLimit
+        } else {
+            LiquidationConstraint::Health
+        };
+
+        // A tiny per-lot gain can make lot counts overflow, so don't panic on it
+        let lots_overflow = |settle_token: I80F48| {
+            error_msg_typed!(
+                MangoError::MathOverflow,
+                "{}: lots for {} settle token at {} per lot overflow",
+                step,
+                settle_token,
+                expected_settle_token_per_lot
+            )
+        };
+        let lots_for = |settle_token: I80F48| -> Result<I80F48> {
+            settle_token
+                .checked_div(expected_settle_token_per_lot)
+                .ok_or_else(|| lots_overflow(settle_token))
+        };
+
+        // How many lots to transfer?
+        let mut base_lots = lots_for(max_settle_token)?
+            .checked_ceil() // overshoot to aim for init_health >= 0
+            .and_then(|lots| lots.checked_to_num::<i64>())
+            .ok_or_else(|| lots_overflow(max_settle_token))?;
+        let liqee_lots_left = liqee_base_lots.abs() - base_reduction;
+        if liqee_lots_left < base_lots {
+            base_lots = liqee_lots_left;
+            constraint = LiquidationConstraint::LiqeeBasePosition;
+        }
+        let max_lots_left = max_base_transfer.abs() - base_reduction;
+        if max_lots_left < base_lots {
+            base_lots = max_lots_left;
+            constraint = LiquidationConstraint::MaxBaseTransfer;
+        }
+        base_lots = base_lots.max(0);
+
+        let expected_health_after = |base_lots: i64| {
+            let expected_settle_token_gain =
+                expected_settle_token_per_lot * I80F48::from(base_lots);
+            let new_expected_settle_token = *current_settle_token + expected_settle_token_gain;
+            *current_health
+                - settle_token_info.health_contribution(liq_end_type, *current_settle_token)
+                + settle_token_info.health_contribution(liq_end_type, new_expected_settle_token)
+        };
+
+        // Rounding up may push the liqee far into positive health when lots are large.
+        // Give back lots that would exceed the cap, but always keep making progress.
+        if let Some(max_health) = max_overshoot_health {
+            let max_settle_token = spot_amount_given_for_health_zero(
+                *current_health - max_health,
+                *current_settle_token,
+                settle_token_info.asset_weighted_price(liq_end_type),
+                settle_token_info.liab_weighted_price(liq_end_type),
+            )?;
+            let max_lots = lots_for(max_settle_token)?
+                .floor()
+                .saturating_to_num::<i64>()
+                .max(1);
+            if max_lots < base_lots {
+                base_lots = max_lots;
+                constraint = LiquidationConstraint::MaxOvershootHealth;
             }
+        }
 
-            Self {
-                group,
-                settle_bank,
-                settle_oracle,
-                other_bank,
-                other_oracle,
-                perp_market,
-                perp_oracle,
-                liqee,
-                liqor,
+        // Unlike the overshoot cap, this may stop the base reduction entirely
+        if let Some(max_settle_token) =
+            max_settle_token_for_improvement(*current_health, *current_settle_token)?
+        {
+            let max_lots = lots_for(max_settle_token)?
+                .floor()
+                .saturating_to_num::<i64>()
+                .max(0);
+            if max_lots < base_lots {
+                base_lots = max_lots;
+                constraint = LiquidationConstraint::MaxHealthImprovement;
             }
         }
 
-        fn liqee_health_cache(&self) -> HealthCache {
-            let mut setup = self.clone();
+        let lots_for_health = lots_for(max_settle_token_for_health)?
+            .checked_ceil()
+            .ok_or_else(|| lots_overflow(max_settle_token_for_health))?
+            .saturating_to_num::<i64>()
+            .max(0);
+        explanation.base_transfer_for_zero = base_reduction.saturating_add(lots_for_health);
+        explanation.binding_constraint = constraint;
 
-            let ais = vec![
-                setup.settle_bank.as_account_info(),
-                setup.other_bank.as_account_info(),
-                setup.settle_oracle.as_account_info(),
-                setup.other_oracle.as_account_info(),
-                setup.perp_market.as_account_info(),
-                setup.perp_oracle.as_account_info(),
-            ];
-            let retriever =
-                ScanningAccountRetriever::new_with_staleness(&ais, &setup.group, None).unwrap();
+        // Note, the expected health is just for logging
+        let new_expected_health = expected_health_after(base_lots);
 
-            health::new_health_cache(&setup.liqee.borrow(), &retriever, 0).unwrap()
-        }
+        let uhupnl_gain = uhupnl_per_lot * I80F48::from(base_lots);
+        let new_uhupnl = *current_uhupnl + uhupnl_gain;
 
-        fn run(&self, max_base: i64, max_pnl: u64) -> Result<Self> {
-            let mut setup = self.clone();
+        let new_settle_token =
+            *current_settle_token + actual_settle_token_per_lot * I80F48::from(base_lots);
+        let new_health = *current_health
+            - settle_token_info.health_contribution(liq_end_type, *current_settle_token)
+            + settle_token_info.health_contribution(liq_end_type, new_settle_token);
 
-            let mut liqee_health_cache = setup.liqee_health_cache();
-            let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+        msg!(
+            "{} total: {} lots, health {} -> {}, exhealth -> {}, uhupnl: {} -> {}",
+            step,
+            base_lots,
+            // logging i64 is significantly cheaper
+            current_health.to_num::<i64>(),
+            new_health.to_num::<i64>(),
+            new_expected_health.to_num::<i64>(),
+            current_uhupnl.to_num::<i64>(),
+            new_uhupnl.to_num::<i64>(),
+        );
 
-            liquidation_action(
-                setup.perp_market.data(),
-                setup.settle_bank.data(),
-                &mut setup.liqor.borrow_mut(),
-                &mut setup.liqee.borrow_mut(),
-                &mut liqee_health_cache,
-                liqee_liq_end_health,
-                0,
-                max_base,
-                max_pnl,
-            )?;
+        *current_settle_token = new_settle_token;
+        *current_health = new_health;
+        *current_uhupnl = new_uhupnl;
 
-            Ok(setup)
-        }
-    }
+        base_reduction += base_lots;
+        Ok(())
+    };
 
-    fn token_p(account: &mut MangoAccountValue) -> &mut TokenPosition {
-        account.token_position_mut(0).unwrap().0
+    let settle_pnl = |step: &str,
+                      settle_token_per_settle: I80F48,
+                      pnl_transfer: &mut I80F48,
+                      current_uhupnl: &mut I80F48,
+                      current_settle_token: &mut I80F48,
+                      current_health: &mut I80F48|
+     -> Result<()> {
+        let max_settle_token_for_health = spot_amount_given_for_health_zero(
+            *current_health,
+            *current_settle_token,
+            settle_token_info.asset_weighted_price(liq_end_type),
+            settle_token_info.liab_weighted_price(liq_end_type),
+        )?;
+
+        let max_settle_token =
+            match max_settle_token_for_improvement(*current_health, *current_settle_token)? {
+                Some(max_for_improvement) => max_settle_token_for_health.min(max_for_improvement),
+                None => max_settle_token_for_health,
+            };
+
+        // How many units to settle?
+        let settle = (max_settle_token / settle_token_per_settle)
+            .min(max_pnl_transfer - *pnl_transfer)
+            .min(*current_uhupnl)
+            .max(I80F48::ZERO);
+
+        let settle_token_gain = settle * settle_token_per_settle;
+        let new_settle_token = *current_settle_token + settle_token_gain;
+        let new_uhupnl = *current_uhupnl - settle;
+        let new_expected_health = *current_health
+            - settle_token_info.health_contribution(liq_end_type, *current_settle_token)
+            + settle_token_info.health_contribution(liq_end_type, new_settle_token);
+
+        msg!(
+            "{}: {} settled, health {} -> {}, uhupnl: {} -> {}",
+            step,
+            settle.to_num::<i64>(),
+            current_health.to_num::<i64>(),
+            new_expected_health.to_num::<i64>(),
+            current_uhupnl.to_num::<i64>(),
+            new_uhupnl.to_num::<i64>(),
+        );
+
+        *current_settle_token = new_settle_token;
+        *current_health = new_expected_health;
+        *current_uhupnl = new_uhupnl;
+
+        *pnl_transfer += settle;
+        Ok(())
+    };
+
+    //
+    // Step 1: While the perp unsettled health is negative, any perp base position reduction
+    // directly increases it for the full amount.
+    //
+    if current_uhupnl < 0 {
+        reduce_base(
+            "negative",
+            -current_uhupnl,
+            // for negative values uhupnl and hupnl are the same and increases
+            // directly improve health
+            uhupnl_per_lot,
+            uhupnl_per_lot,
+            uhupnl_per_lot,
+            &mut current_uhupnl,
+            &mut current_settle_token,
+            &mut current_health,
+        )?;
     }
-    fn other_p(account: &mut MangoAccountValue) -> &mut TokenPosition {
-        account.token_position_mut(1).unwrap().0
+
+    //
+    // Step 2: pnl settlement if uhupnl > 0
+    //
+    if current_uhupnl >= 0 && spot_gain_per_settled > init_overall_asset_weight {
+        // Settlement produces direct spot (after fees) and loses perp-positive-uhupnl weighted settle token pos
+        let settle_token_per_settle = spot_gain_per_settled - init_overall_asset_weight;
+        settle_pnl(
+            "pre-settle",
+            settle_token_per_settle,
+            &mut pnl_transfer,
+            &mut current_uhupnl,
+            &mut current_settle_token,
+            &mut current_health,
+        )?;
     }
-    fn perp_p(account: &mut MangoAccountValue) -> &mut PerpPosition {
-        account.perp_position_mut(9).unwrap()
+
+    //
+    // Step 3: If perp unsettled health is positive but below max_settle, perp base position reductions
+    // benefit account health slightly less because of the settlement liquidation fee.
+    //
+    if current_uhupnl >= 0 && pnl_transfer < max_pnl_transfer {
+        reduce_base(
+            "settleable",
+            max_pnl_transfer - pnl_transfer,
+            uhupnl_per_lot * spot_gain_per_settled,
+            uhupnl_per_lot * init_overall_asset_weight,
+            uhupnl_per_lot,
+            &mut current_uhupnl,
+            &mut current_settle_token,
+            &mut current_health,
+        )?;
     }
 
-    macro_rules! assert_eq_f {
-        ($value:expr, $expected:expr, $max_error:expr) => {
-            let value = $value;
-            let expected = $expected;
-            let ok = (value.to_num::<f64>() - expected).abs() < $max_error;
-            assert!(ok, "value: {value}, expected: {expected}");
-        };
+    //
+    // Step 4: Again, pnl settlement if uhupnl > 0
+    //
+    if current_uhupnl >= 0 && spot_gain_per_settled > init_overall_asset_weight {
+        // Settlement produces direct spot (after fees) and loses perp-positive-uhupnl weighted settle token pos
+        let settle_token_per_settle = spot_gain_per_settled - init_overall_asset_weight;
+        settle_pnl(
+            "post-settle",
+            settle_token_per_settle,
+            &mut pnl_transfer,
+            &mut current_uhupnl,
+            &mut current_settle_token,
+            &mut current_health,
+        )?;
     }
 
-    #[test]
-    fn test_liq_base_or_positive_pnl() {
-        let test_cases = vec![
-            (
+    //
+    // Step 5: Above that, perp base positions only benefit account health if the pnl asset weight is positive
+    //
+    // Using -0.5 as a health target prevents rounding related issues. Any health >-1 will be enough
+    // to get the account out of being-liquidated state.
+    let health_target = I80F48::from_num(-0.5);
+    if current_health < health_target && init_overall_asset_weight > 0 {
+        let settle_token_per_lot = uhupnl_per_lot * init_overall_asset_weight;
+        reduce_base(
+            "positive",
+            I80F48::MAX,
+            settle_token_per_lot,
+            settle_token_per_lot,
+            uhupnl_per_lot,
+            &mut current_uhupnl,
+            &mut current_settle_token,
+            &mut current_health,
+        )?;
+    }
+
+    //
+    // Execute the base reduction. This is essentially a forced trade and updates the
+    // liqee and liqors entry and break even prices.
+    //
+    // It's allowed in reduce-only markets: it reduces the liqee position, and liqors must be
+    // able to take over positions while a market is wound down.
+    //
+    assert!(base_reduction <= liqee_base_lots.abs());
+    perp_market.check_liquidation_base_lots(liqee_base_lots, base_reduction)?;
+    let base_transfer = direction * base_reduction;
+    let quote_transfer_base = -I80F48::from(base_transfer) * price_per_lot;
+    // The last lots of the liqee position earn the liqor an extra fee. The steps above
+    // don't take it into account, it's small compared to the health gained.
+    let dust_lots = perp_market.liquidation_dust_lots(liqee_base_lots, base_reduction);
+    let dust_fee = I80F48::from(dust_lots)
+        * price_per_lot
+        * perp_market.dust_liquidation_fee_boost(base_liquidation_fee);
+    let quote_transfer_liqee = quote_transfer_base * base_fee_factor_all - dust_fee;
+    // The rounding buffer ends up in the platform fee below
+    let rounding_buffer = if base_transfer != 0 {
+        I80F48::from_num(perp_market.liquidation_quote_rounding_buffer)
+    } else {
+        I80F48::ZERO
+    };
+    let quote_transfer_liqor =
+        -quote_transfer_base * base_fee_factor_liqor + dust_fee - rounding_buffer;
+    #[cfg(feature = "paranoid")]
+    {
+        liqee_perp_position.validate(perp_market)?;
+        liqor_perp_position.validate(perp_market)?;
+    }
+
+    if base_transfer != 0 {
+        msg!(
+            "transfering: {} base lots and {} quote",
+            base_transfer,
+            quote_transfer_liqee
+        );
+        liqee_perp_position.record_trade(perp_market, base_transfer, quote_transfer_liqee);
+        liqor_perp_position.record_trade(perp_market, -base_transfer, quote_transfer_liqor);
+    }
+
+    // We know that this is positive:
+    // liq a long: base_transfer < 0, quote_transfer_base > 0, base_fee_factor < 1
+    //   and -q_t_liqor >= q_t_liqee (both sides positive; take more from the liqor than we give to the liqee)
+    // liq a short: base_transfer > 0, quote_transfer_base < 0, base_fee_factor > 1
+    //   and -q_t_liqor >= q_t_liqee (both sides negative; we take more from the liqee than we give to the liqor)
+    let platform_fee = (-quote_transfer_liqor - quote_transfer_liqee).max(I80F48::ZERO);
+    perp_market.fees_accrued += platform_fee;
+    perp_market.accrued_liquidation_fees += platform_fee;
+
+    //
+    // Let the liqor take over positive pnl until the account health is positive,
+    // but only while the health_unsettled_pnl is positive (otherwise it would decrease liqee health!)
+    //
+    let limit_transfer_recurring: i64;
+    let limit_transfer_oneshot: i64;
+    let mut pnl_takeover_reward = I80F48::ZERO;
+    if pnl_transfer > 0 {
+        // Allow taking over *more* than the liqee_positive_settle_limit. In exchange, the liqor
+        // also can't settle fully immediately and just takes over a fractional chunk of the limit.
+        //
+        // If this takeover were limited by the settle limit, then we couldn't always bring the liqee
+        // base position to zero and would need to deal with that in bankruptcy. Also, the settle
+        // limit changes with the base position price, so it'd be hard to say when this liquidation
+        // step is done.
+        {
+            // take care, liqee_limit may be i64::MAX
+            let liqee_limit: i128 = liqee_positive_settle_limit.into();
+            let liqee_oneshot_positive = liqee_perp_position
+                .oneshot_settle_pnl_allowance
+                .ceil()
+                .to_num::<i128>()
+                .max(0);
+            let liqee_recurring = (liqee_limit - liqee_oneshot_positive).max(0);
+            let liqee_pnl = liqee_perp_position
+                .unsettled_pnl(perp_market, oracle_price)?
+                .ceil()
+                .to_num::<i128>()
+                .max(1);
+            let settle = pnl_transfer.floor().to_num::<i128>();
+            let total = liqee_pnl.max(settle);
+            let transfer_recurring: i64 = (liqee_recurring * settle / total).try_into().unwrap();
+            let transfer_oneshot: i64 = (liqee_oneshot_positive * settle / total)
+                .try_into()
+                .unwrap();
+
+            // never transfer more than pnl_transfer rounded up
+            // and transfer at least 1, to compensate for rounding down `settle` and int div
+            let max_transfer = pnl_transfer.ceil().to_num::<i64>();
+            limit_transfer_recurring = transfer_recurring.min(max_transfer).max(1);
+            // make is so the sum of recurring and oneshot doesn't exceed max_transfer
+            limit_transfer_oneshot = transfer_oneshot
+                .min(max_transfer - limit_transfer_recurring)
+                .max(0);
+        };
+
+        // The liqor pays less than the full amount to receive the positive pnl
+        let token_transfer = pnl_transfer * spot_gain_per_settled;
+        pnl_takeover_reward = pnl_transfer - token_transfer;
+
+        liqor_perp_position.record_liquidation_pnl_takeover(
+            pnl_transfer,
+            limit_transfer_recurring,
+            limit_transfer_oneshot,
+        );
+        liqee_perp_position.record_settle(pnl_transfer, &perp_market);
+
+        // Update the accounts' perp_spot_transfer statistics.
+        let transfer_i64 = token_transfer.round_to_zero().to_num::<i64>();
+        liqor_perp_position.perp_spot_transfers -= transfer_i64;
+        liqee_perp_position.perp_spot_transfers += transfer_i64;
+        liqor.fixed.perp_spot_transfers -= transfer_i64;
+        liqee.fixed.perp_spot_transfers += transfer_i64;
+        liqee.fixed.record_perp_liquidation_fee(pnl_takeover_reward);
+
+        // Transfer token balance
+        let liqor_token_position = liqor.token_position_mut(settle_token_index)?.0;
+        let liqee_token_position = liqee.token_position_mut(settle_token_index)?.0;
+        settle_bank.deposit(liqee_token_position, token_transfer, now_ts)?;
+        settle_bank.withdraw_without_fee(liqor_token_position, token_transfer, now_ts)?;
+        liqee_health_cache.adjust_token_balance(&settle_bank, token_transfer)?;
+
+        msg!(
+            "pnl {} was transferred to liqor for quote {} with settle limit {} recurring/{} oneshot",
+            pnl_transfer,
+            token_transfer,
+            limit_transfer_recurring,
+            limit_transfer_oneshot,
+        );
+    } else {
+        limit_transfer_oneshot = 0;
+        limit_transfer_recurring = 0;
+    };
+
+    // The liqor's reward is the base liquidation fee it receives plus the discount
+    // on the taken over pnl. Abort if that doesn't cover the liqor's costs.
+    if let Some(min_reward) = min_liqor_reward {
+        let liqor_reward = quote_transfer_base + quote_transfer_liqor + pnl_takeover_reward;
+        require_msg_typed!(
+            liqor_reward >= min_reward,
+            MangoError::LiquidationNotProfitable,
+            "liqor reward {} is less than required {}",
+            liqor_reward,
+            min_reward
+        );
+    }
+
+    if base_transfer != 0 {
+        liqee
+            .fixed
+            .record_liquidation(base_transfer, quote_transfer_liqee);
+        liqee
+            .fixed
+            .record_perp_liquidation_fee(quote_transfer_base - quote_transfer_liqee);
+    }
+
+    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+    liqee_health_cache.recompute_perp_info(liqee_perp_position, &perp_market)?;
+
+    #[cfg(feature = "paranoid")]
+    {
+        liqee_perp_position.validate(perp_market)?;
+        liqor
+            .perp_position(perp_market_index)?
+            .validate(perp_market)?;
+    }
+
+    Ok((
+        base_transfer,
+        quote_transfer_liqee,
+        quote_transfer_liqor,
+        platform_fee,
+        pnl_transfer,
+        limit_transfer_recurring,
+        limit_transfer_oneshot,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::{self, test::*};
+
+    #[derive(Clone)]
+    struct TestSetup {
+        group: Pubkey,
+        settle_bank: TestAccount<Bank>,
+        settle_oracle: TestAccount<StubOracle>,
+        other_bank: TestAccount<Bank>,
+        other_oracle: TestAccount<StubOracle>,
+        perp_market: TestAccount<PerpMarket>,
+        perp_oracle: TestAccount<StubOracle>,
+        liqee: MangoAccountValue,
+        liqor: MangoAccountValue,
+        max_overshoot_health: Option<I80F48>,
+        min_liqor_reward: Option<I80F48>,
+        max_health_improvement: Option<I80F48>,
+        platform_fee_waived: bool,
+    }
+
+    impl TestSetup {
+        fn new() -> Self {
+            let group = Pubkey::new_unique();
+            let (settle_bank, settle_oracle) = mock_bank_and_oracle(group, 0, 1.0, 0.0, 0.0);
+            let (other_bank, other_oracle) = mock_bank_and_oracle(group, 1, 1.0, 0.0, 0.0);
+            let (_bank2, perp_oracle) = mock_bank_and_oracle(group, 4, 1.0, 0.5, 0.3);
+            let mut perp_market =
+                mock_perp_market(group, perp_oracle.pubkey, 1.0, 9, (0.2, 0.1), (0.05, 0.02));
+            perp_market.data().base_lot_size = 1;
+
+            let liqee_buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+            let mut liqee = MangoAccountValue::from_bytes(&liqee_buffer).unwrap();
+            {
+                liqee.ensure_token_position(0).unwrap();
+                liqee.ensure_token_position(1).unwrap();
+                liqee.ensure_perp_position(9, 0).unwrap();
+            }
+
+            let liqor_buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+            let mut liqor = MangoAccountValue::from_bytes(&liqor_buffer).unwrap();
+            {
+                liqor.ensure_token_position(0).unwrap();
+                liqor.ensure_token_position(1).unwrap();
+                liqor.ensure_perp_position(9, 0).unwrap();
+            }
+
+            Self {
+                group,
+                settle_bank,
+                settle_oracle,
+                other_bank,
+                other_oracle,
+                perp_market,
+                perp_oracle,
+                liqee,
+                liqor,
+                max_overshoot_health: None,
+                min_liqor_reward: None,
+                max_health_improvement: None,
+                platform_fee_waived: false,
+            }
+        }
+
+        fn liqee_health_cache(&self) -> HealthCache {
+            self.liqee_health_cache_at(None).unwrap()
+        }
+
+        /// Like the handler, with oracle staleness checked against `now` = (ts, slot)
+        fn liqee_health_cache_at(&self, now: Option<(u64, u64)>) -> Result<HealthCache> {
+            let mut setup = self.clone();
+
+            let ais = vec![
+                setup.settle_bank.as_account_info(),
+                setup.other_bank.as_account_info(),
+                setup.settle_oracle.as_account_info(),
+                setup.other_oracle.as_account_info(),
+                setup.perp_market.as_account_info(),
+                setup.perp_oracle.as_account_info(),
+            ];
+            let retriever = ScanningAccountRetriever::new_with_staleness(&ais, &setup.group, now)?;
+
+            health::new_health_cache(&setup.liqee.borrow(), &retriever, 0)
+        }
+
+        fn run(&self, max_base: i64, max_pnl: u64) -> Result<Self> {
+            self.run_with_price(max_base, max_pnl, None)
+        }
+
+        fn run_with_price(
+            &self,
+            max_base: i64,
+            max_pnl: u64,
+            liqor_price: Option<f64>,
+        ) -> Result<Self> {
+            let mut setup = self.clone();
+
+            let mut liqee_health_cache = setup.liqee_health_cache();
+            let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+
+            let base_liquidation_fee = setup.perp_market.data().base_liquidation_fee;
+            liquidation_action(
+                setup.perp_market.data(),
+                base_liquidation_fee,
+                setup.settle_bank.data(),
+                &mut setup.liqor.borrow_mut(),
+                &mut setup.liqee.borrow_mut(),
+                &mut liqee_health_cache,
+                liqee_liq_end_health,
+                0,
+                max_base,
+                max_pnl,
+                liqor_price_from_num(liqor_price)?,
+                setup.max_overshoot_health,
+                setup.min_liqor_reward,
+                setup.max_health_improvement,
+                setup.platform_fee_waived,
+            )?;
+
+            Ok(setup)
+        }
+
+        fn run_explained(
+            &self,
+            max_base: i64,
+            max_pnl: u64,
+            liqor_price: Option<f64>,
+        ) -> Result<(Self, LiquidationExplanation)> {
+            let mut setup = self.clone();
+
+            let mut liqee_health_cache = setup.liqee_health_cache();
+            let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+
+            let mut explanation = LiquidationExplanation::default();
+            let base_liquidation_fee = setup.perp_market.data().base_liquidation_fee;
+            liquidation_action_explained(
+                setup.perp_market.data(),
+                base_liquidation_fee,
+                setup.settle_bank.data(),
+                &mut setup.liqor.borrow_mut(),
+                &mut setup.liqee.borrow_mut(),
+                &mut liqee_health_cache,
+                liqee_liq_end_health,
+                0,
+                max_base,
+                max_pnl,
+                liqor_price_from_num(liqor_price)?,
+                setup.max_overshoot_health,
+                setup.min_liqor_reward,
+                setup.max_health_improvement,
+                setup.platform_fee_waived,
+                &mut explanation,
+            )?;
+
+            Ok((setup, explanation))
+        }
+    }
+
+    fn token_p(account: &mut MangoAccountValue) -> &mut TokenPosition {
+        account.token_position_mut(0).unwrap().0
+    }
+    fn other_p(account: &mut MangoAccountValue) -> &mut TokenPosition {
+        account.token_position_mut(1).unwrap().0
+    }
+    fn perp_p(account: &mut MangoAccountValue) -> &mut PerpPosition {
+        account.perp_position_mut(9).unwrap()
+    }
+
+    macro_rules! assert_eq_f {
+        ($value:expr, $expected:expr, $max_error:expr) => {
+            let value = $value;
+            let expected = $expected;
+            let ok = (value.to_num::<f64>() - expected).abs() < $max_error;
+            assert!(ok, "value: {value}, expected: {expected}");
+        };
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl() {
+        let test_cases = vec![
+            (
                 "nothing",
                 (0.9, 0.9, 0.0),
                 (0.0, 0, 0.0, 0.0),
@@ -1006,130 +2171,1547 @@ mod tests {
                 (60, 20),
             ),
             (
-                "settle fee 3",
-                (0.5, 0.5, 0.25),
-                (-7.0, 70, -30.0, -6.0),
-                // no "reduce while health negative"
-                // pre-settle 5
-                // reduce 25 + post-settle 12
-                (-7.0 + 12.75, 45, -22.0), // 5.75 + (45*0.5 - 22)*0.5 = 6
-                (60, 20),
-            ),
+                "settle fee 3",
+                (0.5, 0.5, 0.25),
+                (-7.0, 70, -30.0, -6.0),
+                // no "reduce while health negative"
+                // pre-settle 5
+                // reduce 25 + post-settle 12
+                (-7.0 + 12.75, 45, -22.0), // 5.75 + (45*0.5 - 22)*0.5 = 6
+                (60, 20),
+            ),
+            (
+                "settle fee 4",
+                (0.5, 0.5, 0.25),
+                (-7.0, 70, -30.0, 4.0),
+                // no "reduce while health negative"
+                // pre-settle 2
+                (-7.0 + 1.5, 70, -32.0), // -5.5 + (70*0.5 - 32)*0.5 = -4
+                (60, 20),
+            ),
+        ];
+
+        for (
+            name,
+            // the perp base asset weight (liab is symmetric) and the perp overall asset weight to use
+            (base_weight, overall_weight, pos_pnl_liq_fee),
+            // the liqee's starting point: (-5, 10, -5, 1) would mean:
+            // USDC: -5, perp base: 10, perp quote -5, other token: 1 (which also has weight/price 1)
+            (init_liqee_spot, init_liqee_base, init_liqee_quote, init_other_spot),
+            // the expected liqee end position
+            (exp_liqee_spot, exp_liqee_base, exp_liqee_quote),
+            // maximum liquidation the liqor requests
+            (max_base, max_pnl),
+        ) in test_cases
+        {
+            println!("test: {name}");
+            let mut setup = TestSetup::new();
+            {
+                let pm = setup.perp_market.data();
+                pm.init_base_asset_weight = I80F48::from_num(base_weight);
+                pm.init_base_liab_weight = I80F48::from_num(2.0 - base_weight);
+                pm.init_overall_asset_weight = I80F48::from_num(overall_weight);
+                pm.positive_pnl_liquidation_fee = I80F48::from_num(pos_pnl_liq_fee);
+            }
+            {
+                let p = perp_p(&mut setup.liqee);
+                p.record_trade(
+                    setup.perp_market.data(),
+                    init_liqee_base,
+                    I80F48::from_num(init_liqee_quote),
+                );
+                p.oneshot_settle_pnl_allowance = p
+                    .unsettled_pnl(setup.perp_market.data(), I80F48::ONE)
+                    .unwrap();
+
+                let settle_bank = setup.settle_bank.data();
+                settle_bank
+                    .change_without_fee(
+                        token_p(&mut setup.liqee),
+                        I80F48::from_num(init_liqee_spot),
+                        0,
+                    )
+                    .unwrap();
+                settle_bank
+                    .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                    .unwrap();
+
+                let other_bank = setup.other_bank.data();
+                other_bank
+                    .change_without_fee(
+                        other_p(&mut setup.liqee),
+                        I80F48::from_num(init_other_spot),
+                        0,
+                    )
+                    .unwrap();
+            }
+
+            let mut result = setup.run(max_base, max_pnl).unwrap();
+
+            let liqee_perp = perp_p(&mut result.liqee);
+            assert_eq!(liqee_perp.base_position_lots(), exp_liqee_base);
+            assert_eq_f!(liqee_perp.quote_position_native(), exp_liqee_quote, 0.01);
+
+            let liqor_perp = perp_p(&mut result.liqor);
+            assert_eq!(
+                liqor_perp.base_position_lots(),
+                -(exp_liqee_base - init_liqee_base)
+            );
+            assert_eq_f!(
+                liqor_perp.quote_position_native(),
+                -(exp_liqee_quote - init_liqee_quote),
+                0.01
+            );
+            // The settle limit taken over matches the quote pos when removing the
+            // quote gains from giving away base lots
+            assert_eq_f!(
+                I80F48::from_num(liqor_perp.recurring_settle_pnl_allowance)
+                    + liqor_perp.oneshot_settle_pnl_allowance,
+                liqor_perp.quote_position_native.to_num::<f64>()
+                    + liqor_perp.base_position_lots as f64,
+                1.1
+            );
+
+            let settle_bank = result.settle_bank.data();
+            assert_eq_f!(
+                token_p(&mut result.liqee).native(settle_bank),
+                exp_liqee_spot,
+                0.01
+            );
+            assert_eq_f!(
+                token_p(&mut result.liqor).native(settle_bank),
+                1000.0 - (exp_liqee_spot - init_liqee_spot),
+                0.01
+            );
+        }
+    }
+
+    // Checks that the stable price does _not_ affect the liquidation target amount
+    #[test]
+    fn test_liq_base_or_positive_pnl_stable_price() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.stable_price_model.stable_price = 0.5;
+            pm.init_base_asset_weight = I80F48::from_num(0.6);
+            pm.maint_base_asset_weight = I80F48::from_num(0.8);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+        }
+
+        let hc = setup.liqee_health_cache();
+        assert_eq_f!(
+            hc.health(HealthType::Init),
+            5.0 + (-30.0 + 30.0 * 0.5 * 0.6), // init + stable
+            0.1
+        );
+        assert_eq_f!(
+            hc.health(HealthType::LiquidationEnd),
+            5.0 + (-30.0 + 30.0 * 0.6), // init + oracle
+            0.1
+        );
+        assert_eq_f!(
+            hc.health(HealthType::Maint),
+            5.0 + (-30.0 + 30.0 * 0.8), // maint + oracle
+            0.1
+        );
+
+        let mut result = setup.run(100, 0).unwrap();
+
+        let liqee_perp = perp_p(&mut result.liqee);
+        assert_eq!(liqee_perp.base_position_lots(), 12);
+
+        let hc = result.liqee_health_cache();
+        assert_eq_f!(
+            hc.health(HealthType::LiquidationEnd),
+            5.0 + (-12.0 + 12.0 * 0.6),
+            0.1
+        );
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_liqor_price() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.init_overall_asset_weight = I80F48::from_num(0.5);
+            pm.liquidation_price_tolerance = 0.02;
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                20,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+        }
+
+        // the oracle price is 1.0, a supplied price within 2% is used for the transfer
+        let mut result = setup.run_with_price(100, 100, Some(0.99)).unwrap();
+        let liqee_perp = perp_p(&mut result.liqee);
+        assert_eq!(liqee_perp.base_position_lots(), 0);
+        assert_eq_f!(
+            liqee_perp.quote_position_native(),
+            -30.0 + 20.0 * 0.99,
+            0.01
+        );
+        let liqor_perp = perp_p(&mut result.liqor);
+        assert_eq!(liqor_perp.base_position_lots(), 20);
+        assert_eq_f!(liqor_perp.quote_position_native(), -20.0 * 0.99, 0.01);
+
+        // out of tolerance and unrepresentable prices are rejected
+        for price in [0.97, 1.03, f64::NAN, f64::INFINITY, -f64::INFINITY, 1e30] {
+            let result = setup.run_with_price(100, 100, Some(price));
+            assert!(result.is_anchor_error_with_code(
+                MangoError::LiquidationPriceOutOfTolerance.error_code()
+            ));
+        }
+    }
+
+    // Checks that the settle token's own weights are used when sizing the base transfer
+    #[test]
+    fn test_liq_base_or_positive_pnl_settle_token_weight() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        // settle token weight 1: 10 + (-5 + (-b + 0.5 * b)) >= 0 for b <= 10
+        let mut result = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 10);
+
+        // settle token liab weight 1.2: 10 + 1.2 * (-5 - 0.5 * b) >= 0 for b <= 6.67
+        setup.settle_bank.data().init_liab_weight = I80F48::from_num(1.2);
+        let hc = setup.liqee_health_cache();
+        assert_eq_f!(
+            hc.health(HealthType::LiquidationEnd),
+            10.0 + 1.2 * (-5.0 - 15.0),
+            0.1
+        );
+
+        let mut result = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 6);
+
+        let hc = result.liqee_health_cache();
+        assert!(hc.health(HealthType::LiquidationEnd) >= 0);
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_explanation() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        // health: 10 + (-5 + (-b + 0.5 * b)), zero at b = 10
+        let (mut result, explanation) = setup.run_explained(100, 0, None).unwrap();
+        assert_eq!(explanation.oracle_price, I80F48::ONE);
+        assert_eq!(explanation.liquidation_price, I80F48::ONE);
+        assert_eq!(explanation.price_per_lot, I80F48::ONE);
+        assert_eq!(explanation.health_per_lot, I80F48::from_num(0.5));
+        assert_eq!(explanation.base_transfer_for_zero, 20);
+        assert_eq!(
+            explanation.binding_constraint,
+            LiquidationConstraint::Health
+        );
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 10);
+
+        // the explained run does the same as the regular one
+        let mut regular = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut regular.liqee).base_position_lots(), 10);
+        assert_eq!(
+            perp_p(&mut regular.liqee).quote_position_native(),
+            perp_p(&mut result.liqee).quote_position_native()
+        );
+
+        // the liqor's max_base_transfer binds; the last step ("positive") would have wanted
+        // 5 + ceil(7.5 / (0.5 * 0.95)) lots
+        let (mut result, explanation) = setup.run_explained(5, 0, None).unwrap();
+        assert_eq!(explanation.base_transfer_for_zero, 21);
+        assert_eq!(
+            explanation.binding_constraint,
+            LiquidationConstraint::MaxBaseTransfer
+        );
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 25);
+
+        // the liqor supplied price changes the quote per lot, but not the health per lot,
+        // which is valued at the oracle price
+        setup.perp_market.data().liquidation_price_tolerance = 0.02;
+        let (_, explanation) = setup.run_explained(100, 0, Some(0.99)).unwrap();
+        assert_eq!(explanation.oracle_price, I80F48::ONE);
+        assert_eq!(explanation.liquidation_price, I80F48::from_num(0.99));
+        assert_eq!(explanation.price_per_lot, I80F48::from_num(0.99));
+        assert_eq!(explanation.health_per_lot, I80F48::from_num(0.5));
+        assert_eq!(explanation.base_transfer_for_zero, 20);
+    }
+
+    // Checks that a liqor position that nets to zero doesn't keep stale entry prices
+    #[test]
+    fn test_liq_base_or_positive_pnl_liqor_nets_to_zero() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+            perp_p(&mut setup.liqor).record_trade(
+                setup.perp_market.data(),
+                -20,
+                I80F48::from_num(30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+        assert_eq!(perp_p(&mut setup.liqor).avg_entry_price_per_base_lot, 1.5);
+
+        // health: 10 + (-5 + (-b + 0.5 * b)), zero at b = 10: transfers exactly 20 lots
+        let mut result = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 10);
+
+        let perp_market = result.perp_market.data();
+        let liqor_perp = perp_p(&mut result.liqor);
+        assert_eq!(liqor_perp.base_position_lots(), 0);
+        assert_eq!(liqor_perp.quote_position_native(), I80F48::from(10));
+        assert_eq!(liqor_perp.avg_entry_price_per_base_lot, 0.0);
+        assert_eq!(liqor_perp.quote_running_native, 0);
+        assert_eq!(liqor_perp.realized_pnl_for_position_native, I80F48::ZERO);
+        assert_eq!(liqor_perp.break_even_price(perp_market), 0.0);
+
+        // re-opening starts from the new trade's price only
+        liqor_perp.record_trade(perp_market, 5, I80F48::from_num(-10));
+        assert_eq!(liqor_perp.avg_entry_price(perp_market), 2.0);
+        assert_eq!(liqor_perp.break_even_price(perp_market), 2.0);
+    }
+
+    // Quote positions are tracked in native units, so the quote transfer isn't quantized to
+    // quote lots. Checks that the liqee, liqor and platform fee amounts still add up exactly.
+    #[test]
+    fn test_liq_base_or_positive_pnl_quote_transfer_consistency() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.quote_lot_size = 7;
+            pm.base_liquidation_fee = I80F48::from_num(0.03);
+            pm.platform_liquidation_fee = I80F48::from_num(0.01);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+        let liqee_quote_before = perp_p(&mut setup.liqee).quote_position_native();
+        let fees_before = setup.perp_market.data().fees_accrued;
+
+        let mut result = setup.run(3, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 27);
+        assert_eq!(perp_p(&mut result.liqor).base_position_lots(), 3);
+
+        let liqee_quote_change =
+            perp_p(&mut result.liqee).quote_position_native() - liqee_quote_before;
+        let liqor_quote_change = perp_p(&mut result.liqor).quote_position_native();
+        let platform_fee = result.perp_market.data().fees_accrued - fees_before;
+        assert_eq_f!(liqee_quote_change, 3.0 * 0.96, 0.0001);
+        assert_eq_f!(liqor_quote_change, -3.0 * 0.97, 0.0001);
+        assert_eq_f!(platform_fee, 3.0 * 0.01, 0.0001);
+
+        // not a multiple of the quote lot size, but nothing is lost or created
+        assert!(liqee_quote_change % I80F48::from(7) != 0);
+        assert_eq!(
+            liqee_quote_change + liqor_quote_change + platform_fee,
+            I80F48::ZERO
+        );
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_oracle_checks() {
+        let mut setup = TestSetup::new();
+        perp_p(&mut setup.liqee).record_trade(setup.perp_market.data(), 30, I80F48::from_num(-30));
+        setup.perp_market.data().oracle_config.max_staleness_slots = 10;
+        setup.perp_oracle.data().last_update_slot = 100;
+
+        // the perp oracle must be recent enough
+        assert!(setup.liqee_health_cache_at(Some((0, 110))).is_ok());
+        assert!(setup
+            .liqee_health_cache_at(Some((0, 111)))
+            .is_anchor_error_with_code(MangoError::OracleStale.error_code()));
+
+        // and confident enough, independently of staleness
+        setup.perp_market.data().oracle_config.conf_filter = I80F48::from_num(0.1);
+        let price = setup.perp_oracle.data().price;
+        setup.perp_oracle.data().deviation = price * I80F48::from_num(0.05);
+        assert!(setup.liqee_health_cache_at(Some((0, 110))).is_ok());
+        setup.perp_oracle.data().deviation = price * I80F48::from_num(0.2);
+        assert!(setup
+            .liqee_health_cache_at(None)
+            .is_anchor_error_with_code(MangoError::OracleConfidence.error_code()));
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_unlimited_sentinel() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        let liqee_lots_after = |setup: &TestSetup, max_base: i64| {
+            let mut result = setup.run(max_base, 0).unwrap();
+            perp_p(&mut result.liqee).base_position_lots()
+        };
+
+        // long liqee: both sentinels liquidate as much as health requires
+        let lots = liqee_lots_after(&setup, i64::MAX);
+        assert!(lots > 0 && lots < 30);
+        assert_eq!(liqee_lots_after(&setup, i64::MIN + 1), lots);
+
+        // short liqee: same in the other direction
+        let mut short_setup = setup.clone();
+        perp_p(&mut short_setup.liqee).record_trade(
+            short_setup.perp_market.data(),
+            -60,
+            I80F48::from_num(60),
+        );
+        let short_lots = liqee_lots_after(&short_setup, i64::MIN + 1);
+        assert!(short_lots < 0 && short_lots > -30);
+        assert_eq!(liqee_lots_after(&short_setup, i64::MAX), short_lots);
+
+        // finite values still need the right sign
+        let wrong_direction = MangoError::LiquidationWrongDirection.error_code();
+        assert!(setup.run(i64::MAX - 1, 0).is_ok());
+        assert!(setup
+            .run(-(i64::MAX - 1), 0)
+            .is_anchor_error_with_code(wrong_direction));
+        assert!(short_setup
+            .run(i64::MAX - 1, 0)
+            .is_anchor_error_with_code(wrong_direction));
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_fee_insurance_fraction() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.base_liquidation_fee = I80F48::from_num(0.03);
+            pm.platform_liquidation_fee = I80F48::from_num(0.01);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        // returns liqee quote change, liqor gain over the oracle value, platform fee, liqee health
+        let run = |fraction: f32| {
+            let mut setup = setup.clone();
+            setup.perp_market.data().liquidation_fee_insurance_fraction = fraction;
+            let liqee_quote_before = perp_p(&mut setup.liqee).quote_position_native();
+            let fees_before = setup.perp_market.data().fees_accrued;
+
+            let mut result = setup.run(3, 0).unwrap();
+            assert_eq!(perp_p(&mut result.liqor).base_position_lots(), 3);
+            let liqor_gain = I80F48::from(3) + perp_p(&mut result.liqor).quote_position_native();
+            (
+                perp_p(&mut result.liqee).quote_position_native() - liqee_quote_before,
+                liqor_gain,
+                result.perp_market.data().fees_accrued - fees_before,
+                result.liqee_health_cache().health(HealthType::Init),
+            )
+        };
+
+        let (liqee_full, liqor_full, platform_full, health_full) = run(0.0);
+        assert_eq_f!(liqee_full, 3.0 * 0.96, 0.0001);
+        assert_eq_f!(liqor_full, 3.0 * 0.03, 0.0001);
+        assert_eq_f!(platform_full, 3.0 * 0.01, 0.0001);
+
+        // the liqee is unaffected, the liqor's gain is halved and the rest goes to the market
+        let (liqee_half, liqor_half, platform_half, health_half) = run(0.5);
+        assert_eq!(liqee_half, liqee_full);
+        assert_eq!(health_half, health_full);
+        assert_eq_f!(liqor_half, 3.0 * 0.015, 0.0001);
+        assert_eq_f!(platform_half, 3.0 * (0.01 + 0.015), 0.0001);
+        // nothing is lost or created: the liqor pays 3 - liqor_gain
+        assert_eq!(liqee_half + platform_half, I80F48::from(3) - liqor_half);
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_dust_fee() {
+        let new_setup = || {
+            let mut setup = TestSetup::new();
+            {
+                let pm = setup.perp_market.data();
+                pm.init_base_asset_weight = I80F48::from_num(0.5);
+                pm.init_base_liab_weight = I80F48::from_num(1.5);
+                pm.base_liquidation_fee = I80F48::from_num(0.03);
+                pm.platform_liquidation_fee = I80F48::from_num(0.01);
+                pm.dust_threshold_lots = 15;
+                pm.dust_liquidation_fee = I80F48::from_num(0.13);
+            }
+            {
+                perp_p(&mut setup.liqee).record_trade(
+                    setup.perp_market.data(),
+                    30,
+                    I80F48::from_num(-30),
+                );
+
+                let settle_bank = setup.settle_bank.data();
+                settle_bank
+                    .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                    .unwrap();
+                settle_bank
+                    .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                    .unwrap();
+
+                let other_bank = setup.other_bank.data();
+                other_bank
+                    .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                    .unwrap();
+            }
+            setup
+        };
+
+        let quote_changes = |max_base_transfer: i64| {
+            let mut setup = new_setup();
+            let liqee_quote_before = perp_p(&mut setup.liqee).quote_position_native();
+            let fees_before = setup.perp_market.data().fees_accrued;
+
+            let mut result = setup.run(max_base_transfer, 0).unwrap();
+            assert_eq!(
+                perp_p(&mut result.liqee).base_position_lots(),
+                30 - max_base_transfer
+            );
             (
-                "settle fee 4",
-                (0.5, 0.5, 0.25),
-                (-7.0, 70, -30.0, 4.0),
-                // no "reduce while health negative"
-                // pre-settle 2
-                (-7.0 + 1.5, 70, -32.0), // -5.5 + (70*0.5 - 32)*0.5 = -4
-                (60, 20),
-            ),
-        ];
+                perp_p(&mut result.liqee).quote_position_native() - liqee_quote_before,
+                perp_p(&mut result.liqor).quote_position_native(),
+                result.perp_market.data().fees_accrued - fees_before,
+            )
+        };
+
+        // staying above the dust region pays the base fee only
+        let (liqee_change, liqor_change, platform_fee) = quote_changes(10);
+        assert_eq_f!(liqee_change, 10.0 * 0.96, 0.0001);
+        assert_eq_f!(liqor_change, -10.0 * 0.97, 0.0001);
+        assert_eq_f!(platform_fee, 10.0 * 0.01, 0.0001);
+
+        // going from 30 to 12 lots, the last 3 are in the dust region
+        let (liqee_change, liqor_change, platform_fee) = quote_changes(18);
+        assert_eq_f!(liqee_change, 18.0 * 0.96 - 3.0 * 0.1, 0.0001);
+        assert_eq_f!(liqor_change, -18.0 * 0.97 + 3.0 * 0.1, 0.0001);
+        assert_eq_f!(platform_fee, 18.0 * 0.01, 0.0001);
+        assert_eq!(liqee_change + liqor_change + platform_fee, I80F48::ZERO);
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_platform_fee_waived() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.base_liquidation_fee = I80F48::from_num(0.03);
+            pm.platform_liquidation_fee = I80F48::from_num(0.01);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
 
-        for (
-            name,
-            // the perp base asset weight (liab is symmetric) and the perp overall asset weight to use
-            (base_weight, overall_weight, pos_pnl_liq_fee),
-            // the liqee's starting point: (-5, 10, -5, 1) would mean:
-            // USDC: -5, perp base: 10, perp quote -5, other token: 1 (which also has weight/price 1)
-            (init_liqee_spot, init_liqee_base, init_liqee_quote, init_other_spot),
-            // the expected liqee end position
-            (exp_liqee_spot, exp_liqee_base, exp_liqee_quote),
-            // maximum liquidation the liqor requests
-            (max_base, max_pnl),
-        ) in test_cases
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+        let fees_before = setup.perp_market.data().fees_accrued;
+
+        // without the waiver the liqor pays 0.97 per lot and the platform gets 0.01
+        let mut result = setup.run(3, 0).unwrap();
+        assert_eq_f!(
+            perp_p(&mut result.liqee).quote_position_native(),
+            -30.0 + 3.0 * 0.96,
+            0.0001
+        );
+        assert_eq_f!(
+            perp_p(&mut result.liqor).quote_position_native(),
+            -3.0 * 0.97,
+            0.0001
+        );
+        assert_eq_f!(
+            result.perp_market.data().fees_accrued - fees_before,
+            3.0 * 0.01,
+            0.0001
+        );
+
+        // with the waiver the liqor keeps the platform fee, the liqee pays the same
+        setup.platform_fee_waived = true;
+        let mut result = setup.run(3, 0).unwrap();
+        assert_eq_f!(
+            perp_p(&mut result.liqee).quote_position_native(),
+            -30.0 + 3.0 * 0.96,
+            0.0001
+        );
+        assert_eq_f!(
+            perp_p(&mut result.liqor).quote_position_native(),
+            -3.0 * 0.96,
+            0.0001
+        );
+        assert_eq!(result.perp_market.data().fees_accrued, fees_before);
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_quote_rounding_buffer() {
+        let mut setup = TestSetup::new();
         {
-            println!("test: {name}");
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        for (base, quote) in [(30, -30.0), (-30, 30.0)] {
+            let mut setup = setup.clone();
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                base,
+                I80F48::from_num(quote),
+            );
+            let fees_before = setup.perp_market.data().fees_accrued;
+
+            // liquidating a long, the liqor pays 3 for 3 lots; for a short they get 3
+            let liqor_quote = -3.0 * base.signum() as f64;
+            let mut result = setup.run(3 * base.signum(), 0).unwrap();
+            assert_eq_f!(
+                perp_p(&mut result.liqor).quote_position_native(),
+                liqor_quote,
+                0.0001
+            );
+            assert_eq!(result.perp_market.data().fees_accrued, fees_before);
+
+            // with the buffer, the liqor pays more or gets less by exactly the buffer,
+            // the liqee is unaffected and the market keeps the difference
+            setup.perp_market.data().liquidation_quote_rounding_buffer = 0.25;
+            let mut buffered = setup.run(3 * base.signum(), 0).unwrap();
+            assert_eq_f!(
+                perp_p(&mut buffered.liqor).quote_position_native(),
+                liqor_quote - 0.25,
+                0.0001
+            );
+            assert_eq!(
+                perp_p(&mut buffered.liqee).quote_position_native(),
+                perp_p(&mut result.liqee).quote_position_native()
+            );
+            assert_eq_f!(
+                buffered.perp_market.data().fees_accrued - fees_before,
+                0.25,
+                0.0001
+            );
+        }
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_accumulated() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.base_liquidation_fee = I80F48::from_num(0.25);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(13.0), 0)
+                .unwrap();
+        }
+        setup.liqee.fixed.set_being_liquidated(true);
+
+        // health: 13 - 5 - 15 + 0.25 * b, zero at b = 28
+        let mut current = setup.clone();
+        for call in 1..=2 {
+            current = current.run(4, 0).unwrap();
+            assert_eq!(current.liqee.fixed.liquidation_base_accumulated, 4 * call);
+            assert_eq!(current.liqee.fixed.liquidation_quote_accumulated, 3 * call);
+            assert!(current.liqee.fixed.being_liquidated());
+        }
+
+        current = current.run(100, 0).unwrap();
+        assert_eq!(current.liqee.fixed.liquidation_base_accumulated, 28);
+        assert_eq!(current.liqee.fixed.liquidation_quote_accumulated, 21);
+
+        // recovering resets the totals
+        let health = current
+            .liqee_health_cache()
+            .health(HealthType::LiquidationEnd);
+        assert!(current
+            .liqee
+            .fixed
+            .maybe_recover_from_being_liquidated(health));
+        assert_eq!(current.liqee.fixed.liquidation_base_accumulated, 0);
+        assert_eq!(current.liqee.fixed.liquidation_quote_accumulated, 0);
+    }
+
+    #[test]
+    fn test_liquidation_open_interest_change() {
+        // flat or same-side liqors take over the position: conserved
+        assert_eq!(liquidation_open_interest_change(10, 0, -10), 0);
+        assert_eq!(liquidation_open_interest_change(10, 5, -4), 0);
+        assert_eq!(liquidation_open_interest_change(-10, -5, 4), 0);
+        // liqors with an opposite position net it out
+        assert_eq!(liquidation_open_interest_change(10, -20, -10), -20);
+        assert_eq!(liquidation_open_interest_change(10, -4, -10), -8);
+        assert_eq!(liquidation_open_interest_change(-10, 4, 6), -8);
+
+        for liqor_base in [0i64, 5, -20, -5] {
             let mut setup = TestSetup::new();
             {
                 let pm = setup.perp_market.data();
-                pm.init_base_asset_weight = I80F48::from_num(base_weight);
-                pm.init_base_liab_weight = I80F48::from_num(2.0 - base_weight);
-                pm.init_overall_asset_weight = I80F48::from_num(overall_weight);
-                pm.positive_pnl_liquidation_fee = I80F48::from_num(pos_pnl_liq_fee);
+                pm.init_base_asset_weight = I80F48::from_num(0.5);
+                pm.init_base_liab_weight = I80F48::from_num(1.5);
             }
             {
-                let p = perp_p(&mut setup.liqee);
-                p.record_trade(
+                perp_p(&mut setup.liqee).record_trade(
                     setup.perp_market.data(),
-                    init_liqee_base,
-                    I80F48::from_num(init_liqee_quote),
+                    30,
+                    I80F48::from_num(-30),
                 );
-                p.oneshot_settle_pnl_allowance = p
-                    .unsettled_pnl(setup.perp_market.data(), I80F48::ONE)
+                perp_p(&mut setup.liqor).record_trade(
+                    setup.perp_market.data(),
+                    liqor_base,
+                    I80F48::from(-liqor_base),
+                );
+
+                let settle_bank = setup.settle_bank.data();
+                settle_bank
+                    .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                    .unwrap();
+                settle_bank
+                    .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                    .unwrap();
+
+                let other_bank = setup.other_bank.data();
+                other_bank
+                    .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
                     .unwrap();
+            }
+            let open_interest_before = setup.perp_market.data().open_interest;
+
+            // transfers 20 lots
+            let mut result = setup.run(100, 0).unwrap();
+            assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 10);
+            assert_eq!(
+                result.perp_market.data().open_interest - open_interest_before,
+                liquidation_open_interest_change(30, liqor_base, -20),
+                "liqor base {liqor_base}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_liqor_quote_required() {
+        // receiving quote needs no funding
+        assert_eq!(liqor_quote_required(I80F48::from(15)), 0);
+        assert_eq!(liqor_quote_required(I80F48::ZERO), 0);
+        // paying quote needs the full amount
+        assert_eq!(liqor_quote_required(I80F48::from(-15)), 15);
+        assert_eq!(
+            liqor_quote_required(I80F48::from_num(-0.5)),
+            I80F48::from_num(0.5)
+        );
+
+        for liqee_base in [30i64, -30] {
+            let mut setup = TestSetup::new();
+            {
+                let pm = setup.perp_market.data();
+                pm.init_base_asset_weight = I80F48::from_num(0.5);
+                pm.init_base_liab_weight = I80F48::from_num(1.5);
+            }
+            {
+                perp_p(&mut setup.liqee).record_trade(
+                    setup.perp_market.data(),
+                    liqee_base,
+                    I80F48::from(-liqee_base),
+                );
 
                 let settle_bank = setup.settle_bank.data();
                 settle_bank
-                    .change_without_fee(
-                        token_p(&mut setup.liqee),
-                        I80F48::from_num(init_liqee_spot),
-                        0,
-                    )
+                    .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                    .unwrap();
+
+                let other_bank = setup.other_bank.data();
+                other_bank
+                    .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(5.0), 0)
+                    .unwrap();
+            }
+
+            let mut result = setup.run(100, 0).unwrap();
+            let liqor_quote = perp_p(&mut result.liqor).quote_position_native();
+            let required = liqor_quote_required(liqor_quote);
+            if liqee_base > 0 {
+                assert!(liqor_quote < 0);
+                assert_eq!(required, -liqor_quote);
+            } else {
+                assert!(liqor_quote > 0);
+                assert_eq!(required, 0);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_estimate_full_liquidation() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        // health: 10 + (-5 + (-b + 0.5 * b)), zero at b = 10
+        for (max_base, expected_calls) in [(100, 1), (20, 1), (6, 4), (1, 20)] {
+            let (base, quote, calls) = setup
+                .liqee_health_cache()
+                .estimate_full_liquidation(setup.perp_market.data(), max_base)
+                .unwrap();
+            assert_eq!(base, -20);
+            assert_eq!(quote, I80F48::from(20));
+            assert_eq!(calls, expected_calls, "max_base {max_base}");
+
+            // iterate the real liquidation until the liqee is healthy
+            let mut current = setup.clone();
+            let mut actual_calls = 0;
+            while current
+                .liqee_health_cache()
+                .health(HealthType::LiquidationEnd)
+                < 0
+            {
+                current = current.run(max_base, 0).unwrap();
+                actual_calls += 1;
+                assert!(actual_calls <= 100);
+            }
+            assert_eq!(actual_calls, calls);
+            assert_eq!(
+                perp_p(&mut current.liqee).base_position_lots(),
+                perp_p(&mut setup.liqee).base_position_lots() + base
+            );
+            assert_eq!(
+                perp_p(&mut current.liqee).quote_position_native(),
+                perp_p(&mut setup.liqee).quote_position_native() + quote
+            );
+        }
+
+        // nothing to do for a healthy account
+        setup
+            .other_bank
+            .data()
+            .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(20.0), 0)
+            .unwrap();
+        let estimate = setup
+            .liqee_health_cache()
+            .estimate_full_liquidation(setup.perp_market.data(), 100)
+            .unwrap();
+        assert_eq!(estimate, (0, I80F48::ZERO, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_recommended_max_base_transfer() {
+        for side in [1i64, -1] {
+            let mut setup = TestSetup::new();
+            {
+                let pm = setup.perp_market.data();
+                pm.init_base_asset_weight = I80F48::from_num(0.5);
+                pm.init_base_liab_weight = I80F48::from_num(1.5);
+            }
+            {
+                perp_p(&mut setup.liqee).record_trade(
+                    setup.perp_market.data(),
+                    side * 30,
+                    I80F48::from(-side * 30),
+                );
+
+                let settle_bank = setup.settle_bank.data();
+                settle_bank
+                    .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
                     .unwrap();
                 settle_bank
                     .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
                     .unwrap();
 
-                let other_bank = setup.other_bank.data();
-                other_bank
-                    .change_without_fee(
-                        other_p(&mut setup.liqee),
-                        I80F48::from_num(init_other_spot),
-                        0,
-                    )
-                    .unwrap();
+                let other_bank = setup.other_bank.data();
+                other_bank
+                    .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                    .unwrap();
+            }
+
+            // health: 10 + (-5 - 15 + 0.5 * b), zero at b = 20
+            let max_base = setup
+                .liqee_health_cache()
+                .recommended_max_base_transfer(setup.perp_market.data(), I80F48::ONE)
+                .unwrap();
+            assert_eq!(max_base, side * 20, "side {side}");
+
+            let (_, explanation) = setup.run_explained(side * 100, 0, None).unwrap();
+            assert_eq!(explanation.base_transfer_for_zero, max_base.abs());
+
+            // a single call with the recommended amount restores health
+            let mut result = setup.run(max_base, 0).unwrap();
+            assert_eq!(perp_p(&mut result.liqee).base_position_lots(), side * 10);
+            let hc = result.liqee_health_cache();
+            assert!(hc.health(HealthType::LiquidationEnd) >= 0);
+
+            // clamped to the position if closing it is not enough
+            other_p(&mut setup.liqee).indexed_position = I80F48::ZERO;
+            let max_base = setup
+                .liqee_health_cache()
+                .recommended_max_base_transfer(setup.perp_market.data(), I80F48::ONE)
+                .unwrap();
+            assert_eq!(max_base, side * 30, "side {side}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_perp_liquidation_fully_recovers() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        // reducing by 20 lots brings liquidation end health to zero, and init health
+        // is the same since the stable price matches the oracle
+        assert!(setup
+            .liqee_health_cache()
+            .perp_liquidation_fully_recovers(setup.perp_market.data())
+            .unwrap());
+
+        // init health values the remaining 10 lots at the lower stable price: -2.5
+        let mut low_stable = setup.clone();
+        low_stable
+            .perp_market
+            .data()
+            .stable_price_model
+            .reset_to_price(0.5, 0);
+        assert!(!low_stable
+            .liqee_health_cache()
+            .perp_liquidation_fully_recovers(low_stable.perp_market.data())
+            .unwrap());
+
+        // closing the whole position is not enough
+        other_p(&mut setup.liqee).indexed_position = I80F48::ZERO;
+        assert!(!setup
+            .liqee_health_cache()
+            .perp_liquidation_fully_recovers(setup.perp_market.data())
+            .unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_perp_liquidation_uneconomical() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.base_liquidation_fee = I80F48::from_num(0.03);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+        let uneconomical = |setup: &mut TestSetup, min_reward: f64| {
+            let cache = setup.liqee_health_cache();
+            cache
+                .perp_liquidation_uneconomical(
+                    &[&*setup.perp_market.data()],
+                    I80F48::from_num(min_reward),
+                )
+                .unwrap()
+        };
+
+        // each lot gains 0.47 health, so 22 lots are needed: the liqor earns 22 * 0.03 = 0.66
+        assert!(setup.liqee_health_cache().health(HealthType::Maint) < 0);
+        assert!(!uneconomical(&mut setup, 0.65));
+        assert!(uneconomical(&mut setup, 0.67));
+
+        // the quote rounding buffer reduces the reward
+        let mut buffered = setup.clone();
+        buffered
+            .perp_market
+            .data()
+            .liquidation_quote_rounding_buffer = 0.1;
+        assert!(uneconomical(&mut buffered, 0.6));
+
+        // healthy accounts are never flagged
+        let mut healthy = setup.clone();
+        healthy
+            .settle_bank
+            .data()
+            .change_without_fee(token_p(&mut healthy.liqee), I80F48::from_num(100.0), 0)
+            .unwrap();
+        assert!(healthy.liqee_health_cache().health(HealthType::Maint) >= 0);
+        assert!(!uneconomical(&mut healthy, 1000.0));
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_base_transfer_for_init_health_target() {
+        for base_lot_size in [1, 3] {
+            for fee in [0.0, 0.02] {
+                for collateral in [10.0, 14.0, 30.0] {
+                    for target in [0.0, 1.5] {
+                        let mut setup = TestSetup::new();
+                        {
+                            let pm = setup.perp_market.data();
+                            pm.base_lot_size = base_lot_size;
+                            pm.init_base_asset_weight = I80F48::from_num(0.5);
+                            pm.init_base_liab_weight = I80F48::from_num(1.5);
+                            pm.base_liquidation_fee = I80F48::from_num(fee);
+                        }
+                        perp_p(&mut setup.liqee).record_trade(
+                            setup.perp_market.data(),
+                            30,
+                            I80F48::from(-30 * base_lot_size),
+                        );
+                        let settle_bank = setup.settle_bank.data();
+                        settle_bank
+                            .change_without_fee(
+                                token_p(&mut setup.liqee),
+                                I80F48::from_num(-5.0),
+                                0,
+                            )
+                            .unwrap();
+                        let other_bank = setup.other_bank.data();
+                        other_bank
+                            .change_without_fee(
+                                other_p(&mut setup.liqee),
+                                I80F48::from_num(collateral),
+                                0,
+                            )
+                            .unwrap();
+
+                        let name = format!("lot size {base_lot_size}, fee {fee}, collateral {collateral}, target {target}");
+                        let hc = setup.liqee_health_cache();
+                        let target = I80F48::from_num(target);
+                        let (base_transfer, quote_transfer) = hc
+                            .base_transfer_for_init_health_target(setup.perp_market.data(), target)
+                            .unwrap();
+                        assert!(base_transfer <= 0, "{name}");
+
+                        // each lot sold at price 1 gains (1 - fee) quote and loses 0.5 in init health
+                        let lot_size = I80F48::from(base_lot_size);
+                        let one_lot_health =
+                            lot_size * (I80F48::from_num(0.5) - I80F48::from_num(fee));
+                        let health_after = |lots: i64| {
+                            let mut cache = hc.clone();
+                            let perp_info = &mut cache.perp_infos[0];
+                            perp_info.base_lots -= lots;
+                            perp_info.quote += I80F48::from(lots)
+                                * lot_size
+                                * (I80F48::ONE - I80F48::from_num(fee));
+                            cache.health(HealthType::Init)
+                        };
+                        let lots = -base_transfer;
+                        assert_eq!(
+                            quote_transfer,
+                            I80F48::from(lots) * lot_size * (I80F48::ONE - I80F48::from_num(fee)),
+                            "{name}"
+                        );
+
+                        let health = health_after(lots);
+                        if hc.health(HealthType::Init) >= target {
+                            assert_eq!(lots, 0, "{name}");
+                        } else if lots == 30 {
+                            // closing the position may not be enough
+                            assert!(health_after(29) < target, "{name}");
+                        } else {
+                            assert!(health >= target, "{name}");
+                            assert!(health < target + one_lot_health, "{name}");
+                            assert!(health_after(lots - 1) < target, "{name}");
+                        }
+                    }
+                }
             }
+        }
+    }
 
-            let mut result = setup.run(max_base, max_pnl).unwrap();
+    #[test]
+    fn test_liq_base_or_positive_pnl_max_overshoot_health() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.init_overall_asset_weight = I80F48::ZERO;
+            setup.perp_oracle.data().price = I80F48::from(10);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                -3,
+                I80F48::from_num(30),
+            );
 
-            let liqee_perp = perp_p(&mut result.liqee);
-            assert_eq!(liqee_perp.base_position_lots(), exp_liqee_base);
-            assert_eq_f!(liqee_perp.quote_position_native(), exp_liqee_quote, 0.01);
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(8.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+        }
 
-            let liqor_perp = perp_p(&mut result.liqor);
-            assert_eq!(
-                liqor_perp.base_position_lots(),
-                -(exp_liqee_base - init_liqee_base)
-            );
-            assert_eq_f!(
-                liqor_perp.quote_position_native(),
-                -(exp_liqee_quote - init_liqee_quote),
-                0.01
+        // health: 8 + 30 - 3 * 10 * 1.5 = -7, each lot gives 5 health
+        let hc = setup.liqee_health_cache();
+        assert_eq_f!(hc.health(HealthType::LiquidationEnd), -7.0, 0.01);
+
+        // without a cap, rounding up overshoots to health 3
+        let mut result = setup.run(-100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), -1);
+        let hc = result.liqee_health_cache();
+        assert_eq_f!(hc.health(HealthType::LiquidationEnd), 3.0, 0.01);
+
+        // with a cap the transfer is reduced by a lot
+        setup.max_overshoot_health = Some(I80F48::ONE);
+        let mut result = setup.run(-100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), -2);
+        let hc = result.liqee_health_cache();
+        assert_eq_f!(hc.health(HealthType::LiquidationEnd), -2.0, 0.01);
+
+        // but still makes progress if a single lot overshoots
+        let mut result = result.run(-100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), -1);
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_min_liqor_reward() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.init_overall_asset_weight = I80F48::ZERO;
+            pm.base_liquidation_fee = I80F48::from_num(0.125);
+            setup.perp_oracle.data().price = I80F48::from(10);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                -3,
+                I80F48::from_num(30),
             );
-            // The settle limit taken over matches the quote pos when removing the
-            // quote gains from giving away base lots
-            assert_eq_f!(
-                I80F48::from_num(liqor_perp.recurring_settle_pnl_allowance)
-                    + liqor_perp.oneshot_settle_pnl_allowance,
-                liqor_perp.quote_position_native.to_num::<f64>()
-                    + liqor_perp.base_position_lots as f64,
-                1.1
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(8.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+        }
+
+        // health: 8 + 30 - 3 * 10 * 1.5 = -7, each lot gives 15 - 11.25 = 3.75 health
+        // so two lots get liquidated, for a liqor reward of 2 * 10 * 0.125 = 2.5
+        let mut result = setup.run(-100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), -1);
+
+        // at the threshold
+        setup.min_liqor_reward = Some(I80F48::from_num(2.5));
+        let mut result = setup.run(-100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), -1);
+        assert_eq_f!(
+            token_p(&mut result.liqor).native(result.settle_bank.data()),
+            1000.0,
+            0.01
+        );
+
+        // below the threshold
+        setup.min_liqor_reward = Some(I80F48::from_num(2.5) + I80F48::DELTA);
+        assert!(setup
+            .run(-100, 0)
+            .is_anchor_error_with_code(MangoError::LiquidationNotProfitable.error_code()));
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_min_base_lots() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.min_liquidation_base_lots = 10;
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
             );
 
-            let settle_bank = result.settle_bank.data();
-            assert_eq_f!(
-                token_p(&mut result.liqee).native(settle_bank),
-                exp_liqee_spot,
-                0.01
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        let too_small = MangoError::LiquidationTooSmall.error_code();
+        assert!(setup.run(1, 0).is_anchor_error_with_code(too_small));
+        assert!(setup.run(9, 0).is_anchor_error_with_code(too_small));
+
+        let mut result = setup.run(10, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 20);
+    }
+
+    #[test]
+    fn test_liquidation_state_hash() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
             );
-            assert_eq_f!(
-                token_p(&mut result.liqor).native(settle_bank),
-                1000.0 - (exp_liqee_spot - init_liqee_spot),
-                0.01
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+        let state_hash =
+            |account: &MangoAccountValue| liquidation_state_hash(&account.borrow(), 9, 0).unwrap();
+        let liqee_before = state_hash(&setup.liqee);
+        let liqor_before = state_hash(&setup.liqor);
+
+        // nothing transferred, nothing changed
+        let result = setup.run(0, 0).unwrap();
+        assert_eq!(state_hash(&result.liqee), liqee_before);
+        assert_eq!(state_hash(&result.liqor), liqor_before);
+
+        // replaying the logged base transfer on the old state gives the new hash
+        let mut result = setup.run(10, 0).unwrap();
+        let liqee_after = state_hash(&result.liqee);
+        assert_ne!(liqee_after, liqee_before);
+        assert_ne!(state_hash(&result.liqor), liqor_before);
+
+        let quote_transfer_liqee = perp_p(&mut result.liqee).quote_position_native()
+            - perp_p(&mut setup.liqee).quote_position_native();
+        let mut replayed = setup.clone();
+        perp_p(&mut replayed.liqee).record_trade(
+            replayed.perp_market.data(),
+            -10,
+            quote_transfer_liqee,
+        );
+        assert_eq!(state_hash(&replayed.liqee), liqee_after);
+
+        // any other change to the position shows up
+        perp_p(&mut replayed.liqee).perp_spot_transfers += 1;
+        assert_ne!(state_hash(&replayed.liqee), liqee_after);
+    }
+
+    #[test]
+    fn test_liqee_liquidation_cost() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.base_liquidation_fee = I80F48::from_num(0.03);
+            pm.platform_liquidation_fee = I80F48::from_num(0.01);
+            pm.liquidation_price_tolerance = 0.02;
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
             );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
         }
+        let equity = |setup: &mut TestSetup| {
+            liquidation_equity(
+                &setup.liqee.borrow(),
+                setup.perp_market.data(),
+                setup.settle_bank.data(),
+                I80F48::ONE,
+            )
+            .unwrap()
+        };
+        let equity_before = equity(&mut setup);
+
+        // base only: the fees on the liquidated notional
+        let mut result = setup.run(10, 0).unwrap();
+        let cost = liqee_liquidation_cost(equity_before, equity(&mut result));
+        assert_eq_f!(cost, 10.0 * 1.0 * (0.03 + 0.01), 0.0001);
+
+        // with a liqor price, the difference to the oracle price is a cost too
+        let mut result = setup.run_with_price(10, 0, Some(0.99)).unwrap();
+        let cost = liqee_liquidation_cost(equity_before, equity(&mut result));
+        assert_eq_f!(cost, 10.0 * (1.0 - 0.99 * 0.96), 0.0001);
     }
 
-    // Checks that the stable price does _not_ affect the liquidation target amount
     #[test]
-    fn test_liq_base_or_positive_pnl_stable_price() {
+    fn test_liq_base_or_positive_pnl_reduce_only_market() {
         let mut setup = TestSetup::new();
         {
             let pm = setup.perp_market.data();
-            pm.stable_price_model.stable_price = 0.5;
-            pm.init_base_asset_weight = I80F48::from_num(0.6);
-            pm.maint_base_asset_weight = I80F48::from_num(0.8);
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.reduce_only = 1;
         }
         {
             perp_p(&mut setup.liqee).record_trade(
@@ -1140,40 +3722,156 @@ mod tests {
 
             let settle_bank = setup.settle_bank.data();
             settle_bank
-                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(5.0), 0)
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
                 .unwrap();
             settle_bank
                 .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
                 .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
         }
 
-        let hc = setup.liqee_health_cache();
-        assert_eq_f!(
-            hc.health(HealthType::Init),
-            5.0 + (-30.0 + 30.0 * 0.5 * 0.6), // init + stable
-            0.1
-        );
-        assert_eq_f!(
-            hc.health(HealthType::LiquidationEnd),
-            5.0 + (-30.0 + 30.0 * 0.6), // init + oracle
-            0.1
-        );
-        assert_eq_f!(
-            hc.health(HealthType::Maint),
-            5.0 + (-30.0 + 30.0 * 0.8), // maint + oracle
-            0.1
+        // the liqor opens a position while reducing the liqee's
+        let mut result = setup.run(10, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 20);
+        assert_eq!(perp_p(&mut result.liqor).base_position_lots(), 10);
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_max_health_improvement() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+        // health: 10 + (-5 - 15 + 0.5 * b), each lot improves health by 0.5
+        let health_before = setup
+            .liqee_health_cache()
+            .health(HealthType::LiquidationEnd);
+        assert_eq!(health_before, I80F48::from(-10));
+
+        // the cap binds: 3.2 health allow 6 lots
+        setup.max_health_improvement = Some(I80F48::from_num(3.2));
+        let (mut result, explanation) = setup.run_explained(100, 0, None).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 24);
+        assert_eq!(
+            explanation.binding_constraint,
+            LiquidationConstraint::MaxHealthImprovement
         );
+        let health_after = result
+            .liqee_health_cache()
+            .health(HealthType::LiquidationEnd);
+        assert!(health_after - health_before <= I80F48::from_num(3.2));
+        assert!(health_after < 0);
+
+        // a zero cap stops the liquidation
+        setup.max_health_improvement = Some(I80F48::ZERO);
+        let mut result = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 30);
 
+        // a large cap doesn't change anything
+        setup.max_health_improvement = Some(I80F48::from(1000));
         let mut result = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 10);
+    }
 
-        let liqee_perp = perp_p(&mut result.liqee);
-        assert_eq!(liqee_perp.base_position_lots(), 12);
+    #[test]
+    fn test_liq_base_or_positive_pnl_error_codes() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-30),
+            );
 
-        let hc = result.liqee_health_cache();
-        assert_eq_f!(
-            hc.health(HealthType::LiquidationEnd),
-            5.0 + (-12.0 + 12.0 * 0.6),
-            0.1
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-5.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        // the liqee is long, so base may only be bought from it
+        let wrong_direction = MangoError::LiquidationWrongDirection.error_code();
+        assert!(setup.run(-10, 0).is_anchor_error_with_code(wrong_direction));
+        setup.run(10, 0).unwrap();
+
+        // and the other way around for shorts
+        let mut short_setup = setup.clone();
+        perp_p(&mut short_setup.liqee).record_trade(
+            short_setup.perp_market.data(),
+            -60,
+            I80F48::from_num(60),
         );
+        assert!(short_setup
+            .run(10, 0)
+            .is_anchor_error_with_code(wrong_direction));
+        short_setup.run(-10, 0).unwrap();
+    }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_tiny_health_per_lot() {
+        let mut setup = TestSetup::new();
+        {
+            // every lot improves health by a single I80F48::DELTA
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::ONE - I80F48::DELTA;
+            pm.base_liquidation_fee = I80F48::ZERO;
+            pm.platform_liquidation_fee = I80F48::ZERO;
+            pm.positive_pnl_liquidation_fee = I80F48::ZERO;
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                30,
+                I80F48::from_num(-20),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(token_p(&mut setup.liqee), I80F48::from_num(-1_000_000.0), 0)
+                .unwrap();
+        }
+
+        // the lots needed to restore health don't fit into an i64
+        let result = setup.run(30, 10_000_000);
+        assert!(result.is_anchor_error_with_code(MangoError::MathOverflow.error_code()));
     }
 }
@@ -39,6 +39,10 @@ pub fn perp_liq_force_cancel_orders(
     // Cancel orders
     //
     {
+        account
+            .perp_position_mut(perp_market.perp_market_index)?
+            .settle_funding(&perp_market);
+
         let mut book = Orderbook {
             bids: ctx.accounts.bids.load_mut()?,
             asks: ctx.accounts.asks.load_mut()?,
@@ -62,10 +62,11 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
     let mut liqor = ctx.accounts.liqor.load_full_mut()?;
     // account constraint #1
     require!(
-        liqor
-            .fixed
-            .is_owner_or_delegate(ctx.accounts.liqor_owner.key()),
-        MangoError::SomeError
+        liqor.fixed.has_permission(
+            ctx.accounts.liqor_owner.key(),
+            DelegatePermission::Liquidate
+        ),
+        MangoError::UnauthorizedLiqor
     );
     require_msg_typed!(
         !liqor.fixed.being_liquidated(),
@@ -79,6 +80,80 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
     drop(retriever);
     let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
 
+    // A bankrupt liqee's positive pnl would block the bankruptcy, see sweep_bankrupt_positive_pnl()
+    let sweep_enabled = ctx.accounts.group.load()?.sweep_bankrupt_perp_pnl()
+        && ctx.accounts.settle_vault.mint == ctx.accounts.insurance_vault.mint;
+    if sweep_enabled
+        && liqee
+            .perp_position(perp_market_index)?
+            .quote_position_native()
+            > 0
+    {
+        if liqee.check_liquidatable(&liqee_health_cache)? != CheckLiquidatable::Liquidatable {
+            return Ok(());
+        }
+        liqor.ensure_perp_position(perp_market_index, settle_token_index)?;
+        liqor.ensure_token_position(settle_token_index)?;
+
+        let perp_market = ctx.accounts.perp_market.load()?;
+        let (swept_pnl, insurance_payment) = {
+            let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
+            sweep_bankrupt_positive_pnl(
+                &perp_market,
+                &mut settle_bank,
+                &mut liqor.borrow_mut(),
+                &mut liqee.borrow_mut(),
+                &mut liqee_health_cache,
+                now_ts,
+            )?
+        };
+
+        if insurance_payment > 0 {
+            let group = ctx.accounts.group.load()?;
+            let group_seeds = group_seeds!(group);
+            token::transfer(
+                ctx.accounts
+                    .settle_to_insurance_transfer_ctx()
+                    .with_signer(&[group_seeds]),
+                insurance_payment,
+            )?;
+        }
+
+        if swept_pnl > 0 {
+            let settle_bank = ctx.accounts.settle_bank.load()?;
+            let liqor_token_position = liqor.token_position(settle_token_index)?;
+            emit_stack(TokenBalanceLog {
+                mango_group,
+                mango_account: ctx.accounts.liqor.key(),
+                token_index: settle_token_index,
+                indexed_position: liqor_token_position.indexed_position.to_bits(),
+                deposit_index: settle_bank.deposit_index.to_bits(),
+                borrow_index: settle_bank.borrow_index.to_bits(),
+            });
+            emit_perp_balances(
+                mango_group,
+                ctx.accounts.liqor.key(),
+                liqor.perp_position(perp_market_index)?,
+                &perp_market,
+            );
+            emit_perp_balances(
+                mango_group,
+                ctx.accounts.liqee.key(),
+                liqee.perp_position(perp_market_index)?,
+                &perp_market,
+            );
+        }
+        drop(perp_market);
+
+        check_liqor_health(
+            &liqor.borrow(),
+            ctx.remaining_accounts,
+            &mango_group,
+            now_ts,
+        )?;
+        return Ok(());
+    }
+
     // Guarantees that perp base position is 0 and perp quote position is <= 0.
     liqee_health_cache.require_after_phase2_liquidation()?;
 
@@ -95,6 +170,11 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
     }
 
     let mut perp_market = ctx.accounts.perp_market.load_mut()?;
+    let liquidation_fee = ctx
+        .accounts
+        .group
+        .load()?
+        .capped_liquidation_fee(perp_market.base_liquidation_fee);
 
     let (settlement, insurance_transfer) = {
         let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
@@ -108,6 +188,7 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
             ctx.accounts.group.key(),
             &mut perp_market,
             perp_oracle_price,
+            liquidation_fee,
             &mut settle_bank,
             settle_token_oracle_price,
             insurance_bank_opt.as_mut().map(|v| v.deref_mut()),
@@ -197,20 +278,28 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
 
     drop(perp_market);
 
-    // Check liqor's health
+    check_liqor_health(
+        &liqor.borrow(),
+        ctx.remaining_accounts,
+        &mango_group,
+        now_ts,
+    )?;
+
+    Ok(())
+}
+
+fn check_liqor_health(
+    liqor: &MangoAccountRef,
+    remaining_accounts: &[AccountInfo],
+    mango_group: &Pubkey,
+    now_ts: u64,
+) -> Result<()> {
     if !liqor.fixed.is_in_health_region() {
-        let account_retriever =
-            ScanningAccountRetriever::new(ctx.remaining_accounts, &mango_group)?;
-        let liqor_health = compute_health(
-            &liqor.borrow(),
-            HealthType::Init,
-            &account_retriever,
-            now_ts,
-        )
-        .context("compute liqor health")?;
+        let account_retriever = ScanningAccountRetriever::new(remaining_accounts, mango_group)?;
+        let liqor_health = compute_health(liqor, HealthType::Init, &account_retriever, now_ts)
+            .context("compute liqor health")?;
         require!(liqor_health >= 0, MangoError::HealthMustBePositive);
     }
-
     Ok(())
 }
 
@@ -218,6 +307,8 @@ pub(crate) fn liquidation_action(
     group_key: Pubkey,
     perp_market: &mut PerpMarket,
     perp_oracle_price: I80F48,
+    // the perp market's base_liquidation_fee, capped by Group::max_liquidation_fee
+    liquidation_fee: I80F48,
     settle_bank: &mut Bank,
     settle_token_oracle_price: I80F48,
     insurance_bank_opt: Option<&mut Bank>,
@@ -308,6 +399,9 @@ pub(crate) fn liquidation_action(
     // - taken by the liqor in exchange for spot from the insurance fund, or
     // - wiped away and socialized among all perp participants (this does not involve the liqor)
     //
+    // The liqor can also have the insurance fund cover bad debt before the base position is
+    // liquidated, see perp_liq_base_or_positive_pnl_v3 with settle_bad_debt_first.
+    //
     let insurance_transfer;
     if settlement == max_settlement_liqee && liqee_pnl < 0 {
         // Preparation that's needed for both, insurance fund based pnl takeover and socialized loss
@@ -350,7 +444,7 @@ pub(crate) fn liquidation_action(
             0
         };
 
-        let liquidation_fee_factor = I80F48::ONE + perp_market.base_liquidation_fee;
+        let liquidation_fee_factor = I80F48::ONE + liquidation_fee;
         let settle_token_price_with_fee = settle_token_oracle_price * liquidation_fee_factor;
 
         // Amount given to the liqor from the insurance fund
@@ -393,13 +487,16 @@ pub(crate) fn liquidation_action(
         }
 
         // Socialize loss if the insurance fund is exhausted
+        //
+        // Markets with adl enabled leave the remaining loss on the liqee instead, where
+        // perp_adl can take it from profitable accounts.
 
         // At this point, we don't care about the liqor's requested max_liab_tranfer
         let remaining_liab = max_liab_transfer_from_liqee - insurance_liab_transfer;
         let mut socialized_loss = I80F48::ZERO;
         let (starting_long_funding, starting_short_funding) =
             (perp_market.long_funding, perp_market.short_funding);
-        if insurance_fund_exhausted && remaining_liab > 0 {
+        if insurance_fund_exhausted && remaining_liab > 0 && !perp_market.is_adl_enabled() {
             perp_market.socialize_loss(-remaining_liab)?;
             liqee_perp_position.record_settle(-remaining_liab, &perp_market);
             socialized_loss = remaining_liab;
@@ -425,6 +522,88 @@ pub(crate) fn liquidation_action(
     Ok((settlement, insurance_transfer))
 }
 
+/// Sells the liqee's positive pnl in this market to the liqor if the liqee is bankrupt:
+/// it has no base positions and no spot assets left, only borrows or negative pnl.
+///
+/// Otherwise the pnl would be stuck on the account, blocking its bankruptcy (see
+/// HealthCache::require_after_phase2_liquidation()) while nobody takes it over.
+/// The liqor pays for the pnl and its settle limit like in perp_liq_base_or_positive_pnl,
+/// but the payment goes to the insurance fund, which covers the account's bad debt.
+/// Used when Group::sweep_bankrupt_perp_pnl is set.
+///
+/// Returns the pnl that was moved and the native settle token amount that must be moved
+/// from the settle vault to the insurance vault. The token transfer happens outside
+/// of this function to ensure this is unittestable.
+pub(crate) fn sweep_bankrupt_positive_pnl(
+    perp_market: &PerpMarket,
+    settle_bank: &mut Bank,
+    liqor: &mut MangoAccountRefMut,
+    liqee: &mut MangoAccountRefMut,
+    liqee_health_cache: &mut HealthCache,
+    now_ts: u64,
+) -> Result<(I80F48, u64)> {
+    let perp_market_index = perp_market.perp_market_index;
+    let settle_token_index = perp_market.settle_token_index;
+
+    let bankrupt = !liqee_health_cache.has_phase1_liquidatable()
+        && !liqee_health_cache.has_liq_spot_assets()
+        && !liqee_health_cache.has_perp_base_positions()
+        && !liqee_health_cache.has_perp_open_fills()
+        && liqee_health_cache.has_phase3_liquidatable();
+    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+    let pnl = liqee_perp_position.quote_position_native();
+    if !bankrupt || pnl <= 0 {
+        return Ok((I80F48::ZERO, 0));
+    }
+
+    // All of the liqee's settle limit moves with its pnl
+    let liqee_limit: i128 = liqee_perp_position.settle_limit(perp_market).1.into();
+    let liqee_oneshot: i128 = liqee_perp_position
+        .oneshot_settle_pnl_allowance
+        .ceil()
+        .to_num::<i128>()
+        .max(0);
+    let max_transfer: i128 = pnl.ceil().to_num();
+    let limit_transfer_recurring: i64 = (liqee_limit - liqee_oneshot)
+        .clamp(0, max_transfer)
+        .try_into()
+        .unwrap();
+    let limit_transfer_oneshot: i64 = liqee_oneshot
+        .min(max_transfer - i128::from(limit_transfer_recurring))
+        .max(0)
+        .try_into()
+        .unwrap();
+
+    // The liqor pays less than the full amount to receive the positive pnl
+    let insurance_payment = (pnl * (I80F48::ONE - perp_market.positive_pnl_liquidation_fee))
+        .floor()
+        .to_num::<u64>();
+    let payment = I80F48::from(insurance_payment);
+
+    liqee_perp_position.record_settle(pnl, perp_market);
+    liqee_health_cache.recompute_perp_info(liqee_perp_position, perp_market)?;
+
+    let liqor_perp_position = liqor.perp_position_mut(perp_market_index)?;
+    liqor_perp_position.record_liquidation_pnl_takeover(
+        pnl,
+        limit_transfer_recurring,
+        limit_transfer_oneshot,
+    );
+    let payment_i64 = payment.to_num::<i64>();
+    liqor_perp_position.perp_spot_transfers -= payment_i64;
+    liqor.fixed.perp_spot_transfers -= payment_i64;
+
+    let liqor_token_position = liqor.token_position_mut(settle_token_index)?.0;
+    settle_bank.withdraw_without_fee(liqor_token_position, payment, now_ts)?;
+
+    msg!(
+        "swept {} positive pnl of a bankrupt account, {} to the insurance fund",
+        pnl,
+        insurance_payment
+    );
+    Ok((pnl, insurance_payment))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -498,6 +677,23 @@ mod tests {
             }
         }
 
+        fn liqee_health_cache(&self) -> HealthCache {
+            let mut setup = self.clone();
+            let ais = vec![
+                setup.insurance_bank.as_account_info(),
+                setup.settle_bank.as_account_info(),
+                setup.other_bank.as_account_info(),
+                setup.insurance_oracle.as_account_info(),
+                setup.settle_oracle.as_account_info(),
+                setup.other_oracle.as_account_info(),
+                setup.perp_market.as_account_info(),
+                setup.perp_oracle.as_account_info(),
+            ];
+            let retriever =
+                ScanningAccountRetriever::new_with_staleness(&ais, &setup.group, None).unwrap();
+            health::new_health_cache(&setup.liqee.borrow(), &retriever, 0).unwrap()
+        }
+
         fn run(&self, max_liab_transfer: u64) -> Result<Self> {
             let mut setup = self.clone();
 
@@ -550,6 +746,8 @@ mod tests {
                     .unwrap()
             };
 
+            let base_liquidation_fee = setup.perp_market.data().base_liquidation_fee;
+
             // There's no way to construct a TokenAccount directly...
             let mut buffer = [0u8; 165];
             use solana_program::program_pack::Pack;
@@ -561,6 +759,7 @@ mod tests {
                 setup.group.key(),
                 setup.perp_market.data(),
                 perp_price,
+                base_liquidation_fee,
                 setup.settle_bank.data(),
                 settle_price,
                 Some(setup.insurance_bank.data()),
@@ -823,4 +1022,66 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_sweep_bankrupt_positive_pnl() {
+        let mut setup = TestSetup::new();
+        {
+            // closed position with 5 positive pnl
+            let pm = setup.perp_market.data();
+            pm.positive_pnl_liquidation_fee = I80F48::from_num(0.2);
+            let p = perp_p(&mut setup.liqee);
+            p.record_trade(pm, 10, I80F48::from(-10));
+            p.record_trade(pm, -10, I80F48::from(15));
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(settle_p(&mut setup.liqee), I80F48::from_num(-10.0), 0)
+                .unwrap();
+            settle_bank
+                .change_without_fee(settle_p(&mut setup.liqor), I80F48::from_num(10.0), 0)
+                .unwrap();
+        }
+
+        let sweep = |setup: &mut TestSetup, hc: &mut HealthCache| {
+            let perp_market = *setup.perp_market.data();
+            sweep_bankrupt_positive_pnl(
+                &perp_market,
+                setup.settle_bank.data(),
+                &mut setup.liqor.borrow_mut(),
+                &mut setup.liqee.borrow_mut(),
+                hc,
+                0,
+            )
+            .unwrap()
+        };
+
+        // with spot assets left, the account isn't bankrupt yet
+        {
+            let mut setup = setup.clone();
+            let other_bank = setup.other_bank.data();
+            other_bank
+                .change_without_fee(other_p(&mut setup.liqee), I80F48::from_num(3.0), 0)
+                .unwrap();
+            let mut hc = setup.liqee_health_cache();
+            assert_eq!(sweep(&mut setup, &mut hc), (I80F48::ZERO, 0));
+            assert_eq!(perp_p(&mut setup.liqee).quote_position_native(), 5);
+        }
+
+        // only borrows left: the liqor pays for the pnl, the payment goes to insurance
+        let mut hc = setup.liqee_health_cache();
+        assert!(hc.has_perp_positive_pnl_no_base());
+        assert_eq!(sweep(&mut setup, &mut hc), (I80F48::from(5), 4));
+        assert_eq!(perp_p(&mut setup.liqee).quote_position_native(), 0);
+        assert_eq!(perp_p(&mut setup.liqor).quote_position_native(), 5);
+        assert_eq_f!(
+            settle_p(&mut setup.liqor).native(setup.settle_bank.data()),
+            6.0,
+            0.01
+        );
+
+        // which unblocks bankruptcy
+        assert!(!hc.has_perp_positive_pnl_no_base());
+        hc.require_after_phase2_liquidation().unwrap();
+    }
 }
@@ -44,7 +44,9 @@ pub fn perp_place_order(
     let mut account = ctx.accounts.account.load_full_mut()?;
     // account constraint #1
     require!(
-        account.fixed.is_owner_or_delegate(ctx.accounts.owner.key()),
+        account
+            .fixed
+            .has_permission(ctx.accounts.owner.key(), DelegatePermission::Trade),
         MangoError::SomeError
     );
 
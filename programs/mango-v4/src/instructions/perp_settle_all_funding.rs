@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::accounts_ix::*;
+use crate::accounts_zerocopy::*;
+use crate::error::*;
+use crate::logs::{
+    emit_perp_balances, emit_stack, PerpSettleAllFundingLog, PerpSettleFundingDetail,
+};
+use crate::state::*;
+
+pub fn perp_settle_all_funding(ctx: Context<PerpSettleAllFunding>) -> Result<()> {
+    let group_pk = ctx.accounts.group.key();
+    let mut account = ctx.accounts.account.load_full_mut()?;
+
+    let mut settled = Vec::with_capacity(ctx.remaining_accounts.len());
+    for ai in ctx.remaining_accounts.iter() {
+        let perp_market = ai.load::<PerpMarket>()?;
+        require_keys_eq!(perp_market.group, group_pk);
+        let perp_market_index = perp_market.perp_market_index;
+        require_msg!(
+            !settled
+                .iter()
+                .any(|d: &PerpSettleFundingDetail| d.perp_market_index == perp_market_index),
+            "perp market {} was passed twice",
+            perp_market_index
+        );
+
+        let perp_position = account.perp_position_mut(perp_market_index)?;
+        let funding = perp_position.unsettled_funding(perp_market);
+        perp_position.settle_funding(perp_market);
+
+        emit_perp_balances(
+            group_pk,
+            ctx.accounts.account.key(),
+            perp_position,
+            perp_market,
+        );
+        settled.push(PerpSettleFundingDetail {
+            perp_market_index,
+            funding: funding.to_bits(),
+        });
+    }
+
+    // Every active perp position must have been settled
+    for perp_position in account.active_perp_positions() {
+        require_msg!(
+            settled
+                .iter()
+                .any(|d| d.perp_market_index == perp_position.market_index),
+            "perp market {} was not passed",
+            perp_position.market_index
+        );
+    }
+
+    emit_stack(PerpSettleAllFundingLog {
+        mango_group: group_pk,
+        mango_account: ctx.accounts.account.key(),
+        settled_funding: settled,
+    });
+
+    Ok(())
+}
@@ -53,7 +53,7 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
     };
 
     let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
-    let perp_market = ctx.accounts.perp_market.load()?;
+    let mut perp_market = ctx.accounts.perp_market.load_mut()?;
 
     // Verify that the bank is the quote currency bank (#2)
     require!(
@@ -168,6 +168,7 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
         a_liq_end_health,
         a_maint_health,
     )?;
+    let protocol_fee = perp_market.compute_settle_protocol_fee(settlement, settlement - fee);
 
     // Update the accounts' perp_spot_transfer statistics.
     //
@@ -175,22 +176,26 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
     // Think about it like this: a's pnl reduces by `settlement` and spot increases by `settlement - fee`.
     // That means that it managed to extract `settlement - fee` from perp interactions.
     let settlement_i64 = settlement.round_to_zero().to_num::<i64>();
-    let fee_i64 = fee.round_to_zero().to_num::<i64>();
+    let fee_i64 = (fee + protocol_fee).round_to_zero().to_num::<i64>();
     (a_perp_position.perp_spot_transfers += settlement_i64 - fee_i64);
     (b_perp_position.perp_spot_transfers -= settlement_i64);
     (account_a.fixed.perp_spot_transfers += settlement_i64 - fee_i64);
     (account_b.fixed.perp_spot_transfers -= settlement_i64);
 
     // Transfer token balances
-    // The fee is paid by the account with positive unsettled pnl
+    // The fees are paid by the account with positive unsettled pnl
     let a_token_position = account_a.token_position_mut(settle_token_index)?.0;
     let b_token_position = account_b.token_position_mut(settle_token_index)?.0;
-    settle_bank.deposit(a_token_position, settlement - fee, now_ts)?;
+    settle_bank.deposit(a_token_position, settlement - fee - protocol_fee, now_ts)?;
     // Don't charge loan origination fees on borrows created via settling:
     // Even small loan origination fees could accumulate if a perp position is
     // settled back and forth repeatedly.
     settle_bank.withdraw_without_fee(b_token_position, settlement, now_ts)?;
 
+    // The protocol fee stays in the vault, withdrawable like other collected fees
+    settle_bank.collected_fees_native += protocol_fee;
+    perp_market.accrued_settle_fees += protocol_fee;
+
     emit_stack(TokenBalanceLog {
         mango_group: ctx.accounts.group.key(),
         mango_account: ctx.accounts.account_a.key(),
@@ -250,6 +255,11 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
         fee: fee.to_bits(),
     });
 
-    msg!("settled pnl = {}, fee = {}", settlement, fee);
+    msg!(
+        "settled pnl = {}, fee = {}, protocol fee = {}",
+        settlement,
+        fee,
+        protocol_fee
+    );
     Ok(())
 }
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use fixed::types::I80F48;
+
+use crate::accounts_ix::*;
+use crate::error::*;
+use crate::health::{new_health_cache, HealthType, ScanningAccountRetriever};
+use crate::state::*;
+use crate::util::clock_now;
+
+/// Clears the account's being_liquidated flag if its maint health is non-negative.
+///
+/// The flag is normally only cleared once liquidation brings the account back above
+/// LiquidationEnd health (or a deposit does). If liquidation stalls, for example because
+/// the relevant markets are halted, this lets the owner leave the liquidating state as
+/// soon as the account is no longer liquidatable.
+pub fn recover_from_being_liquidated(ctx: Context<RecoverFromBeingLiquidated>) -> Result<()> {
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    if !account.fixed.being_liquidated() {
+        msg!("account is not being liquidated");
+        return Ok(());
+    }
+
+    let (now_ts, now_slot) = clock_now();
+    let retriever =
+        ScanningAccountRetriever::new(ctx.remaining_accounts, &ctx.accounts.group.key())
+            .context("create account retriever")?;
+    let health_cache = new_health_cache(&account.borrow(), &retriever, now_ts)
+        .context("recover_from_being_liquidated health cache")?;
+
+    let maint_health = health_cache.health(HealthType::Maint);
+    require_msg_typed!(
+        maint_health >= I80F48::ZERO,
+        MangoError::HealthMustBePositive,
+        "maint health {} is negative",
+        maint_health
+    );
+
+    msg!(
+        "recovering from being_liquidated, set in slot {}, now {}",
+        account.fixed.being_liquidated_slot,
+        now_slot
+    );
+    account.fixed.end_being_liquidated();
+
+    Ok(())
+}
@@ -12,7 +12,9 @@ pub fn serum3_create_open_orders(ctx: Context<Serum3CreateOpenOrders>) -> Result
     let mut account = ctx.accounts.account.load_full_mut()?;
     // account constraint #1
     require!(
-        account.fixed.is_owner_or_delegate(ctx.accounts.owner.key()),
+        account
+            .fixed
+            .has_permission(ctx.accounts.owner.key(), DelegatePermission::Trade),
         MangoError::SomeError
     );
 
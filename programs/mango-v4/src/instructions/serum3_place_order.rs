@@ -46,7 +46,9 @@ pub fn serum3_place_order(
         let account = ctx.accounts.account.load_full()?;
         // account constraint #1
         require!(
-            account.fixed.is_owner_or_delegate(ctx.accounts.owner.key()),
+            account
+                .fixed
+                .has_permission(ctx.accounts.owner.key(), DelegatePermission::Trade),
             MangoError::SomeError
         );
 
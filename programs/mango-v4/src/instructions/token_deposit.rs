@@ -68,6 +68,12 @@ impl<'a, 'info> DepositCommon<'a, 'info> {
                     MangoError::TokenInReduceOnlyMode
                 );
             }
+            // Repaying borrows is fine even if the token isn't allowed (anymore)
+            require!(
+                account.fixed.is_token_allowed(token_index)
+                    || amount_i80f48 <= (-position.native(&bank)).ceil(),
+                MangoError::TokenNotAllowedForAccount
+            );
             amount_i80f48
         };
 
@@ -5,6 +5,12 @@ use crate::accounts_ix::*;
 use crate::{accounts_zerocopy::LoadZeroCopyRef, state::*};
 use anchor_lang::AccountsClose;
 
+/// Closes the banks, vaults and mint info of a token.
+///
+/// Banks and mint infos are separate accounts whose addresses derive from the token index,
+/// so token indexes are never remapped and simply stay unused after deregistering. The
+/// token position slots that accounts free up can be compacted with
+/// account_compact_token_positions.
 #[allow(clippy::too_many_arguments)]
 pub fn token_deregister<'key, 'accounts, 'remaining, 'info>(
     ctx: Context<'key, 'accounts, 'remaining, 'info, TokenDeregister<'info>>,
@@ -15,6 +21,12 @@ pub fn token_deregister<'key, 'accounts, 'remaining, 'info>(
         require_eq!(total_banks * 2, ctx.remaining_accounts.len());
     }
 
+    {
+        let mut group = ctx.accounts.group.load_mut()?;
+        // Groups that listed tokens before the count was tracked undercount them
+        group.token_count = group.token_count.saturating_sub(1);
+    }
+
     let group = ctx.accounts.group.load()?;
     let group_seeds = group_seeds!(group);
 
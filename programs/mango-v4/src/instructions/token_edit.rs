@@ -56,16 +56,27 @@ pub fn token_edit(
     disable_asset_liquidation_opt: Option<bool>,
     collateral_fee_per_day: Option<f32>,
     force_withdraw_opt: Option<bool>,
+    project_borrow_interest_in_health_opt: Option<bool>,
 ) -> Result<()> {
     let group = ctx.accounts.group.load()?;
 
     let mut mint_info = ctx.accounts.mint_info.load_mut()?;
     mint_info.verify_banks_ais(ctx.remaining_accounts)?;
 
+    let weights_changed = maint_asset_weight_opt.is_some()
+        || init_asset_weight_opt.is_some()
+        || maint_liab_weight_opt.is_some()
+        || init_liab_weight_opt.is_some();
+    let now_slot = Clock::get()?.slot;
+
     let mut require_group_admin = false;
     for ai in ctx.remaining_accounts.iter() {
         let mut bank = ai.load_mut::<Bank>()?;
 
+        if weights_changed {
+            bank.record_weight_change(now_slot, group.min_weight_change_interval_slots)?;
+        }
+
         if let Some(oracle_config) = oracle_config_opt.as_ref() {
             msg!(
                 "Oracle config: old - conf_filter {:?}, max_staleness_slots {:?},  new - conf_filter {:?}, max_staleness_slots {:?}",
@@ -521,6 +532,22 @@ pub fn token_edit(
             bank.force_withdraw = u8::from(force_withdraw);
             require_group_admin = true;
         }
+
+        if let Some(project_borrow_interest) = project_borrow_interest_in_health_opt {
+            msg!(
+                "Project borrow interest in health old {:?}, new {:?}",
+                bank.project_borrow_interest_in_health,
+                project_borrow_interest
+            );
+            bank.project_borrow_interest_in_health = u8::from(project_borrow_interest);
+            require_group_admin = true;
+        }
+
+        // Only enforced when the weight is edited: existing tokens, like trustless listings
+        // with a zero init asset weight, must stay editable.
+        if init_asset_weight_opt.is_some() {
+            bank.check_init_asset_weight_floor(group.init_asset_weight_floor)?;
+        }
     }
 
     // account constraint #1
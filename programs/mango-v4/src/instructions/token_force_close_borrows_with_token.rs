@@ -25,10 +25,11 @@ pub fn token_force_close_borrows_with_token(
     let mut liqor = ctx.accounts.liqor.load_full_mut()?;
     // account constraint #1
     require!(
-        liqor
-            .fixed
-            .is_owner_or_delegate(ctx.accounts.liqor_owner.key()),
-        MangoError::SomeError
+        liqor.fixed.has_permission(
+            ctx.accounts.liqor_owner.key(),
+            DelegatePermission::Liquidate
+        ),
+        MangoError::UnauthorizedLiqor
     );
     require_msg_typed!(
         !liqor.fixed.being_liquidated(),
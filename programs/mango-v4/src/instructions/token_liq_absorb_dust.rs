@@ -0,0 +1,201 @@
+use crate::accounts_ix::*;
+use crate::error::*;
+use crate::health::*;
+use crate::logs::{emit_stack, TokenBalanceLog, TokenLiqAbsorbDustLog};
+use crate::state::*;
+use anchor_lang::prelude::*;
+use fixed::types::I80F48;
+
+/// Lets the liqor take over a healthy liqee's tiny deposit of `dust_token_index` at oracle
+/// price, paying for it in `quote_token_index`.
+///
+/// This closes leftover dust positions, for example after liquidations, so the liqee
+/// account can be closed. Only deposits worth up to the group's max_dust_absorb_quote
+/// can be absorbed. Dust borrows can be repaid with a regular deposit instead.
+pub fn token_liq_absorb_dust(
+    ctx: Context<TokenLiqAbsorbDust>,
+    dust_token_index: TokenIndex,
+    quote_token_index: TokenIndex,
+) -> Result<()> {
+    let group_pk = &ctx.accounts.group.key();
+    let max_dust_quote = I80F48::from(ctx.accounts.group.load()?.max_dust_absorb_quote);
+    require_msg!(max_dust_quote > 0, "dust absorption is disabled");
+    require_neq!(dust_token_index, quote_token_index, MangoError::SomeError);
+
+    let mut account_retriever = ScanningAccountRetriever::new(ctx.remaining_accounts, group_pk)
+        .context("create account retriever")?;
+
+    require_keys_neq!(ctx.accounts.liqor.key(), ctx.accounts.liqee.key());
+    let mut liqor = ctx.accounts.liqor.load_full_mut()?;
+    // account constraint #1
+    require!(
+        liqor.fixed.has_permission(
+            ctx.accounts.liqor_owner.key(),
+            DelegatePermission::Liquidate
+        ),
+        MangoError::UnauthorizedLiqor
+    );
+    require_msg_typed!(
+        !liqor.fixed.being_liquidated(),
+        MangoError::BeingLiquidated,
+        "liqor account"
+    );
+
+    let mut liqee = ctx.accounts.liqee.load_full_mut()?;
+    require_msg_typed!(
+        !liqee.fixed.being_liquidated(),
+        MangoError::BeingLiquidated,
+        "liqee account"
+    );
+
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+    // Unhealthy accounts need to go through liquidation instead
+    {
+        let liqee_health_cache = new_health_cache(&liqee.borrow(), &mut account_retriever, now_ts)
+            .context("create liqee health cache")?;
+        require!(
+            liqee_health_cache.health(HealthType::Maint) >= 0,
+            MangoError::HealthMustBePositive
+        );
+    }
+
+    //
+    // Transfer the dust token from liqee to liqor and the quote token back
+    //
+    {
+        let liqor: &mut MangoAccountRefMut = &mut liqor.borrow_mut();
+        let liqor_key = ctx.accounts.liqor.key();
+        let liqee: &mut MangoAccountRefMut = &mut liqee.borrow_mut();
+        let liqee_key = ctx.accounts.liqee.key();
+
+        let (dust_bank, dust_price, opt_quote_bank_and_price) =
+            account_retriever.banks_mut_and_oracles(dust_token_index, quote_token_index)?;
+        let (quote_bank, quote_price) = opt_quote_bank_and_price.unwrap();
+
+        let (liqee_dust_position, liqee_dust_raw_index) =
+            liqee.token_position_and_raw_index(dust_token_index)?;
+        let dust_transfer = liqee_dust_position.native(dust_bank);
+        require_msg!(
+            dust_transfer > 0,
+            "liqee has no deposits of token {}",
+            dust_token_index
+        );
+
+        let dust_value = dust_transfer * dust_price;
+        require_msg!(
+            dust_value <= max_dust_quote,
+            "dust value {} exceeds the max of {}",
+            dust_value,
+            max_dust_quote
+        );
+        let quote_transfer = dust_value / quote_price;
+
+        // Apply the balance changes to the liqor and liqee accounts
+        let liqee_dust_position = liqee.token_position_mut_by_raw_index(liqee_dust_raw_index);
+        let liqee_dust_active = dust_bank.withdraw_without_fee_with_dusting(
+            liqee_dust_position,
+            dust_transfer,
+            now_ts,
+        )?;
+        let liqee_dust_indexed_position = liqee_dust_position.indexed_position;
+
+        let (liqor_dust_position, liqor_dust_raw_index, _) =
+            liqor.ensure_token_position(dust_token_index)?;
+        let liqor_dust_active = dust_bank.deposit(liqor_dust_position, dust_transfer, now_ts)?;
+        let liqor_dust_indexed_position = liqor_dust_position.indexed_position;
+
+        let (liqee_quote_position, liqee_quote_raw_index, _) =
+            liqee.ensure_token_position(quote_token_index)?;
+        let liqee_quote_active =
+            quote_bank.deposit(liqee_quote_position, quote_transfer, now_ts)?;
+        let liqee_quote_indexed_position = liqee_quote_position.indexed_position;
+
+        let (liqor_quote_position, liqor_quote_raw_index, _) =
+            liqor.ensure_token_position(quote_token_index)?;
+        let liqor_quote_withdraw_result =
+            quote_bank.withdraw_with_fee(liqor_quote_position, quote_transfer, now_ts)?;
+        let liqor_quote_indexed_position = liqor_quote_position.indexed_position;
+
+        msg!(
+            "Absorbed {} dust for {} quote",
+            dust_transfer,
+            quote_transfer
+        );
+
+        // liqee dust
+        emit_stack(TokenBalanceLog {
+            mango_group: liqee.fixed.group,
+            mango_account: liqee_key,
+            token_index: dust_token_index,
+            indexed_position: liqee_dust_indexed_position.to_bits(),
+            deposit_index: dust_bank.deposit_index.to_bits(),
+            borrow_index: dust_bank.borrow_index.to_bits(),
+        });
+        // liqee quote
+        emit_stack(TokenBalanceLog {
+            mango_group: liqee.fixed.group,
+            mango_account: liqee_key,
+            token_index: quote_token_index,
+            indexed_position: liqee_quote_indexed_position.to_bits(),
+            deposit_index: quote_bank.deposit_index.to_bits(),
+            borrow_index: quote_bank.borrow_index.to_bits(),
+        });
+        // liqor dust
+        emit_stack(TokenBalanceLog {
+            mango_group: liqee.fixed.group,
+            mango_account: liqor_key,
+            token_index: dust_token_index,
+            indexed_position: liqor_dust_indexed_position.to_bits(),
+            deposit_index: dust_bank.deposit_index.to_bits(),
+            borrow_index: dust_bank.borrow_index.to_bits(),
+        });
+        // liqor quote
+        emit_stack(TokenBalanceLog {
+            mango_group: liqee.fixed.group,
+            mango_account: liqor_key,
+            token_index: quote_token_index,
+            indexed_position: liqor_quote_indexed_position.to_bits(),
+            deposit_index: quote_bank.deposit_index.to_bits(),
+            borrow_index: quote_bank.borrow_index.to_bits(),
+        });
+
+        emit_stack(TokenLiqAbsorbDustLog {
+            mango_group: liqee.fixed.group,
+            liqor: liqor_key,
+            liqee: liqee_key,
+            dust_token_index,
+            quote_token_index,
+            dust_transfer: dust_transfer.to_bits(),
+            quote_transfer: quote_transfer.to_bits(),
+            dust_price: dust_price.to_bits(),
+            quote_price: quote_price.to_bits(),
+        });
+
+        // Since we use a scanning account retriever, it's safe to deactivate inactive token positions
+        if !liqee_dust_active {
+            liqee.deactivate_token_position_and_log(liqee_dust_raw_index, liqee_key);
+        }
+        if !liqee_quote_active {
+            liqee.deactivate_token_position_and_log(liqee_quote_raw_index, liqee_key);
+        }
+        if !liqor_dust_active {
+            liqor.deactivate_token_position_and_log(liqor_dust_raw_index, liqor_key);
+        }
+        if !liqor_quote_withdraw_result.position_is_active {
+            liqor.deactivate_token_position_and_log(liqor_quote_raw_index, liqor_key);
+        }
+    }
+
+    // Check liqor's health
+    let liqor_health = compute_health(
+        &liqor.borrow(),
+        HealthType::Init,
+        &mut account_retriever,
+        now_ts,
+    )
+    .context("compute liqor health")?;
+    require!(liqor_health >= 0, MangoError::HealthMustBePositive);
+
+    Ok(())
+}
@@ -48,10 +48,11 @@ pub fn token_liq_bankruptcy(
     let mut liqor = ctx.accounts.liqor.load_full_mut()?;
     // account constraint #1
     require!(
-        liqor
-            .fixed
-            .is_owner_or_delegate(ctx.accounts.liqor_owner.key()),
-        MangoError::SomeError
+        liqor.fixed.has_permission(
+            ctx.accounts.liqor_owner.key(),
+            DelegatePermission::Liquidate
+        ),
+        MangoError::UnauthorizedLiqor
     );
     require_msg_typed!(
         !liqor.fixed.being_liquidated(),
@@ -60,13 +61,14 @@ pub fn token_liq_bankruptcy(
     );
 
     let mut account_retriever = ScanningAccountRetriever::new(health_ais, group_pk)?;
-    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    let clock = Clock::get()?;
+    let now_ts: u64 = clock.unix_timestamp.try_into().unwrap();
 
     let mut liqee = ctx.accounts.liqee.load_full_mut()?;
     let mut liqee_health_cache = new_health_cache(&liqee.borrow(), &account_retriever, now_ts)
         .context("create liqee health cache")?;
     liqee_health_cache.require_after_phase2_liquidation()?;
-    liqee.fixed.set_being_liquidated(true);
+    liqee.fixed.start_being_liquidated(clock.slot);
 
     let liab_is_insurance_token = liab_token_index == insurance_token_index;
     let (liab_bank, liab_oracle_price, opt_insurance_bank_and_price) =
@@ -170,6 +172,9 @@ pub fn token_liq_bankruptcy(
                 liqor.ensure_token_position(liab_token_index)?;
             let liqor_liab_withdraw_result =
                 liab_bank.withdraw_with_fee(liqor_liab, liab_transfer, now_ts)?;
+            if liqor_liab_withdraw_result.has_loan() {
+                liab_bank.check_net_borrows(liab_oracle_price)?;
+            }
 
             // liqor liab
             emit_stack(TokenBalanceLog {
@@ -16,21 +16,86 @@ pub fn token_liq_with_token(
     liab_token_index: TokenIndex,
     max_liab_transfer: I80F48,
 ) -> Result<()> {
-    let group_pk = &ctx.accounts.group.key();
+    token_liq_with_token_internal(
+        &ctx.accounts.group,
+        &ctx.accounts.liqor,
+        ctx.accounts.liqor_owner.key(),
+        &ctx.accounts.liqee,
+        ctx.remaining_accounts,
+        asset_token_index,
+        liab_token_index,
+        max_liab_transfer,
+        0,
+    )
+}
+
+/// Emergency liquidation that the group admin can use when oracles lag behind but
+/// accounts are deeply underwater.
+///
+/// Oracles are allowed to be up to `extra_staleness_slots` staler than their
+/// configured max_staleness_slots.
+pub fn token_liq_with_token_relaxed_staleness(
+    ctx: Context<TokenLiqWithTokenRelaxedStaleness>,
+    asset_token_index: TokenIndex,
+    liab_token_index: TokenIndex,
+    max_liab_transfer: I80F48,
+    extra_staleness_slots: u64,
+) -> Result<()> {
+    msg!(
+        "admin {} relaxes oracle staleness by {} slots",
+        ctx.accounts.admin.key(),
+        extra_staleness_slots
+    );
+    token_liq_with_token_internal(
+        &ctx.accounts.group,
+        &ctx.accounts.liqor,
+        ctx.accounts.liqor_owner.key(),
+        &ctx.accounts.liqee,
+        ctx.remaining_accounts,
+        asset_token_index,
+        liab_token_index,
+        max_liab_transfer,
+        extra_staleness_slots,
+    )
+}
+
+fn token_liq_with_token_internal<'a, 'info>(
+    group: &AccountLoader<'info, Group>,
+    liqor_loader: &AccountLoader<'info, MangoAccountFixed>,
+    liqor_owner: Pubkey,
+    liqee_loader: &AccountLoader<'info, MangoAccountFixed>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+    asset_token_index: TokenIndex,
+    liab_token_index: TokenIndex,
+    max_liab_transfer: I80F48,
+    extra_staleness_slots: u64,
+) -> Result<()> {
+    let group_pk = &group.key();
 
     require!(asset_token_index != liab_token_index, MangoError::SomeError);
-    let mut account_retriever = ScanningAccountRetriever::new(ctx.remaining_accounts, group_pk)
-        .context("create account retriever")?;
-    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    let clock = Clock::get()?;
+    let now_ts: u64 = clock.unix_timestamp.try_into().unwrap();
 
-    require_keys_neq!(ctx.accounts.liqor.key(), ctx.accounts.liqee.key());
-    let mut liqor = ctx.accounts.liqor.load_full_mut()?;
+    // Checking staleness against an earlier point in time relaxes the bound
+    let staleness_now = (
+        now_ts.saturating_sub(extra_staleness_slots.saturating_mul(MS_PER_SLOT) / 1000),
+        clock.slot.saturating_sub(extra_staleness_slots),
+    );
+    let mut account_retriever = ScanningAccountRetriever::new_with_staleness(
+        remaining_accounts,
+        group_pk,
+        Some(staleness_now),
+    )
+    .context("create account retriever")?;
+
+    require_keys_neq!(liqor_loader.key(), liqee_loader.key());
+    let mut liqor = liqor_loader.load_full_mut()?;
     // account constraint #1
     require!(
         liqor
             .fixed
-            .is_owner_or_delegate(ctx.accounts.liqor_owner.key()),
-        MangoError::SomeError
+            .has_permission(liqor_owner, DelegatePermission::Liquidate),
+        MangoError::UnauthorizedLiqor
     );
     require_msg_typed!(
         !liqor.fixed.being_liquidated(),
@@ -38,7 +103,7 @@ pub fn token_liq_with_token(
         "liqor account"
     );
 
-    let mut liqee = ctx.accounts.liqee.load_full_mut()?;
+    let mut liqee = liqee_loader.load_full_mut()?;
 
     // Initial liqee health check
     let mut liqee_health_cache = new_health_cache(&liqee.borrow(), &account_retriever, now_ts)
@@ -59,9 +124,9 @@ pub fn token_liq_with_token(
         liab_token_index,
         asset_token_index,
         &mut liqor.borrow_mut(),
-        ctx.accounts.liqor.key(),
+        liqor_loader.key(),
         &mut liqee.borrow_mut(),
-        ctx.accounts.liqee.key(),
+        liqee_loader.key(),
         &mut liqee_health_cache,
         liqee_liq_end_health,
         now_ts,
@@ -248,6 +313,11 @@ pub(crate) fn liquidation_action(
     let liqor_liab_withdraw_result =
         liab_bank.withdraw_with_fee(liqor_liab_position, liab_transfer, now_ts)?;
     let liqor_liab_indexed_position = liqor_liab_position.indexed_position;
+    // Deposits into the liqee are never limited, but a liqor that borrows to take on the
+    // liab is subject to the same net borrow limit as any other borrower
+    if liqor_liab_withdraw_result.has_loan() {
+        liab_bank.check_net_borrows(liab_oracle_price)?;
+    }
     let liqee_liab_native_after = liqee_liab_position.native(liab_bank);
 
     let (liqor_asset_position, liqor_asset_raw_index, _) =
@@ -600,6 +670,62 @@ mod tests {
         assert_eq_f!(hc.health(HealthType::LiquidationEnd), 0.0, 0.01);
     }
 
+    // A borrow makes the account liquidatable: the liqor takes over the borrow in exchange
+    // for deposits worth the borrow plus the liquidation fee, until health is restored
+    #[test]
+    fn test_liq_with_token_restores_health() {
+        let mut setup = TestSetup::new();
+        {
+            let ab = setup.asset_bank.data();
+            ab.liquidation_fee = I80F48::ZERO;
+            let lb = setup.liab_bank.data();
+            lb.liquidation_fee = I80F48::from_num(0.05);
+            lb.init_liab_weight = I80F48::from_num(1.4);
+            lb.maint_liab_weight = I80F48::from_num(1.2);
+        }
+        {
+            let asset_bank = setup.asset_bank.data();
+            asset_bank
+                .change_without_fee(asset_p(&mut setup.liqee), I80F48::from_num(10.0), 0)
+                .unwrap();
+
+            let liab_bank = setup.liab_bank.data();
+            liab_bank
+                .change_without_fee(liab_p(&mut setup.liqor), I80F48::from_num(1000.0), 0)
+                .unwrap();
+            liab_bank
+                .change_without_fee(liab_p(&mut setup.liqee), I80F48::from_num(-9.0), 0)
+                .unwrap();
+        }
+
+        let hc = setup.liqee_health_cache();
+        assert_eq_f!(hc.health(HealthType::Maint), 10.0 - 9.0 * 1.2, 0.01);
+        assert_eq_f!(
+            hc.health(HealthType::LiquidationEnd),
+            10.0 - 9.0 * 1.4,
+            0.01
+        );
+
+        let mut result = setup.run(I80F48::from(100)).unwrap();
+
+        // 10 - 1.05 * x - 1.4 * (9 - x) = 0
+        let liab_transfer = 2.6 / 0.35;
+        let asset_transfer = liab_transfer * 1.05;
+        let liqee_asset = asset_p(&mut result.liqee).native(&result.asset_bank.data());
+        assert_eq_f!(liqee_asset, 10.0 - asset_transfer, 0.01);
+        let liqee_liab = liab_p(&mut result.liqee).native(&result.liab_bank.data());
+        assert_eq_f!(liqee_liab, -9.0 + liab_transfer, 0.01);
+
+        let liqor_asset = asset_p(&mut result.liqor).native(&result.asset_bank.data());
+        assert_eq_f!(liqor_asset, asset_transfer, 0.01);
+        let liqor_liab = liab_p(&mut result.liqor).native(&result.liab_bank.data());
+        assert_eq_f!(liqor_liab, 1000.0 - liab_transfer, 0.01);
+
+        let hc = result.liqee_health_cache();
+        assert_eq_f!(hc.health(HealthType::LiquidationEnd), 0.0, 0.01);
+        assert!(hc.health(HealthType::Maint) > 0);
+    }
+
     #[test]
     fn test_liq_with_token_while_perp() {
         let test_cases = vec![
@@ -126,7 +126,10 @@ pub fn token_register(
         collected_liquidation_fees: I80F48::ZERO,
         collected_collateral_fees: I80F48::ZERO,
         collateral_fee_per_day,
-        reserved: [0; 1900],
+        project_borrow_interest_in_health: 0,
+        padding2: [0; 3],
+        last_weight_change_slot: 0,
+        reserved: [0; 1888],
     };
 
     let oracle_ref = &AccountInfoRef::borrow(ctx.accounts.oracle.as_ref())?;
@@ -139,6 +142,11 @@ pub fn token_register(
     }
 
     bank.verify()?;
+    {
+        let mut group = ctx.accounts.group.load_mut()?;
+        bank.check_init_asset_weight_floor(group.init_asset_weight_floor)?;
+        group.token_count += 1;
+    }
     check_is_valid_fallback_oracle(&AccountInfoRef::borrow(
         ctx.accounts.fallback_oracle.as_ref(),
     )?)?;
@@ -33,6 +33,7 @@ pub fn token_register_trustless(
             group.allowed_fast_listings_per_interval,
             group.fast_listings_in_interval
         );
+        group.token_count += 1;
     }
 
     let net_borrow_limit_window_size_ts = 24 * 60 * 60u64;
@@ -111,7 +112,10 @@ pub fn token_register_trustless(
         collected_liquidation_fees: I80F48::ZERO,
         collected_collateral_fees: I80F48::ZERO,
         collateral_fee_per_day: 0.0, // TODO
-        reserved: [0; 1900],
+        project_borrow_interest_in_health: 0,
+        padding2: [0; 3],
+        last_weight_change_slot: 0,
+        reserved: [0; 1888],
     };
     let oracle_ref = &AccountInfoRef::borrow(ctx.accounts.oracle.as_ref())?;
     if let Ok(oracle_price) = bank.oracle_price(&OracleAccountInfos::from_reader(oracle_ref), None)
@@ -65,6 +65,12 @@ pub fn token_withdraw(ctx: Context<TokenWithdraw>, amount: u64, allow_borrow: bo
     if bank.are_borrows_reduce_only() {
         require!(!is_borrow, MangoError::TokenInReduceOnlyMode);
     }
+    if is_borrow {
+        require!(
+            account.fixed.is_token_allowed(token_index),
+            MangoError::TokenNotAllowedForAccount
+        );
+    }
 
     let amount_i80f48 = I80F48::from(amount);
 
@@ -120,6 +126,13 @@ pub fn token_withdraw(ctx: Context<TokenWithdraw>, amount: u64, allow_borrow: bo
 
     // Delegates have heavy restrictions on withdraws. #1
     if account.fixed.is_delegate(ctx.accounts.owner.key()) {
+        require!(
+            account
+                .fixed
+                .has_permission(ctx.accounts.owner.key(), DelegatePermission::Withdraw),
+            MangoError::DelegatePermissionMissing
+        );
+
         // Delegates can only withdrawing into the actual owner's ATA
         let owner_ata = associated_token::get_associated_token_address(
             &account.fixed.owner,
@@ -69,6 +69,7 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    /// admin_opt must be None, the admin is changed with group_set_pending_admin.
     #[allow(clippy::too_many_arguments)]
     pub fn group_edit(
         ctx: Context<GroupEdit>,
@@ -85,6 +86,15 @@ pub mod mango_v4 {
         buyback_fees_expiry_interval_opt: Option<u64>,
         allowed_fast_listings_per_interval_opt: Option<u16>,
         collateral_fee_interval_opt: Option<u64>,
+        max_liquidation_overshoot_health_opt: Option<u64>,
+        max_dust_absorb_quote_opt: Option<u64>,
+        init_asset_weight_floor_opt: Option<f32>,
+        liqor_fee_waiver_min_mngo_opt: Option<u64>,
+        min_weight_change_interval_slots_opt: Option<u64>,
+        protocol_liquidator_opt: Option<Pubkey>,
+        max_liquidation_fee_opt: Option<f32>,
+        sweep_bankrupt_perp_pnl_opt: Option<bool>,
+        liqor_fee_waiver_registrar_opt: Option<Pubkey>,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::group_edit(
@@ -102,10 +112,38 @@ pub mod mango_v4 {
             buyback_fees_expiry_interval_opt,
             allowed_fast_listings_per_interval_opt,
             collateral_fee_interval_opt,
+            max_liquidation_overshoot_health_opt,
+            max_dust_absorb_quote_opt,
+            init_asset_weight_floor_opt,
+            liqor_fee_waiver_min_mngo_opt,
+            min_weight_change_interval_slots_opt,
+            protocol_liquidator_opt,
+            max_liquidation_fee_opt,
+            sweep_bankrupt_perp_pnl_opt,
+            liqor_fee_waiver_registrar_opt,
         )?;
         Ok(())
     }
 
+    /// Proposes a new group admin, which takes over once it calls group_accept_admin.
+    ///
+    /// Pubkey::default() withdraws the proposal.
+    pub fn group_set_pending_admin(
+        ctx: Context<GroupSetPendingAdmin>,
+        pending_admin: Pubkey,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::group_set_pending_admin(ctx, pending_admin)?;
+        Ok(())
+    }
+
+    /// Makes the pending admin proposed with group_set_pending_admin the group admin
+    pub fn group_accept_admin(ctx: Context<GroupAcceptAdmin>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::group_accept_admin(ctx)?;
+        Ok(())
+    }
+
     pub fn group_withdraw_insurance_fund(
         ctx: Context<GroupWithdrawInsuranceFund>,
         amount: u64,
@@ -127,6 +165,12 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    pub fn group_status(ctx: Context<GroupStatus>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::group_status(ctx)?;
+        Ok(())
+    }
+
     pub fn group_close(ctx: Context<GroupClose>) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::group_close(ctx)?;
@@ -260,6 +304,7 @@ pub mod mango_v4 {
         disable_asset_liquidation_opt: Option<bool>,
         collateral_fee_per_day_opt: Option<f32>,
         force_withdraw_opt: Option<bool>,
+        project_borrow_interest_in_health_opt: Option<bool>,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::token_edit(
@@ -305,6 +350,7 @@ pub mod mango_v4 {
             disable_asset_liquidation_opt,
             collateral_fee_per_day_opt,
             force_withdraw_opt,
+            project_borrow_interest_in_health_opt,
         )?;
         Ok(())
     }
@@ -451,12 +497,55 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    pub fn account_set_owner(
+        ctx: Context<AccountSetOwner>,
+        new_owner: Pubkey,
+        clear_delegates: bool,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::account_set_owner(ctx, new_owner, clear_delegates)?;
+        Ok(())
+    }
+
     pub fn account_toggle_freeze(ctx: Context<AccountToggleFreeze>, freeze: bool) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::account_toggle_freeze(ctx, freeze)?;
         Ok(())
     }
 
+    /// Moves the account's active token positions into the front slots, filling the gaps
+    /// that deactivated positions leave. Token indexes and the active order are unchanged.
+    pub fn account_compact_token_positions(
+        ctx: Context<AccountCompactTokenPositions>,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::account_compact_token_positions(ctx)?;
+        Ok(())
+    }
+
+    pub fn account_set_delegate_permissions(
+        ctx: Context<AccountSetDelegatePermissions>,
+        delegate_permissions: u8,
+        temporary_delegate_permissions: u8,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::account_set_delegate_permissions(
+            ctx,
+            delegate_permissions,
+            temporary_delegate_permissions,
+        )?;
+        Ok(())
+    }
+
+    pub fn account_set_token_allowlist(
+        ctx: Context<AccountSetTokenAllowlist>,
+        token_indexes: Vec<TokenIndex>,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::account_set_token_allowlist(ctx, token_indexes)?;
+        Ok(())
+    }
+
     pub fn account_close(ctx: Context<AccountClose>, force_close: bool) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::account_close(ctx, force_close)?;
@@ -488,6 +577,28 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    /// Returns init and maint health as return data, see instructions::compute_account_health
+    pub fn compute_account_health(ctx: Context<ComputeAccountHealth>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::compute_account_health(ctx)?;
+        Ok(())
+    }
+
+    /// Stores the account's health for the current slot, see instructions::cache_account_health
+    pub fn cache_account_health(ctx: Context<CacheAccountHealth>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::cache_account_health(ctx)?;
+        Ok(())
+    }
+
+    /// Lets the owner clear being_liquidated on a healthy account, see
+    /// instructions::recover_from_being_liquidated
+    pub fn recover_from_being_liquidated(ctx: Context<RecoverFromBeingLiquidated>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::recover_from_being_liquidated(ctx)?;
+        Ok(())
+    }
+
     // todo:
     // ckamm: generally, using an I80F48 arg will make it harder to call
     // because generic anchor clients won't know how to deal with it
@@ -824,6 +935,26 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    /// Admin-only variant of token_liq_with_token for emergencies, where oracles may
+    /// be up to extra_staleness_slots staler than usually allowed.
+    pub fn token_liq_with_token_relaxed_staleness(
+        ctx: Context<TokenLiqWithTokenRelaxedStaleness>,
+        asset_token_index: TokenIndex,
+        liab_token_index: TokenIndex,
+        max_liab_transfer: I80F48,
+        extra_staleness_slots: u64,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::token_liq_with_token_relaxed_staleness(
+            ctx,
+            asset_token_index,
+            liab_token_index,
+            max_liab_transfer,
+            extra_staleness_slots,
+        )?;
+        Ok(())
+    }
+
     pub fn token_force_close_borrows_with_token(
         ctx: Context<TokenForceCloseBorrowsWithToken>,
         asset_token_index: TokenIndex,
@@ -840,6 +971,18 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    /// Lets the liqor take over a healthy account's tiny dust_token_index deposit
+    /// at oracle price, in exchange for quote_token_index.
+    pub fn token_liq_absorb_dust(
+        ctx: Context<TokenLiqAbsorbDust>,
+        dust_token_index: TokenIndex,
+        quote_token_index: TokenIndex,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::token_liq_absorb_dust(ctx, dust_token_index, quote_token_index)?;
+        Ok(())
+    }
+
     pub fn token_liq_bankruptcy(
         ctx: Context<TokenLiqBankruptcy>,
         max_liab_transfer: I80F48,
@@ -960,6 +1103,23 @@ pub mod mango_v4 {
         name_opt: Option<String>,
         force_close_opt: Option<bool>,
         platform_liquidation_fee_opt: Option<f32>,
+        liquidation_price_tolerance_opt: Option<f32>,
+        base_liquidation_fee_bps_opt: Option<u32>,
+        use_ema_for_trigger_opt: Option<bool>,
+        ema_interval_seconds_opt: Option<u32>,
+        large_position_threshold_lots_opt: Option<i64>,
+        large_position_whitelist_opt: Option<Vec<Pubkey>>,
+        dust_threshold_lots_opt: Option<i64>,
+        dust_liquidation_fee_opt: Option<f32>,
+        settle_fee_bps_opt: Option<u32>,
+        max_liquidation_base_per_slot_opt: Option<i64>,
+        liquidation_throttle_bypass_health_opt: Option<f64>,
+        liquidation_fee_insurance_fraction_opt: Option<f32>,
+        adl_enabled_opt: Option<bool>,
+        underlying_id_opt: Option<u16>,
+        min_liquidation_base_lots_opt: Option<i64>,
+        liquidation_quote_rounding_buffer_opt: Option<f64>,
+        alt_settle_token_index_opt: Option<TokenIndex>,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::perp_edit_market(
@@ -995,6 +1155,23 @@ pub mod mango_v4 {
             name_opt,
             force_close_opt,
             platform_liquidation_fee_opt,
+            liquidation_price_tolerance_opt,
+            base_liquidation_fee_bps_opt,
+            use_ema_for_trigger_opt,
+            ema_interval_seconds_opt,
+            large_position_threshold_lots_opt,
+            large_position_whitelist_opt,
+            dust_threshold_lots_opt,
+            dust_liquidation_fee_opt,
+            settle_fee_bps_opt,
+            max_liquidation_base_per_slot_opt,
+            liquidation_throttle_bypass_health_opt,
+            liquidation_fee_insurance_fraction_opt,
+            adl_enabled_opt,
+            underlying_id_opt,
+            min_liquidation_base_lots_opt,
+            liquidation_quote_rounding_buffer_opt,
+            alt_settle_token_index_opt,
         )?;
         Ok(())
     }
@@ -1340,6 +1517,20 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    /// Takes the loss of a bankrupt perp position that the insurance fund couldn't cover
+    /// from the last num_counterparties remaining accounts, ranked by descending pnl.
+    pub fn perp_adl(ctx: Context<PerpAdl>, num_counterparties: u8) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_adl(ctx, num_counterparties)?;
+        Ok(())
+    }
+
+    pub fn perp_settle_all_funding(ctx: Context<PerpSettleAllFunding>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_settle_all_funding(ctx)?;
+        Ok(())
+    }
+
     pub fn perp_settle_fees(ctx: Context<PerpSettleFees>, max_settle_amount: u64) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::perp_settle_fees(ctx, max_settle_amount)?;
@@ -1352,7 +1543,105 @@ pub mod mango_v4 {
         max_pnl_transfer: u64,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
-        instructions::perp_liq_base_or_positive_pnl(ctx, max_base_transfer, max_pnl_transfer)?;
+        instructions::perp_liq_base_or_positive_pnl(
+            ctx.accounts,
+            ctx.remaining_accounts,
+            max_base_transfer,
+            max_pnl_transfer,
+            None,
+            None,
+            0,
+            None,
+            None,
+        )?;
+        Ok(())
+    }
+
+    /// Like perp_liq_base_or_positive_pnl, but allows the liqor to supply the price
+    /// (in native quote per native base) that the base position is transferred at.
+    ///
+    /// With min_net_quote_profit_opt the liquidation only goes through if the liqor reward
+    /// minus liquidation_cost (both in native settle token) reaches that minimum.
+    ///
+    /// With max_health_improvement_opt the liqor limits the liqee's health improvement
+    /// (in native quote) in this call.
+    pub fn perp_liq_base_or_positive_pnl_v2(
+        ctx: Context<PerpLiqBaseOrPositivePnl>,
+        max_base_transfer: i64,
+        max_pnl_transfer: u64,
+        liqor_price_opt: Option<f64>,
+        min_net_quote_profit_opt: Option<i64>,
+        liquidation_cost: u64,
+        max_health_improvement_opt: Option<u64>,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_liq_base_or_positive_pnl(
+            ctx.accounts,
+            ctx.remaining_accounts,
+            max_base_transfer,
+            max_pnl_transfer,
+            liqor_price_opt,
+            min_net_quote_profit_opt,
+            liquidation_cost,
+            max_health_improvement_opt,
+            None,
+        )?;
+        Ok(())
+    }
+
+    /// Like perp_liq_base_or_positive_pnl_v2. With settle_bad_debt_first the group insurance
+    /// fund first covers the liqee's bad debt in the market, before the base position is
+    /// liquidated against the improved health.
+    ///
+    /// With liqor_quote_token_index the liqor pays for taken over positive pnl in the market's
+    /// alternative settle token instead, converted at oracle prices.
+    pub fn perp_liq_base_or_positive_pnl_v3(
+        ctx: Context<PerpLiqBaseOrPositivePnlV3>,
+        max_base_transfer: i64,
+        max_pnl_transfer: u64,
+        liqor_price_opt: Option<f64>,
+        min_net_quote_profit_opt: Option<i64>,
+        liquidation_cost: u64,
+        max_health_improvement_opt: Option<u64>,
+        settle_bad_debt_first: bool,
+        liqor_quote_token_index: Option<TokenIndex>,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_liq_base_or_positive_pnl_v3(
+            ctx,
+            max_base_transfer,
+            max_pnl_transfer,
+            liqor_price_opt,
+            min_net_quote_profit_opt,
+            liquidation_cost,
+            max_health_improvement_opt,
+            settle_bad_debt_first,
+            liqor_quote_token_index,
+        )?;
+        Ok(())
+    }
+
+    /// Emits what perp_liq_base_or_positive_pnl_v2 would do with these arguments,
+    /// without changing any accounts.
+    pub fn perp_explain_liquidation(
+        ctx: Context<PerpExplainLiquidation>,
+        max_base_transfer: i64,
+        max_pnl_transfer: u64,
+        liqor_price_opt: Option<f64>,
+        min_net_quote_profit_opt: Option<i64>,
+        liquidation_cost: u64,
+        max_health_improvement_opt: Option<u64>,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_explain_liquidation(
+            ctx,
+            max_base_transfer,
+            max_pnl_transfer,
+            liqor_price_opt,
+            min_net_quote_profit_opt,
+            liquidation_cost,
+            max_health_improvement_opt,
+        )?;
         Ok(())
     }
 
@@ -444,6 +444,14 @@ pub struct DeactivateTokenPositionLog {
     pub cumulative_borrow_interest: f64,
 }
 
+/// The account's token positions after account_compact_token_positions, in slot order
+#[event]
+pub struct AccountCompactTokenPositionsLog {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub token_indexes: Vec<u16>,
+}
+
 #[event]
 pub struct DeactivatePerpPositionLog {
     pub mango_group: Pubkey,
@@ -543,6 +551,37 @@ pub struct PerpLiqBaseOrPositivePnlLogV3 {
     pub price: i128,
 }
 
+#[event]
+pub struct PerpLiqBaseOrPositivePnlLogV4 {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub base_transfer_liqee: i64,
+    pub quote_transfer_liqee: i128,
+    pub quote_transfer_liqor: i128,
+    pub quote_platform_fee: i128,
+    pub pnl_transfer: i128,
+    pub pnl_settle_limit_transfer_recurring: i64,
+    pub pnl_settle_limit_transfer_oneshot: i64,
+    pub price: i128,
+    /// See liquidation_state_hash(), all zeros unless built with liquidation-state-hash
+    pub liqee_state_hash_before: [u8; 32],
+    pub liqee_state_hash_after: [u8; 32],
+    pub liqor_state_hash_before: [u8; 32],
+    pub liqor_state_hash_after: [u8; 32],
+    /// Prices of the positions after the liquidation, in native/native units
+    pub liqee_avg_entry_price: f64,
+    pub liqee_break_even_price: f64,
+    pub liqor_avg_entry_price: f64,
+    pub liqor_break_even_price: f64,
+    /// Liqee equity lost to the liquidation, see liqee_liquidation_cost() (I80F48)
+    pub liqee_liquidation_cost: i128,
+    pub oracle_price: i128,             // I80F48
+    pub liqee_init_health_before: i128, // I80F48
+    pub liqee_init_health_after: i128,  // I80F48
+}
+
 #[event]
 pub struct PerpLiqBankruptcyLog {
     pub mango_group: Pubkey,
@@ -557,6 +596,27 @@ pub struct PerpLiqBankruptcyLog {
     pub ending_short_funding: i128,
 }
 
+#[event]
+pub struct PerpLiqBadDebtInsuranceLog {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub bad_debt: i128,
+    pub insurance_transfer: u64,
+}
+
+#[event]
+pub struct PerpLiqPnlTakeoverTokenLog {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub token_index: u16,
+    pub settle_token_transfer: i128, // I80F48
+    pub token_transfer: i128,        // I80F48
+}
+
 #[event]
 pub struct PerpLiqNegativePnlOrBankruptcyLog {
     pub mango_group: Pubkey,
@@ -585,6 +645,19 @@ pub struct PerpSettleFeesLog {
     pub settlement: i128,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PerpSettleFundingDetail {
+    pub perp_market_index: u16,
+    pub funding: i128, // I80F48, native quote paid by the account
+}
+
+#[event]
+pub struct PerpSettleAllFundingLog {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub settled_funding: Vec<PerpSettleFundingDetail>,
+}
+
 #[event]
 pub struct AccountBuybackFeesWithMngoLog {
     pub mango_group: Pubkey,
@@ -815,3 +888,63 @@ pub struct ForceWithdrawLog {
     pub price: i128, // I80F48
     pub to_token_account: Pubkey,
 }
+
+#[event]
+pub struct TokenLiqAbsorbDustLog {
+    pub mango_group: Pubkey,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub dust_token_index: u16,
+    pub quote_token_index: u16,
+    pub dust_transfer: i128,  // I80F48
+    pub quote_transfer: i128, // I80F48
+    pub dust_price: i128,     // I80F48
+    pub quote_price: i128,    // I80F48
+}
+
+/// Emitted when perp_liq_base_or_positive_pnl starts on a liquidatable account, to help
+/// spot accounts that hover around the maint health threshold
+#[event]
+pub struct PerpLiqHealthLog {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub init_health: i128,    // I80F48
+    pub maint_health: i128,   // I80F48
+    pub liq_end_health: i128, // I80F48
+}
+
+/// How a perp base liquidation changed the market's open interest
+#[event]
+pub struct GroupRiskDeltaLog {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub open_interest_before: i64,
+    pub open_interest_after: i64,
+}
+
+/// One per counterparty of a perp_adl, followed by a PerpAdlLog
+#[event]
+pub struct PerpAdlCounterpartyLog {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqee: Pubkey,
+    pub counterparty: Pubkey,
+    pub base_closed: i64,
+    pub pnl_absorbed: i128, // I80F48
+}
+
+#[event]
+pub struct PerpAdlLog {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqee: Pubkey,
+    pub oracle_price: i128, // I80F48
+    pub liqee_base_lots_remaining: i64,
+    pub deficit: i128,           // I80F48
+    pub remaining_deficit: i128, // I80F48
+    pub num_counterparties: u8,
+}
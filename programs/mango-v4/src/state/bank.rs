@@ -8,10 +8,10 @@ use crate::util;
 use anchor_lang::prelude::*;
 use derivative::Derivative;
 use fixed::types::I80F48;
-use oracle::oracle_log_context;
+use oracle::{oracle_log_context, OracleState};
 use static_assertions::const_assert_eq;
 
-use std::mem::size_of;
+use std::mem::{align_of, size_of};
 
 pub const HOUR: i64 = 3600;
 pub const DAY: i64 = 86400;
@@ -231,8 +231,20 @@ pub struct Bank {
     /// The daily collateral fees rate for fully utilized collateral.
     pub collateral_fee_per_day: f32,
 
+    /// If set to 1, health computations value borrows with the interest that accrued
+    /// since index_last_updated, instead of waiting for the next index update.
+    pub project_borrow_interest_in_health: u8,
+
+    #[derivative(Debug = "ignore")]
+    pub padding2: [u8; 3],
+
+    /// Slot of the last token_edit that changed this token's weights, 0 if never.
+    ///
+    /// See Group::min_weight_change_interval_slots.
+    pub last_weight_change_slot: u64,
+
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 1900],
+    pub reserved: [u8; 1888],
 }
 const_assert_eq!(
     size_of::<Bank>(),
@@ -271,10 +283,14 @@ const_assert_eq!(
         + 8
         + 16 * 4
         + 4
-        + 1900
+        + 1
+        + 3
+        + 8
+        + 1888
 );
 const_assert_eq!(size_of::<Bank>(), 3064);
 const_assert_eq!(size_of::<Bank>() % 8, 0);
+const_assert_eq!(align_of::<Bank>(), 8);
 
 pub struct WithdrawResult {
     pub position_is_active: bool,
@@ -382,7 +398,10 @@ impl Bank {
             zero_util_rate: existing_bank.zero_util_rate,
             platform_liquidation_fee: existing_bank.platform_liquidation_fee,
             collateral_fee_per_day: existing_bank.collateral_fee_per_day,
-            reserved: [0; 1900],
+            project_borrow_interest_in_health: existing_bank.project_borrow_interest_in_health,
+            padding2: [0; 3],
+            last_weight_change_slot: existing_bank.last_weight_change_slot,
+            reserved: [0; 1888],
         }
     }
 
@@ -400,6 +419,7 @@ impl Bank {
         require_gte!(self.stable_price_model.delay_growth_limit, 0.0);
         require_gte!(self.stable_price_model.stable_growth_limit, 0.0);
         require_gte!(self.init_asset_weight, 0.0);
+        require_gte!(I80F48::ONE, self.init_asset_weight);
         require_gte!(self.maint_asset_weight, self.init_asset_weight);
         require_gte!(self.maint_liab_weight, 0.0);
         require_gte!(self.init_liab_weight, self.maint_liab_weight);
@@ -434,6 +454,41 @@ impl Bank {
         Ok(())
     }
 
+    /// Checks the init asset weight against the group's init_asset_weight_floor.
+    ///
+    /// Reduce-only tokens are exempt: they are wound down by setting their weights to zero.
+    pub fn check_init_asset_weight_floor(&self, floor: f32) -> Result<()> {
+        if self.reduce_only == 0 {
+            require_msg_typed!(
+                self.init_asset_weight >= I80F48::from_num(floor),
+                MangoError::InitAssetWeightBelowFloor,
+                "init asset weight {} is below the floor {}",
+                self.init_asset_weight,
+                floor
+            );
+        }
+        Ok(())
+    }
+
+    /// Records a weight change at `now_slot`, failing if the previous one was less than
+    /// `min_interval_slots` ago.
+    pub fn record_weight_change(&mut self, now_slot: u64, min_interval_slots: u64) -> Result<()> {
+        if self.last_weight_change_slot != 0 {
+            let earliest_slot = self
+                .last_weight_change_slot
+                .saturating_add(min_interval_slots);
+            require_msg_typed!(
+                now_slot >= earliest_slot,
+                MangoError::WeightChangeTooSoon,
+                "weights were last changed in slot {}, next change allowed in slot {}",
+                self.last_weight_change_slot,
+                earliest_slot
+            );
+        }
+        self.last_weight_change_slot = now_slot;
+        Ok(())
+    }
+
     pub fn name(&self) -> &str {
         std::str::from_utf8(&self.name)
             .unwrap()
@@ -460,6 +515,10 @@ impl Bank {
         self.disable_asset_liquidation == 0
     }
 
+    pub fn projects_borrow_interest_in_health(&self) -> bool {
+        self.project_borrow_interest_in_health == 1
+    }
+
     #[inline(always)]
     pub fn native_borrows(&self) -> I80F48 {
         self.borrow_index * self.indexed_borrows
@@ -470,6 +529,19 @@ impl Bank {
         self.deposit_index * self.indexed_deposits
     }
 
+    /// Value of all borrows in a group, for monitoring.
+    ///
+    /// Takes all banks of the group together with the oracle price of their token.
+    /// Tokens with several banks contribute the borrows of each of them.
+    pub fn total_borrows_quote<'a>(
+        banks_and_prices: impl IntoIterator<Item = (&'a Bank, I80F48)>,
+    ) -> I80F48 {
+        banks_and_prices
+            .into_iter()
+            .map(|(bank, price)| bank.native_borrows() * price)
+            .sum()
+    }
+
     pub fn maint_weights(&self, now_ts: u64) -> (I80F48, I80F48) {
         if self.maint_weight_shift_duration_inv.is_zero() || now_ts <= self.maint_weight_shift_start
         {
@@ -966,6 +1038,20 @@ impl Bank {
         I80F48::from(self.deposit_limit) - total
     }
 
+    /// Native tokens the bank can still accept before reaching deposit_limit, at least zero.
+    ///
+    /// Based on remaining_deposits_until_limit(), so depositing this amount is safe.
+    /// Returns u64::MAX if there is no limit.
+    pub fn remaining_deposit_capacity(&self) -> u64 {
+        if self.deposit_limit == 0 {
+            return u64::MAX;
+        }
+        self.remaining_deposits_until_limit()
+            .max(I80F48::ZERO)
+            .floor()
+            .to_num::<u64>()
+    }
+
     pub fn check_deposit_and_oo_limit(&self) -> Result<()> {
         if self.deposit_limit == 0 {
             return Ok(());
@@ -1062,6 +1148,21 @@ impl Bank {
         ))
     }
 
+    /// The borrow index the bank would have if the interest since index_last_updated
+    /// were applied at `now_ts`.
+    ///
+    /// Only uses this bank's deposits and borrows for the utilization, while the
+    /// index update uses the totals over all banks of the token.
+    pub fn projected_borrow_index(&self, now_ts: u64) -> Result<I80F48> {
+        if now_ts <= self.index_last_updated {
+            return Ok(self.borrow_index);
+        }
+        let diff_ts = I80F48::from_num(now_ts - self.index_last_updated);
+        let (_, borrow_index, _, _, _) =
+            self.compute_index(self.indexed_deposits, self.indexed_borrows, diff_ts)?;
+        Ok(borrow_index)
+    }
+
     /// Current utilization, clamped to 0..1
     ///
     /// Above 100% utilization can happen natually when utilization is 100% and interest is paid out,
@@ -1094,6 +1195,22 @@ impl Bank {
         )
     }
 
+    /// Annual borrow rate paid at `utilization`, including the loan fee rate
+    ///
+    /// Like all rates here this is a simple, non-compounding APR. For display purposes.
+    pub fn borrow_apr(&self, utilization: I80F48) -> I80F48 {
+        self.compute_interest_rate(utilization) + self.loan_fee_rate
+    }
+
+    /// Annual deposit rate earned at `utilization`
+    ///
+    /// Depositors share the borrow interest in proportion to utilization and don't
+    /// receive the loan fee, see compute_index(). For display purposes.
+    pub fn deposit_apr(&self, utilization: I80F48) -> I80F48 {
+        let utilization = utilization.max(I80F48::ZERO).min(I80F48::ONE);
+        self.compute_interest_rate(utilization) * utilization
+    }
+
     /// calculator function that can be used to compute an interest
     /// rate based on the given parameters
     #[inline(always)]
@@ -1186,6 +1303,15 @@ impl Bank {
         oracle_acc_infos: &OracleAccountInfos<T>,
         now: Option<(u64, u64)>, // (now_ts, now_slot)
     ) -> Result<I80F48> {
+        Ok(self.oracle_state(oracle_acc_infos, now)?.price)
+    }
+
+    /// Like oracle_price(), but returns the full state of the oracle that was used
+    pub fn oracle_state<T: KeyedAccountReader>(
+        &self,
+        oracle_acc_infos: &OracleAccountInfos<T>,
+        now: Option<(u64, u64)>, // (now_ts, now_slot)
+    ) -> Result<OracleState> {
         require_keys_eq!(self.oracle, *oracle_acc_infos.oracle.key());
         let primary_state = oracle::oracle_state_unchecked(oracle_acc_infos, self.mint_decimals)?;
         let primary_ok =
@@ -1204,12 +1330,12 @@ impl Bank {
                     oracle_log_context(self.name(), &fallback_state, &self.oracle_config, now)
                 )
             })?;
-            Ok(fallback_state.price)
+            Ok(fallback_state)
         } else {
             primary_ok.with_context(|| {
                 oracle_log_context(self.name(), &primary_state, &self.oracle_config, now)
             })?;
-            Ok(primary_state.price)
+            Ok(primary_state)
         }
     }
 
@@ -1577,6 +1703,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bank_remaining_deposit_capacity() {
+        let mut bank = Bank::zeroed();
+        bank.deposit_index = I80F48::ONE;
+        assert_eq!(bank.remaining_deposit_capacity(), u64::MAX);
+
+        bank.deposit_limit = 100;
+        let capacity = |indexed_deposits: f64, serum: u64| {
+            let mut b = bank;
+            b.indexed_deposits = I80F48::from_num(indexed_deposits);
+            b.potential_serum_tokens = serum;
+            b.remaining_deposit_capacity()
+        };
+
+        // rounding is in favor of the limit: a deposit of 100 would exceed it
+        assert_eq!(capacity(0.0, 0), 99);
+        assert_eq!(capacity(50.0, 0), 49);
+        assert_eq!(capacity(50.0, 30), 19);
+        assert_eq!(capacity(99.5, 0), 0);
+        assert_eq!(capacity(100.0, 0), 0);
+        assert_eq!(capacity(150.0, 0), 0);
+        assert_eq!(capacity(0.0, 200), 0);
+    }
+
+    #[test]
+    fn test_bank_init_asset_weight_floor() {
+        let mut bank = Bank::zeroed();
+        bank.init_asset_weight = I80F48::ZERO;
+        assert!(bank.check_init_asset_weight_floor(0.0).is_ok());
+        assert!(bank
+            .check_init_asset_weight_floor(0.1)
+            .is_anchor_error_with_code(MangoError::InitAssetWeightBelowFloor.error_code()));
+
+        bank.init_asset_weight = I80F48::from_num(0.5);
+        assert!(bank.check_init_asset_weight_floor(0.1).is_ok());
+        assert!(bank.check_init_asset_weight_floor(0.5).is_ok());
+
+        // reduce-only tokens are exempt
+        bank.init_asset_weight = I80F48::ZERO;
+        bank.reduce_only = 1;
+        assert!(bank.check_init_asset_weight_floor(0.1).is_ok());
+    }
+
+    #[test]
+    fn test_bank_record_weight_change() {
+        let mut bank = Bank::zeroed();
+
+        // the first change is always allowed
+        bank.record_weight_change(1000, 100).unwrap();
+        assert_eq!(bank.last_weight_change_slot, 1000);
+
+        // within the interval
+        assert!(bank
+            .record_weight_change(1099, 100)
+            .is_anchor_error_with_code(MangoError::WeightChangeTooSoon.error_code()));
+        assert_eq!(bank.last_weight_change_slot, 1000);
+
+        // outside the interval
+        bank.record_weight_change(1100, 100).unwrap();
+        assert_eq!(bank.last_weight_change_slot, 1100);
+
+        // no interval
+        bank.record_weight_change(1100, 0).unwrap();
+        bank.record_weight_change(1101, 0).unwrap();
+        assert_eq!(bank.last_weight_change_slot, 1101);
+    }
+
     #[test]
     fn test_compute_new_avg_utilization() {
         let mut bank = Bank::zeroed();
@@ -1755,6 +1948,73 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_bank_apr() {
+        let mut bank = Bank::zeroed();
+        bank.zero_util_rate = I80F48::from_num(0.01);
+        bank.util0 = I80F48::from_num(0.5);
+        bank.rate0 = I80F48::from_num(0.05);
+        bank.util1 = I80F48::from_num(0.8);
+        bank.rate1 = I80F48::from_num(0.2);
+        bank.max_rate = I80F48::from_num(1.0);
+        bank.loan_fee_rate = I80F48::from_num(0.005);
+        bank.deposit_index = I80F48::ONE;
+        bank.borrow_index = I80F48::ONE;
+
+        let d = |a: I80F48, b: f64| (a.to_num::<f64>() - b).abs();
+        let eps = 0.000001;
+        for (util, rate) in [
+            (0.0, 0.01),
+            (0.25, 0.03),
+            (0.5, 0.05),
+            (0.8, 0.2),
+            (0.9, 0.6),
+            (1.0, 1.0),
+        ] {
+            let utilization = I80F48::from_num(util);
+            assert!(d(bank.borrow_apr(utilization), rate + 0.005) < eps);
+            assert!(d(bank.deposit_apr(utilization), rate * util) < eps);
+
+            // over one year without compounding, the indexes grow by exactly the aprs
+            let deposits = I80F48::from(1_000_000);
+            let (deposit_index, borrow_index, _, _, _) = bank
+                .compute_index(deposits, deposits * utilization, YEAR_I80F48)
+                .unwrap();
+            assert!(d(borrow_index - I80F48::ONE, rate + 0.005) < eps);
+            assert!(d(deposit_index - I80F48::ONE, rate * util) < eps);
+        }
+
+        // out of range utilizations are clamped
+        assert!(d(bank.deposit_apr(I80F48::from_num(1.5)), 1.0) < eps);
+        assert!(d(bank.deposit_apr(I80F48::from_num(-0.5)), 0.0) < eps);
+    }
+
+    #[test]
+    fn test_total_borrows_quote() {
+        let make_bank = |token_index: TokenIndex, borrow_index: f64, indexed_borrows: f64| {
+            let mut bank = Bank::zeroed();
+            bank.token_index = token_index;
+            bank.borrow_index = I80F48::from_num(borrow_index);
+            bank.indexed_borrows = I80F48::from_num(indexed_borrows);
+            bank
+        };
+        let bank0 = make_bank(0, 1.0, 1000.0);
+        let bank1 = make_bank(1, 1.5, 200.0);
+        let bank1b = make_bank(1, 1.5, 100.0);
+        let bank2 = make_bank(2, 2.0, 0.0);
+
+        assert_eq!(Bank::total_borrows_quote([]), I80F48::ZERO);
+
+        let total = Bank::total_borrows_quote([
+            (&bank0, I80F48::ONE),
+            (&bank1, I80F48::from(20)),
+            (&bank1b, I80F48::from(20)),
+            (&bank2, I80F48::from(1000)),
+        ]);
+        // 1000 + (300 + 150) * 20 + 0
+        assert_eq!(total, I80F48::from(10000));
+    }
+
     #[test]
     fn test_bank_interest_rate_curve() {
         let mut bank = Bank::zeroed();
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
+use fixed::types::I80F48;
 use static_assertions::const_assert_eq;
-use std::mem::size_of;
+use std::mem::{align_of, size_of};
 
 // TODO: Assuming we allow up to 65536 different tokens
 pub type TokenIndex = u16;
@@ -103,7 +104,72 @@ pub struct Group {
     /// Intervals in which collateral fee is applied
     pub collateral_fee_interval: u64,
 
-    pub reserved: [u8; 1800],
+    /// Perp base liquidation avoids pushing the liqee's liquidation end health above
+    /// this value (in quote native), as long as it can still make progress.
+    ///
+    /// When set to 0, there's no limit.
+    pub max_liquidation_overshoot_health: u64,
+
+    /// Token positions worth at most this much (in quote native) can be taken over
+    /// by anyone with token_liq_absorb_dust, to allow closing accounts with dust left.
+    ///
+    /// When set to 0, dust absorption is disabled.
+    pub max_dust_absorb_quote: u64,
+
+    /// Tokens that aren't reduce-only must have an init_asset_weight of at least this value.
+    ///
+    /// Guards against accidentally listing or editing tokens to provide no init collateral.
+    pub init_asset_weight_floor: f32,
+
+    /// Upper limit on the base liquidation fee of every perp market, see
+    /// capped_liquidation_fee().
+    ///
+    /// When set to 0, market fees are not limited.
+    pub max_liquidation_fee: f32,
+
+    /// Liqors whose owner has at least this much native mngo locked in the governance
+    /// stake registry liqor_fee_waiver_registrar receive the platform liquidation fee on
+    /// perp base liquidations, instead of the group.
+    ///
+    /// When set to 0, no liqor receives the platform fee.
+    pub liqor_fee_waiver_min_mngo: u64,
+
+    /// Minimum number of slots between two token_edit calls that change a token's weights.
+    ///
+    /// When set to 0, weights can be changed at any time.
+    pub min_weight_change_interval_slots: u64,
+
+    /// Group-operated mango account that liquidates on behalf of the protocol.
+    ///
+    /// Its health isn't checked after perp liquidations, since the protocol backstops it.
+    /// Unset if Pubkey::default().
+    pub protocol_liquidator: Pubkey,
+
+    /// If set, perp_liq_negative_pnl_or_bankruptcy sells positive perp pnl that is left
+    /// on a bankrupt account to the liqor and pays the proceeds into the insurance fund,
+    /// see sweep_bankrupt_positive_pnl().
+    pub sweep_bankrupt_perp_pnl: u8,
+
+    pub padding3: [u8; 1],
+
+    /// Number of registered tokens, maintained by token_register(_trustless) and
+    /// token_deregister.
+    ///
+    /// Groups that listed tokens before this was tracked undercount them.
+    pub token_count: u16,
+
+    pub padding4: [u8; 4],
+
+    /// Admin proposed with group_set_pending_admin, becomes the admin once it calls group_accept_admin.
+    ///
+    /// Unset if Pubkey::default().
+    pub pending_admin: Pubkey,
+
+    /// Voter stake registry Registrar of the mngo governance realm, see
+    /// liqor_fee_waiver_min_mngo. Unset if Pubkey::default().
+    pub liqor_fee_waiver_registrar: Pubkey,
+
+    pub reserved: [u8; 1656],
 }
 const_assert_eq!(
     size_of::<Group>(),
@@ -123,16 +189,34 @@ const_assert_eq!(
         + 2 * 2
         + 4
         + 8
-        + 1800
+        + 8
+        + 8
+        + 4
+        + 4
+        + 8
+        + 8
+        + 32
+        + 1
+        + 1
+        + 2
+        + 4
+        + 32
+        + 32
+        + 1656
 );
 const_assert_eq!(size_of::<Group>(), 2736);
 const_assert_eq!(size_of::<Group>() % 8, 0);
+const_assert_eq!(align_of::<Group>(), 8);
 
 impl Group {
     pub fn buyback_fees(&self) -> bool {
         self.buyback_fees == 1
     }
 
+    pub fn sweep_bankrupt_perp_pnl(&self) -> bool {
+        self.sweep_bankrupt_perp_pnl == 1
+    }
+
     pub fn is_testing(&self) -> bool {
         self.testing == 1
     }
@@ -156,6 +240,32 @@ impl Group {
     pub fn openbook_v2_supported(&self) -> bool {
         self.is_testing()
     }
+
+    pub fn is_protocol_liquidator(&self, account: &Pubkey) -> bool {
+        self.protocol_liquidator != Pubkey::default() && self.protocol_liquidator == *account
+    }
+
+    /// A perp market's base liquidation fee, limited by max_liquidation_fee
+    pub fn capped_liquidation_fee(&self, base_liquidation_fee: I80F48) -> I80F48 {
+        if self.max_liquidation_fee <= 0.0 {
+            return base_liquidation_fee;
+        }
+        base_liquidation_fee.min(I80F48::from_num(self.max_liquidation_fee))
+    }
+
+    /// Ratio of the insurance vault balance to the total open interest value of the
+    /// group's perp markets, see PerpMarket::open_interest_value().
+    ///
+    /// Returns I80F48::MAX if there is no open interest.
+    pub fn insurance_coverage_ratio(
+        insurance_balance: u64,
+        total_open_interest_value: I80F48,
+    ) -> I80F48 {
+        if total_open_interest_value <= 0 {
+            return I80F48::MAX;
+        }
+        I80F48::from(insurance_balance) / total_open_interest_value
+    }
 }
 
 /// Enum for lookup into ix gate
@@ -249,6 +359,19 @@ pub enum IxGate {
     SequenceCheck = 73,
     HealthCheck = 74,
     GroupChangeInsuranceFund = 76,
+    AccountSetOwner = 77,
+    PerpSettleAllFunding = 78,
+    TokenLiqWithTokenRelaxedStaleness = 79,
+    TokenLiqAbsorbDust = 80,
+    PerpAdl = 81,
+    AccountSetTokenAllowlist = 82,
+    ComputeAccountHealth = 83,
+    CacheAccountHealth = 84,
+    RecoverFromBeingLiquidated = 85,
+    AccountSetDelegatePermissions = 86,
+    GroupStatus = 87,
+    PerpExplainLiquidation = 88,
+    AccountCompactTokenPositions = 89,
     // NOTE: Adding new variants requires matching changes in ts and the ix_gate_set instruction.
 }
 
@@ -266,3 +389,42 @@ macro_rules! group_seeds {
 }
 
 pub use group_seeds;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_insurance_coverage_ratio() {
+        let total_oi_value = I80F48::from(4000) + I80F48::from(6000);
+        assert_eq!(
+            Group::insurance_coverage_ratio(2500, total_oi_value),
+            I80F48::from_num(0.25)
+        );
+        assert_eq!(
+            Group::insurance_coverage_ratio(0, total_oi_value),
+            I80F48::ZERO
+        );
+        assert_eq!(
+            Group::insurance_coverage_ratio(20000, total_oi_value),
+            I80F48::from(2)
+        );
+        assert_eq!(
+            Group::insurance_coverage_ratio(2500, I80F48::ZERO),
+            I80F48::MAX
+        );
+    }
+
+    #[test]
+    fn test_is_protocol_liquidator() {
+        let mut group = Group::zeroed();
+        let liqor = Pubkey::new_unique();
+        assert!(!group.is_protocol_liquidator(&liqor));
+        assert!(!group.is_protocol_liquidator(&Pubkey::default()));
+
+        group.protocol_liquidator = liqor;
+        assert!(group.is_protocol_liquidator(&liqor));
+        assert!(!group.is_protocol_liquidator(&Pubkey::new_unique()));
+    }
+}
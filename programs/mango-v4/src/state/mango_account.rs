@@ -13,6 +13,7 @@ use static_assertions::const_assert_eq;
 
 use crate::error::*;
 use crate::health::{HealthCache, HealthType};
+use crate::i80f48::ClampToInt;
 use crate::logs::{emit_stack, DeactivatePerpPositionLog, DeactivateTokenPositionLog};
 use crate::util;
 
@@ -36,6 +37,24 @@ const BORSH_VEC_SIZE_BYTES: usize = 4;
 const DEFAULT_MANGO_ACCOUNT_VERSION: u8 = 1;
 const DYNAMIC_RESERVED_BYTES: usize = 64;
 
+/// Actions a delegate can be restricted to, see MangoAccountFixed::has_permission()
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u8)]
+pub enum DelegatePermission {
+    /// Acting as the liqor in liquidations and token conditional swaps
+    Liquidate = 0,
+    /// Placing orders, creating token conditional swaps and flash loans
+    Trade = 1,
+    /// Withdrawing into the owner's token accounts
+    Withdraw = 2,
+}
+
+impl DelegatePermission {
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
 // Return variants for check_liquidatable method, should be wrapped in a Result
 // for a future possiblity of returning any error
 #[derive(PartialEq)]
@@ -156,8 +175,47 @@ pub struct MangoAccount {
     /// Time at which the last collateral fee was charged
     pub last_collateral_fee_charge: u64,
 
+    /// Perp base lots taken over from this account during the current liquidation.
+    /// Reset when the account stops being liquidated.
+    pub liquidation_base_accumulated: i64,
+    /// Perp quote (native, absolute) exchanged for liquidation_base_accumulated.
+    pub liquidation_quote_accumulated: i64,
+
+    /// Perp trading fees paid over the account's lifetime, in native quote.
+    /// Maker rebates reduce it.
+    pub perp_trading_fees_paid: i64,
+    /// Perp funding paid by perp positions that have been deactivated, in native quote.
+    /// See perp_funding_paid() for the lifetime total.
+    pub perp_funding_paid_deactivated: i64,
+    /// Perp liquidation fees paid as a liqee over the account's lifetime, in native quote:
+    /// base liquidation fees and the discount on positive pnl taken over by liqors.
+    pub perp_liquidation_fees_paid: i64,
+
+    /// Tokens the account may deposit into or borrow, set by the group admin.
+    /// Only the first token_allowlist_len entries are used. All tokens are allowed if empty.
+    pub token_allowlist: [TokenIndex; 8],
+    pub token_allowlist_len: u8,
+
+    /// DelegatePermission bits for delegate and temporary_delegate, see
+    /// MangoAccountFixed::has_permission(). Zero means unrestricted.
+    pub delegate_permissions: u8,
+    pub temporary_delegate_permissions: u8,
+
+    #[derivative(Debug = "ignore")]
+    pub padding: [u8; 5],
+
+    /// Health stored by cache_account_health and health_region_end, see
+    /// MangoAccountFixed::cached_health()
+    pub cached_init_health: i64,
+    pub cached_maint_health: i64,
+    /// Slot the cached health was computed in. Zero if it's invalid.
+    pub cached_health_slot: u64,
+
+    /// Slot in which being_liquidated was last set. Zero if the account isn't being liquidated.
+    pub being_liquidated_slot: u64,
+
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 152],
+    pub reserved: [u8; 56],
 
     // dynamic
     pub header_version: u8,
@@ -211,7 +269,21 @@ impl MangoAccount {
             temporary_delegate: Pubkey::default(),
             temporary_delegate_expiry: 0,
             last_collateral_fee_charge: 0,
-            reserved: [0; 152],
+            liquidation_base_accumulated: 0,
+            liquidation_quote_accumulated: 0,
+            perp_trading_fees_paid: 0,
+            perp_funding_paid_deactivated: 0,
+            perp_liquidation_fees_paid: 0,
+            token_allowlist: [0; 8],
+            token_allowlist_len: 0,
+            delegate_permissions: 0,
+            temporary_delegate_permissions: 0,
+            padding: Default::default(),
+            cached_init_health: 0,
+            cached_maint_health: 0,
+            cached_health_slot: 0,
+            being_liquidated_slot: 0,
+            reserved: [0; 56],
             header_version: DEFAULT_MANGO_ACCOUNT_VERSION,
             padding3: Default::default(),
             padding4: Default::default(),
@@ -336,11 +408,25 @@ pub struct MangoAccountFixed {
     pub temporary_delegate: Pubkey,
     pub temporary_delegate_expiry: u64,
     pub last_collateral_fee_charge: u64,
-    pub reserved: [u8; 152],
+    pub liquidation_base_accumulated: i64,
+    pub liquidation_quote_accumulated: i64,
+    pub perp_trading_fees_paid: i64,
+    pub perp_funding_paid_deactivated: i64,
+    pub perp_liquidation_fees_paid: i64,
+    pub token_allowlist: [TokenIndex; 8],
+    pub token_allowlist_len: u8,
+    pub delegate_permissions: u8,
+    pub temporary_delegate_permissions: u8,
+    pub padding: [u8; 5],
+    pub cached_init_health: i64,
+    pub cached_maint_health: i64,
+    pub cached_health_slot: u64,
+    pub being_liquidated_slot: u64,
+    pub reserved: [u8; 56],
 }
 const_assert_eq!(
     size_of::<MangoAccountFixed>(),
-    32 * 4 + 8 + 8 * 8 + 32 + 8 + 8 + 152
+    32 * 4 + 8 + 8 * 8 + 32 + 8 + 8 + 8 * 2 + 8 * 3 + 2 * 8 + 1 + 2 + 5 + 8 * 3 + 8 + 56
 );
 const_assert_eq!(size_of::<MangoAccountFixed>(), 400);
 const_assert_eq!(size_of::<MangoAccountFixed>() % 8, 0);
@@ -357,10 +443,53 @@ impl MangoAccountFixed {
         self.frozen_until < now_ts
     }
 
+    /// The (init, maint) health stored by cache_account_health or health_region_end, if it was
+    /// computed in `now_slot`.
+    ///
+    /// Loading the account mutably invalidates it, so it's never older than the account's
+    /// positions. It uses the oracle prices at the time of caching though, which can change
+    /// within a slot.
+    pub fn cached_health(&self, now_slot: u64) -> Option<(i64, i64)> {
+        (self.cached_health_slot != 0 && self.cached_health_slot == now_slot)
+            .then_some((self.cached_init_health, self.cached_maint_health))
+    }
+
+    /// Stores health values, rounded down so the cache never overstates health
+    pub fn set_cached_health(&mut self, init_health: I80F48, maint_health: I80F48, now_slot: u64) {
+        self.cached_init_health = init_health.floor().saturating_to_num();
+        self.cached_maint_health = maint_health.floor().saturating_to_num();
+        self.cached_health_slot = now_slot;
+    }
+
+    pub fn invalidate_cached_health(&mut self) {
+        self.cached_health_slot = 0;
+    }
+
     pub fn is_owner_or_delegate(&self, ix_signer: Pubkey) -> bool {
         self.owner == ix_signer || self.is_delegate(ix_signer)
     }
 
+    /// Rejects delegates that would make the account delegate to itself:
+    /// the owner or the account's own address. The default pubkey unsets the delegate.
+    pub fn check_delegate(&self, account: &Pubkey, delegate: &Pubkey) -> Result<()> {
+        if *delegate == Pubkey::default() {
+            return Ok(());
+        }
+        require_msg_typed!(
+            *delegate != self.owner,
+            MangoError::InvalidDelegate,
+            "delegate {} is the account owner",
+            delegate
+        );
+        require_msg_typed!(
+            delegate != account,
+            MangoError::InvalidDelegate,
+            "delegate {} is the account itself",
+            delegate
+        );
+        Ok(())
+    }
+
     pub fn is_delegate(&self, ix_signer: Pubkey) -> bool {
         if self.delegate == ix_signer {
             return true;
@@ -374,6 +503,25 @@ impl MangoAccountFixed {
         self.temporary_delegate == ix_signer
     }
 
+    /// Whether `ix_signer` is the owner, or a delegate that is allowed to do `permission`.
+    ///
+    /// Delegates without any permission bits set are unrestricted, like before permissions
+    /// existed.
+    pub fn has_permission(&self, ix_signer: Pubkey, permission: DelegatePermission) -> bool {
+        if self.owner == ix_signer {
+            return true;
+        }
+        let allowed = |permissions: u8| permissions == 0 || permissions & permission.bit() != 0;
+        if self.delegate == ix_signer && allowed(self.delegate_permissions) {
+            return true;
+        }
+
+        let now_ts: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        now_ts <= self.temporary_delegate_expiry
+            && self.temporary_delegate == ix_signer
+            && allowed(self.temporary_delegate_permissions)
+    }
+
     pub fn being_liquidated(&self) -> bool {
         self.being_liquidated == 1
     }
@@ -382,6 +530,22 @@ impl MangoAccountFixed {
         self.being_liquidated = u8::from(b);
     }
 
+    /// Sets being_liquidated, remembering the slot if the flag wasn't set before
+    pub fn start_being_liquidated(&mut self, now_slot: u64) {
+        if !self.being_liquidated() {
+            self.set_being_liquidated(true);
+            self.being_liquidated_slot = now_slot;
+        }
+    }
+
+    /// Clears being_liquidated and the liquidation progress that goes with it
+    pub fn end_being_liquidated(&mut self) {
+        self.set_being_liquidated(false);
+        self.being_liquidated_slot = 0;
+        self.liquidation_base_accumulated = 0;
+        self.liquidation_quote_accumulated = 0;
+    }
+
     pub fn is_in_health_region(&self) -> bool {
         self.in_health_region == 1
     }
@@ -394,13 +558,65 @@ impl MangoAccountFixed {
         // This is used as threshold to flip flag instead of 0 because of dust issues
         let one_native_usdc = I80F48::ONE;
         if self.being_liquidated() && liq_end_health > -one_native_usdc {
-            self.set_being_liquidated(false);
+            self.end_being_liquidated();
             true
         } else {
             false
         }
     }
 
+    /// Adds a perp base liquidation step to the liquidation_*_accumulated totals
+    pub fn record_liquidation(&mut self, base_transfer: i64, quote_transfer: I80F48) {
+        self.liquidation_base_accumulated = self
+            .liquidation_base_accumulated
+            .saturating_add(base_transfer.saturating_abs());
+        self.liquidation_quote_accumulated = self
+            .liquidation_quote_accumulated
+            .saturating_add(quote_transfer.abs().round().clamp_to_i64());
+    }
+
+    /// Adds a perp trading fee (negative for rebates) to perp_trading_fees_paid
+    pub fn record_perp_trading_fee(&mut self, fee: I80F48) {
+        self.perp_trading_fees_paid = self
+            .perp_trading_fees_paid
+            .saturating_add(fee.round().clamp_to_i64());
+    }
+
+    pub fn record_deactivated_perp_funding(&mut self, funding_paid: i64) {
+        self.perp_funding_paid_deactivated = self
+            .perp_funding_paid_deactivated
+            .saturating_add(funding_paid);
+    }
+
+    /// Adds a perp liquidation fee paid by this account to perp_liquidation_fees_paid
+    pub fn record_perp_liquidation_fee(&mut self, fee: I80F48) {
+        self.perp_liquidation_fees_paid = self
+            .perp_liquidation_fees_paid
+            .saturating_add(fee.round().clamp_to_i64());
+    }
+
+    pub fn token_allowlist(&self) -> &[TokenIndex] {
+        &self.token_allowlist[..self.token_allowlist_len as usize]
+    }
+
+    /// Replaces the token allowlist, an empty list allows all tokens
+    pub fn set_token_allowlist(&mut self, token_indexes: &[TokenIndex]) -> Result<()> {
+        require_msg!(
+            token_indexes.len() <= self.token_allowlist.len(),
+            "token allowlist can have at most {} entries, got {}",
+            self.token_allowlist.len(),
+            token_indexes.len()
+        );
+        self.token_allowlist = [0; 8];
+        self.token_allowlist[..token_indexes.len()].copy_from_slice(token_indexes);
+        self.token_allowlist_len = token_indexes.len() as u8;
+        Ok(())
+    }
+
+    pub fn is_token_allowed(&self, token_index: TokenIndex) -> bool {
+        self.token_allowlist_len == 0 || self.token_allowlist().contains(&token_index)
+    }
+
     /// Updates the buyback_fees_* fields for staggered expiry of available amounts.
     pub fn expire_buyback_fees(&mut self, now_ts: u64, interval: u64) {
         if interval == 0 || now_ts < self.buyback_fees_expiry_timestamp {
@@ -885,6 +1101,17 @@ impl<
         self.fixed().being_liquidated()
     }
 
+    /// Perp funding paid over the account's lifetime, in native quote
+    ///
+    /// Active perp positions track their funding until they are deactivated.
+    pub fn perp_funding_paid(&self) -> i64 {
+        self.active_perp_positions()
+            .map(|p| p.cumulative_funding_paid())
+            .fold(self.fixed().perp_funding_paid_deactivated, |sum, paid| {
+                sum.saturating_add(paid)
+            })
+    }
+
     fn token_conditional_swap_by_index_unchecked(&self, index: usize) -> &TokenConditionalSwap {
         get_helper(
             self.dynamic(),
@@ -992,10 +1219,12 @@ impl<
     ) -> Result<(&mut TokenPosition, usize, usize)> {
         let mut active_index = 0;
         let mut match_or_free = None;
+        let mut exists = false;
         for (raw_index, position) in self.all_token_positions().enumerate() {
             if position.is_active_for_token(token_index) {
                 // Can't return early because of lifetimes
                 match_or_free = Some((raw_index, active_index));
+                exists = true;
                 break;
             }
             if position.is_active() {
@@ -1004,9 +1233,17 @@ impl<
                 match_or_free = Some((raw_index, active_index));
             }
         }
+        // All ways of acquiring a token go through here: only the allowlist's tokens
+        // can get new positions
+        require_msg_typed!(
+            exists || self.fixed().is_token_allowed(token_index),
+            MangoError::TokenNotAllowedForAccount,
+            "when opening a position for token index {}",
+            token_index
+        );
         if let Some((raw_index, bank_index)) = match_or_free {
             let v = self.token_position_mut_by_raw_index(raw_index);
-            if !v.is_active_for_token(token_index) {
+            if !exists {
                 *v = TokenPosition {
                     indexed_position: I80F48::ZERO,
                     token_index,
@@ -1048,6 +1285,32 @@ impl<
         self.token_position_mut_by_raw_index(raw_index).token_index = TokenIndex::MAX;
     }
 
+    /// Moves the active token positions into the front slots, keeping their order, and
+    /// resets the slots behind them.
+    ///
+    /// Deactivated positions leave unused slots in between that lookups step over. Since
+    /// positions are found by token index and the active order doesn't change, this is
+    /// invisible to everything but raw indexes, which are never stored.
+    ///
+    /// Returns the number of positions that moved.
+    pub fn compact_token_positions(&mut self) -> usize {
+        let mut moved = 0;
+        let mut active_index = 0;
+        for raw_index in 0..self.header().token_count() {
+            let position = *self.token_position_mut_by_raw_index(raw_index);
+            if !position.is_active() {
+                continue;
+            }
+            if raw_index != active_index {
+                *self.token_position_mut_by_raw_index(active_index) = position;
+                *self.token_position_mut_by_raw_index(raw_index) = TokenPosition::default();
+                moved += 1;
+            }
+            active_index += 1;
+        }
+        moved
+    }
+
     /// Decrements the in_use_count for the token position for the bank.
     ///
     /// If it goes to 0, the position may be dusted (if between 0 and 1 native tokens)
@@ -1176,7 +1439,11 @@ impl<
         perp_market_index: PerpMarketIndex,
         settle_token_index: TokenIndex,
     ) -> Result<()> {
-        self.perp_position_mut(perp_market_index)?.market_index = PerpMarketIndex::MAX;
+        let perp_position = self.perp_position_mut(perp_market_index)?;
+        let funding_paid = perp_position.cumulative_funding_paid();
+        perp_position.market_index = PerpMarketIndex::MAX;
+        self.fixed_mut()
+            .record_deactivated_perp_funding(funding_paid);
 
         let settle_token_position = self.token_position_mut(settle_token_index)?.0;
         settle_token_position.decrement_in_use();
@@ -1204,7 +1471,10 @@ impl<
             perp_spot_transfers: perp_position.perp_spot_transfers,
         });
 
+        let funding_paid = perp_position.cumulative_funding_paid();
         perp_position.market_index = PerpMarketIndex::MAX;
+        self.fixed_mut()
+            .record_deactivated_perp_funding(funding_paid);
 
         let settle_token_position = self.token_position_mut(settle_token_index)?.0;
         settle_token_position.decrement_in_use();
@@ -1284,6 +1554,7 @@ impl<
             f.expire_buyback_fees(now_ts, group.buyback_fees_expiry_interval);
             f.accrue_buyback_fees(fees.floor().to_num::<u64>());
         }
+        self.fixed_mut().record_perp_trading_fee(fees);
         let pa = self.perp_position_mut(perp_market_index)?;
         pa.settle_funding(perp_market);
         pa.record_trading_fee(fees);
@@ -1464,6 +1735,11 @@ impl<
         Ok(())
     }
 
+    /// Decides whether liquidation may proceed, based on the health of the account as
+    /// it is at the time of the liquidation instruction.
+    ///
+    /// That includes deposits made earlier in the same transaction: liqees that save
+    /// themselves that way are not liquidated.
     pub fn check_liquidatable(&mut self, health_cache: &HealthCache) -> Result<CheckLiquidatable> {
         // Once maint_health falls below 0, we want to start liquidating,
         // we want to allow liquidation to continue until init_health is positive,
@@ -1483,7 +1759,8 @@ impl<
                 msg!("Liqee is not liquidatable");
                 return Ok(CheckLiquidatable::NotLiquidatable);
             }
-            self.fixed_mut().set_being_liquidated(true);
+            let now_slot = Clock::get()?.slot;
+            self.fixed_mut().start_being_liquidated(now_slot);
         }
         return Ok(CheckLiquidatable::Liquidatable);
     }
@@ -1860,9 +2137,13 @@ impl<'a, 'info: 'a> MangoAccountLoader<'a> for &'a AccountLoader<'info, MangoAcc
         let (_, data) = RefMut::map_split(data, |d| d.split_at_mut(8));
         let (fixed_bytes, dynamic) =
             RefMut::map_split(data, |d| d.split_at_mut(size_of::<MangoAccountFixed>()));
+        let mut fixed: RefMut<MangoAccountFixed> =
+            RefMut::map(fixed_bytes, |b| bytemuck::from_bytes_mut(b));
+        // Any instruction with mutable access may change the account's health
+        fixed.invalidate_cached_health();
         Ok(MangoAccountLoadedRefCellMut {
             header,
-            fixed: RefMut::map(fixed_bytes, |b| bytemuck::from_bytes_mut(b)),
+            fixed,
             dynamic,
         })
     }
@@ -2074,6 +2355,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_compact_token_positions() {
+        let mut account = make_test_account();
+        for token_index in [1, 7, 42] {
+            let position = account.ensure_token_position(token_index).unwrap().0;
+            position.indexed_position = I80F48::from(token_index);
+            position.in_use_count = 1;
+        }
+        account.token_position_mut(1).unwrap().0.in_use_count = 0;
+        account.deactivate_token_position(0);
+
+        // (unused, 7, 42) becomes (7, 42, unused)
+        assert_eq!(account.compact_token_positions(), 2);
+        let token_indexes = account
+            .all_token_positions()
+            .map(|p| p.token_index)
+            .collect_vec();
+        assert_eq!(token_indexes, vec![7, 42, TokenIndex::MAX]);
+        assert_eq!(
+            *account.token_position_by_raw_index_unchecked(2),
+            TokenPosition::default()
+        );
+
+        // positions keep their data and still resolve by token index
+        for (token_index, raw_index) in [(7, 0), (42, 1)] {
+            let (position, raw) = account.token_position_and_raw_index(token_index).unwrap();
+            assert_eq!(raw, raw_index);
+            assert_eq!(position.indexed_position, I80F48::from(token_index));
+            assert_eq!(position.in_use_count, 1);
+        }
+        assert!(account.token_position(1).is_err());
+
+        // nothing to do when there are no gaps
+        assert_eq!(account.compact_token_positions(), 0);
+
+        // new positions go after the compacted ones
+        assert_eq!(account.ensure_token_position(3).unwrap().1, 2);
+    }
+
     #[test]
     fn test_serum3_orders() {
         let mut account = make_test_account();
@@ -2236,6 +2556,43 @@ mod tests {
         assert_eq!(fixed.buyback_fees_accrued(), 0);
     }
 
+    #[test]
+    fn test_lifetime_fees() {
+        let mut account = make_test_account();
+
+        let fixed = account.fixed_mut();
+        fixed.record_perp_trading_fee(I80F48::from(10));
+        fixed.record_perp_trading_fee(I80F48::from_num(-2.4));
+        assert_eq!(fixed.perp_trading_fees_paid, 8);
+        fixed.record_perp_liquidation_fee(I80F48::from_num(3.6));
+        assert_eq!(fixed.perp_liquidation_fees_paid, 4);
+
+        account.ensure_perp_position(1, 0).unwrap();
+        account.ensure_perp_position(2, 0).unwrap();
+        {
+            let pos = account.perp_position_mut(1).unwrap();
+            pos.cumulative_long_funding = 7.0;
+            pos.cumulative_short_funding = 2.0;
+        }
+        account
+            .perp_position_mut(2)
+            .unwrap()
+            .cumulative_short_funding = 3.0;
+        assert_eq!(account.perp_funding_paid(), 2);
+
+        account.deactivate_perp_position(1, 0).unwrap();
+        assert_eq!(account.fixed().perp_funding_paid_deactivated, 5);
+        assert_eq!(account.perp_funding_paid(), 2);
+
+        // a reactivated position starts from zero, the previous funding isn't counted twice
+        account.ensure_perp_position(1, 0).unwrap();
+        assert_eq!(account.perp_funding_paid(), 2);
+
+        account.deactivate_perp_position(2, 0).unwrap();
+        assert_eq!(account.fixed().perp_funding_paid_deactivated, 2);
+        assert_eq!(account.perp_funding_paid(), 2);
+    }
+
     #[test]
     fn test_token_conditional_swap() {
         let mut account = make_test_account();
@@ -2915,7 +3272,21 @@ mod tests {
                 temporary_delegate: fixed.temporary_delegate,
                 temporary_delegate_expiry: fixed.temporary_delegate_expiry,
                 last_collateral_fee_charge: fixed.last_collateral_fee_charge,
-                reserved: [0u8; 152],
+                liquidation_base_accumulated: fixed.liquidation_base_accumulated,
+                liquidation_quote_accumulated: fixed.liquidation_quote_accumulated,
+                perp_trading_fees_paid: fixed.perp_trading_fees_paid,
+                perp_funding_paid_deactivated: fixed.perp_funding_paid_deactivated,
+                perp_liquidation_fees_paid: fixed.perp_liquidation_fees_paid,
+                token_allowlist: fixed.token_allowlist,
+                token_allowlist_len: fixed.token_allowlist_len,
+                delegate_permissions: fixed.delegate_permissions,
+                temporary_delegate_permissions: fixed.temporary_delegate_permissions,
+                padding: Default::default(),
+                cached_init_health: fixed.cached_init_health,
+                cached_maint_health: fixed.cached_maint_health,
+                cached_health_slot: fixed.cached_health_slot,
+                being_liquidated_slot: fixed.being_liquidated_slot,
+                reserved: [0u8; 56],
 
                 header_version: *zerocopy_reader.header_version(),
                 padding3: Default::default(),
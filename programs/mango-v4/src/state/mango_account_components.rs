@@ -6,6 +6,7 @@ use static_assertions::const_assert_eq;
 use std::cmp::Ordering;
 use std::mem::size_of;
 
+use crate::error::*;
 use crate::i80f48::ClampToInt;
 use crate::state::*;
 
@@ -364,6 +365,41 @@ impl Default for PerpPosition {
     }
 }
 
+/// Current version of the PerpPositionWire format
+pub const PERP_POSITION_WIRE_VERSION: u8 = 1;
+
+/// Stable serialization of PerpPosition for clients and indexers.
+///
+/// Unlike PerpPosition it has no padding or reserved bytes and stores I80F48 values
+/// as their i128 bits, so it doesn't change when the zero-copy layout is rearranged.
+/// Fields may only ever be appended, together with a bump of the version.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PerpPositionWire {
+    pub version: u8,
+    pub market_index: PerpMarketIndex,
+    pub settle_pnl_limit_window: u32,
+    pub settle_pnl_limit_settled_in_current_window_native: i64,
+    pub base_position_lots: i64,
+    pub quote_position_native: i128,
+    pub quote_running_native: i64,
+    pub long_settled_funding: i128,
+    pub short_settled_funding: i128,
+    pub bids_base_lots: i64,
+    pub asks_base_lots: i64,
+    pub taker_base_lots: i64,
+    pub taker_quote_lots: i64,
+    pub cumulative_long_funding: f64,
+    pub cumulative_short_funding: f64,
+    pub maker_volume: u64,
+    pub taker_volume: u64,
+    pub perp_spot_transfers: i64,
+    pub avg_entry_price_per_base_lot: f64,
+    pub deprecated_realized_trade_pnl_native: i128,
+    pub oneshot_settle_pnl_allowance: i128,
+    pub recurring_settle_pnl_allowance: i64,
+    pub realized_pnl_for_position_native: i128,
+}
+
 impl PerpPosition {
     /// Add taker trade after it has been matched but before it has been process on EventQueue
     pub fn add_taker_trade(&mut self, side: Side, base_lots: i64, quote_lots: i64) {
@@ -412,11 +448,95 @@ impl PerpPosition {
         self.base_position_lots
     }
 
+    /// Sign of the max_base_transfer to use when liquidating this position in
+    /// perp_liq_base_or_positive_pnl: 1 for longs, -1 for shorts and 0 if flat
+    pub fn liquidation_direction(&self) -> i8 {
+        self.base_position_lots.signum() as i8
+    }
+
+    pub fn to_wire(&self) -> PerpPositionWire {
+        PerpPositionWire {
+            version: PERP_POSITION_WIRE_VERSION,
+            market_index: self.market_index,
+            settle_pnl_limit_window: self.settle_pnl_limit_window,
+            settle_pnl_limit_settled_in_current_window_native: self
+                .settle_pnl_limit_settled_in_current_window_native,
+            base_position_lots: self.base_position_lots,
+            quote_position_native: self.quote_position_native.to_bits(),
+            quote_running_native: self.quote_running_native,
+            long_settled_funding: self.long_settled_funding.to_bits(),
+            short_settled_funding: self.short_settled_funding.to_bits(),
+            bids_base_lots: self.bids_base_lots,
+            asks_base_lots: self.asks_base_lots,
+            taker_base_lots: self.taker_base_lots,
+            taker_quote_lots: self.taker_quote_lots,
+            cumulative_long_funding: self.cumulative_long_funding,
+            cumulative_short_funding: self.cumulative_short_funding,
+            maker_volume: self.maker_volume,
+            taker_volume: self.taker_volume,
+            perp_spot_transfers: self.perp_spot_transfers,
+            avg_entry_price_per_base_lot: self.avg_entry_price_per_base_lot,
+            deprecated_realized_trade_pnl_native: self
+                .deprecated_realized_trade_pnl_native
+                .to_bits(),
+            oneshot_settle_pnl_allowance: self.oneshot_settle_pnl_allowance.to_bits(),
+            recurring_settle_pnl_allowance: self.recurring_settle_pnl_allowance,
+            realized_pnl_for_position_native: self.realized_pnl_for_position_native.to_bits(),
+        }
+    }
+
+    pub fn from_wire(wire: &PerpPositionWire) -> Result<Self> {
+        require_msg!(
+            wire.version == PERP_POSITION_WIRE_VERSION,
+            "unsupported perp position wire version {}, expected {}",
+            wire.version,
+            PERP_POSITION_WIRE_VERSION
+        );
+        Ok(Self {
+            market_index: wire.market_index,
+            settle_pnl_limit_window: wire.settle_pnl_limit_window,
+            settle_pnl_limit_settled_in_current_window_native: wire
+                .settle_pnl_limit_settled_in_current_window_native,
+            base_position_lots: wire.base_position_lots,
+            quote_position_native: I80F48::from_bits(wire.quote_position_native),
+            quote_running_native: wire.quote_running_native,
+            long_settled_funding: I80F48::from_bits(wire.long_settled_funding),
+            short_settled_funding: I80F48::from_bits(wire.short_settled_funding),
+            bids_base_lots: wire.bids_base_lots,
+            asks_base_lots: wire.asks_base_lots,
+            taker_base_lots: wire.taker_base_lots,
+            taker_quote_lots: wire.taker_quote_lots,
+            cumulative_long_funding: wire.cumulative_long_funding,
+            cumulative_short_funding: wire.cumulative_short_funding,
+            maker_volume: wire.maker_volume,
+            taker_volume: wire.taker_volume,
+            perp_spot_transfers: wire.perp_spot_transfers,
+            avg_entry_price_per_base_lot: wire.avg_entry_price_per_base_lot,
+            deprecated_realized_trade_pnl_native: I80F48::from_bits(
+                wire.deprecated_realized_trade_pnl_native,
+            ),
+            oneshot_settle_pnl_allowance: I80F48::from_bits(wire.oneshot_settle_pnl_allowance),
+            recurring_settle_pnl_allowance: wire.recurring_settle_pnl_allowance,
+            realized_pnl_for_position_native: I80F48::from_bits(
+                wire.realized_pnl_for_position_native,
+            ),
+            ..Self::default()
+        })
+    }
+
     // This takes into account base lots from unprocessed events, but not anything from open orders
     pub fn effective_base_position_lots(&self) -> i64 {
         self.base_position_lots + self.taker_base_lots
     }
 
+    /// Signed number of base lots to trade to reach `target_base_lots`.
+    ///
+    /// Positive values mean buying. Based on the effective base position, so base lots
+    /// from unprocessed fill events are taken into account.
+    pub fn lots_to_target(&self, target_base_lots: i64) -> i64 {
+        target_base_lots - self.effective_base_position_lots()
+    }
+
     pub fn quote_position_native(&self) -> I80F48 {
         self.quote_position_native
     }
@@ -630,6 +750,105 @@ impl PerpPosition {
             / ((self.base_position_lots * market.base_lot_size) as f64)
     }
 
+    /// Calculate the cumulative funding rate at which the position's pnl relative to its
+    /// break even price is used up, as a fraction of `oracle_price`.
+    ///
+    /// Positive rates mean longs pay shorts. Longs become net-negative when funding exceeds
+    /// the returned rate, shorts when it falls below it.
+    pub fn breakeven_funding_rate(&self, perp_market: &PerpMarket, oracle_price: I80F48) -> I80F48 {
+        if self.base_position_lots == 0 || oracle_price <= 0 {
+            return I80F48::ZERO;
+        }
+        let break_even_price = I80F48::from_num(self.break_even_price(perp_market));
+        (oracle_price - break_even_price) / oracle_price
+    }
+
+    /// Estimate the funding the position pays over `seconds_held` if `funding_rate` (per day,
+    /// like PerpUpdateFundingLogV2::instantaneous_funding_rate) and `oracle_price` stayed
+    /// constant. Positive values are paid, negative values received.
+    ///
+    /// Uses the same formula as PerpMarket::update_funding_and_stable_price(), but doesn't
+    /// limit the time step. Open orders are ignored.
+    pub fn projected_funding(
+        &self,
+        perp_market: &PerpMarket,
+        funding_rate: I80F48,
+        oracle_price: I80F48,
+        seconds_held: u64,
+    ) -> I80F48 {
+        let time_factor = I80F48::from_num(seconds_held) / DAY_I80F48;
+        let funding_per_lot =
+            oracle_price * I80F48::from(perp_market.base_lot_size) * funding_rate * time_factor;
+        funding_per_lot * I80F48::from(self.base_position_lots)
+    }
+
+    /// Check invariants that every position should satisfy
+    ///
+    /// Used to catch corrupted positions around liquidation when the "paranoid" feature
+    /// is enabled.
+    pub fn validate(&self, perp_market: &PerpMarket) -> Result<()> {
+        require_eq!(self.market_index, perp_market.perp_market_index);
+        require_msg!(
+            self.bids_base_lots >= 0 && self.asks_base_lots >= 0,
+            "negative open order lots: bids {}, asks {}",
+            self.bids_base_lots,
+            self.asks_base_lots
+        );
+        require_msg!(
+            self.base_position_lots != i64::MIN
+                && self
+                    .base_position_lots
+                    .checked_mul(perp_market.base_lot_size)
+                    .is_some(),
+            "base position {} overflows in native units",
+            self.base_position_lots
+        );
+
+        // I80F48::MIN can't be negated and is what a wrapped value typically ends up as
+        for (name, value) in [
+            ("quote_position_native", self.quote_position_native),
+            ("long_settled_funding", self.long_settled_funding),
+            ("short_settled_funding", self.short_settled_funding),
+            (
+                "oneshot_settle_pnl_allowance",
+                self.oneshot_settle_pnl_allowance,
+            ),
+            (
+                "realized_pnl_for_position_native",
+                self.realized_pnl_for_position_native,
+            ),
+        ] {
+            require_msg!(value != I80F48::MIN, "{} overflowed", name);
+        }
+        for (name, value) in [
+            ("cumulative_long_funding", self.cumulative_long_funding),
+            ("cumulative_short_funding", self.cumulative_short_funding),
+            (
+                "avg_entry_price_per_base_lot",
+                self.avg_entry_price_per_base_lot,
+            ),
+        ] {
+            require_msg!(value.is_finite(), "{} is {}", name, value);
+        }
+
+        // record_trade() clears these when the base position reaches zero
+        if self.base_position_lots == 0 {
+            require_msg!(
+                self.avg_entry_price_per_base_lot == 0.0 && self.quote_running_native == 0,
+                "no base position, but avg entry price {} and quote running {}",
+                self.avg_entry_price_per_base_lot,
+                self.quote_running_native
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Funding paid by this position so far, in native quote (rounded)
+    pub fn cumulative_funding_paid(&self) -> i64 {
+        (self.cumulative_long_funding - self.cumulative_short_funding).round() as i64
+    }
+
     /// Calculate the PnL of the position for a given price
     pub fn unsettled_pnl(&self, perp_market: &PerpMarket, price: I80F48) -> Result<I80F48> {
         require_eq!(self.market_index, perp_market.perp_market_index);
@@ -780,6 +999,15 @@ impl PerpPosition {
         self.realized_pnl_for_position_native -= fee;
     }
 
+    /// Update the perp position when perp_adl takes pnl away from it
+    ///
+    /// Treated like a funding payment: settle limits are not consumed.
+    pub fn record_adl(&mut self, change: I80F48) {
+        self.change_quote_position(change);
+        self.oneshot_settle_pnl_allowance += change;
+        self.realized_pnl_for_position_native += change;
+    }
+
     /// Adds immediately-settleable realized pnl when a liqor takes over pnl during liquidation
     pub fn record_liquidation_quote_change(&mut self, change: I80F48) {
         self.change_quote_position(change);
@@ -884,7 +1112,7 @@ mod tests {
     use fixed::types::I80F48;
     use rand::Rng;
 
-    use super::PerpPosition;
+    use super::{PerpPosition, PerpPositionWire, PERP_POSITION_WIRE_VERSION};
 
     fn create_perp_position(
         market: &PerpMarket,
@@ -1280,6 +1508,239 @@ mod tests {
         assert_eq!(pnl, I80F48::from(50 * 10 * 1), "short profitable");
     }
 
+    #[test]
+    fn test_perp_position_breakeven_funding_rate() {
+        let mut market = test_perp_market(10.0);
+        market.base_lot_size = 10;
+
+        let flat_pos = create_perp_position(&market, 0, 0);
+        assert_eq!(
+            flat_pos.breakeven_funding_rate(&market, I80F48::from(10)),
+            I80F48::ZERO
+        );
+
+        // long 50 lots with a break even price of 10
+        let long_pos = create_perp_position(&market, 50, 100);
+        assert_eq!(long_pos.break_even_price(&market), 10.0);
+        assert_eq!(
+            long_pos.breakeven_funding_rate(&market, I80F48::from(16)),
+            I80F48::from_num(0.375),
+            "long in profit can pay 37.5% funding"
+        );
+        assert_eq!(
+            long_pos.breakeven_funding_rate(&market, I80F48::from(10)),
+            I80F48::ZERO,
+            "long at break even"
+        );
+        assert_eq!(
+            long_pos.breakeven_funding_rate(&market, I80F48::from(8)),
+            I80F48::from_num(-0.25),
+            "long in loss needs to receive funding"
+        );
+
+        // paying exactly the break even funding rate leaves zero pnl
+        let oracle_price = I80F48::from(16);
+        let rate = long_pos.breakeven_funding_rate(&market, oracle_price);
+        let funding = rate * oracle_price * long_pos.base_position_native(&market);
+        let pnl = long_pos.unsettled_pnl(&market, oracle_price).unwrap();
+        assert_eq!(pnl - funding, I80F48::ZERO);
+    }
+
+    #[test]
+    fn test_perp_position_projected_funding() {
+        let mut market = test_perp_market(10.0);
+        market.base_lot_size = 10;
+        let day = 24 * 60 * 60;
+        let rate = I80F48::from_num(0.015625);
+        let price = I80F48::from(20);
+
+        // 50 lots of 10 base at price 20 are worth 10000, 1/64 per day is 156.25 per day
+        let long_pos = create_perp_position(&market, 50, 200);
+        assert_eq!(
+            long_pos.projected_funding(&market, rate, price, day),
+            I80F48::from_num(156.25),
+            "long pays positive funding"
+        );
+        assert_eq!(
+            long_pos.projected_funding(&market, rate, price, 3 * day),
+            I80F48::from_num(468.75)
+        );
+        assert_eq!(
+            long_pos.projected_funding(&market, -rate, price, day),
+            I80F48::from_num(-156.25),
+            "long receives negative funding"
+        );
+
+        let short_pos = create_perp_position(&market, -50, 200);
+        assert_eq!(
+            short_pos.projected_funding(&market, rate, price, day / 2),
+            I80F48::from_num(-78.125),
+            "short receives positive funding"
+        );
+        assert_eq!(
+            short_pos.projected_funding(&market, -rate, price, day / 2),
+            I80F48::from_num(78.125),
+            "short pays negative funding"
+        );
+
+        let flat_pos = create_perp_position(&market, 0, 0);
+        assert_eq!(
+            flat_pos.projected_funding(&market, rate, price, day),
+            I80F48::ZERO
+        );
+
+        // matches the funding that settle_funding() books for the same period
+        let mut pos = create_perp_position(&market, 50, 200);
+        let projected = pos.projected_funding(&market, rate, price, day);
+        market.long_funding += price * I80F48::from(market.base_lot_size) * rate;
+        market.short_funding += price * I80F48::from(market.base_lot_size) * rate;
+        let quote_before = pos.quote_position_native();
+        pos.settle_funding(&market);
+        assert_eq!(quote_before - pos.quote_position_native(), projected);
+    }
+
+    #[test]
+    fn test_perp_position_lots_to_target() {
+        let market = test_perp_market(10.0);
+
+        let long_pos = create_perp_position(&market, 5, 0);
+        assert_eq!(long_pos.lots_to_target(8), 3, "increase");
+        assert_eq!(long_pos.lots_to_target(2), -3, "decrease");
+        assert_eq!(long_pos.lots_to_target(5), 0, "at target");
+        assert_eq!(long_pos.lots_to_target(-4), -9, "flip to short");
+
+        let short_pos = create_perp_position(&market, -5, 0);
+        assert_eq!(short_pos.lots_to_target(-8), -3, "increase short");
+        assert_eq!(short_pos.lots_to_target(4), 9, "flip to long");
+
+        // unprocessed taker fills count towards the position
+        let mut pos = create_perp_position(&market, 5, 0);
+        pos.taker_base_lots = 2;
+        assert_eq!(pos.lots_to_target(8), 1);
+    }
+
+    #[test]
+    fn test_perp_position_liquidation_direction() {
+        let market = test_perp_market(10.0);
+        assert_eq!(
+            create_perp_position(&market, 5, 10).liquidation_direction(),
+            1,
+            "long"
+        );
+        assert_eq!(
+            create_perp_position(&market, -5, 10).liquidation_direction(),
+            -1,
+            "short"
+        );
+        assert_eq!(
+            create_perp_position(&market, 0, 0).liquidation_direction(),
+            0,
+            "flat"
+        );
+    }
+
+    fn wire_test_position() -> PerpPosition {
+        let market = test_perp_market(10.0);
+        let mut pos = create_perp_position(&market, 7, 12);
+        pos.settle_pnl_limit_window = 3;
+        pos.settle_pnl_limit_settled_in_current_window_native = -4;
+        pos.long_settled_funding = I80F48::from_num(1.5);
+        pos.short_settled_funding = I80F48::from_num(-2.25);
+        pos.bids_base_lots = 5;
+        pos.asks_base_lots = 6;
+        pos.taker_base_lots = -2;
+        pos.taker_quote_lots = 20;
+        pos.cumulative_long_funding = 0.5;
+        pos.cumulative_short_funding = -0.75;
+        pos.maker_volume = 100;
+        pos.taker_volume = 200;
+        pos.perp_spot_transfers = -30;
+        pos.deprecated_realized_trade_pnl_native = I80F48::from(8);
+        pos.oneshot_settle_pnl_allowance = I80F48::from(-9);
+        pos.recurring_settle_pnl_allowance = 10;
+        pos.realized_pnl_for_position_native = I80F48::from_num(11.125);
+        pos
+    }
+
+    #[test]
+    fn test_perp_position_wire_roundtrip() {
+        use anchor_lang::{AnchorDeserialize, AnchorSerialize};
+
+        for pos in [PerpPosition::default(), wire_test_position()] {
+            let wire = pos.to_wire();
+            assert_eq!(wire.version, PERP_POSITION_WIRE_VERSION);
+            assert_eq!(PerpPosition::from_wire(&wire).unwrap(), pos);
+
+            let bytes = wire.try_to_vec().unwrap();
+            let decoded = PerpPositionWire::try_from_slice(&bytes).unwrap();
+            assert_eq!(decoded, wire);
+            assert_eq!(PerpPosition::from_wire(&decoded).unwrap(), pos);
+        }
+
+        let mut wire = wire_test_position().to_wire();
+        wire.version += 1;
+        assert!(PerpPosition::from_wire(&wire).is_err());
+    }
+
+    #[test]
+    fn test_perp_position_wire_stable() {
+        use anchor_lang::AnchorSerialize;
+
+        let pos = wire_test_position();
+        let bytes = pos.to_wire().try_to_vec().unwrap();
+
+        // 1 + 2 + 4 + 8 * 2 + 16 + 8 + 16 * 2 + 8 * 4 + 8 * 2 + 8 * 3 + 8 + 16 * 2 + 8 + 16
+        assert_eq!(bytes.len(), 215);
+        assert_eq!(bytes[0], PERP_POSITION_WIRE_VERSION);
+        assert_eq!(&bytes[1..3], &pos.market_index.to_le_bytes());
+        assert_eq!(&bytes[3..7], &3u32.to_le_bytes());
+        assert_eq!(&bytes[15..23], &7i64.to_le_bytes());
+        assert_eq!(&bytes[23..39], &I80F48::from(-84).to_bits().to_le_bytes());
+        assert_eq!(
+            &bytes[199..215],
+            &I80F48::from_num(11.125).to_bits().to_le_bytes()
+        );
+
+        // changes to padding and reserved bytes don't show up in the wire format
+        let mut changed = pos;
+        changed.padding = [1, 2];
+        changed.reserved = [3; 88];
+        assert_eq!(changed.to_wire().try_to_vec().unwrap(), bytes);
+        assert_eq!(PerpPosition::from_wire(&changed.to_wire()).unwrap(), pos);
+    }
+
+    #[test]
+    fn test_perp_position_validate() {
+        let mut market = test_perp_market(10.0);
+        market.base_lot_size = 10;
+
+        let mut pos = create_perp_position(&market, 0, 0);
+        pos.validate(&market).unwrap();
+        pos.record_trade(&mut market, 10, I80F48::from(-100));
+        pos.validate(&market).unwrap();
+        pos.record_trade(&mut market, -10, I80F48::from(120));
+        pos.validate(&market).unwrap();
+        wire_test_position().validate(&market).unwrap();
+
+        let corruptions: Vec<fn(&mut PerpPosition)> = vec![
+            |p| p.market_index += 1,
+            |p| p.bids_base_lots = -1,
+            |p| p.base_position_lots = i64::MIN,
+            |p| p.base_position_lots = i64::MAX / 2,
+            |p| p.quote_position_native = I80F48::MIN,
+            |p| p.long_settled_funding = I80F48::MIN,
+            |p| p.cumulative_short_funding = f64::NAN,
+            |p| p.avg_entry_price_per_base_lot = f64::INFINITY,
+            |p| p.base_position_lots = 0,
+        ];
+        for corrupt in corruptions {
+            let mut pos = create_perp_position(&market, 5, 10);
+            pos.validate(&market).unwrap();
+            corrupt(&mut pos);
+            assert!(pos.validate(&market).is_err());
+        }
+    }
+
     #[test]
     fn test_perp_settle_limit_allowance_consumption() {
         let market = test_perp_market(10.0);
@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use derivative::Derivative;
 use static_assertions::const_assert_eq;
-use std::mem::size_of;
+use std::mem::{align_of, size_of};
 
 use crate::error::*;
 
@@ -44,6 +44,7 @@ const_assert_eq!(
 );
 const_assert_eq!(size_of::<MintInfo>(), 3056);
 const_assert_eq!(size_of::<MintInfo>() % 8, 0);
+const_assert_eq!(align_of::<MintInfo>(), 8);
 
 impl MintInfo {
     // used for health purposes
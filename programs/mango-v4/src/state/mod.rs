@@ -29,3 +29,5 @@ mod perp_market;
 mod serum3_market;
 mod stable_price;
 mod token_conditional_swap;
+
+pub mod voter_stake_registry;
@@ -50,6 +50,9 @@ pub const QUOTE_DECIMALS: i8 = 6;
 pub const SOL_DECIMALS: i8 = 9;
 pub const QUOTE_NATIVE_TO_UI: I80F48 = power_of_ten(-QUOTE_DECIMALS);
 
+/// Conservative estimate of the slot duration, for converting slot counts to time
+pub const MS_PER_SLOT: u64 = 450;
+
 pub mod switchboard_v1_devnet_oracle {
     use solana_program::declare_id;
     declare_id!("7azgmy1pFXHikv36q1zZASvFq5vFa39TT9NweVugKKTU");
@@ -129,6 +132,7 @@ pub enum OracleType {
     PythV2,
 }
 
+#[derive(Clone, Copy)]
 pub struct OracleState {
     pub price: I80F48,
     pub deviation: I80F48,
@@ -166,7 +170,7 @@ impl OracleState {
         if self.last_update_time.is_some() {
             let current_time_in_msecs = now_ts * 1000;
             let last_update_time_in_msecs = self.last_update_time.unwrap() * 1000;
-            let max_acceptable_update_age_in_ms = (config.max_staleness_slots as u64) * 450;
+            let max_acceptable_update_age_in_ms = (config.max_staleness_slots as u64) * MS_PER_SLOT;
 
             let oldest_acceptable_time =
                 current_time_in_msecs.saturating_sub(max_acceptable_update_age_in_ms);
@@ -204,6 +208,18 @@ const_assert_eq!(size_of::<StubOracle>(), 32 + 32 + 16 + 8 + 8 + 16 + 104);
 const_assert_eq!(size_of::<StubOracle>(), 216);
 const_assert_eq!(size_of::<StubOracle>() % 8, 0);
 
+// note: the sdk's anchor version differs from ours, hence the copy of PriceUpdateV2's discriminator
+const PYTH_PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Whether the account is a Pyth pull oracle: a PriceUpdateV2 account posted to the
+/// pyth receiver program, instead of a continuously updated push feed
+pub fn is_pyth_pull_oracle(acc_info: &(impl KeyedAccountReader + ?Sized)) -> bool {
+    let data = acc_info.data();
+    acc_info.owner() == &pyth_solana_receiver_sdk::ID
+        && data.len() >= 8
+        && data[0..8] == PYTH_PRICE_UPDATE_V2_DISCRIMINATOR
+}
+
 pub fn determine_oracle_type(acc_info: &impl KeyedAccountReader) -> Result<OracleType> {
     let data = acc_info.data();
 
@@ -231,6 +247,12 @@ pub fn determine_oracle_type(acc_info: &impl KeyedAccountReader) -> Result<Oracl
     } else if acc_info.owner() == &raydium_mainnet::ID {
         return Ok(OracleType::RaydiumCLMM);
     } else if acc_info.owner() == &pyth_solana_receiver_sdk::ID {
+        require_msg_typed!(
+            is_pyth_pull_oracle(acc_info),
+            MangoError::UnknownOracleType,
+            "account {} is owned by the pyth receiver but is not a PriceUpdateV2 account",
+            acc_info.key()
+        );
         return Ok(OracleType::PythV2);
     }
 
@@ -317,13 +339,29 @@ pub fn get_pyth_on_demand_state(
     acc_info: &(impl KeyedAccountReader + ?Sized),
     base_decimals: u8,
 ) -> Result<OracleState> {
-    let mut data = acc_info.data();
-    data = &data[8..];
-    let price_account =
-        pyth_solana_receiver_sdk::price_update::PriceUpdateV2::deserialize(&mut data).unwrap();
-    if price_account.verification_level != VerificationLevel::Full {
-        return Err(MangoError::OracleConfidence.into());
-    }
+    require_msg_typed!(
+        is_pyth_pull_oracle(acc_info),
+        MangoError::UnexpectedOracle,
+        "oracle {} is not a pyth pull oracle price update account",
+        acc_info.key()
+    );
+    let mut data = &acc_info.data()[8..];
+    let price_account = pyth_solana_receiver_sdk::price_update::PriceUpdateV2::deserialize(
+        &mut data,
+    )
+    .map_err(|_| {
+        error_msg_typed!(
+            MangoError::UnexpectedOracle,
+            "could not deserialize pyth price update {}",
+            acc_info.key()
+        )
+    })?;
+    require_msg_typed!(
+        price_account.verification_level == VerificationLevel::Full,
+        MangoError::OracleConfidence,
+        "pyth price update {} is only partially verified",
+        acc_info.key()
+    );
 
     let decimals =
         (price_account.price_message.exponent as i8) + QUOTE_DECIMALS - (base_decimals as i8);
@@ -613,6 +651,76 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn test_pyth_pull_oracle_detection() -> Result<()> {
+        let load =
+            |name: &str| read_file(find_file(&format!("resources/test/{}.bin", name)).unwrap());
+        let pull_key = Pubkey::from_str("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE").unwrap();
+        let push_key = Pubkey::from_str("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix").unwrap();
+
+        // a pull oracle price update
+        let mut pull_data = load("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
+        {
+            let data = RefCell::new(&mut pull_data[..]);
+            let ai = &AccountInfoRef {
+                key: &pull_key,
+                owner: &pyth_solana_receiver_sdk::ID,
+                data: data.borrow(),
+            };
+            assert!(is_pyth_pull_oracle(ai));
+            assert!(determine_oracle_type(ai).unwrap() == OracleType::PythV2);
+            assert!(get_pyth_on_demand_state(ai, 6).is_ok());
+        }
+
+        // a push feed is not a pull oracle and can't be read as one
+        let mut push_data = load("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix");
+        {
+            let data = RefCell::new(&mut push_data[..]);
+            let ai = &AccountInfoRef {
+                key: &push_key,
+                owner: &Pubkey::default(),
+                data: data.borrow(),
+            };
+            assert!(!is_pyth_pull_oracle(ai));
+            assert!(determine_oracle_type(ai).unwrap() == OracleType::Pyth);
+            assert!(get_pyth_on_demand_state(ai, 6)
+                .is_anchor_error_with_code(MangoError::UnexpectedOracle.error_code()));
+        }
+
+        // other accounts of the pyth receiver are rejected
+        {
+            let data = RefCell::new(&mut push_data[..]);
+            let ai = &AccountInfoRef {
+                key: &push_key,
+                owner: &pyth_solana_receiver_sdk::ID,
+                data: data.borrow(),
+            };
+            assert!(!is_pyth_pull_oracle(ai));
+            assert!(determine_oracle_type(ai)
+                .is_anchor_error_with_code(MangoError::UnknownOracleType.error_code()));
+        }
+
+        // partially verified price updates are rejected:
+        // replace the Full verification level with Partial { num_signatures: 3 }
+        assert_eq!(pull_data[40], 1);
+        pull_data[40] = 0;
+        pull_data.insert(41, 3);
+        {
+            let data = RefCell::new(&mut pull_data[..]);
+            let ai = &AccountInfoRef {
+                key: &pull_key,
+                owner: &pyth_solana_receiver_sdk::ID,
+                data: data.borrow(),
+            };
+            assert!(is_pyth_pull_oracle(ai));
+            let res = get_pyth_on_demand_state(ai, 6);
+            assert!(res.is_anchor_error_with_code(MangoError::OracleConfidence.error_code()));
+            assert!(res.is_oracle_error());
+        }
+
+        Ok(())
+    }
+
     #[test]
     pub fn lookup_test() {
         for idx in -12..0 {
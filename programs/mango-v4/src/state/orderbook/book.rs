@@ -485,6 +485,7 @@ fn apply_fees(
         .fixed
         .accrue_buyback_fees(taker_dao_fees.floor().to_num::<u64>());
 
+    account.fixed.record_perp_trading_fee(taker_fees);
     let perp_position = account.perp_position_mut(market.perp_market_index)?;
     perp_position.record_trading_fee(taker_fees);
 
@@ -506,6 +507,7 @@ fn apply_penalty(market: &mut PerpMarket, account: &mut MangoAccountRefMut) -> R
         .fixed
         .accrue_buyback_fees(fee_penalty.floor().to_num::<u64>());
 
+    account.fixed.record_perp_trading_fee(fee_penalty);
     let perp_position = account.perp_position_mut(market.perp_market_index)?;
     perp_position.record_trading_fee(fee_penalty);
     market.fees_accrued += fee_penalty;
@@ -21,7 +21,10 @@ mod queue;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::state::{Group, MangoAccount, MangoAccountValue, PerpMarket, FREE_ORDER_SLOT};
+    use crate::state::{
+        Group, MangoAccount, MangoAccountValue, OracleState, OracleType, PerpMarket, StableModel,
+        FREE_ORDER_SLOT,
+    };
     use anchor_lang::prelude::*;
     use bytemuck::Zeroable;
     use fixed::types::I80F48;
@@ -1063,4 +1066,72 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_update_funding_positive_premium() {
+        let oracle_state = OracleState {
+            price: I80F48::from(1000),
+            deviation: I80F48::ZERO,
+            last_update_slot: 0,
+            last_update_time: None,
+            oracle_type: OracleType::Stub,
+        };
+
+        // Compute the funding after one hour with a bid and ask on the book
+        let funding_after_one_hour = |bid_price_lots: i64, ask_price_lots: i64| {
+            let (mut market, oracle_price, mut event_queue, book_accs) = test_setup(1000.0);
+            let mut book = book_accs.orderbook();
+            market.impact_quantity = 1;
+            market.min_funding = I80F48::from_num(-0.05);
+            market.max_funding = I80F48::from_num(0.05);
+            market.stable_price_model = StableModel::default();
+            market.stable_price_model.reset_to_price(1000.0, 0);
+
+            let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+            let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+            account
+                .ensure_perp_position(market.perp_market_index, 0)
+                .unwrap();
+            for (side, price_lots) in [(Side::Bid, bid_price_lots), (Side::Ask, ask_price_lots)] {
+                book.new_order(
+                    Order {
+                        side,
+                        max_base_lots: 1,
+                        max_quote_lots: i64::MAX,
+                        client_order_id: 0,
+                        time_in_force: 0,
+                        reduce_only: false,
+                        self_trade_behavior: SelfTradeBehavior::DecrementTake,
+                        params: OrderParams::Fixed {
+                            price_lots,
+                            order_type: PostOrderType::Limit,
+                        },
+                    },
+                    &mut market,
+                    &mut event_queue,
+                    oracle_price,
+                    &mut account.borrow_mut(),
+                    &Pubkey::default(),
+                    0,
+                    u8::MAX,
+                )
+                .unwrap();
+            }
+
+            market
+                .update_funding_and_stable_price(&book, &oracle_state, 3600)
+                .unwrap();
+            assert_eq!(market.long_funding, market.short_funding);
+            market.long_funding.to_num::<f64>()
+        };
+
+        // mid price 1020 is a 2% premium, one hour of it is paid per lot of 1000
+        let funding = funding_after_one_hour(1010, 1030);
+        assert!(funding > 0.0);
+        assert!((funding - 1000.0 * 0.02 / 24.0).abs() < 0.0001);
+
+        // a 20% premium gets clamped to max_funding
+        let funding = funding_after_one_hour(1100, 1300);
+        assert!((funding - 1000.0 * 0.05 / 24.0).abs() < 0.0001);
+    }
 }
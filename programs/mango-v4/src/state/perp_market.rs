@@ -1,4 +1,4 @@
-use std::mem::size_of;
+use std::mem::{align_of, size_of};
 
 use anchor_lang::prelude::*;
 use derivative::Derivative;
@@ -198,8 +198,111 @@ pub struct PerpMarket {
     /// liquidation fees that happened. So never decreases (different to fees_accrued).
     pub accrued_liquidation_fees: I80F48,
 
+    /// Max relative deviation of a liqor-supplied liquidation price from the oracle price.
+    ///
+    /// Allows liquidators to use an externally computed (e.g. index) price for the base
+    /// transfer. With 0 only the oracle price itself is accepted.
+    pub liquidation_price_tolerance: f32,
+
+    /// If true, maint health (the liquidation trigger) values the base position at
+    /// ema_price instead of the oracle price. Liquidation transfers still use the oracle price.
+    pub use_ema_for_trigger: u8,
+
+    #[derivative(Debug = "ignore")]
+    pub padding5: [u8; 3],
+
+    /// Exponential moving average of the oracle price, updated with funding
+    pub ema_price: f64,
+    /// Timestamp of the last ema_price update
+    pub ema_last_updated: u64,
+    /// Time constant of the ema_price in seconds
+    pub ema_interval_seconds: u32,
+
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 1848],
+    pub padding6: [u8; 4],
+
+    /// Liqors may only grow their base position above this many lots in liquidations
+    /// if they are in large_position_whitelist. Disabled if 0.
+    pub large_position_threshold_lots: i64,
+
+    /// Liqor mango accounts that may exceed large_position_threshold_lots.
+    /// Unused entries are Pubkey::default().
+    pub large_position_whitelist: [Pubkey; 4],
+
+    /// Liqee positions of at most this many lots are dust: base liquidation of these
+    /// last lots pays dust_liquidation_fee instead of base_liquidation_fee, to give liqors
+    /// a reason to fully close positions. Disabled if 0.
+    pub dust_threshold_lots: i64,
+
+    /// Liquidation fee for the lots in the dust region, see dust_threshold_lots.
+    ///
+    /// Only applies if it's larger than base_liquidation_fee.
+    pub dust_liquidation_fee: I80F48,
+
+    /// Settle fees collected by the protocol (in native settle token)
+    ///
+    /// They are added to the settle bank's collected_fees_native.
+    pub accrued_settle_fees: I80F48,
+
+    /// Fee taken by the protocol from the positive side of pnl settlements, in basis points
+    pub settle_fee_bps: u32,
+
+    #[derivative(Debug = "ignore")]
+    pub padding7: [u8; 4],
+
+    /// Max number of base lots that may be liquidated in a single slot, to slow down
+    /// liquidation cascades. Disabled if 0.
+    pub max_liquidation_base_per_slot: i64,
+
+    /// Base lots liquidated in liquidation_base_slot
+    pub liquidation_base_in_slot: i64,
+
+    /// Slot that liquidation_base_in_slot refers to
+    pub liquidation_base_slot: u64,
+
+    /// Liqees with a maint health below minus this value (in quote native) are so far
+    /// underwater that they're liquidated regardless of max_liquidation_base_per_slot.
+    /// No liqee bypasses the limit if 0.
+    pub liquidation_throttle_bypass_health: f64,
+
+    /// Fraction of base_liquidation_fee that goes to the market's fees instead of the liqor.
+    ///
+    /// The liqee always pays the full fee. Between 0 and 1.
+    pub liquidation_fee_insurance_fraction: f32,
+
+    /// If set, losses of bankrupt accounts that the insurance fund can't cover are not
+    /// socialized through funding but left for perp_adl to take from profitable accounts.
+    pub adl_enabled: u8,
+
+    #[derivative(Debug = "ignore")]
+    pub padding8: [u8; 1],
+
+    /// Markets with the same non-zero underlying_id track the same underlying asset,
+    /// like a perpetual and a dated future on it. Zero means not grouped with other markets.
+    ///
+    /// Only used for reporting net exposure, see HealthCache::net_delta_for_underlying().
+    pub underlying_id: u16,
+
+    /// Base liquidations must transfer at least this many lots, unless they close the
+    /// liqee's base position. Stops dust liquidations that each cost the liqee a fee.
+    /// Disabled if 0.
+    pub min_liquidation_base_lots: i64,
+
+    /// Quote (in native settle token) kept back from the liqor on every base liquidation
+    /// and added to fees_accrued, so rounding in the transfer can't cost the protocol.
+    /// Disabled if 0.
+    pub liquidation_quote_rounding_buffer: f64,
+
+    /// Token that liqors may pay for taken over positive pnl in instead of the settle token,
+    /// see perp_liq_base_or_positive_pnl_v3. Only used if alt_settle_token_enabled is set.
+    pub alt_settle_token_index: TokenIndex,
+    pub alt_settle_token_enabled: u8,
+
+    #[derivative(Debug = "ignore")]
+    pub padding9: [u8; 5],
+
+    #[derivative(Debug = "ignore")]
+    pub reserved: [u8; 1568],
 }
 
 const_assert_eq!(
@@ -237,10 +340,35 @@ const_assert_eq!(
         + 3 * 16
         + 8
         + 2 * 16
-        + 1848
+        + 4
+        + 1
+        + 3
+        + 8
+        + 8
+        + 4
+        + 4
+        + 8
+        + 32 * 4
+        + 8
+        + 16
+        + 16
+        + 4
+        + 4
+        + 8 * 4
+        + 4
+        + 1
+        + 1
+        + 2
+        + 8
+        + 8
+        + 2
+        + 1
+        + 5
+        + 1568
 );
 const_assert_eq!(size_of::<PerpMarket>(), 2808);
 const_assert_eq!(size_of::<PerpMarket>() % 8, 0);
+const_assert_eq!(align_of::<PerpMarket>(), 8);
 
 impl PerpMarket {
     pub fn name(&self) -> &str {
@@ -257,6 +385,15 @@ impl PerpMarket {
         self.force_close == 1
     }
 
+    pub fn is_adl_enabled(&self) -> bool {
+        self.adl_enabled == 1
+    }
+
+    /// The token that liqors may pay taken over pnl in besides the settle token, if any
+    pub fn alt_settle_token_index(&self) -> Option<TokenIndex> {
+        (self.alt_settle_token_enabled == 1).then_some(self.alt_settle_token_index)
+    }
+
     pub fn elligible_for_group_insurance_fund(&self) -> bool {
         self.group_insurance_fund == 1
     }
@@ -299,6 +436,37 @@ impl PerpMarket {
         I80F48::from_num(self.stable_price_model.stable_price)
     }
 
+    pub fn uses_ema_for_trigger(&self) -> bool {
+        self.use_ema_for_trigger == 1
+    }
+
+    /// The price maint health should value the base position at, if it's not the oracle price
+    ///
+    /// ema_price is only updated with funding. If that didn't happen within the last
+    /// ema_interval_seconds the average may lag a real price move, so the oracle price is
+    /// used instead.
+    pub fn maint_trigger_price(&self, now_ts: u64) -> Option<I80F48> {
+        let ema_is_fresh =
+            now_ts.saturating_sub(self.ema_last_updated) <= self.ema_interval_seconds as u64;
+        (self.uses_ema_for_trigger() && self.ema_price > 0.0 && ema_is_fresh)
+            .then(|| I80F48::from_num(self.ema_price))
+    }
+
+    /// Moves ema_price towards `oracle_price`.
+    ///
+    /// The weight of the new price is 1 - exp(-dt / ema_interval_seconds) for the time dt
+    /// since the last update, so the average decays the same no matter how often it's updated.
+    pub fn update_ema_price(&mut self, now_ts: u64, oracle_price: f64) {
+        if self.ema_price <= 0.0 || self.ema_interval_seconds == 0 {
+            self.ema_price = oracle_price;
+        } else {
+            let dt = now_ts.saturating_sub(self.ema_last_updated) as f64;
+            let weight = 1.0 - (-dt / self.ema_interval_seconds as f64).exp();
+            self.ema_price += (oracle_price - self.ema_price) * weight;
+        }
+        self.ema_last_updated = now_ts;
+    }
+
     /// Use current order book price and index price to update the instantaneous funding
     pub fn update_funding_and_stable_price(
         &mut self,
@@ -354,6 +522,7 @@ impl PerpMarket {
 
         self.stable_price_model
             .update(now_ts, oracle_price.to_num());
+        self.update_ema_price(now_ts, oracle_price.to_num());
 
         emit_stack(PerpUpdateFundingLogV2 {
             mango_group: self.group,
@@ -385,6 +554,15 @@ impl PerpMarket {
             .to_num()
     }
 
+    /// Quote notional of the spot trade that neutralizes the exposure of taking on `base_lots`
+    /// perp base lots at `oracle_price`.
+    ///
+    /// Positive means buying base on spot, negative means selling. For example after
+    /// taking over a long perp position the result is negative: the base should be sold.
+    pub fn base_to_spot_notional(&self, base_lots: i64, oracle_price: I80F48) -> I80F48 {
+        -I80F48::from(base_lots) * I80F48::from(self.base_lot_size) * oracle_price
+    }
+
     /// Is `native_price` an acceptable order for the `side` of this market, given `oracle_price`?
     pub fn inside_price_limit(
         &self,
@@ -398,6 +576,156 @@ impl PerpMarket {
         }
     }
 
+    /// The base liquidation fee in basis points, rounded to the nearest bps
+    ///
+    /// None if the fee is negative or too large for u32.
+    pub fn liquidation_fee_bps(&self) -> Option<u32> {
+        (self.base_liquidation_fee * I80F48::from(10_000))
+            .round()
+            .checked_to_num::<u32>()
+    }
+
+    /// Converts a liquidation fee in basis points to the fraction stored on the market
+    pub fn liquidation_fee_from_bps(bps: u32) -> I80F48 {
+        I80F48::from(bps) / I80F48::from(10_000)
+    }
+
+    /// Is a liqor-supplied `liquidation_price` close enough to `oracle_price` to be used
+    /// for liquidation transfers?
+    pub fn check_liquidation_price(
+        &self,
+        liquidation_price: I80F48,
+        oracle_price: I80F48,
+    ) -> Result<()> {
+        let max_deviation = oracle_price.abs() * I80F48::from_num(self.liquidation_price_tolerance);
+        require_msg_typed!(
+            (liquidation_price - oracle_price).abs() <= max_deviation,
+            MangoError::LiquidationPriceOutOfTolerance,
+            "liquidation price {} deviates from oracle price {} by more than {}",
+            liquidation_price,
+            oracle_price,
+            max_deviation
+        );
+        Ok(())
+    }
+
+    /// Checks that a liqor whose base position grows above large_position_threshold_lots
+    /// in a liquidation is in the large_position_whitelist
+    pub fn check_liqor_large_position(
+        &self,
+        liqor: &Pubkey,
+        base_lots_before: i64,
+        base_lots_after: i64,
+    ) -> Result<()> {
+        let threshold = self.large_position_threshold_lots;
+        if threshold <= 0
+            || base_lots_after.abs() <= threshold
+            || base_lots_after.abs() <= base_lots_before.abs()
+        {
+            return Ok(());
+        }
+        require_msg_typed!(
+            self.large_position_whitelist.contains(liqor),
+            MangoError::LiqorNotWhitelistedForLargePosition,
+            "liqor {} would hold {} base lots, more than the threshold of {}",
+            liqor,
+            base_lots_after,
+            threshold
+        );
+        Ok(())
+    }
+
+    /// Number of lots of a base liquidation that are in the dust region, when a liqee
+    /// position of `liqee_base_lots` gets reduced by `base_reduction` lots
+    pub fn liquidation_dust_lots(&self, liqee_base_lots: i64, base_reduction: i64) -> i64 {
+        let threshold = self.dust_threshold_lots.max(0);
+        let before = liqee_base_lots.abs();
+        let after = before - base_reduction;
+        before.min(threshold) - after.min(threshold)
+    }
+
+    /// Additional liquidation fee for lots in the dust region
+    ///
+    /// `base_liquidation_fee` is the fee the liquidation uses, see Group::capped_liquidation_fee().
+    pub fn dust_liquidation_fee_boost(&self, base_liquidation_fee: I80F48) -> I80F48 {
+        if self.dust_threshold_lots <= 0 {
+            return I80F48::ZERO;
+        }
+        (self.dust_liquidation_fee - base_liquidation_fee).max(I80F48::ZERO)
+    }
+
+    /// Fails if a base liquidation of `base_reduction` lots is below min_liquidation_base_lots
+    /// without closing the liqee position of `liqee_base_lots`
+    pub fn check_liquidation_base_lots(
+        &self,
+        liqee_base_lots: i64,
+        base_reduction: i64,
+    ) -> Result<()> {
+        if base_reduction == 0 || base_reduction == liqee_base_lots.abs() {
+            return Ok(());
+        }
+        require_msg_typed!(
+            base_reduction >= self.min_liquidation_base_lots,
+            MangoError::LiquidationTooSmall,
+            "base transfer of {} lots is below the minimum of {} lots and doesn't close the liqee position of {} lots",
+            base_reduction,
+            self.min_liquidation_base_lots,
+            liqee_base_lots
+        );
+        Ok(())
+    }
+
+    /// Adds `base_lots` to the lots liquidated in `now_slot`, failing if that exceeds
+    /// max_liquidation_base_per_slot, unless the liqee is deeply underwater
+    pub fn record_liquidation_base(
+        &mut self,
+        base_lots: i64,
+        now_slot: u64,
+        liqee_maint_health: I80F48,
+    ) -> Result<()> {
+        if self.max_liquidation_base_per_slot <= 0 {
+            return Ok(());
+        }
+        if self.liquidation_base_slot != now_slot {
+            self.liquidation_base_slot = now_slot;
+            self.liquidation_base_in_slot = 0;
+        }
+        let new_base_in_slot = self
+            .liquidation_base_in_slot
+            .saturating_add(base_lots.abs());
+
+        let bypass = self.liquidation_throttle_bypass_health > 0.0
+            && liqee_maint_health < -I80F48::from_num(self.liquidation_throttle_bypass_health);
+        if !bypass {
+            require_msg_typed!(
+                new_base_in_slot <= self.max_liquidation_base_per_slot,
+                MangoError::LiquidationThrottled,
+                "liquidating {} base lots would exceed the limit of {} per slot, already liquidated {}",
+                base_lots.abs(),
+                self.max_liquidation_base_per_slot,
+                self.liquidation_base_in_slot
+            );
+        }
+
+        self.liquidation_base_in_slot = new_base_in_slot;
+        Ok(())
+    }
+
+    /// Part of `base_liquidation_fee` that goes to the liqor
+    ///
+    /// `base_liquidation_fee` is the fee the liquidation uses, see Group::capped_liquidation_fee().
+    pub fn base_liquidation_fee_to_liqor(&self, base_liquidation_fee: I80F48) -> I80F48 {
+        base_liquidation_fee
+            * (I80F48::ONE - I80F48::from_num(self.liquidation_fee_insurance_fraction))
+    }
+
+    /// Value of the open base lot pairs at `price`, in native settle token
+    ///
+    /// Only counts one side, since open_interest contains both long and short lots.
+    pub fn open_interest_value(&self, price: I80F48) -> I80F48 {
+        I80F48::from(self.open_interest / 2) * I80F48::from(self.base_lot_size) * price
+    }
+
     /// Socialize the loss in this account across all longs and shorts
     ///
     /// `loss` is in settle token native units
@@ -478,6 +806,19 @@ impl PerpMarket {
         Ok(fee)
     }
 
+    /// settle_fee_bps as a fraction of the settled amount
+    pub fn settle_fee_fraction(&self) -> I80F48 {
+        I80F48::from(self.settle_fee_bps) / I80F48::from(10_000)
+    }
+
+    /// Protocol fee on a pnl settlement, see settle_fee_bps
+    ///
+    /// Never more than `max_fee`, the settlement amount left after the settler's fee.
+    pub fn compute_settle_protocol_fee(&self, settlement: I80F48, max_fee: I80F48) -> I80F48 {
+        let fee = settlement * self.settle_fee_fraction();
+        fee.min(max_fee).max(I80F48::ZERO)
+    }
+
     /// Creates default market for tests
     pub fn default_for_tests() -> PerpMarket {
         PerpMarket {
@@ -535,7 +876,290 @@ impl PerpMarket {
             fees_withdrawn: 0,
             platform_liquidation_fee: I80F48::ZERO,
             accrued_liquidation_fees: I80F48::ZERO,
-            reserved: [0; 1848],
+            liquidation_price_tolerance: 0.0,
+            use_ema_for_trigger: 0,
+            padding5: Default::default(),
+            ema_price: 0.0,
+            ema_last_updated: 0,
+            ema_interval_seconds: 0,
+            padding6: Default::default(),
+            large_position_threshold_lots: 0,
+            large_position_whitelist: [Pubkey::default(); 4],
+            dust_threshold_lots: 0,
+            dust_liquidation_fee: I80F48::ZERO,
+            accrued_settle_fees: I80F48::ZERO,
+            settle_fee_bps: 0,
+            padding7: Default::default(),
+            max_liquidation_base_per_slot: 0,
+            liquidation_base_in_slot: 0,
+            liquidation_base_slot: 0,
+            liquidation_throttle_bypass_health: 0.0,
+            liquidation_fee_insurance_fraction: 0.0,
+            adl_enabled: 0,
+            padding8: Default::default(),
+            underlying_id: 0,
+            min_liquidation_base_lots: 0,
+            liquidation_quote_rounding_buffer: 0.0,
+            alt_settle_token_index: 0,
+            alt_settle_token_enabled: 0,
+            padding9: Default::default(),
+            reserved: [0; 1568],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::IsAnchorErrorWithCode;
+
+    #[test]
+    fn test_base_to_spot_notional() {
+        let mut market = PerpMarket::default_for_tests();
+        market.base_lot_size = 100;
+        let price = I80F48::from_num(2.5);
+
+        // inherited a long: sell 3 * 100 base at 2.5 on spot
+        assert_eq!(
+            market.base_to_spot_notional(3, price),
+            I80F48::from_num(-750)
+        );
+        // inherited a short: buy it back on spot
+        assert_eq!(
+            market.base_to_spot_notional(-3, price),
+            I80F48::from_num(750)
+        );
+        assert_eq!(market.base_to_spot_notional(0, price), I80F48::ZERO);
+    }
+
+    #[test]
+    fn test_liquidation_fee_bps_roundtrip() {
+        let mut market = PerpMarket::default_for_tests();
+        for bps in [0, 1, 2, 5, 25, 100, 125, 250, 333, 1000, 9999, 10000] {
+            market.base_liquidation_fee = PerpMarket::liquidation_fee_from_bps(bps);
+            assert_eq!(market.liquidation_fee_bps(), Some(bps));
         }
+
+        // fractions set as f32 round to the nearest bps
+        market.base_liquidation_fee = I80F48::from_num(0.012f32);
+        assert_eq!(market.liquidation_fee_bps(), Some(120));
+
+        market.base_liquidation_fee = I80F48::from_num(-0.01);
+        assert_eq!(market.liquidation_fee_bps(), None);
+    }
+
+    #[test]
+    fn test_update_ema_price() {
+        let mut market = PerpMarket::default_for_tests();
+        market.ema_interval_seconds = 100;
+        assert_eq!(market.maint_trigger_price(0), None);
+
+        // the first update jumps to the price
+        market.update_ema_price(1000, 10.0);
+        assert_eq!(market.ema_price, 10.0);
+
+        // a short spike only moves the ema a little
+        market.update_ema_price(1010, 20.0);
+        let weight = 1.0 - (-0.1f64).exp();
+        assert!((market.ema_price - (10.0 + 10.0 * weight)).abs() < 1e-9);
+        assert!(market.ema_price < 11.0);
+
+        // the decay doesn't depend on how often the ema is updated
+        let mut one_step = market;
+        one_step.update_ema_price(1110, 8.0);
+        let mut two_steps = market;
+        two_steps.update_ema_price(1035, 8.0);
+        two_steps.update_ema_price(1110, 8.0);
+        assert!((one_step.ema_price - two_steps.ema_price).abs() < 1e-9);
+        let expected = 8.0 + (market.ema_price - 8.0) * (-1.0f64).exp();
+        assert!((one_step.ema_price - expected).abs() < 1e-9);
+
+        assert_eq!(market.maint_trigger_price(1010), None);
+        market.use_ema_for_trigger = 1;
+        let ema = Some(I80F48::from_num(market.ema_price));
+        assert_eq!(market.maint_trigger_price(1010), ema);
+        assert_eq!(market.maint_trigger_price(1110), ema);
+
+        // a stale ema falls back to the oracle price
+        assert_eq!(market.maint_trigger_price(1111), None);
+    }
+
+    #[test]
+    fn test_compute_settle_protocol_fee() {
+        let mut market = PerpMarket::default_for_tests();
+        let settlement = I80F48::from(1000);
+        assert_eq!(
+            market.compute_settle_protocol_fee(settlement, settlement),
+            0
+        );
+
+        market.settle_fee_bps = 25;
+        assert_eq!(
+            market.compute_settle_protocol_fee(settlement, settlement),
+            I80F48::from_num(2.5)
+        );
+        // capped by what is left after the settler fee
+        assert_eq!(
+            market.compute_settle_protocol_fee(settlement, I80F48::from(1)),
+            1
+        );
+        assert_eq!(
+            market.compute_settle_protocol_fee(settlement, I80F48::ZERO),
+            0
+        );
+    }
+
+    #[test]
+    fn test_record_liquidation_base() {
+        let throttled = MangoError::LiquidationThrottled.error_code();
+        let mut market = PerpMarket::default_for_tests();
+        let health = I80F48::from(-10);
+
+        // disabled
+        market.record_liquidation_base(1000, 1, health).unwrap();
+
+        market.max_liquidation_base_per_slot = 100;
+        market.record_liquidation_base(60, 2, health).unwrap();
+        market.record_liquidation_base(-40, 2, health).unwrap();
+        assert_eq!(market.liquidation_base_in_slot, 100);
+
+        // the cap is reached for this slot
+        assert!(market
+            .record_liquidation_base(1, 2, health)
+            .is_anchor_error_with_code(throttled));
+        assert_eq!(market.liquidation_base_in_slot, 100);
+
+        // the next slot starts fresh
+        market.record_liquidation_base(70, 3, health).unwrap();
+        assert_eq!(market.liquidation_base_in_slot, 70);
+        assert!(market
+            .record_liquidation_base(31, 3, health)
+            .is_anchor_error_with_code(throttled));
+
+        // deeply underwater liqees bypass the cap
+        market.liquidation_throttle_bypass_health = 1000.0;
+        assert!(market
+            .record_liquidation_base(31, 3, I80F48::from(-1000))
+            .is_anchor_error_with_code(throttled));
+        market
+            .record_liquidation_base(31, 3, I80F48::from(-1001))
+            .unwrap();
+        assert_eq!(market.liquidation_base_in_slot, 101);
+        assert!(market
+            .record_liquidation_base(1, 3, health)
+            .is_anchor_error_with_code(throttled));
+    }
+
+    #[test]
+    fn test_liquidation_dust_lots() {
+        let mut market = PerpMarket::default_for_tests();
+        market.base_liquidation_fee = I80F48::from_num(0.03);
+        market.dust_liquidation_fee = I80F48::from_num(0.13);
+
+        // disabled
+        assert_eq!(market.liquidation_dust_lots(30, 30), 0);
+        assert_eq!(
+            market.dust_liquidation_fee_boost(market.base_liquidation_fee),
+            0
+        );
+
+        market.dust_threshold_lots = 10;
+        assert_eq!(
+            market.dust_liquidation_fee_boost(market.base_liquidation_fee),
+            I80F48::from_num(0.13) - I80F48::from_num(0.03)
+        );
+        // above the dust region
+        assert_eq!(market.liquidation_dust_lots(30, 20), 0);
+        assert_eq!(market.liquidation_dust_lots(-30, 15), 0);
+        // crossing into it
+        assert_eq!(market.liquidation_dust_lots(30, 25), 5);
+        assert_eq!(market.liquidation_dust_lots(-30, 30), 10);
+        // within it
+        assert_eq!(market.liquidation_dust_lots(8, 3), 3);
+        assert_eq!(market.liquidation_dust_lots(-8, 8), 8);
+
+        // a dust fee below the base fee is not used
+        market.dust_liquidation_fee = I80F48::from_num(0.01);
+        assert_eq!(
+            market.dust_liquidation_fee_boost(market.base_liquidation_fee),
+            0
+        );
+    }
+
+    #[test]
+    fn test_check_liquidation_base_lots() {
+        let mut market = PerpMarket::default_for_tests();
+
+        // disabled
+        assert!(market.check_liquidation_base_lots(30, 1).is_ok());
+
+        market.min_liquidation_base_lots = 10;
+        assert!(market.check_liquidation_base_lots(30, 10).is_ok());
+        assert!(market.check_liquidation_base_lots(-30, 12).is_ok());
+        // nothing transferred, only pnl settlement
+        assert!(market.check_liquidation_base_lots(30, 0).is_ok());
+        // closing the position is always allowed
+        assert!(market.check_liquidation_base_lots(3, 3).is_ok());
+        assert!(market.check_liquidation_base_lots(-3, 3).is_ok());
+
+        let too_small = MangoError::LiquidationTooSmall.error_code();
+        assert!(market
+            .check_liquidation_base_lots(30, 9)
+            .is_anchor_error_with_code(too_small));
+        assert!(market
+            .check_liquidation_base_lots(-3, 2)
+            .is_anchor_error_with_code(too_small));
+    }
+
+    #[test]
+    fn test_check_liqor_large_position() {
+        let mut market = PerpMarket::default_for_tests();
+        let whitelisted = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        // disabled by default
+        assert!(market.check_liqor_large_position(&other, 0, 1000).is_ok());
+
+        market.large_position_threshold_lots = 100;
+        market.large_position_whitelist[0] = whitelisted;
+
+        // below the threshold anyone can liquidate
+        assert!(market.check_liqor_large_position(&other, 0, 100).is_ok());
+        assert!(market.check_liqor_large_position(&other, 0, -100).is_ok());
+
+        // above it only whitelisted liqors
+        assert!(market
+            .check_liqor_large_position(&other, 50, 101)
+            .is_anchor_error_with_code(
+                MangoError::LiqorNotWhitelistedForLargePosition.error_code()
+            ));
+        assert!(market
+            .check_liqor_large_position(&other, -50, -101)
+            .is_anchor_error_with_code(
+                MangoError::LiqorNotWhitelistedForLargePosition.error_code()
+            ));
+        assert!(market
+            .check_liqor_large_position(&whitelisted, 50, 101)
+            .is_ok());
+        assert!(market
+            .check_liqor_large_position(&whitelisted, 0, -1000)
+            .is_ok());
+
+        // reducing a large position is always fine
+        assert!(market.check_liqor_large_position(&other, 200, 150).is_ok());
+    }
+
+    #[test]
+    fn test_open_interest_value() {
+        let mut market = PerpMarket::default_for_tests();
+        market.base_lot_size = 10;
+        market.open_interest = 200;
+        assert_eq!(
+            market.open_interest_value(I80F48::from(2)),
+            I80F48::from(2000)
+        );
+
+        market.open_interest = 0;
+        assert_eq!(market.open_interest_value(I80F48::from(2)), I80F48::ZERO);
     }
 }
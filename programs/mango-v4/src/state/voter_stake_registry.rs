@@ -0,0 +1,188 @@
+//! Reads Voter accounts of the governance voter stake registry program
+//!
+//! The layout constants mirror voter-stake-registry's zero-copy `Voter` and `DepositEntry`
+//! structs; the program isn't a dependency.
+
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+
+declare_id!("4Q6WW2ouZ6V3iaNm56MTd5n2tnTm4C5fiH8miFHnAFHo");
+
+/// Anchor discriminator of Voter accounts, sha256("account:Voter")[..8]
+pub const VOTER_DISCRIMINATOR: [u8; 8] = [241, 93, 35, 191, 254, 147, 17, 202];
+pub const VOTER_AUTHORITY_OFFSET: usize = 8;
+pub const VOTER_REGISTRAR_OFFSET: usize = VOTER_AUTHORITY_OFFSET + 32;
+pub const VOTER_DEPOSITS_OFFSET: usize = VOTER_REGISTRAR_OFFSET + 32;
+pub const VOTER_DEPOSIT_COUNT: usize = 32;
+/// Discriminator, authority, registrar, deposits, two bumps and 94 reserved bytes
+pub const VOTER_SIZE: usize = VOTER_DEPOSITS_OFFSET + VOTER_DEPOSIT_COUNT * DEPOSIT_SIZE + 2 + 94;
+
+/// DepositEntry: a 32 byte Lockup, then amounts and flags
+pub const DEPOSIT_SIZE: usize = 80;
+pub const DEPOSIT_LOCKUP_END_TS_OFFSET: usize = 8;
+pub const DEPOSIT_LOCKUP_KIND_OFFSET: usize = 16;
+pub const DEPOSIT_AMOUNT_OFFSET: usize = 32;
+pub const DEPOSIT_IS_USED_OFFSET: usize = 48;
+pub const DEPOSIT_VOTING_MINT_CONFIG_IDX_OFFSET: usize = 50;
+
+pub const LOCKUP_KIND_CLIFF: u8 = 3;
+pub const LOCKUP_KIND_CONSTANT: u8 = 4;
+
+/// Amount of governance tokens in a voter stake registry Voter account that can't be
+/// withdrawn before `now_ts`
+///
+/// Only cliff lockups that haven't ended and constant lockups count, so tokens that are
+/// deposited and withdrawn within a transaction never do. Deposits of vesting lockups are
+/// ignored, since parts of them may be withdrawable already. Only the registrar's first
+/// voting mint, the governance token, is considered.
+pub fn voter_locked_amount(
+    data: &[u8],
+    registrar: &Pubkey,
+    voter_authority: &Pubkey,
+    now_ts: u64,
+) -> Result<u64> {
+    require_msg!(
+        data.len() >= VOTER_SIZE && data[..8] == VOTER_DISCRIMINATOR,
+        "not a voter stake registry Voter account"
+    );
+    let pubkey_at = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).unwrap();
+    require_keys_eq!(pubkey_at(VOTER_AUTHORITY_OFFSET), *voter_authority);
+    require_keys_eq!(pubkey_at(VOTER_REGISTRAR_OFFSET), *registrar);
+
+    let now_ts: i64 = now_ts.try_into().unwrap();
+    let mut locked: u64 = 0;
+    for deposit in data[VOTER_DEPOSITS_OFFSET..]
+        .chunks_exact(DEPOSIT_SIZE)
+        .take(VOTER_DEPOSIT_COUNT)
+    {
+        let end_ts = i64::from_le_bytes(
+            deposit[DEPOSIT_LOCKUP_END_TS_OFFSET..DEPOSIT_LOCKUP_END_TS_OFFSET + 8]
+                .try_into()
+                .unwrap(),
+        );
+        let kind = deposit[DEPOSIT_LOCKUP_KIND_OFFSET];
+        let amount = u64::from_le_bytes(
+            deposit[DEPOSIT_AMOUNT_OFFSET..DEPOSIT_AMOUNT_OFFSET + 8]
+                .try_into()
+                .unwrap(),
+        );
+        let is_used = deposit[DEPOSIT_IS_USED_OFFSET] != 0;
+        let voting_mint_config_idx = deposit[DEPOSIT_VOTING_MINT_CONFIG_IDX_OFFSET];
+        let is_locked =
+            kind == LOCKUP_KIND_CONSTANT || (kind == LOCKUP_KIND_CLIFF && end_ts > now_ts);
+        if is_used && voting_mint_config_idx == 0 && is_locked {
+            locked = locked.saturating_add(amount);
+        }
+    }
+    Ok(locked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::{Pod, Zeroable};
+
+    // Copies of voter-stake-registry's zero-copy account structs, to serialize fixtures
+
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    #[repr(C)]
+    struct Lockup {
+        start_ts: i64,
+        end_ts: i64,
+        kind: u8,
+        reserved: [u8; 15],
+    }
+
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    #[repr(C)]
+    struct DepositEntry {
+        lockup: Lockup,
+        amount_deposited_native: u64,
+        amount_initially_locked_native: u64,
+        is_used: u8,
+        allow_clawback: u8,
+        voting_mint_config_idx: u8,
+        reserved: [u8; 29],
+    }
+
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    #[repr(C)]
+    struct Voter {
+        voter_authority: Pubkey,
+        registrar: Pubkey,
+        deposits: [DepositEntry; 32],
+        voter_bump: u8,
+        voter_weight_record_bump: u8,
+        reserved: [u8; 94],
+    }
+
+    fn serialize(voter: &Voter) -> Vec<u8> {
+        let mut data =
+            anchor_lang::solana_program::hash::hash(b"account:Voter").to_bytes()[..8].to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(voter));
+        data
+    }
+
+    #[test]
+    fn test_voter_layout() {
+        assert_eq!(std::mem::size_of::<DepositEntry>(), DEPOSIT_SIZE);
+        assert_eq!(8 + std::mem::size_of::<Voter>(), VOTER_SIZE);
+        assert_eq!(
+            anchor_lang::solana_program::hash::hash(b"account:Voter").to_bytes()[..8],
+            VOTER_DISCRIMINATOR
+        );
+    }
+
+    #[test]
+    fn test_voter_locked_amount() {
+        let registrar = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let now_ts = 1_000_000;
+
+        let mut voter = Voter::zeroed();
+        voter.voter_authority = authority;
+        voter.registrar = registrar;
+        let deposit = |kind: u8, end_ts: i64, amount: u64, voting_mint_config_idx: u8| {
+            let mut d = DepositEntry::zeroed();
+            d.lockup.kind = kind;
+            d.lockup.start_ts = 1;
+            d.lockup.end_ts = end_ts;
+            d.amount_deposited_native = amount;
+            d.amount_initially_locked_native = amount;
+            d.is_used = 1;
+            d.voting_mint_config_idx = voting_mint_config_idx;
+            d
+        };
+        // counted: cliff lockup that hasn't ended, constant lockup
+        voter.deposits[0] = deposit(LOCKUP_KIND_CLIFF, now_ts as i64 + 1, 100, 0);
+        voter.deposits[3] = deposit(LOCKUP_KIND_CONSTANT, 0, 20, 0);
+        // ignored: ended cliff, unlocked, daily vesting, other voting mint, unused
+        voter.deposits[1] = deposit(LOCKUP_KIND_CLIFF, now_ts as i64, 1000, 0);
+        voter.deposits[2] = deposit(0, 0, 1000, 0);
+        voter.deposits[4] = deposit(1, now_ts as i64 + 1000, 1000, 0);
+        voter.deposits[5] = deposit(LOCKUP_KIND_CONSTANT, 0, 1000, 1);
+        voter.deposits[31] = DepositEntry {
+            is_used: 0,
+            ..deposit(LOCKUP_KIND_CONSTANT, 0, 1000, 0)
+        };
+        let data = serialize(&voter);
+
+        assert_eq!(
+            voter_locked_amount(&data, &registrar, &authority, now_ts).unwrap(),
+            120
+        );
+
+        // wrong registrar or authority
+        assert!(voter_locked_amount(&data, &Pubkey::new_unique(), &authority, now_ts).is_err());
+        assert!(voter_locked_amount(&data, &registrar, &Pubkey::new_unique(), now_ts).is_err());
+
+        // not a voter account
+        let mut other = data.clone();
+        other[0] ^= 1;
+        assert!(voter_locked_amount(&other, &registrar, &authority, now_ts).is_err());
+        assert!(
+            voter_locked_amount(&data[..VOTER_SIZE - 1], &registrar, &authority, now_ts).is_err()
+        );
+    }
+}
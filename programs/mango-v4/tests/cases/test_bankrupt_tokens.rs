@@ -187,6 +187,23 @@ async fn test_bankrupt_tokens_socialize_loss() -> Result<(), TransportError> {
     let liqee = get_mango_account(solana, account).await;
     assert!(liqee.being_liquidated());
 
+    // ownership can't be transferred away while being liquidated
+    let res = send_tx(
+        solana,
+        AccountSetOwnerInstruction {
+            account,
+            owner,
+            new_owner: payer.pubkey(),
+            clear_delegates: false,
+        },
+    )
+    .await;
+    assert_mango_error(
+        &res,
+        MangoError::BeingLiquidated.into(),
+        "can't set owner while being liquidated".to_string(),
+    );
+
     // eat collateral2, leaving the account bankrupt
     send_tx(
         solana,
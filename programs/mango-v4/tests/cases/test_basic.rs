@@ -281,6 +281,8 @@ async fn test_basic() -> Result<(), TransportError> {
     )
     .await
     .unwrap();
+    let group_data: Group = solana.get_account(group).await;
+    assert_eq!(group_data.token_count, 0);
 
     // close stub oracle
     send_tx(
@@ -517,6 +519,235 @@ async fn test_bank_maint_weight_shift() -> Result<(), TransportError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_bank_init_asset_weight_floor() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..1];
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..mango_setup::GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    send_tx(
+        solana,
+        GroupEdit {
+            group,
+            admin,
+            options: mango_v4::instruction::GroupEdit {
+                init_asset_weight_floor_opt: Some(0.1),
+                ..group_edit_instruction_default()
+            },
+        },
+    )
+    .await
+    .unwrap();
+
+    let token_edit = |options: mango_v4::instruction::TokenEdit| TokenEdit {
+        group,
+        admin,
+        mint: mints[0].pubkey,
+        fallback_oracle: Pubkey::default(),
+        options,
+    };
+
+    //
+    // TEST: A zero init asset weight is rejected
+    //
+    let res = send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            init_asset_weight_opt: Some(0.0),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await;
+    assert_mango_error(
+        &res,
+        MangoError::InitAssetWeightBelowFloor.into(),
+        "zero init asset weight".to_string(),
+    );
+
+    //
+    // TEST: A maint asset weight below the init asset weight is rejected
+    //
+    let res = send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            maint_asset_weight_opt: Some(0.5),
+            init_asset_weight_opt: Some(0.7),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await;
+    assert!(res.is_err());
+
+    //
+    // TEST: Weights above the floor are fine
+    //
+    send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            init_asset_weight_opt: Some(0.2),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: Reduce-only tokens may go to zero
+    //
+    send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            reduce_only_opt: Some(1),
+            init_asset_weight_opt: Some(0.0),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await
+    .unwrap();
+
+    let bank: Bank = solana.get_account(tokens[0].bank).await;
+    assert_eq!(bank.init_asset_weight, I80F48::ZERO);
+
+    //
+    // TEST: Edits that don't touch the init asset weight work on tokens below the floor
+    //
+    send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            reduce_only_opt: Some(0),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            deposit_limit_opt: Some(1_000_000),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await
+    .unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bank_weight_change_interval() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..1];
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..mango_setup::GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    send_tx(
+        solana,
+        GroupEdit {
+            group,
+            admin,
+            options: mango_v4::instruction::GroupEdit {
+                min_weight_change_interval_slots_opt: Some(100),
+                ..group_edit_instruction_default()
+            },
+        },
+    )
+    .await
+    .unwrap();
+
+    let token_edit = |options: mango_v4::instruction::TokenEdit| TokenEdit {
+        group,
+        admin,
+        mint: mints[0].pubkey,
+        fallback_oracle: Pubkey::default(),
+        options,
+    };
+
+    //
+    // TEST: The first weight change is fine
+    //
+    send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            init_asset_weight_opt: Some(0.5),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await
+    .unwrap();
+    let bank: Bank = solana.get_account(tokens[0].bank).await;
+    assert!(bank.last_weight_change_slot > 0);
+
+    //
+    // TEST: Changing weights again within the interval is rejected
+    //
+    let res = send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            init_asset_weight_opt: Some(0.6),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await;
+    assert_mango_error(
+        &res,
+        MangoError::WeightChangeTooSoon.into(),
+        "weight change within interval".to_string(),
+    );
+
+    // other edits are unaffected
+    send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            deposit_limit_opt: Some(1000),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: Changing weights after the interval is fine
+    //
+    solana.advance_by_slots(100).await;
+    send_tx(
+        solana,
+        token_edit(mango_v4::instruction::TokenEdit {
+            init_asset_weight_opt: Some(0.6),
+            ..token_edit_instruction_default()
+        }),
+    )
+    .await
+    .unwrap();
+
+    let bank: Bank = solana.get_account(tokens[0].bank).await;
+    assert_eq!(bank.init_asset_weight, I80F48::from_num(0.6));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_bank_deposit_limit() -> Result<(), TransportError> {
     let context = TestContext::new().await;
@@ -688,6 +919,299 @@ async fn test_bank_deposit_limit() -> Result<(), TransportError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_account_token_allowlist() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..mango_setup::GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    // provide liquidity for borrows
+    create_funded_account(&solana, group, owner, 0, &context.users[1], mints, 1000, 0).await;
+
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+
+    send_tx(
+        solana,
+        AccountSetTokenAllowlistInstruction {
+            account,
+            admin,
+            token_indexes: vec![tokens[0].index],
+        },
+    )
+    .await
+    .unwrap();
+    let mango_account = get_mango_account(solana, account).await;
+    assert_eq!(mango_account.fixed.token_allowlist(), &[tokens[0].index]);
+
+    let deposit_ix = |mint_index: usize| TokenDepositInstruction {
+        amount: 100,
+        reduce_only: false,
+        account,
+        owner,
+        token_account: context.users[1].token_accounts[mint_index],
+        token_authority: payer,
+        bank_index: 0,
+    };
+    let borrow_ix = |mint_index: usize| TokenWithdrawInstruction {
+        amount: 10,
+        allow_borrow: true,
+        account,
+        owner,
+        token_account: context.users[1].token_accounts[mint_index],
+        bank_index: 0,
+    };
+
+    //
+    // TEST: Only the allowed token can be deposited and borrowed
+    //
+    send_tx(solana, deposit_ix(0)).await.unwrap();
+    send_tx_expect_error!(solana, deposit_ix(1), MangoError::TokenNotAllowedForAccount);
+    send_tx_expect_error!(solana, borrow_ix(1), MangoError::TokenNotAllowedForAccount);
+
+    //
+    // TEST: Other ways of opening a token position are restricted too, like absorbing dust
+    //
+    send_tx(
+        solana,
+        GroupEdit {
+            group,
+            admin,
+            options: mango_v4::instruction::GroupEdit {
+                max_dust_absorb_quote_opt: Some(10),
+                ..group_edit_instruction_default()
+            },
+        },
+    )
+    .await
+    .unwrap();
+    let dust_account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        2,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+    send_tx(
+        solana,
+        TokenDepositInstruction {
+            amount: 5,
+            account: dust_account,
+            ..deposit_ix(1)
+        },
+    )
+    .await
+    .unwrap();
+    send_tx_expect_error!(
+        solana,
+        TokenLiqAbsorbDustInstruction {
+            liqee: dust_account,
+            liqor: account,
+            liqor_owner: owner,
+            dust_token_index: tokens[1].index,
+            dust_bank_index: 0,
+            quote_token_index: tokens[0].index,
+            quote_bank_index: 0,
+        },
+        MangoError::TokenNotAllowedForAccount
+    );
+
+    //
+    // TEST: Only the group admin can change the allowlist
+    //
+    assert!(send_tx(
+        solana,
+        AccountSetTokenAllowlistInstruction {
+            account,
+            admin: owner,
+            token_indexes: vec![],
+        },
+    )
+    .await
+    .is_err());
+
+    //
+    // TEST: An empty allowlist allows all tokens
+    //
+    send_tx(
+        solana,
+        AccountSetTokenAllowlistInstruction {
+            account,
+            admin,
+            token_indexes: vec![],
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(solana, borrow_ix(1)).await.unwrap();
+
+    //
+    // TEST: Existing borrows can be repaid even if the token isn't allowed
+    //
+    send_tx(
+        solana,
+        AccountSetTokenAllowlistInstruction {
+            account,
+            admin,
+            token_indexes: vec![tokens[0].index],
+        },
+    )
+    .await
+    .unwrap();
+    send_tx_expect_error!(solana, deposit_ix(1), MangoError::TokenNotAllowedForAccount);
+    assert!(account_position(solana, account, tokens[1].bank).await < 0);
+    send_tx(
+        solana,
+        TokenDepositInstruction {
+            reduce_only: true,
+            ..deposit_ix(1)
+        },
+    )
+    .await
+    .unwrap();
+    assert!(account_position(solana, account, tokens[1].bank).await >= 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_group_admin_transfer() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let new_admin = TestKeypair::new();
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..1];
+
+    let mango_setup::GroupWithTokens { group, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..mango_setup::GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let set_admin = |signer: TestKeypair, admin: Pubkey| GroupSetPendingAdminInstruction {
+        group,
+        admin: signer,
+        pending_admin: admin,
+    };
+
+    //
+    // TEST: group_edit can't change the admin
+    //
+    send_tx(
+        solana,
+        GroupEdit {
+            group,
+            admin,
+            options: mango_v4::instruction::GroupEdit {
+                admin_opt: Some(new_admin.pubkey()),
+                ..group_edit_instruction_default()
+            },
+        },
+    )
+    .await
+    .unwrap_err();
+
+    //
+    // TEST: Setting a new admin only proposes it, and it can be withdrawn
+    //
+    send_tx(solana, set_admin(admin, payer.pubkey()))
+        .await
+        .unwrap();
+    let group_data: Group = solana.get_account(group).await;
+    assert_eq!(group_data.pending_admin, payer.pubkey());
+
+    send_tx(solana, set_admin(admin, Pubkey::default()))
+        .await
+        .unwrap();
+    let group_data: Group = solana.get_account(group).await;
+    assert_eq!(group_data.pending_admin, Pubkey::default());
+
+    send_tx(solana, set_admin(admin, new_admin.pubkey()))
+        .await
+        .unwrap();
+    let group_data: Group = solana.get_account(group).await;
+    assert_eq!(group_data.admin, admin.pubkey());
+    assert_eq!(group_data.pending_admin, new_admin.pubkey());
+
+    //
+    // TEST: Only the pending admin can accept
+    //
+    send_tx(
+        solana,
+        GroupAcceptAdminInstruction {
+            group,
+            pending_admin: payer,
+        },
+    )
+    .await
+    .unwrap_err();
+
+    send_tx(
+        solana,
+        GroupAcceptAdminInstruction {
+            group,
+            pending_admin: new_admin,
+        },
+    )
+    .await
+    .unwrap();
+    let group_data: Group = solana.get_account(group).await;
+    assert_eq!(group_data.admin, new_admin.pubkey());
+    assert_eq!(group_data.pending_admin, Pubkey::default());
+
+    //
+    // TEST: The old admin lost its rights, accepting again fails
+    //
+    send_tx(solana, set_admin(admin, admin.pubkey()))
+        .await
+        .unwrap_err();
+    send_tx(solana, set_admin(new_admin, admin.pubkey()))
+        .await
+        .unwrap();
+    send_tx(
+        solana,
+        GroupAcceptAdminInstruction {
+            group,
+            pending_admin: new_admin,
+        },
+    )
+    .await
+    .unwrap_err();
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_withdraw_skip_bank() -> Result<(), TransportError> {
     let context = TestContext::new().await;
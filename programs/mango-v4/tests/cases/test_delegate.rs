@@ -46,6 +46,28 @@ async fn test_delegate() -> Result<(), TransportError> {
         .unwrap();
     }
 
+    //
+    // TEST: Setting the owner or the account itself as delegate fails
+    //
+    for self_delegate in [owner.pubkey(), account] {
+        let res = send_tx(
+            solana,
+            AccountEditInstruction {
+                delegate: self_delegate,
+                account_num: 0,
+                group,
+                owner,
+                name: "new_name".to_owned(),
+            },
+        )
+        .await;
+        assert_mango_error(
+            &res,
+            MangoError::InvalidDelegate.into(),
+            "self delegate".to_string(),
+        );
+    }
+
     //
     // TEST: Edit account as delegate - should fail
     //
@@ -135,5 +157,162 @@ async fn test_delegate() -> Result<(), TransportError> {
         assert!(res.is_err());
     }
 
+    //
+    // TEST: Transfer ownership and clear the delegate
+    //
+    let new_owner = TestKeypair::new();
+    {
+        send_tx(
+            solana,
+            AccountSetOwnerInstruction {
+                account,
+                owner,
+                new_owner: new_owner.pubkey(),
+                clear_delegates: true,
+            },
+        )
+        .await
+        .unwrap();
+
+        let mango_account = get_mango_account(solana, account).await;
+        assert_eq!(mango_account.fixed.owner, new_owner.pubkey());
+        assert_eq!(mango_account.fixed.delegate, Pubkey::default());
+    }
+
+    //
+    // TEST: The previous owner can no longer act on the account
+    //
+    {
+        let res = send_tx(
+            solana,
+            AccountSetOwnerInstruction {
+                account,
+                owner,
+                new_owner: owner.pubkey(),
+                clear_delegates: false,
+            },
+        )
+        .await;
+        assert!(res.is_err());
+    }
+
+    //
+    // TEST: The new owner can close the account
+    //
+    {
+        send_tx(
+            solana,
+            AccountCloseInstruction {
+                group,
+                account,
+                owner: new_owner,
+                sol_destination: payer.pubkey(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delegate_permissions() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let delegate = context.users[1].key;
+    let mints = &context.mints[0..1];
+
+    //
+    // SETUP: Create a group and an account with a delegate
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let account =
+        create_funded_account(&solana, group, owner, 0, &context.users[1], mints, 100, 0).await;
+
+    send_tx(
+        solana,
+        AccountEditInstruction {
+            delegate: delegate.pubkey(),
+            account_num: 0,
+            group,
+            owner,
+            name: "new_name".to_owned(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let set_permissions = |owner, delegate_permissions| AccountSetDelegatePermissionsInstruction {
+        account,
+        owner,
+        delegate_permissions,
+        temporary_delegate_permissions: 0,
+    };
+    let delegate_withdraw = || TokenWithdrawInstruction {
+        amount: u64::MAX,
+        allow_borrow: false,
+        account,
+        owner: delegate,
+        token_account: context.users[0].token_accounts[0],
+        bank_index: 0,
+    };
+
+    //
+    // TEST: Only the owner can set permissions, and only known bits
+    //
+    assert!(send_tx(
+        solana,
+        set_permissions(delegate, DelegatePermission::Withdraw.bit())
+    )
+    .await
+    .is_err());
+    assert!(send_tx(solana, set_permissions(owner, 1 << 3))
+        .await
+        .is_err());
+
+    //
+    // TEST: A liquidate-only delegate can't withdraw
+    //
+    send_tx(
+        solana,
+        set_permissions(owner, DelegatePermission::Liquidate.bit()),
+    )
+    .await
+    .unwrap();
+    send_tx_expect_error!(
+        solana,
+        delegate_withdraw(),
+        MangoError::DelegatePermissionMissing
+    );
+
+    //
+    // TEST: With the withdraw permission it can, as can unrestricted delegates
+    //
+    send_tx(
+        solana,
+        set_permissions(
+            owner,
+            DelegatePermission::Liquidate.bit() | DelegatePermission::Withdraw.bit(),
+        ),
+    )
+    .await
+    .unwrap();
+    send_tx(solana, delegate_withdraw()).await.unwrap();
+    assert!(account_position_closed(solana, account, tokens[0].bank).await);
+
     Ok(())
 }
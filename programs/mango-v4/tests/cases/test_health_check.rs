@@ -1,6 +1,9 @@
 use crate::cases::{
-    create_funded_account, mango_setup, send_tx, tokio, HealthAccountSkipping,
-    HealthCheckInstruction, TestContext, TestKeypair, TokenWithdrawInstruction,
+    account_init_health, account_maint_health, create_funded_account, get_mango_account,
+    mango_setup, send_tx, send_tx_get_metadata, tokio, CacheAccountHealthInstruction,
+    ClientTransaction, ComputeAccountHealthInstruction, HealthAccountSkipping,
+    HealthCheckInstruction, HealthRegionBeginInstruction, HealthRegionEndInstruction, TestContext,
+    TestKeypair, TokenWithdrawInstruction, I80F48,
 };
 use crate::send_tx_expect_error;
 use mango_v4::accounts_ix::{HealthCheck, HealthCheckKind};
@@ -174,3 +177,314 @@ async fn test_health_check() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_compute_account_health() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let payer_token_accounts = &context.users[1].token_accounts;
+    let mints = &context.mints[0..3];
+
+    let mango_setup::GroupWithTokens { group, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..mango_setup::GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    // Funding to fill the vaults
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints,
+        1_000_000,
+        0,
+    )
+    .await;
+
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..2],
+        1000,
+        0,
+    )
+    .await;
+
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 775,
+            allow_borrow: true,
+            account,
+            owner,
+            token_account: payer_token_accounts[2],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let account_data_before = solana.get_account_data(account).await.unwrap();
+
+    let result = send_tx_get_metadata(solana, ComputeAccountHealthInstruction { account })
+        .await
+        .unwrap();
+    result.result.unwrap();
+    let return_data = result.metadata.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, mango_v4::id());
+    assert_eq!(return_data.data.len(), 32);
+    let health_at = |offset: usize| {
+        I80F48::from_bits(i128::from_le_bytes(
+            return_data.data[offset..offset + 16].try_into().unwrap(),
+        ))
+        .to_num::<f64>()
+    };
+
+    //
+    // TEST: The returned values match the health the program computes elsewhere
+    //
+    assert_eq!(health_at(0), account_init_health(solana, account).await);
+    assert_eq!(health_at(16), account_maint_health(solana, account).await);
+    assert!(health_at(0) < health_at(16));
+
+    //
+    // TEST: The account isn't modified
+    //
+    assert_eq!(
+        solana.get_account_data(account).await.unwrap(),
+        account_data_before
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cache_account_health() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let payer_token_accounts = &context.users[1].token_accounts;
+    let mints = &context.mints[0..3];
+
+    let mango_setup::GroupWithTokens { group, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..mango_setup::GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    // Funding to fill the vaults
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints,
+        1_000_000,
+        0,
+    )
+    .await;
+
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..2],
+        1000,
+        0,
+    )
+    .await;
+
+    let withdraw = |amount: u64| TokenWithdrawInstruction {
+        amount,
+        allow_borrow: true,
+        account,
+        owner,
+        token_account: payer_token_accounts[2],
+        bank_index: 0,
+    };
+    send_tx(solana, withdraw(500)).await.unwrap();
+
+    //
+    // TEST: The keeper call stores the health, rounded down
+    //
+    let init_health = account_init_health(solana, account).await;
+    let maint_health = account_maint_health(solana, account).await;
+    send_tx(solana, CacheAccountHealthInstruction { account })
+        .await
+        .unwrap();
+    let fixed = get_mango_account(solana, account).await.fixed;
+    assert!(fixed.cached_health_slot > 0);
+    assert_eq!(
+        fixed.cached_health(fixed.cached_health_slot),
+        Some((init_health.floor() as i64, maint_health.floor() as i64))
+    );
+    assert_eq!(fixed.cached_health(fixed.cached_health_slot + 1), None);
+
+    //
+    // TEST: Changing a position invalidates it
+    //
+    send_tx(solana, withdraw(200)).await.unwrap();
+    let fixed = get_mango_account(solana, account).await.fixed;
+    assert_eq!(fixed.cached_health_slot, 0);
+    assert_eq!(fixed.cached_health(0), None);
+
+    //
+    // TEST: The next keeper call refreshes it
+    //
+    let init_health_after = account_init_health(solana, account).await;
+    assert!(init_health_after < init_health);
+    send_tx(solana, CacheAccountHealthInstruction { account })
+        .await
+        .unwrap();
+    let fixed = get_mango_account(solana, account).await.fixed;
+    assert_eq!(
+        fixed.cached_health(fixed.cached_health_slot).unwrap().0,
+        init_health_after.floor() as i64
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_health_check_reuses_cached_health() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let payer_token_accounts = &context.users[1].token_accounts;
+    let mints = &context.mints[0..3];
+
+    let mango_setup::GroupWithTokens { group, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..mango_setup::GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    // Funding to fill the vaults
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints,
+        1_000_000,
+        0,
+    )
+    .await;
+
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..2],
+        1000,
+        0,
+    )
+    .await;
+
+    let withdraw = |amount: u64| TokenWithdrawInstruction {
+        amount,
+        allow_borrow: true,
+        account,
+        owner,
+        token_account: payer_token_accounts[2],
+        bank_index: 0,
+    };
+    send_tx(solana, withdraw(500)).await.unwrap();
+
+    let init_health = account_init_health(solana, account).await;
+    let check = |min_health_value: f64, check_kind| HealthCheckInstruction {
+        account,
+        owner,
+        min_health_value,
+        check_kind,
+    };
+    let health_region_tx = || async move {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction(HealthRegionEndInstruction {
+            account,
+            affected_bank: None,
+        })
+        .await;
+        tx
+    };
+
+    //
+    // TEST: After a health region, the Init check reuses the health stored by health_region_end,
+    // while the LiquidationEnd check computes health again
+    //
+    let mut tx = health_region_tx().await;
+    tx.add_instruction(check(init_health.floor(), HealthCheckKind::Init))
+        .await;
+    let result = tx.send_get_metadata().await.unwrap();
+    assert!(result.result.is_ok());
+    let cached_cu = result.metadata.unwrap().compute_units_consumed;
+
+    let mut tx = health_region_tx().await;
+    tx.add_instruction(check(init_health.floor(), HealthCheckKind::LiquidationEnd))
+        .await;
+    let result = tx.send_get_metadata().await.unwrap();
+    assert!(result.result.is_ok());
+    let uncached_cu = result.metadata.unwrap().compute_units_consumed;
+
+    println!("health_check CU: cached {cached_cu}, computed {uncached_cu}");
+    assert!(cached_cu < uncached_cu);
+
+    //
+    // TEST: The cached health is still checked against the minimum
+    //
+    let mut tx = health_region_tx().await;
+    tx.add_instruction(check(init_health.floor() + 1.0, HealthCheckKind::Init))
+        .await;
+    tx.send_expect_error(MangoError::InvalidHealth)
+        .await
+        .unwrap();
+
+    //
+    // TEST: A later change to the account invalidates the cached health
+    //
+    let mut tx = health_region_tx().await;
+    tx.add_instruction(withdraw(100)).await;
+    tx.add_instruction(check(init_health.floor(), HealthCheckKind::Init))
+        .await;
+    tx.send_expect_error(MangoError::InvalidHealth)
+        .await
+        .unwrap();
+
+    Ok(())
+}
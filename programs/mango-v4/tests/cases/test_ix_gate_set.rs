@@ -143,6 +143,22 @@ async fn test_ix_gate_set() -> Result<(), TransportError> {
     assert!(!group_data.is_ix_enabled(IxGate::TokenDeposit));
     assert!(!group_data.is_ix_enabled(IxGate::TokenWithdraw));
 
+    //
+    // TEST: group status reports the disabled instructions
+    //
+    send_tx(solana, GroupStatusInstruction { group })
+        .await
+        .unwrap();
+    let status = solana
+        .program_log_events::<mango_v4::events::GroupStatusData>()
+        .pop()
+        .unwrap();
+    assert_eq!(status.group, group);
+    assert_eq!(status.admin, admin.pubkey());
+    assert_eq!(status.security_admin, payer.pubkey());
+    assert_eq!(status.ix_gate, group_data.ix_gate);
+    assert_eq!(status.token_count, mints.len() as u16);
+
     //
     // TEST: admin can re-enable
     //
@@ -184,5 +200,12 @@ async fn test_ix_gate_set() -> Result<(), TransportError> {
     .await
     .unwrap();
 
+    //
+    // TEST: the read-only instructions are gated too
+    //
+    send_tx(solana, GroupStatusInstruction { group })
+        .await
+        .unwrap_err();
+
     Ok(())
 }
@@ -750,3 +750,500 @@ async fn test_liq_perps_bankruptcy_other_insurance_fund() -> Result<(), Transpor
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_liq_perps_bankruptcy_adl() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(200_000); // PerpLiqNegativePnlOrBankruptcy takes a lot of CU
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..3];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let GroupWithTokens {
+        group,
+        tokens,
+        insurance_vault,
+        ..
+    } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    send_tx(
+        solana,
+        TokenEditWeights {
+            group,
+            admin,
+            mint: mints[2].pubkey,
+            maint_liab_weight: 1.0,
+            maint_asset_weight: 1.0,
+            init_liab_weight: 1.0,
+            init_asset_weight: 1.0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let base_token = &tokens[1]; // used for perp market
+    let collateral_token = &tokens[2]; // used for adjusting account health
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        200,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            quote_lot_size: 1,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.8,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.2,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.05,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            // no settle limit: the liqee's negative pnl can't be settled
+            settle_pnl_limit_factor: 0.0,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpSetAdlEnabled {
+            group,
+            admin,
+            perp_market,
+            adl_enabled: true,
+        },
+    )
+    .await
+    .unwrap();
+    set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 1.0).await;
+    set_bank_stub_oracle_price(solana, group, &collateral_token, admin, 1.0).await;
+
+    //
+    // SETUP: accounts
+    //
+    let deposit_amount = 1000;
+    let counterparty = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[2..3],
+        deposit_amount,
+        0,
+    )
+    .await;
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[2..3],
+        deposit_amount,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Trade perps between accounts twice: the counterparty gains 50, the account loses 50
+    //
+    let mut tx = ClientTransaction::new(solana);
+    tx.add_instruction(PerpPlaceOrderInstruction {
+        account: counterparty,
+        perp_market,
+        owner,
+        side: Side::Bid,
+        price_lots: 100,
+        max_base_lots: 1,
+        ..PerpPlaceOrderInstruction::default()
+    })
+    .await;
+    tx.add_instruction(PerpPlaceOrderInstruction {
+        account,
+        perp_market,
+        owner,
+        side: Side::Ask,
+        price_lots: 100,
+        max_base_lots: 1,
+        ..PerpPlaceOrderInstruction::default()
+    })
+    .await;
+    tx.add_instruction(PerpConsumeEventsInstruction {
+        perp_market,
+        mango_accounts: vec![account, counterparty],
+    })
+    .await;
+    tx.send().await.unwrap();
+
+    set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 1.5).await;
+    let mut tx = ClientTransaction::new(solana);
+    tx.add_instruction(PerpPlaceOrderInstruction {
+        account: counterparty,
+        perp_market,
+        owner,
+        side: Side::Ask,
+        price_lots: 150,
+        max_base_lots: 1,
+        ..PerpPlaceOrderInstruction::default()
+    })
+    .await;
+    tx.add_instruction(PerpPlaceOrderInstruction {
+        account,
+        perp_market,
+        owner,
+        side: Side::Bid,
+        price_lots: 150,
+        max_base_lots: 1,
+        ..PerpPlaceOrderInstruction::default()
+    })
+    .await;
+    tx.add_instruction(PerpConsumeEventsInstruction {
+        perp_market,
+        mango_accounts: vec![account, counterparty],
+    })
+    .await;
+    tx.send().await.unwrap();
+
+    set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 1.0).await;
+
+    // account health = 1000 * 0.01 - 50 = -40
+    set_bank_stub_oracle_price(solana, group, &collateral_token, admin, 0.01).await;
+    assert_eq!(account_init_health(solana, account).await.round(), -40.0);
+
+    let fund_insurance = |amount: u64| async move {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction_direct(
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &payer_mint_accounts[0],
+                &insurance_vault,
+                &payer.pubkey(),
+                &[&payer.pubkey()],
+                amount,
+            )
+            .unwrap(),
+        );
+        tx.add_signer(payer);
+        tx.send().await.unwrap();
+    };
+    fund_insurance(5).await;
+
+    let adl_ix = PerpAdlInstruction {
+        admin,
+        liqee: account,
+        perp_market,
+        counterparties: vec![counterparty],
+    };
+
+    //
+    // TEST: The insurance fund must be used first
+    //
+    assert!(send_tx(solana, adl_ix.clone()).await.is_err());
+
+    //
+    // TEST: Only the liqor's owner or delegate can liquidate with it
+    //
+    send_tx_expect_error!(
+        solana,
+        PerpLiqNegativePnlOrBankruptcyInstruction {
+            liqor,
+            liqor_owner: payer,
+            liqee: account,
+            perp_market,
+            max_liab_transfer: u64::MAX,
+        },
+        MangoError::UnauthorizedLiqor
+    );
+
+    //
+    // TEST: Bankruptcy uses the insurance fund, but doesn't socialize the remaining loss
+    //
+    send_tx(
+        solana,
+        PerpLiqNegativePnlOrBankruptcyInstruction {
+            liqor,
+            liqor_owner: owner,
+            liqee: account,
+            perp_market,
+            max_liab_transfer: u64::MAX,
+        },
+    )
+    .await
+    .unwrap();
+    let bankruptcy_log = solana
+        .program_log_events::<mango_v4::logs::PerpLiqBankruptcyLog>()
+        .pop()
+        .unwrap();
+    assert_eq!(
+        I80F48::from_bits(bankruptcy_log.insurance_transfer).to_num::<u64>(),
+        5
+    );
+    assert_eq!(I80F48::from_bits(bankruptcy_log.socialized_loss), 0);
+
+    let insurance_liab = 5.0 / 1.05;
+    let acc_data = solana.get_account::<MangoAccount>(account).await;
+    assert_eq_fixed_f64!(
+        acc_data.perps[0].quote_position_native(),
+        -50.0 + insurance_liab,
+        0.01
+    );
+    assert_eq!(acc_data.being_liquidated, 1);
+
+    //
+    // TEST: ADL takes the rest of the deficit from the counterparty
+    //
+    send_tx(solana, adl_ix.clone()).await.unwrap();
+    let adl_log = solana
+        .program_log_events::<mango_v4::logs::PerpAdlLog>()
+        .pop()
+        .unwrap();
+    let deficit = 40.0 - insurance_liab;
+    assert_eq_fixed_f64!(I80F48::from_bits(adl_log.deficit), deficit, 0.01);
+    assert_eq!(I80F48::from_bits(adl_log.remaining_deficit), 0);
+    assert_eq!(adl_log.num_counterparties, 1);
+    let counterparty_logs = solana.program_log_events::<mango_v4::logs::PerpAdlCounterpartyLog>();
+    assert_eq!(counterparty_logs.len(), 1);
+    assert_eq!(counterparty_logs[0].counterparty, counterparty);
+    assert_eq!(counterparty_logs[0].base_closed, 0);
+    assert_eq_fixed_f64!(
+        I80F48::from_bits(counterparty_logs[0].pnl_absorbed),
+        deficit,
+        0.01
+    );
+
+    let acc_data = solana.get_account::<MangoAccount>(account).await;
+    assert_eq_fixed_f64!(acc_data.perps[0].quote_position_native(), -10.0, 0.01);
+    let counterparty_data = solana.get_account::<MangoAccount>(counterparty).await;
+    assert_eq_fixed_f64!(
+        counterparty_data.perps[0].quote_position_native(),
+        50.0 - deficit,
+        0.01
+    );
+    assert!(account_maint_health(solana, counterparty).await >= 0.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_perps_bankruptcy_adl_base() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(200_000);
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+
+    //
+    // SETUP: Create a group, a perp market with adl and an empty insurance fund
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    send_tx(
+        solana,
+        TokenEditWeights {
+            group,
+            admin,
+            mint: mints[2].pubkey,
+            maint_liab_weight: 1.0,
+            maint_asset_weight: 1.0,
+            init_liab_weight: 1.0,
+            init_asset_weight: 1.0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let base_token = &tokens[1]; // used for perp market
+    let collateral_token = &tokens[2]; // used for adjusting account health
+
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            quote_lot_size: 1,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.8,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.2,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.05,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            settle_pnl_limit_factor: 0.0,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpSetAdlEnabled {
+            group,
+            admin,
+            perp_market,
+            adl_enabled: true,
+        },
+    )
+    .await
+    .unwrap();
+    set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 1.0).await;
+    set_bank_stub_oracle_price(solana, group, &collateral_token, admin, 1.0).await;
+
+    //
+    // SETUP: the account goes long 1 lot at 1.0, the counterparty short
+    //
+    let deposit_amount = 1000;
+    let counterparty = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[2..3],
+        deposit_amount,
+        0,
+    )
+    .await;
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[2..3],
+        deposit_amount,
+        0,
+    )
+    .await;
+
+    let mut tx = ClientTransaction::new(solana);
+    tx.add_instruction(PerpPlaceOrderInstruction {
+        account: counterparty,
+        perp_market,
+        owner,
+        side: Side::Ask,
+        price_lots: 100,
+        max_base_lots: 1,
+        ..PerpPlaceOrderInstruction::default()
+    })
+    .await;
+    tx.add_instruction(PerpPlaceOrderInstruction {
+        account,
+        perp_market,
+        owner,
+        side: Side::Bid,
+        price_lots: 100,
+        max_base_lots: 1,
+        ..PerpPlaceOrderInstruction::default()
+    })
+    .await;
+    tx.add_instruction(PerpConsumeEventsInstruction {
+        perp_market,
+        mango_accounts: vec![account, counterparty],
+    })
+    .await;
+    tx.send().await.unwrap();
+
+    // The base price halves: the account is at -50 pnl, the counterparty at +50
+    set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 0.5).await;
+    set_bank_stub_oracle_price(solana, group, &collateral_token, admin, 0.01).await;
+    assert!(account_maint_health(solana, account).await < 0.0);
+
+    //
+    // TEST: ADL closes the base positions at the oracle price and covers the deficit
+    //
+    send_tx(
+        solana,
+        PerpAdlInstruction {
+            admin,
+            liqee: account,
+            perp_market,
+            counterparties: vec![counterparty],
+        },
+    )
+    .await
+    .unwrap();
+    let adl_log = solana
+        .program_log_events::<mango_v4::logs::PerpAdlLog>()
+        .pop()
+        .unwrap();
+    assert_eq!(adl_log.liqee_base_lots_remaining, 0);
+    // liquidation end health after closing the base = 1000 * 0.01 - 50 = -40
+    assert_eq_fixed_f64!(I80F48::from_bits(adl_log.deficit), 40.0, 0.01);
+    assert_eq!(I80F48::from_bits(adl_log.remaining_deficit), 0);
+    assert_eq!(adl_log.num_counterparties, 1);
+    let counterparty_logs = solana.program_log_events::<mango_v4::logs::PerpAdlCounterpartyLog>();
+    assert_eq!(counterparty_logs.len(), 1);
+    assert_eq!(counterparty_logs[0].base_closed, 1);
+    assert_eq_fixed_f64!(
+        I80F48::from_bits(counterparty_logs[0].pnl_absorbed),
+        40.0,
+        0.01
+    );
+
+    let acc_data = solana.get_account::<MangoAccount>(account).await;
+    assert_eq!(acc_data.perps[0].base_position_lots(), 0);
+    assert_eq_fixed_f64!(acc_data.perps[0].quote_position_native(), -10.0, 0.01);
+    let counterparty_data = solana.get_account::<MangoAccount>(counterparty).await;
+    assert_eq!(counterparty_data.perps[0].base_position_lots(), 0);
+    assert_eq_fixed_f64!(
+        counterparty_data.perps[0].quote_position_native(),
+        10.0,
+        0.01
+    );
+    assert!(account_maint_health(solana, counterparty).await >= 0.0);
+
+    Ok(())
+}
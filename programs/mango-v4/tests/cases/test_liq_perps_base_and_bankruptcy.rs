@@ -197,6 +197,8 @@ async fn test_liq_perps_base_and_bankruptcy() -> Result<(), TransportError> {
     //
     // TEST: Liquidate base position with limit
     //
+    let liqee_init_health = account_init_health(solana, account_0).await;
+    let liqee_maint_health = account_maint_health(solana, account_0).await;
     let perp_market_before = solana.get_account::<PerpMarket>(perp_market).await;
     send_tx(
         solana,
@@ -213,8 +215,67 @@ async fn test_liq_perps_base_and_bankruptcy() -> Result<(), TransportError> {
     .unwrap();
     let perp_market_after = solana.get_account::<PerpMarket>(perp_market).await;
 
+    // the healths at liquidation entry are logged
+    let health_log = solana
+        .program_log_events::<mango_v4::logs::PerpLiqHealthLog>()
+        .pop()
+        .unwrap();
+    assert_eq!(health_log.liqee, account_0);
+    assert_eq_fixed_f64!(
+        I80F48::from_bits(health_log.init_health),
+        liqee_init_health,
+        0.1
+    );
+    assert_eq_fixed_f64!(
+        I80F48::from_bits(health_log.maint_health),
+        liqee_maint_health,
+        0.1
+    );
+    assert!(liqee_maint_health < 0.0);
+
+    // the flat liqor takes over the position, open interest is unchanged
+    let risk_log = solana
+        .program_log_events::<mango_v4::logs::GroupRiskDeltaLog>()
+        .pop()
+        .unwrap();
+    assert_eq!(
+        risk_log.open_interest_before,
+        perp_market_before.open_interest
+    );
+    assert_eq!(
+        risk_log.open_interest_after,
+        perp_market_after.open_interest
+    );
+    assert_eq!(
+        perp_market_after.open_interest,
+        perp_market_before.open_interest
+    );
+
     let liqor_amount = 10.0 * 100.0 * 0.6 * (1.0 - 0.03);
     let liqee_amount = 10.0 * 100.0 * 0.6 * (1.0 - 0.05);
+
+    let liq_log = solana
+        .program_log_events::<mango_v4::logs::PerpLiqBaseOrPositivePnlLogV4>()
+        .pop()
+        .unwrap();
+    assert_eq!(liq_log.liqee, account_0);
+    assert_eq!(liq_log.liqor, liqor);
+    assert_eq!(liq_log.base_transfer_liqee, -10);
+    assert_eq_fixed_f64!(
+        I80F48::from_bits(liq_log.quote_transfer_liqee),
+        liqee_amount,
+        0.1
+    );
+    assert_eq_fixed_f64!(I80F48::from_bits(liq_log.oracle_price), 0.6, 0.0001);
+    assert_eq_fixed_f64!(
+        I80F48::from_bits(liq_log.liqee_init_health_before),
+        liqee_init_health,
+        0.1
+    );
+    assert!(
+        I80F48::from_bits(liq_log.liqee_init_health_after)
+            > I80F48::from_bits(liq_log.liqee_init_health_before)
+    );
     let liqor_data = solana.get_account::<MangoAccount>(liqor).await;
     assert_eq!(liqor_data.perps[0].base_position_lots(), 10);
     assert_eq_fixed_f64!(
@@ -247,6 +308,33 @@ async fn test_liq_perps_base_and_bankruptcy() -> Result<(), TransportError> {
         0.1,
     );
 
+    //
+    // TEST: The liquidation log is emitted even if nothing is transferred
+    //
+    send_tx(
+        solana,
+        PerpLiqBaseOrPositivePnlInstruction {
+            liqor,
+            liqor_owner: owner,
+            liqee: account_0,
+            perp_market,
+            max_base_transfer: 0,
+            max_pnl_transfer: 0,
+        },
+    )
+    .await
+    .unwrap();
+    let liq_log = solana
+        .program_log_events::<mango_v4::logs::PerpLiqBaseOrPositivePnlLogV4>()
+        .pop()
+        .unwrap();
+    assert_eq!(liq_log.base_transfer_liqee, 0);
+    assert_eq!(liq_log.quote_transfer_liqee, 0);
+    assert_eq!(
+        liq_log.liqee_init_health_after,
+        liq_log.liqee_init_health_before
+    );
+
     //
     // TEST: Liquidate base position max
     //
@@ -620,3 +708,1014 @@ async fn test_liq_perps_base_and_bankruptcy() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_liq_perps_base_protocol_liquidator() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(150_000); // PerpLiqBaseOrPositivePnl takes a lot of CU
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let base_token = &tokens[2];
+
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        250,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+
+    // a liqor with very little collateral
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        251,
+        &context.users[1],
+        &mints[1..2],
+        100,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Create a perp market and trade between two accounts
+    //
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            settle_token_index: 1,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.7,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.3,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.03,
+            platform_liquidation_fee: 0.02,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            settle_pnl_limit_factor: 0.2,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+
+    let price_lots = {
+        let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+        perp_market.native_price_to_lot(I80F48::ONE)
+    };
+
+    let mut accounts = vec![];
+    for idx in 0..2 {
+        accounts.push(
+            create_funded_account(
+                &solana,
+                group,
+                owner,
+                idx,
+                &context.users[1],
+                &mints[0..1],
+                1330,
+                0,
+            )
+            .await,
+        );
+    }
+    let (account_0, account_1) = (accounts[0], accounts[1]);
+
+    for (account, side) in [(account_0, Side::Bid), (account_1, Side::Ask)] {
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account,
+                perp_market,
+                owner,
+                side,
+                price_lots,
+                max_base_lots: 20,
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+    }
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account_0, account_1],
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Change the oracle to make health go negative for account_0
+    //
+    set_bank_stub_oracle_price(solana, group, base_token, admin, 0.6).await;
+    assert!(account_maint_health(solana, account_0).await < 0.0);
+
+    let liquidate = || PerpLiqBaseOrPositivePnlInstruction {
+        liqor,
+        liqor_owner: owner,
+        liqee: account_0,
+        perp_market,
+        max_base_transfer: 10,
+        max_pnl_transfer: 0,
+    };
+
+    //
+    // TEST: A normal liqor can't take over more than its health allows:
+    // 100 * 0.6 + 10 * 100 * 0.6 * (0.6 - 0.97) is negative
+    //
+    send_tx_expect_error!(solana, liquidate(), MangoError::HealthMustBePositive);
+
+    //
+    // TEST: The protocol liquidator's health isn't checked
+    //
+    send_tx(
+        solana,
+        GroupEdit {
+            group,
+            admin,
+            options: mango_v4::instruction::GroupEdit {
+                protocol_liquidator_opt: Some(liqor),
+                ..group_edit_instruction_default()
+            },
+        },
+    )
+    .await
+    .unwrap();
+
+    send_tx(solana, liquidate()).await.unwrap();
+    let liqor_data = solana.get_account::<MangoAccount>(liqor).await;
+    assert_eq!(liqor_data.perps[0].base_position_lots(), 10);
+    assert!(account_init_health(solana, liqor).await < 0.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_perps_base_max_liquidation_fee() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(150_000); // PerpLiqBaseOrPositivePnl takes a lot of CU
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let base_token = &tokens[2];
+
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        250,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        251,
+        &context.users[1],
+        &mints[1..2],
+        10000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Create a perp market with a 10% liquidation fee and trade between two accounts
+    //
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            settle_token_index: 1,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.7,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.3,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.1,
+            platform_liquidation_fee: 0.0,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            settle_pnl_limit_factor: 0.2,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+
+    let price_lots = {
+        let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+        perp_market.native_price_to_lot(I80F48::ONE)
+    };
+
+    let mut accounts = vec![];
+    for idx in 0..2 {
+        accounts.push(
+            create_funded_account(
+                &solana,
+                group,
+                owner,
+                idx,
+                &context.users[1],
+                &mints[0..1],
+                1330,
+                0,
+            )
+            .await,
+        );
+    }
+    let (account_0, account_1) = (accounts[0], accounts[1]);
+
+    for (account, side) in [(account_0, Side::Bid), (account_1, Side::Ask)] {
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account,
+                perp_market,
+                owner,
+                side,
+                price_lots,
+                max_base_lots: 20,
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+    }
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account_0, account_1],
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Change the oracle to make health go negative for account_0
+    //
+    set_bank_stub_oracle_price(solana, group, base_token, admin, 0.6).await;
+    assert!(account_maint_health(solana, account_0).await < 0.0);
+
+    let set_max_liquidation_fee = |max_liquidation_fee: f32| GroupEdit {
+        group,
+        admin,
+        options: mango_v4::instruction::GroupEdit {
+            max_liquidation_fee_opt: Some(max_liquidation_fee),
+            ..group_edit_instruction_default()
+        },
+    };
+    // Liquidates 2 lots and returns the quote the liqee received per lot
+    let liquidate = move || async move {
+        let liqee_before = solana.get_account::<MangoAccount>(account_0).await;
+        send_tx(
+            solana,
+            PerpLiqBaseOrPositivePnlInstruction {
+                liqor,
+                liqor_owner: owner,
+                liqee: account_0,
+                perp_market,
+                max_base_transfer: 2,
+                max_pnl_transfer: 0,
+            },
+        )
+        .await
+        .unwrap();
+        let liqee_after = solana.get_account::<MangoAccount>(account_0).await;
+        assert_eq!(
+            liqee_before.perps[0].base_position_lots() - liqee_after.perps[0].base_position_lots(),
+            2
+        );
+        (liqee_after.perps[0].quote_position_native()
+            - liqee_before.perps[0].quote_position_native())
+            / 2
+    };
+
+    //
+    // TEST: A group ceiling above the market's fee doesn't change anything
+    //
+    send_tx(solana, set_max_liquidation_fee(0.5)).await.unwrap();
+    assert_eq_fixed_f64!(liquidate().await, 100.0 * 0.6 * 0.9, 0.01);
+
+    //
+    // TEST: A group ceiling below the market's fee binds
+    //
+    send_tx(solana, set_max_liquidation_fee(0.02))
+        .await
+        .unwrap();
+    assert_eq_fixed_f64!(liquidate().await, 100.0 * 0.6 * 0.98, 0.01);
+
+    // the market's configured fee is unchanged
+    let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+    assert_eq_fixed_f64!(perp_market_data.base_liquidation_fee, 0.1, 0.000001);
+
+    //
+    // TEST: Without a ceiling the market's fee applies again
+    //
+    send_tx(solana, set_max_liquidation_fee(0.0)).await.unwrap();
+    assert_eq_fixed_f64!(liquidate().await, 100.0 * 0.6 * 0.9, 0.01);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_perps_base_liqor_deposit_same_tx() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(150_000); // PerpLiqBaseOrPositivePnl takes a lot of CU
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let base_token = &tokens[2];
+
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        250,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+
+    // the liqor starts out with almost nothing
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        251,
+        &context.users[1],
+        &mints[0..1],
+        1,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Create a perp market settling in token 0 and trade between two accounts
+    //
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            settle_token_index: 0,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.7,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.3,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.1,
+            platform_liquidation_fee: 0.0,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            settle_pnl_limit_factor: 0.2,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+
+    let price_lots = {
+        let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+        perp_market.native_price_to_lot(I80F48::ONE)
+    };
+
+    let mut accounts = vec![];
+    for idx in 0..2 {
+        accounts.push(
+            create_funded_account(
+                &solana,
+                group,
+                owner,
+                idx,
+                &context.users[1],
+                &mints[0..1],
+                1100,
+                0,
+            )
+            .await,
+        );
+    }
+    let (account_0, account_1) = (accounts[0], accounts[1]);
+
+    for (account, side) in [(account_0, Side::Bid), (account_1, Side::Ask)] {
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account,
+                perp_market,
+                owner,
+                side,
+                price_lots,
+                max_base_lots: 20,
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+    }
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account_0, account_1],
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Change the oracle to make health go negative for account_0:
+    // 1100 + 2000 * 0.6 * 0.7 - 2000 = -60
+    //
+    set_bank_stub_oracle_price(solana, group, base_token, admin, 0.6).await;
+    assert!(account_maint_health(solana, account_0).await < 0.0);
+
+    let deposit = |amount| TokenDepositInstruction {
+        amount,
+        reduce_only: false,
+        account: liqor,
+        owner,
+        token_account: context.users[1].token_accounts[0],
+        token_authority: payer,
+        bank_index: 0,
+    };
+    let liquidate = || PerpLiqBaseOrPositivePnlInstruction {
+        liqor,
+        liqor_owner: owner,
+        liqee: account_0,
+        perp_market,
+        max_base_transfer: 2,
+        max_pnl_transfer: 0,
+    };
+
+    // Taking over 2 lots, the liqor pays 2 * 100 * 0.6 * 0.9 = 108 for base that's worth
+    // 2 * 100 * 0.6 * 0.6 = 72 in init health, so it needs 36 of extra collateral.
+
+    //
+    // TEST: Without enough collateral the liquidation fails and the deposit is reverted
+    //
+    let mut tx = ClientTransaction::new(solana);
+    tx.add_instruction(deposit(34)).await;
+    tx.add_instruction(liquidate()).await;
+    tx.send_expect_error(MangoError::HealthMustBePositive)
+        .await
+        .unwrap();
+    assert_eq!(account_position(solana, liqor, tokens[0].bank).await, 1);
+
+    //
+    // TEST: Depositing just enough in the same transaction makes it succeed
+    //
+    let mut tx = ClientTransaction::new(solana);
+    tx.add_instruction(deposit(36)).await;
+    tx.add_instruction(liquidate()).await;
+    tx.send().await.unwrap();
+
+    let liqor_data = solana.get_account::<MangoAccount>(liqor).await;
+    assert_eq!(liqor_data.perps[0].base_position_lots(), 2);
+    let liqor_init_health = account_init_health(solana, liqor).await;
+    assert!(liqor_init_health >= 0.0 && liqor_init_health < 2.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_perp_explain_liquidation() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(150_000); // PerpExplainLiquidation takes a lot of CU
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+
+    //
+    // SETUP: Create a group, a liqor and a perp market
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let base_token = &tokens[2];
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        250,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.7,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.3,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.03,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            settle_pnl_limit_factor: 0.2,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+
+    let price_lots = {
+        let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+        perp_market.native_price_to_lot(I80F48::ONE)
+    };
+
+    //
+    // SETUP: Trade between two accounts and make account_0 liquidatable
+    //
+    let mut accounts = vec![];
+    for idx in 0..2 {
+        accounts.push(
+            create_funded_account(
+                &solana,
+                group,
+                owner,
+                idx,
+                &context.users[1],
+                &mints[0..1],
+                1330,
+                0,
+            )
+            .await,
+        );
+    }
+    let (account_0, account_1) = (accounts[0], accounts[1]);
+
+    for (account, side) in [(account_0, Side::Bid), (account_1, Side::Ask)] {
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account,
+                perp_market,
+                owner,
+                side,
+                price_lots,
+                max_base_lots: 20,
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+    }
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account_0, account_1],
+        },
+    )
+    .await
+    .unwrap();
+
+    set_bank_stub_oracle_price(solana, group, base_token, admin, 0.6).await;
+    assert!(account_maint_health(solana, account_0).await < 0.0);
+
+    //
+    // TEST: The explanation leaves the accounts unchanged
+    //
+    send_tx(
+        solana,
+        PerpExplainLiquidationInstruction {
+            liqor,
+            liqee: account_0,
+            perp_market,
+            max_base_transfer: 10,
+            max_pnl_transfer: 0,
+        },
+    )
+    .await
+    .unwrap();
+    let explanation = solana
+        .program_log_events::<mango_v4::events::PerpLiquidationExplanation>()
+        .pop()
+        .unwrap();
+    assert_eq!(explanation.liqee, account_0);
+    assert_eq!(explanation.base_transfer_liqee, -10);
+
+    let liqee_data = solana.get_account::<MangoAccount>(account_0).await;
+    assert_eq!(liqee_data.perps[0].base_position_lots(), 20);
+    let liqor_data = solana.get_account::<MangoAccount>(liqor).await;
+    assert_eq!(liqor_data.perps[0].base_position_lots(), 0);
+
+    //
+    // TEST: The liquidation does what was explained
+    //
+    send_tx(
+        solana,
+        PerpLiqBaseOrPositivePnlInstruction {
+            liqor,
+            liqor_owner: owner,
+            liqee: account_0,
+            perp_market,
+            max_base_transfer: 10,
+            max_pnl_transfer: 0,
+        },
+    )
+    .await
+    .unwrap();
+    let liq_log = solana
+        .program_log_events::<mango_v4::logs::PerpLiqBaseOrPositivePnlLogV4>()
+        .pop()
+        .unwrap();
+    assert_eq!(liq_log.base_transfer_liqee, explanation.base_transfer_liqee);
+    assert_eq!(
+        I80F48::from_bits(liq_log.oracle_price),
+        explanation.oracle_price
+    );
+    assert_eq!(
+        I80F48::from_bits(liq_log.quote_transfer_liqee),
+        explanation.quote_transfer_liqee
+    );
+    assert_eq!(
+        I80F48::from_bits(liq_log.quote_transfer_liqor),
+        explanation.quote_transfer_liqor
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_perps_base_settle_bad_debt_first() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(200_000); // health is computed twice with bad debt
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..3];
+
+    //
+    // SETUP: Create a group and fund the insurance vault
+    //
+
+    let GroupWithTokens {
+        group,
+        tokens,
+        insurance_vault,
+        ..
+    } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let base_token = &tokens[2];
+
+    let insurance_vault_funding = 1000;
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction_direct(
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &payer_mint_accounts[0],
+                &insurance_vault,
+                &payer.pubkey(),
+                &[&payer.pubkey()],
+                insurance_vault_funding,
+            )
+            .unwrap(),
+        );
+        tx.add_signer(payer);
+        tx.send().await.unwrap();
+    }
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        250,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Create a perp market that settles in the insurance token
+    //
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            settle_token_index: 0,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.7,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.3,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.1,
+            platform_liquidation_fee: 0.0,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            settle_pnl_limit_factor: 0.2,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+
+    let price_lots = {
+        let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+        perp_market.native_price_to_lot(I80F48::ONE)
+    };
+
+    //
+    // SETUP: Three accounts buy 20 lots each from a fourth one. The third account also
+    // has some token 1 collateral.
+    //
+    let mut accounts = vec![];
+    for idx in 0..3 {
+        accounts.push(
+            create_funded_account(
+                &solana,
+                group,
+                owner,
+                idx,
+                &context.users[1],
+                &mints[0..1],
+                1100,
+                0,
+            )
+            .await,
+        );
+    }
+    let (account_0, account_1, account_2) = (accounts[0], accounts[1], accounts[2]);
+    send_tx(
+        solana,
+        TokenDepositInstruction {
+            amount: 200,
+            reduce_only: false,
+            account: account_2,
+            owner,
+            token_account: payer_mint_accounts[1],
+            token_authority: payer,
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+    let seller = create_funded_account(
+        &solana,
+        group,
+        owner,
+        3,
+        &context.users[1],
+        &mints[0..1],
+        10000,
+        0,
+    )
+    .await;
+
+    for account in [account_0, account_1, account_2] {
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account,
+                perp_market,
+                owner,
+                side: Side::Bid,
+                price_lots,
+                max_base_lots: 20,
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+    }
+    send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            account: seller,
+            perp_market,
+            owner,
+            side: Side::Ask,
+            price_lots,
+            max_base_lots: 60,
+            ..PerpPlaceOrderInstruction::default()
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account_0, account_1, account_2, seller],
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Drop the base price. account_0 and account_1 now have bad debt:
+    // 1100 + 2000 * 0.4 - 2000 = -100
+    // For account_2 the token 1 deposits cover the perp loss: 1100 + 200 + 800 - 2000 = 100
+    //
+    set_bank_stub_oracle_price(solana, group, base_token, admin, 0.4).await;
+    for account in [account_0, account_1, account_2] {
+        assert!(account_maint_health(solana, account).await < 0.0);
+    }
+
+    let liquidate = |liqee, settle_bad_debt_first| PerpLiqBaseOrPositivePnlV3Instruction {
+        liqor,
+        liqor_owner: owner,
+        liqee,
+        perp_market,
+        max_base_transfer: i64::MAX,
+        max_pnl_transfer: 0,
+        settle_bad_debt_first,
+        liqor_quote_token_index: None,
+    };
+
+    //
+    // TEST: Without settle_bad_debt_first the insurance fund isn't touched
+    //
+    send_tx(solana, liquidate(account_0, false)).await.unwrap();
+    assert!(solana
+        .program_log_events::<mango_v4::logs::PerpLiqBadDebtInsuranceLog>()
+        .is_empty());
+    assert_eq!(
+        solana.token_account_balance(insurance_vault).await,
+        insurance_vault_funding
+    );
+    assert_eq!(
+        account_position(solana, account_0, tokens[0].bank).await,
+        1100
+    );
+    let account_0_data = solana.get_account::<MangoAccount>(account_0).await;
+    assert!(account_0_data.perps[0].base_position_lots() < 20);
+
+    //
+    // TEST: With settle_bad_debt_first the insurance fund covers the bad debt before the
+    // base position is liquidated
+    //
+    send_tx(solana, liquidate(account_1, true)).await.unwrap();
+    let bad_debt_log = solana
+        .program_log_events::<mango_v4::logs::PerpLiqBadDebtInsuranceLog>()
+        .pop()
+        .unwrap();
+    assert_eq!(bad_debt_log.liqee, account_1);
+    assert_eq!(bad_debt_log.insurance_transfer, 100);
+    assert_eq!(
+        solana.token_account_balance(insurance_vault).await,
+        insurance_vault_funding - 100
+    );
+    assert_eq!(
+        account_position(solana, account_1, tokens[0].bank).await,
+        1200
+    );
+    let account_1_data = solana.get_account::<MangoAccount>(account_1).await;
+    assert!(account_1_data.perps[0].base_position_lots() < 20);
+    assert!(
+        account_init_health(solana, account_1).await > account_init_health(solana, account_0).await
+    );
+
+    //
+    // TEST: A liqee whose other assets cover the perp loss gets no insurance
+    //
+    send_tx(solana, liquidate(account_2, true)).await.unwrap();
+    assert!(solana
+        .program_log_events::<mango_v4::logs::PerpLiqBadDebtInsuranceLog>()
+        .is_empty());
+    assert_eq!(
+        solana.token_account_balance(insurance_vault).await,
+        insurance_vault_funding - 100
+    );
+    assert_eq!(
+        account_position(solana, account_2, tokens[0].bank).await,
+        1100
+    );
+
+    Ok(())
+}
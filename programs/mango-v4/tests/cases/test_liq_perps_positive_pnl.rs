@@ -277,6 +277,8 @@ async fn test_liq_perps_positive_pnl() -> Result<(), TransportError> {
         account_position(solana, account_0, settle_token.bank).await,
         95
     );
+    // the liqee pays the 5% discount on the taken over pnl
+    assert_eq!(liqee_data.perp_liquidation_fees_paid, 5);
 
     //
     // TEST: Being willing to take over more positive pnl can trigger more base liquidation
@@ -409,3 +411,319 @@ async fn test_liq_perps_positive_pnl() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_liq_perps_positive_pnl_alt_settle_token() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(200_000); // PerpLiqBaseOrPositivePnlV3Instruction takes a lot of CU
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..4];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..4];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let _quote_token = &tokens[0];
+    let base_token = &tokens[1];
+    let borrow_token = &tokens[2];
+    let settle_token = &tokens[3];
+
+    // deposit some funds, to the vaults aren't empty
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        250,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Create a perp market
+    //
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            settle_token_index: 3,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.8,
+            init_base_asset_weight: 0.5,
+            maint_base_liab_weight: 1.2,
+            init_base_liab_weight: 1.5,
+            maint_overall_asset_weight: 0.0,
+            init_overall_asset_weight: 0.0,
+            base_liquidation_fee: 0.05,
+            positive_pnl_liquidation_fee: 0.05,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            group_insurance_fund: true,
+            settle_pnl_limit_factor: 0.2,
+            settle_pnl_limit_window_size_ts: 24 * 60 * 60,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+
+    set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 10.0).await;
+    let price_lots = {
+        let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+        perp_market.native_price_to_lot(I80F48::from(10))
+    };
+
+    //
+    // SETUP: Make an two accounts and deposit some quote and base
+    //
+    let context_ref = &context;
+    let make_account = |idx: u32| async move {
+        let deposit_amount = 10000;
+        let account = create_funded_account(
+            &solana,
+            group,
+            owner,
+            idx,
+            &context_ref.users[1],
+            &mints[0..1],
+            deposit_amount,
+            0,
+        )
+        .await;
+
+        account
+    };
+    let account_0 = make_account(0).await;
+    let account_1 = make_account(1).await;
+
+    //
+    // SETUP: Borrow some spot on account_0, so we can later make it liquidatable that way
+    // (actually borrowing 1000.5 due to loan origination!)
+    //
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 1000,
+            allow_borrow: true,
+            account: account_0,
+            owner,
+            token_account: payer_mint_accounts[2],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Trade perps between accounts
+    //
+    send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            account: account_0,
+            perp_market,
+            owner,
+            side: Side::Bid,
+            price_lots,
+            max_base_lots: 10,
+            ..PerpPlaceOrderInstruction::default()
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            account: account_1,
+            perp_market,
+            owner,
+            side: Side::Ask,
+            price_lots,
+            max_base_lots: 10,
+            ..PerpPlaceOrderInstruction::default()
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account_0, account_1],
+        },
+    )
+    .await
+    .unwrap();
+
+    // after this order exchange it is changed by
+    //   10*10*100*(0.5-1)*1.4 = -7000 for the long account0
+    //   10*10*100*(1-1.5)*1.4 = -7000 for the short account1
+    // (100 is base lot size)
+    assert_eq!(
+        account_init_health(solana, account_0).await.round(),
+        (10000.0f64 - 1000.5 * 1.4 - 7000.0).round()
+    );
+    assert_eq!(
+        account_init_health(solana, account_1).await.round(),
+        10000.0 - 7000.0
+    );
+
+    //
+    // SETUP: Change the perp oracle to make perp-based health go positive for account_0
+    // perp base value goes to 10*21*100*0.5, exceeding the negative quote
+    // perp uhupnl is 10*21*100*0.5 - 10*10*100 = 500
+    // but health doesn't exceed 10k because of the 0 overall weight
+    //
+    set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 21.0).await;
+    assert_eq!(
+        account_init_health(solana, account_0).await.round(),
+        (10000.0f64 - 1000.5 * 1.4).round()
+    );
+
+    //
+    // SETUP: Increase the price of the borrow so account_0 becomes liquidatable
+    //
+    set_bank_stub_oracle_price(solana, group, &borrow_token, admin, 10.0).await;
+    assert_eq!(
+        account_init_health(solana, account_0).await.round(),
+        (10000.0f64 - 10.0 * 1000.5 * 1.4).round()
+    );
+
+    let liquidate =
+        |max_pnl_transfer, liqor_quote_token_index| PerpLiqBaseOrPositivePnlV3Instruction {
+            liqor,
+            liqor_owner: owner,
+            liqee: account_0,
+            perp_market,
+            max_base_transfer: 0,
+            max_pnl_transfer,
+            settle_bad_debt_first: false,
+            liqor_quote_token_index,
+        };
+
+    //
+    // TEST: Paying in another token needs it to be the market's alternative settle token
+    //
+    assert!(send_tx(solana, liquidate(100, Some(borrow_token.index)))
+        .await
+        .is_err());
+
+    send_tx(
+        solana,
+        PerpSetAltSettleToken {
+            group,
+            admin,
+            perp_market,
+            alt_settle_token_index: borrow_token.index,
+        },
+    )
+    .await
+    .unwrap();
+    let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+    assert_eq!(
+        perp_market_data.alt_settle_token_index(),
+        Some(borrow_token.index)
+    );
+
+    assert!(send_tx(solana, liquidate(100, Some(base_token.index)))
+        .await
+        .is_err());
+
+    //
+    // TEST: Take over positive pnl, paying in the alternative settle token at oracle price
+    //
+    let liqee_borrow_before = account_position_f64(solana, account_0, borrow_token.bank).await;
+    let liqor_borrow_before = account_position_f64(solana, liqor, borrow_token.bank).await;
+
+    send_tx(solana, liquidate(100, Some(borrow_token.index)))
+        .await
+        .unwrap();
+
+    let liqor_data = solana.get_account::<MangoAccount>(liqor).await;
+    assert_eq!(liqor_data.perps[0].base_position_lots(), 0);
+    assert_eq!(liqor_data.perps[0].quote_position_native(), 100);
+    let liqee_data = solana.get_account::<MangoAccount>(account_0).await;
+    assert_eq!(liqee_data.perps[0].quote_position_native(), -10100);
+
+    // no settle token moved
+    assert_eq!(
+        account_position(solana, liqor, settle_token.bank).await,
+        10000
+    );
+    assert_eq!(
+        account_position(solana, account_0, settle_token.bank).await,
+        0
+    );
+
+    // 100 pnl at a 5% discount is worth 95 settle tokens at price 1, or 9.5 borrow tokens at price 10
+    assert!(
+        (account_position_f64(solana, account_0, borrow_token.bank).await
+            - (liqee_borrow_before + 9.5))
+            .abs()
+            < 0.01
+    );
+    assert!(
+        (account_position_f64(solana, liqor, borrow_token.bank).await
+            - (liqor_borrow_before - 9.5))
+            .abs()
+            < 0.01
+    );
+
+    //
+    // TEST: The settle token is still usable and the default
+    //
+    send_tx(solana, liquidate(100, None)).await.unwrap();
+    assert_eq!(
+        account_position(solana, liqor, settle_token.bank).await,
+        10000 - 95
+    );
+    assert_eq!(
+        account_position(solana, account_0, settle_token.bank).await,
+        95
+    );
+
+    //
+    // TEST: Once disabled, the alternative settle token can't be used anymore
+    //
+    send_tx(
+        solana,
+        PerpSetAltSettleToken {
+            group,
+            admin,
+            perp_market,
+            alt_settle_token_index: TokenIndex::MAX,
+        },
+    )
+    .await
+    .unwrap();
+    let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+    assert_eq!(perp_market_data.alt_settle_token_index(), None);
+
+    assert!(send_tx(solana, liquidate(100, Some(borrow_token.index)))
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -586,3 +586,721 @@ async fn test_liq_tokens_with_token() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_liq_tokens_relaxed_staleness() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(100_000); // stale oracles log a lot
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..2];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let borrow_token = &tokens[0];
+    let collateral_token = &tokens[1];
+
+    let vault_account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        2,
+        &context.users[1],
+        mints,
+        100000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Make an account with some collateral and some borrows
+    //
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[1..2],
+        1000,
+        0,
+    )
+    .await;
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 300,
+            allow_borrow: true,
+            account,
+            owner,
+            token_account: payer_mint_accounts[0],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Make the account liquidatable, but with oracles that are too stale
+    //
+    for token in &tokens[0..2] {
+        send_tx(
+            solana,
+            TokenEdit {
+                group,
+                admin,
+                mint: token.mint.pubkey,
+                fallback_oracle: Pubkey::default(),
+                options: mango_v4::instruction::TokenEdit {
+                    oracle_config_opt: Some(OracleConfigParams {
+                        conf_filter: 0.1,
+                        max_staleness_slots: Some(10),
+                    }),
+                    ..token_edit_instruction_default()
+                },
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    solana.advance_clock().await;
+    let last_update_slot = 1;
+    assert!(solana.clock().await.slot > last_update_slot + 10);
+    for (token, price) in [(borrow_token, 3.0), (collateral_token, 1.0)] {
+        send_tx(
+            solana,
+            StubOracleSetTestInstruction {
+                oracle: token.oracle,
+                group,
+                mint: token.mint.pubkey,
+                admin,
+                price,
+                last_update_slot,
+                deviation: 0.0,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    //
+    // TEST: Regular liquidation fails due to stale oracles
+    //
+    let res = send_tx(
+        solana,
+        TokenLiqWithTokenInstruction {
+            liqee: account,
+            liqor: vault_account,
+            liqor_owner: owner,
+            asset_token_index: collateral_token.index,
+            asset_bank_index: 0,
+            liab_token_index: borrow_token.index,
+            liab_bank_index: 0,
+            max_liab_transfer: I80F48::from_num(10.0),
+        },
+    )
+    .await;
+    assert_mango_error(
+        &res,
+        MangoError::OracleStale.into(),
+        "oracles are stale".to_string(),
+    );
+
+    //
+    // TEST: Relaxed staleness needs the group admin
+    //
+    let relaxed_liq = TokenLiqWithTokenRelaxedStalenessInstruction {
+        admin,
+        liqee: account,
+        liqor: vault_account,
+        liqor_owner: owner,
+        asset_token_index: collateral_token.index,
+        asset_bank_index: 0,
+        liab_token_index: borrow_token.index,
+        liab_bank_index: 0,
+        max_liab_transfer: I80F48::from_num(10.0),
+        extra_staleness_slots: 1000,
+    };
+    let res = send_tx(
+        solana,
+        TokenLiqWithTokenRelaxedStalenessInstruction {
+            admin: payer,
+            ..relaxed_liq
+        },
+    )
+    .await;
+    assert!(res.is_err());
+
+    //
+    // TEST: The admin can liquidate with relaxed staleness
+    //
+    send_tx(solana, relaxed_liq).await.unwrap();
+    assert_eq!(
+        account_position(solana, account, borrow_token.bank).await,
+        -300 + 10
+    );
+    assert!(account_position(solana, account, collateral_token.bank).await < 1000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_tokens_absorb_dust() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..2];
+
+    //
+    // SETUP: Create a group and a liqor account with deposits
+    //
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let quote_token = &tokens[0];
+    let dust_token = &tokens[1];
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        2,
+        &context.users[1],
+        mints,
+        100000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Make an account with some quote and a small dust_token deposit
+    //
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+    send_tx(
+        solana,
+        TokenDepositInstruction {
+            amount: 20,
+            reduce_only: false,
+            account,
+            owner,
+            token_account: payer_mint_accounts[1],
+            token_authority: payer.clone(),
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let absorb = || TokenLiqAbsorbDustInstruction {
+        liqee: account,
+        liqor,
+        liqor_owner: owner,
+        dust_token_index: dust_token.index,
+        dust_bank_index: 0,
+        quote_token_index: quote_token.index,
+        quote_bank_index: 0,
+    };
+
+    //
+    // TEST: Absorbing is disabled by default
+    //
+    let res = send_tx(solana, absorb()).await;
+    assert_mango_error(
+        &res,
+        MangoError::SomeError.into(),
+        "dust absorption is disabled".to_string(),
+    );
+
+    send_tx(
+        solana,
+        GroupEdit {
+            group,
+            admin,
+            options: mango_v4::instruction::GroupEdit {
+                max_dust_absorb_quote_opt: Some(10),
+                ..group_edit_instruction_default()
+            },
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: Deposits above the threshold can't be absorbed
+    //
+    let res = send_tx(solana, absorb()).await;
+    assert_mango_error(
+        &res,
+        MangoError::SomeError.into(),
+        "dust value exceeds the max".to_string(),
+    );
+
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 15,
+            allow_borrow: false,
+            account,
+            owner,
+            token_account: payer_mint_accounts[1],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: Dust within the threshold gets absorbed at oracle price
+    //
+    send_tx(solana, absorb()).await.unwrap();
+    assert!(account_position_closed(solana, account, dust_token.bank).await);
+    assert_eq!(
+        account_position(solana, account, quote_token.bank).await,
+        1000 + 5
+    );
+    assert_eq!(
+        account_position(solana, liqor, dust_token.bank).await,
+        100000 + 5
+    );
+    assert_eq!(
+        account_position(solana, liqor, quote_token.bank).await,
+        100000 - 5
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_tokens_after_same_tx_deposit() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(150_000);
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..2];
+
+    //
+    // SETUP: Create a group and a liqor account that also fills the vaults
+    //
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let quote_token = &tokens[0];
+    let borrow_token = &tokens[1];
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        mints,
+        100000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Make an account with quote collateral and a borrow
+    //
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 600,
+            allow_borrow: true,
+            account,
+            owner,
+            token_account: payer_mint_accounts[1],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Change the oracle to make maint health go negative: 1000 - 600 * 1.5 * 1.2 = -80
+    //
+    set_bank_stub_oracle_price(solana, group, borrow_token, admin, 1.5).await;
+    assert!(account_maint_health(solana, account).await < 0.0);
+
+    let deposit = |amount| TokenDepositInstruction {
+        amount,
+        reduce_only: false,
+        account,
+        owner,
+        token_account: payer_mint_accounts[0],
+        token_authority: payer.clone(),
+        bank_index: 0,
+    };
+    let liquidate = || TokenLiqWithTokenInstruction {
+        liqee: account,
+        liqor,
+        liqor_owner: owner,
+        asset_token_index: quote_token.index,
+        asset_bank_index: 0,
+        liab_token_index: borrow_token.index,
+        liab_bank_index: 0,
+        max_liab_transfer: I80F48::from(100),
+    };
+
+    //
+    // TEST: A deposit earlier in the same transaction is seen by the liquidation,
+    // and if it restores health nothing gets liquidated
+    //
+    let mut tx = ClientTransaction::new(solana);
+    tx.add_instruction(deposit(200)).await;
+    tx.add_instruction(liquidate()).await;
+    tx.send().await.unwrap();
+
+    assert_eq!(
+        account_position(solana, account, quote_token.bank).await,
+        1200
+    );
+    assert_eq!(
+        account_position(solana, account, borrow_token.bank).await,
+        -600
+    );
+    assert!(account_maint_health(solana, account).await > 0.0);
+    let liqee = get_mango_account(solana, account).await;
+    assert!(!liqee.being_liquidated());
+
+    //
+    // TEST: A deposit that doesn't restore health doesn't prevent liquidation
+    //
+    set_bank_stub_oracle_price(solana, group, borrow_token, admin, 1.8).await;
+    assert!(account_maint_health(solana, account).await < -50.0);
+
+    let mut tx = ClientTransaction::new(solana);
+    tx.add_instruction(deposit(10)).await;
+    tx.add_instruction(liquidate()).await;
+    tx.send().await.unwrap();
+
+    assert!(account_position(solana, account, borrow_token.bank).await > -600);
+    let liqee = get_mango_account(solana, account).await;
+    assert!(liqee.being_liquidated());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_tokens_recover_from_being_liquidated() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(150_000);
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..2];
+
+    //
+    // SETUP: Create a group and a liqor account that also fills the vaults
+    //
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let quote_token = &tokens[0];
+    let borrow_token = &tokens[1];
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        mints,
+        100000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Make an account with quote collateral and a borrow
+    //
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+    let withdraw = |amount, token_account| TokenWithdrawInstruction {
+        amount,
+        allow_borrow: true,
+        account,
+        owner,
+        token_account,
+        bank_index: 0,
+    };
+    send_tx(solana, withdraw(600, payer_mint_accounts[1]))
+        .await
+        .unwrap();
+
+    //
+    // SETUP: Make maint health negative and liquidate a little: 1000 - 600 * 1.5 * 1.2 = -80
+    //
+    set_bank_stub_oracle_price(solana, group, borrow_token, admin, 1.5).await;
+    assert!(account_maint_health(solana, account).await < 0.0);
+
+    send_tx(
+        solana,
+        TokenLiqWithTokenInstruction {
+            liqee: account,
+            liqor,
+            liqor_owner: owner,
+            asset_token_index: quote_token.index,
+            asset_bank_index: 0,
+            liab_token_index: borrow_token.index,
+            liab_bank_index: 0,
+            max_liab_transfer: I80F48::from(10),
+        },
+    )
+    .await
+    .unwrap();
+    let liqee = get_mango_account(solana, account).await;
+    assert!(liqee.being_liquidated());
+    assert!(liqee.fixed.being_liquidated_slot > 0);
+
+    let recover = || RecoverFromBeingLiquidatedInstruction { account, owner };
+
+    //
+    // TEST: Recovering isn't possible while maint health is negative
+    //
+    send_tx_expect_error!(solana, recover(), MangoError::HealthMustBePositive);
+
+    //
+    // TEST: Once the price moves back, maint health is positive but liquidation end
+    // health is not, so the account stays stuck in liquidation
+    //
+    set_bank_stub_oracle_price(solana, group, borrow_token, admin, 1.3).await;
+    assert!(account_maint_health(solana, account).await > 0.0);
+    assert!(account_init_health(solana, account).await < 0.0);
+
+    send_tx_expect_error!(
+        solana,
+        withdraw(1, payer_mint_accounts[0]),
+        MangoError::BeingLiquidated
+    );
+
+    //
+    // TEST: The owner can clear the flag
+    //
+    send_tx(solana, recover()).await.unwrap();
+    let liqee = get_mango_account(solana, account).await;
+    assert!(!liqee.being_liquidated());
+    assert_eq!(liqee.fixed.being_liquidated_slot, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_tokens_limits() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(150_000);
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..2];
+
+    //
+    // SETUP: Create a group, an account to fill the vaults and a liqor with only quote
+    //
+
+    let mango_setup::GroupWithTokens { group, tokens, .. } = mango_setup::GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let quote_token = &tokens[0];
+    let borrow_token = &tokens[1];
+
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        2,
+        &context.users[1],
+        mints,
+        100000,
+        0,
+    )
+    .await;
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..1],
+        100000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Make an account with quote collateral and a borrow, then make it liquidatable
+    //
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 600,
+            allow_borrow: true,
+            account,
+            owner,
+            token_account: payer_mint_accounts[1],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    set_bank_stub_oracle_price(solana, group, borrow_token, admin, 1.5).await;
+    assert!(account_maint_health(solana, account).await < 0.0);
+
+    let set_net_borrow_limit = |limit| TokenResetNetBorrows {
+        group,
+        admin,
+        mint: borrow_token.mint.pubkey,
+        min_vault_to_deposits_ratio_opt: Some(0.0),
+        net_borrow_limit_per_window_quote_opt: Some(limit),
+        net_borrow_limit_window_size_ts_opt: Some(1000),
+    };
+    let liquidate = || TokenLiqWithTokenInstruction {
+        liqee: account,
+        liqor,
+        liqor_owner: owner,
+        asset_token_index: quote_token.index,
+        asset_bank_index: 0,
+        liab_token_index: borrow_token.index,
+        liab_bank_index: 0,
+        max_liab_transfer: I80F48::from(10),
+    };
+
+    //
+    // TEST: The liqor borrows the liab token, which counts against the net borrow limit
+    //
+    send_tx(solana, set_net_borrow_limit(10)).await.unwrap();
+    send_tx_expect_error!(solana, liquidate(), MangoError::BankNetBorrowsLimitReached);
+
+    //
+    // TEST: Deposit limits don't block liquidation, even though both the liqee's liab and
+    // the liqor's asset positions increase
+    //
+    send_tx(solana, set_net_borrow_limit(1000)).await.unwrap();
+    for token in [quote_token, borrow_token] {
+        send_tx(
+            solana,
+            TokenEdit {
+                group,
+                admin,
+                mint: token.mint.pubkey,
+                fallback_oracle: Pubkey::default(),
+                options: mango_v4::instruction::TokenEdit {
+                    deposit_limit_opt: Some(1),
+                    ..token_edit_instruction_default()
+                },
+            },
+        )
+        .await
+        .unwrap();
+    }
+    send_tx(solana, liquidate()).await.unwrap();
+
+    assert_eq!(
+        account_position(solana, account, borrow_token.bank).await,
+        -590
+    );
+    assert_eq!(
+        account_position(solana, liqor, borrow_token.bank).await,
+        -10
+    );
+
+    Ok(())
+}
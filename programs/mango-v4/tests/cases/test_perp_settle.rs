@@ -818,6 +818,82 @@ async fn test_perp_settle_pnl_fees() -> Result<(), TransportError> {
         );
     }
 
+    //
+    // TEST: Settle with a protocol settle fee, no settler fee because pnl too small
+    //
+    send_tx(
+        solana,
+        PerpSetSettleFee {
+            group,
+            admin,
+            perp_market,
+            settle_fee_bps: 100,
+        },
+    )
+    .await
+    .unwrap();
+    set_perp_stub_oracle_price(solana, group, perp_market, &tokens[1], admin, 1081.0).await;
+
+    let bank_fees_before = solana
+        .get_account::<Bank>(settle_bank)
+        .await
+        .collected_fees_native;
+
+    send_tx(
+        solana,
+        PerpSettlePnlInstruction {
+            settler,
+            settler_owner,
+            account_a: account_0,
+            account_b: account_1,
+            perp_market,
+        },
+    )
+    .await
+    .unwrap();
+
+    let expected_pnl = 1000;
+    let protocol_fee = 10;
+    total_settled_pnl += expected_pnl;
+    {
+        let mango_account_0 = solana.get_account::<MangoAccount>(account_0).await;
+        let mango_account_1 = solana.get_account::<MangoAccount>(account_1).await;
+        assert_eq!(
+            mango_account_0.perps[0].quote_position_native().round(),
+            I80F48::from(-100_000 - total_settled_pnl)
+        );
+        assert_eq!(
+            mango_account_1.perps[0].quote_position_native().round(),
+            I80F48::from(100_000 + total_settled_pnl),
+        );
+        assert_eq!(
+            account_position(solana, account_0, settle_bank).await,
+            initial_token_deposit as i64 + total_settled_pnl - total_fees_paid - protocol_fee
+        );
+        assert_eq!(
+            account_position(solana, account_1, settle_bank).await,
+            initial_token_deposit as i64 - total_settled_pnl
+        );
+        assert_eq!(
+            account_position(solana, settler, settle_bank).await,
+            total_fees_paid
+        );
+
+        let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+        assert_eq!(
+            perp_market_data.accrued_settle_fees,
+            I80F48::from(protocol_fee)
+        );
+        let bank_fees_after = solana
+            .get_account::<Bank>(settle_bank)
+            .await
+            .collected_fees_native;
+        assert_eq!(
+            bank_fees_after - bank_fees_before,
+            I80F48::from(protocol_fee)
+        );
+    }
+
     Ok(())
 }
 
@@ -1230,3 +1306,193 @@ async fn test_perp_pnl_settle_limit() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_perp_settle_all_funding() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..4];
+
+    let initial_token_deposit = 1_000_000;
+
+    //
+    // SETUP: Create a group and two accounts
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let account_0 = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[0..1],
+        initial_token_deposit,
+        0,
+    )
+    .await;
+    let account_1 = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..1],
+        initial_token_deposit,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Create three perp markets, trade one lot on each and leave only
+    // bids on the book so longs pay funding
+    //
+    let mut perp_markets = vec![];
+    for (perp_market_index, base_token) in tokens[1..4].iter().enumerate() {
+        set_bank_stub_oracle_price(solana, group, base_token, admin, 1000.0).await;
+
+        let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+            solana,
+            PerpCreateMarketInstruction {
+                group,
+                admin,
+                payer,
+                perp_market_index: perp_market_index as PerpMarketIndex,
+                quote_lot_size: 10,
+                base_lot_size: 100,
+                maint_base_asset_weight: 0.975,
+                init_base_asset_weight: 0.95,
+                maint_base_liab_weight: 1.025,
+                init_base_liab_weight: 1.05,
+                ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+            },
+        )
+        .await
+        .unwrap();
+
+        let price_lots = {
+            let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+            perp_market.native_price_to_lot(I80F48::from(1000))
+        };
+
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account: account_0,
+                perp_market,
+                owner,
+                side: Side::Bid,
+                price_lots,
+                max_base_lots: 1,
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account: account_1,
+                perp_market,
+                owner,
+                side: Side::Ask,
+                price_lots,
+                max_base_lots: 1,
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+        send_tx(
+            solana,
+            PerpConsumeEventsInstruction {
+                perp_market,
+                mango_accounts: vec![account_0, account_1],
+            },
+        )
+        .await
+        .unwrap();
+
+        send_tx(
+            solana,
+            PerpPlaceOrderInstruction {
+                account: account_1,
+                perp_market,
+                owner,
+                side: Side::Bid,
+                price_lots: price_lots / 2,
+                max_base_lots: 100, // the market's impact quantity
+                ..PerpPlaceOrderInstruction::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        perp_markets.push((perp_market, base_token.oracle));
+    }
+
+    solana.advance_clock().await;
+    for (perp_market, oracle) in perp_markets.iter() {
+        send_tx(
+            solana,
+            PerpUpdateFundingInstruction {
+                perp_market: *perp_market,
+                bank: tokens[0].bank,
+                oracle: *oracle,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let mango_account_0 = solana.get_account::<MangoAccount>(account_0).await;
+    for (i, (perp_market, _)) in perp_markets.iter().enumerate() {
+        let perp_market = solana.get_account::<PerpMarket>(*perp_market).await;
+        assert_eq!(mango_account_0.perps[i].market_index, i as PerpMarketIndex);
+        assert!(mango_account_0.perps[i].unsettled_funding(&perp_market) != 0);
+    }
+
+    //
+    // TEST: Settle funding on all three positions at once
+    //
+    send_tx(
+        solana,
+        PerpSettleAllFundingInstruction { account: account_0 },
+    )
+    .await
+    .unwrap();
+
+    let log = solana
+        .program_log_events::<mango_v4::logs::PerpSettleAllFundingLog>()
+        .pop()
+        .unwrap();
+    assert_eq!(log.mango_account, account_0);
+    assert_eq!(log.settled_funding.len(), 3);
+
+    let mango_account_0 = solana.get_account::<MangoAccount>(account_0).await;
+    for (i, (perp_market, _)) in perp_markets.iter().enumerate() {
+        let perp_market = solana.get_account::<PerpMarket>(*perp_market).await;
+        let perp_position = &mango_account_0.perps[i];
+        assert_eq!(perp_position.unsettled_funding(&perp_market), 0);
+        assert_eq!(perp_position.long_settled_funding, perp_market.long_funding);
+        assert_eq!(
+            log.settled_funding[i].perp_market_index,
+            i as PerpMarketIndex
+        );
+        assert!(log.settled_funding[i].funding != 0);
+    }
+
+    Ok(())
+}
@@ -243,3 +243,154 @@ async fn test_position_lifetime() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+// Check that compacting token positions keeps balances and lookups intact
+#[tokio::test]
+async fn test_compact_token_positions() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..3];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..=2];
+
+    //
+    // SETUP: Create a group, an account to fill the vaults and an account with three tokens
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        mints,
+        1000000,
+        0,
+    )
+    .await;
+    let account =
+        create_funded_account(&solana, group, owner, 0, &context.users[1], mints, 1000, 0).await;
+
+    // withdrawing all of the first token leaves its slot unused
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: u64::MAX,
+            allow_borrow: false,
+            account,
+            owner,
+            token_account: payer_mint_accounts[0],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+    let account_data = get_mango_account(solana, account).await;
+    let token_indexes = account_data
+        .all_token_positions()
+        .map(|p| p.token_index)
+        .take(3)
+        .collect_vec();
+    assert_eq!(
+        token_indexes,
+        vec![TokenIndex::MAX, tokens[1].index, tokens[2].index]
+    );
+    let health_before = account_init_health(solana, account).await;
+
+    //
+    // TEST: Only the group admin can compact
+    //
+    assert!(send_tx(
+        solana,
+        AccountCompactTokenPositionsInstruction {
+            account,
+            admin: owner,
+        },
+    )
+    .await
+    .is_err());
+
+    //
+    // TEST: Compacting moves the positions to the front and keeps their data
+    //
+    send_tx(
+        solana,
+        AccountCompactTokenPositionsInstruction { account, admin },
+    )
+    .await
+    .unwrap();
+
+    let compacted_data = get_mango_account(solana, account).await;
+    let token_indexes = compacted_data
+        .all_token_positions()
+        .map(|p| p.token_index)
+        .take(3)
+        .collect_vec();
+    assert_eq!(
+        token_indexes,
+        vec![tokens[1].index, tokens[2].index, TokenIndex::MAX]
+    );
+    for token in &tokens[1..] {
+        assert_eq!(
+            compacted_data.token_position(token.index).unwrap(),
+            account_data.token_position(token.index).unwrap()
+        );
+    }
+    assert_eq!(account_init_health(solana, account).await, health_before);
+
+    //
+    // TEST: Positions still resolve for withdraws and deposits
+    //
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 500,
+            allow_borrow: false,
+            account,
+            owner,
+            token_account: payer_mint_accounts[2],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(account_position(solana, account, tokens[2].bank).await, 500);
+
+    send_tx(
+        solana,
+        TokenDepositInstruction {
+            amount: 100,
+            reduce_only: false,
+            account,
+            owner,
+            token_account: payer_mint_accounts[0],
+            token_authority: payer.clone(),
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(account_position(solana, account, tokens[0].bank).await, 100);
+    let account_data = get_mango_account(solana, account).await;
+    assert_eq!(
+        account_data
+            .token_position_and_raw_index(tokens[0].index)
+            .unwrap()
+            .1,
+        2
+    );
+
+    Ok(())
+}
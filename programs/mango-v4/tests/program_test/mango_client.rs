@@ -346,6 +346,8 @@ async fn derive_liquidation_remaining_account_metas(
         .active_token_positions()
         .chain(liqor.active_token_positions())
         .map(|ta| ta.token_index)
+        .chain([asset_token_index, liab_token_index])
+        .filter(|&token_index| token_index != TokenIndex::MAX)
         .unique();
     for token_index in token_indexes {
         let mint_info = get_mint_info_by_token_index(account_loader, liqee, token_index).await;
@@ -438,7 +440,7 @@ pub async fn account_init_health(solana: &SolanaCookie, account: Pubkey) -> f64
         .await
         .unwrap();
     let health_data = solana
-        .program_log_events::<mango_v4::events::MangoAccountData>()
+        .program_log_events::<mango_v4::events::MangoAccountDataV2>()
         .pop()
         .unwrap();
     health_data.init_health.to_num::<f64>()
@@ -449,7 +451,7 @@ pub async fn account_maint_health(solana: &SolanaCookie, account: Pubkey) -> f64
         .await
         .unwrap();
     let health_data = solana
-        .program_log_events::<mango_v4::events::MangoAccountData>()
+        .program_log_events::<mango_v4::events::MangoAccountDataV2>()
         .pop()
         .unwrap();
     health_data.maint_health.to_num::<f64>()
@@ -470,7 +472,7 @@ pub async fn check_prev_instruction_post_health(solana: &SolanaCookie, account:
         .unwrap();
 
     let health_data = solana
-        .program_log_events::<mango_v4::events::MangoAccountData>()
+        .program_log_events::<mango_v4::events::MangoAccountDataV2>()
         .pop()
         .unwrap();
     assert_eq!(health_data.init_health.to_num::<f64>(), post_health);
@@ -1324,6 +1326,7 @@ pub fn token_edit_instruction_default() -> mango_v4::instruction::TokenEdit {
         disable_asset_liquidation_opt: None,
         collateral_fee_per_day_opt: None,
         force_withdraw_opt: None,
+        project_borrow_interest_in_health_opt: None,
     }
 }
 
@@ -1843,6 +1846,15 @@ pub fn group_edit_instruction_default() -> mango_v4::instruction::GroupEdit {
         buyback_fees_expiry_interval_opt: None,
         allowed_fast_listings_per_interval_opt: None,
         collateral_fee_interval_opt: None,
+        max_liquidation_overshoot_health_opt: None,
+        max_dust_absorb_quote_opt: None,
+        init_asset_weight_floor_opt: None,
+        liqor_fee_waiver_min_mngo_opt: None,
+        min_weight_change_interval_slots_opt: None,
+        protocol_liquidator_opt: None,
+        max_liquidation_fee_opt: None,
+        sweep_bankrupt_perp_pnl_opt: None,
+        liqor_fee_waiver_registrar_opt: None,
     }
 }
 
@@ -1914,6 +1926,67 @@ impl ClientInstruction for GroupEdit {
     }
 }
 
+pub struct GroupSetPendingAdminInstruction {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub pending_admin: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for GroupSetPendingAdminInstruction {
+    type Accounts = mango_v4::accounts::GroupSetPendingAdmin;
+    type Instruction = mango_v4::instruction::GroupSetPendingAdmin;
+    async fn to_instruction(
+        &self,
+        _account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            pending_admin: self.pending_admin,
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct GroupAcceptAdminInstruction {
+    pub group: Pubkey,
+    pub pending_admin: TestKeypair,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for GroupAcceptAdminInstruction {
+    type Accounts = mango_v4::accounts::GroupAcceptAdmin;
+    type Instruction = mango_v4::instruction::GroupAcceptAdmin;
+    async fn to_instruction(
+        &self,
+        _account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            pending_admin: self.pending_admin.pubkey(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.pending_admin]
+    }
+}
+
 pub struct GroupChangeInsuranceFund {
     pub group: Pubkey,
     pub admin: TestKeypair,
@@ -1998,6 +2071,31 @@ impl ClientInstruction for IxGateSetInstruction {
     }
 }
 
+pub struct GroupStatusInstruction {
+    pub group: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for GroupStatusInstruction {
+    type Accounts = mango_v4::accounts::GroupStatus;
+    type Instruction = mango_v4::instruction::GroupStatus;
+    async fn to_instruction(
+        &self,
+        _account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let accounts = Self::Accounts { group: self.group };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
 pub struct GroupCloseInstruction {
     pub admin: TestKeypair,
     pub group: Pubkey,
@@ -2200,6 +2298,79 @@ impl ClientInstruction for AccountSizeMigrationInstruction {
     }
 }
 
+pub struct AccountSetTokenAllowlistInstruction {
+    pub account: Pubkey,
+    pub admin: TestKeypair,
+    pub token_indexes: Vec<TokenIndex>,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for AccountSetTokenAllowlistInstruction {
+    type Accounts = mango_v4::accounts::AccountSetTokenAllowlist;
+    type Instruction = mango_v4::instruction::AccountSetTokenAllowlist;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            token_indexes: self.token_indexes.clone(),
+        };
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+            admin: self.admin.pubkey(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct AccountCompactTokenPositionsInstruction {
+    pub account: Pubkey,
+    pub admin: TestKeypair,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for AccountCompactTokenPositionsInstruction {
+    type Accounts = mango_v4::accounts::AccountCompactTokenPositions;
+    type Instruction = mango_v4::instruction::AccountCompactTokenPositions;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+            admin: self.admin.pubkey(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
 pub struct AccountEditInstruction {
     pub account_num: u32,
     pub group: Pubkey,
@@ -2249,6 +2420,86 @@ impl ClientInstruction for AccountEditInstruction {
     }
 }
 
+pub struct AccountSetDelegatePermissionsInstruction {
+    pub account: Pubkey,
+    pub owner: TestKeypair,
+    pub delegate_permissions: u8,
+    pub temporary_delegate_permissions: u8,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for AccountSetDelegatePermissionsInstruction {
+    type Accounts = mango_v4::accounts::AccountSetDelegatePermissions;
+    type Instruction = mango_v4::instruction::AccountSetDelegatePermissions;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            delegate_permissions: self.delegate_permissions,
+            temporary_delegate_permissions: self.temporary_delegate_permissions,
+        };
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+            owner: self.owner.pubkey(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner]
+    }
+}
+
+pub struct AccountSetOwnerInstruction {
+    pub account: Pubkey,
+    pub owner: TestKeypair,
+    pub new_owner: Pubkey,
+    pub clear_delegates: bool,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for AccountSetOwnerInstruction {
+    type Accounts = mango_v4::accounts::AccountSetOwner;
+    type Instruction = mango_v4::instruction::AccountSetOwner;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = mango_v4::instruction::AccountSetOwner {
+            new_owner: self.new_owner,
+            clear_delegates: self.clear_delegates,
+        };
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let accounts = mango_v4::accounts::AccountSetOwner {
+            group: account.fixed.group,
+            account: self.account,
+            owner: self.owner.pubkey(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner]
+    }
+}
+
 pub struct AccountCloseInstruction {
     pub group: Pubkey,
     pub account: Pubkey,
@@ -3271,28 +3522,35 @@ impl ClientInstruction for TokenLiqWithTokenInstruction {
     }
 }
 
-pub struct TokenLiqBankruptcyInstruction {
+pub struct TokenLiqWithTokenRelaxedStalenessInstruction {
+    pub admin: TestKeypair,
     pub liqee: Pubkey,
     pub liqor: Pubkey,
     pub liqor_owner: TestKeypair,
 
+    pub asset_token_index: TokenIndex,
+    pub asset_bank_index: usize,
+    pub liab_token_index: TokenIndex,
+    pub liab_bank_index: usize,
     pub max_liab_transfer: I80F48,
-    pub liab_mint_info: Pubkey,
+    pub extra_staleness_slots: u64,
 }
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for TokenLiqBankruptcyInstruction {
-    type Accounts = mango_v4::accounts::TokenLiqBankruptcy;
-    type Instruction = mango_v4::instruction::TokenLiqBankruptcy;
+impl ClientInstruction for TokenLiqWithTokenRelaxedStalenessInstruction {
+    type Accounts = mango_v4::accounts::TokenLiqWithTokenRelaxedStaleness;
+    type Instruction = mango_v4::instruction::TokenLiqWithTokenRelaxedStaleness;
     async fn to_instruction(
         &self,
         account_loader: &(impl ClientAccountLoader + 'async_trait),
     ) -> (Self::Accounts, instruction::Instruction) {
         let program_id = mango_v4::id();
         let instruction = Self::Instruction {
+            asset_token_index: self.asset_token_index,
+            liab_token_index: self.liab_token_index,
             max_liab_transfer: self.max_liab_transfer,
+            extra_staleness_slots: self.extra_staleness_slots,
         };
 
-        let liab_mint_info: MintInfo = account_loader.load(&self.liab_mint_info).await.unwrap();
         let liqee = account_loader
             .load_mango_account(&self.liqee)
             .await
@@ -3301,73 +3559,194 @@ impl ClientInstruction for TokenLiqBankruptcyInstruction {
             .load_mango_account(&self.liqor)
             .await
             .unwrap();
-
-        let group_key = liqee.fixed.group;
-        let group: Group = account_loader.load(&group_key).await.unwrap();
-
-        let insurance_mint_info = Pubkey::find_program_address(
-            &[
-                b"MintInfo".as_ref(),
-                liqee.fixed.group.as_ref(),
-                group.insurance_mint.as_ref(),
-            ],
-            &program_id,
-        )
-        .0;
-        let insurance_mint_info: MintInfo =
-            account_loader.load(&insurance_mint_info).await.unwrap();
-
         let health_check_metas = derive_liquidation_remaining_account_metas(
             account_loader,
             &liqee,
             &liqor,
-            insurance_mint_info.token_index,
-            0,
-            liab_mint_info.token_index,
-            0,
+            self.asset_token_index,
+            self.asset_bank_index,
+            self.liab_token_index,
+            self.liab_bank_index,
         )
         .await;
 
         let accounts = Self::Accounts {
-            group: group_key,
+            group: liqee.fixed.group,
+            admin: self.admin.pubkey(),
             liqee: self.liqee,
             liqor: self.liqor,
             liqor_owner: self.liqor_owner.pubkey(),
-            liab_mint_info: self.liab_mint_info,
-            quote_vault: insurance_mint_info.first_vault(),
-            insurance_vault: group.insurance_vault,
-            token_program: Token::id(),
         };
 
         let mut instruction = make_instruction(program_id, &accounts, &instruction);
-        let mut bank_ams = liab_mint_info
-            .banks()
-            .iter()
-            .map(|bank| AccountMeta {
-                pubkey: *bank,
-                is_signer: false,
-                is_writable: true,
-            })
-            .collect::<Vec<_>>();
-        instruction.accounts.append(&mut bank_ams);
         instruction.accounts.extend(health_check_metas.into_iter());
 
         (accounts, instruction)
     }
 
     fn signers(&self) -> Vec<TestKeypair> {
-        vec![self.liqor_owner]
+        vec![self.admin, self.liqor_owner]
     }
 }
 
-#[derive(Default)]
-pub struct PerpCreateMarketInstruction {
-    pub group: Pubkey,
-    pub admin: TestKeypair,
-    pub oracle: Pubkey,
-    pub bids: Pubkey,
-    pub asks: Pubkey,
-    pub event_queue: Pubkey,
+pub struct TokenLiqAbsorbDustInstruction {
+    pub liqee: Pubkey,
+    pub liqor: Pubkey,
+    pub liqor_owner: TestKeypair,
+
+    pub dust_token_index: TokenIndex,
+    pub dust_bank_index: usize,
+    pub quote_token_index: TokenIndex,
+    pub quote_bank_index: usize,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for TokenLiqAbsorbDustInstruction {
+    type Accounts = mango_v4::accounts::TokenLiqAbsorbDust;
+    type Instruction = mango_v4::instruction::TokenLiqAbsorbDust;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            dust_token_index: self.dust_token_index,
+            quote_token_index: self.quote_token_index,
+        };
+
+        let liqee = account_loader
+            .load_mango_account(&self.liqee)
+            .await
+            .unwrap();
+        let liqor = account_loader
+            .load_mango_account(&self.liqor)
+            .await
+            .unwrap();
+        let health_check_metas = derive_liquidation_remaining_account_metas(
+            account_loader,
+            &liqee,
+            &liqor,
+            self.dust_token_index,
+            self.dust_bank_index,
+            self.quote_token_index,
+            self.quote_bank_index,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: liqee.fixed.group,
+            liqee: self.liqee,
+            liqor: self.liqor,
+            liqor_owner: self.liqor_owner.pubkey(),
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction.accounts.extend(health_check_metas.into_iter());
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.liqor_owner]
+    }
+}
+
+pub struct TokenLiqBankruptcyInstruction {
+    pub liqee: Pubkey,
+    pub liqor: Pubkey,
+    pub liqor_owner: TestKeypair,
+
+    pub max_liab_transfer: I80F48,
+    pub liab_mint_info: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for TokenLiqBankruptcyInstruction {
+    type Accounts = mango_v4::accounts::TokenLiqBankruptcy;
+    type Instruction = mango_v4::instruction::TokenLiqBankruptcy;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            max_liab_transfer: self.max_liab_transfer,
+        };
+
+        let liab_mint_info: MintInfo = account_loader.load(&self.liab_mint_info).await.unwrap();
+        let liqee = account_loader
+            .load_mango_account(&self.liqee)
+            .await
+            .unwrap();
+        let liqor = account_loader
+            .load_mango_account(&self.liqor)
+            .await
+            .unwrap();
+
+        let group_key = liqee.fixed.group;
+        let group: Group = account_loader.load(&group_key).await.unwrap();
+
+        let insurance_mint_info = Pubkey::find_program_address(
+            &[
+                b"MintInfo".as_ref(),
+                liqee.fixed.group.as_ref(),
+                group.insurance_mint.as_ref(),
+            ],
+            &program_id,
+        )
+        .0;
+        let insurance_mint_info: MintInfo =
+            account_loader.load(&insurance_mint_info).await.unwrap();
+
+        let health_check_metas = derive_liquidation_remaining_account_metas(
+            account_loader,
+            &liqee,
+            &liqor,
+            insurance_mint_info.token_index,
+            0,
+            liab_mint_info.token_index,
+            0,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: group_key,
+            liqee: self.liqee,
+            liqor: self.liqor,
+            liqor_owner: self.liqor_owner.pubkey(),
+            liab_mint_info: self.liab_mint_info,
+            quote_vault: insurance_mint_info.first_vault(),
+            insurance_vault: group.insurance_vault,
+            token_program: Token::id(),
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        let mut bank_ams = liab_mint_info
+            .banks()
+            .iter()
+            .map(|bank| AccountMeta {
+                pubkey: *bank,
+                is_signer: false,
+                is_writable: true,
+            })
+            .collect::<Vec<_>>();
+        instruction.accounts.append(&mut bank_ams);
+        instruction.accounts.extend(health_check_metas.into_iter());
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.liqor_owner]
+    }
+}
+
+#[derive(Default)]
+pub struct PerpCreateMarketInstruction {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub oracle: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
     pub payer: TestKeypair,
     pub settle_token_index: TokenIndex,
     pub perp_market_index: PerpMarketIndex,
@@ -3521,6 +3900,23 @@ fn perp_edit_instruction_default() -> mango_v4::instruction::PerpEditMarket {
         name_opt: None,
         force_close_opt: None,
         platform_liquidation_fee_opt: None,
+        liquidation_price_tolerance_opt: None,
+        base_liquidation_fee_bps_opt: None,
+        use_ema_for_trigger_opt: None,
+        ema_interval_seconds_opt: None,
+        large_position_threshold_lots_opt: None,
+        large_position_whitelist_opt: None,
+        dust_threshold_lots_opt: None,
+        dust_liquidation_fee_opt: None,
+        settle_fee_bps_opt: None,
+        max_liquidation_base_per_slot_opt: None,
+        liquidation_throttle_bypass_health_opt: None,
+        liquidation_fee_insurance_fraction_opt: None,
+        adl_enabled_opt: None,
+        underlying_id_opt: None,
+        min_liquidation_base_lots_opt: None,
+        liquidation_quote_rounding_buffer_opt: None,
+        alt_settle_token_index_opt: None,
     }
 }
 
@@ -3603,6 +3999,127 @@ impl ClientInstruction for PerpSetSettleLimitWindow {
     }
 }
 
+pub struct PerpSetSettleFee {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub perp_market: Pubkey,
+    pub settle_fee_bps: u32,
+}
+
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpSetSettleFee {
+    type Accounts = mango_v4::accounts::PerpEditMarket;
+    type Instruction = mango_v4::instruction::PerpEditMarket;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+
+        let instruction = Self::Instruction {
+            settle_fee_bps_opt: Some(self.settle_fee_bps),
+            ..perp_edit_instruction_default()
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct PerpSetAdlEnabled {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub perp_market: Pubkey,
+    pub adl_enabled: bool,
+}
+
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpSetAdlEnabled {
+    type Accounts = mango_v4::accounts::PerpEditMarket;
+    type Instruction = mango_v4::instruction::PerpEditMarket;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+
+        let instruction = Self::Instruction {
+            adl_enabled_opt: Some(self.adl_enabled),
+            ..perp_edit_instruction_default()
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct PerpSetAltSettleToken {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub perp_market: Pubkey,
+    /// TokenIndex::MAX disables the alternative settle token
+    pub alt_settle_token_index: TokenIndex,
+}
+
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpSetAltSettleToken {
+    type Accounts = mango_v4::accounts::PerpEditMarket;
+    type Instruction = mango_v4::instruction::PerpEditMarket;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+
+        let instruction = Self::Instruction {
+            alt_settle_token_index_opt: Some(self.alt_settle_token_index),
+            ..perp_edit_instruction_default()
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, &instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
 pub struct PerpMakeReduceOnly {
     pub group: Pubkey,
     pub admin: TestKeypair,
@@ -4197,13 +4714,53 @@ impl ClientInstruction for PerpForceClosePositionInstruction {
     }
 }
 
-pub struct PerpSettleFeesInstruction {
+pub struct PerpSettleAllFundingInstruction {
     pub account: Pubkey,
-    pub perp_market: Pubkey,
-    pub max_settle_amount: u64,
 }
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for PerpSettleFeesInstruction {
+impl ClientInstruction for PerpSettleAllFundingInstruction {
+    type Accounts = mango_v4::accounts::PerpSettleAllFunding;
+    type Instruction = mango_v4::instruction::PerpSettleAllFunding;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+        };
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction
+            .accounts
+            .extend(account.active_perp_positions().map(|perp| AccountMeta {
+                pubkey: get_perp_market_address_by_index(account.fixed.group, perp.market_index),
+                is_writable: false,
+                is_signer: false,
+            }));
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
+pub struct PerpSettleFeesInstruction {
+    pub account: Pubkey,
+    pub perp_market: Pubkey,
+    pub max_settle_amount: u64,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpSettleFeesInstruction {
     type Accounts = mango_v4::accounts::PerpSettleFees;
     type Instruction = mango_v4::instruction::PerpSettleFees;
     async fn to_instruction(
@@ -4367,6 +4924,154 @@ impl ClientInstruction for PerpLiqBaseOrPositivePnlInstruction {
     }
 }
 
+pub struct PerpLiqBaseOrPositivePnlV3Instruction {
+    pub liqor: Pubkey,
+    pub liqor_owner: TestKeypair,
+    pub liqee: Pubkey,
+    pub perp_market: Pubkey,
+    pub max_base_transfer: i64,
+    pub max_pnl_transfer: u64,
+    pub settle_bad_debt_first: bool,
+    pub liqor_quote_token_index: Option<TokenIndex>,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpLiqBaseOrPositivePnlV3Instruction {
+    type Accounts = mango_v4::accounts::PerpLiqBaseOrPositivePnlV3;
+    type Instruction = mango_v4::instruction::PerpLiqBaseOrPositivePnlV3;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            max_base_transfer: self.max_base_transfer,
+            max_pnl_transfer: self.max_pnl_transfer,
+            liqor_price_opt: None,
+            min_net_quote_profit_opt: None,
+            liquidation_cost: 0,
+            max_health_improvement_opt: None,
+            settle_bad_debt_first: self.settle_bad_debt_first,
+            liqor_quote_token_index: self.liqor_quote_token_index,
+        };
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+        let group_key = perp_market.group;
+        let group: Group = account_loader.load(&group_key).await.unwrap();
+        let liqor = account_loader
+            .load_mango_account(&self.liqor)
+            .await
+            .unwrap();
+        let liqee = account_loader
+            .load_mango_account(&self.liqee)
+            .await
+            .unwrap();
+        let health_check_metas = derive_liquidation_remaining_account_metas(
+            account_loader,
+            &liqee,
+            &liqor,
+            self.liqor_quote_token_index.unwrap_or(TokenIndex::MAX),
+            0,
+            TokenIndex::MAX,
+            0,
+        )
+        .await;
+
+        let settle_mint_info =
+            get_mint_info_by_token_index(account_loader, &liqee, perp_market.settle_token_index)
+                .await;
+
+        let accounts = Self::Accounts {
+            group: group_key,
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+            liqor: self.liqor,
+            liqor_owner: self.liqor_owner.pubkey(),
+            liqee: self.liqee,
+            settle_bank: settle_mint_info.first_bank(),
+            settle_vault: settle_mint_info.first_vault(),
+            settle_oracle: settle_mint_info.oracle,
+            insurance_vault: group.insurance_vault,
+            token_program: Token::id(),
+        };
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction.accounts.extend(health_check_metas);
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.liqor_owner]
+    }
+}
+
+pub struct PerpExplainLiquidationInstruction {
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub perp_market: Pubkey,
+    pub max_base_transfer: i64,
+    pub max_pnl_transfer: u64,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpExplainLiquidationInstruction {
+    type Accounts = mango_v4::accounts::PerpExplainLiquidation;
+    type Instruction = mango_v4::instruction::PerpExplainLiquidation;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            max_base_transfer: self.max_base_transfer,
+            max_pnl_transfer: self.max_pnl_transfer,
+            liqor_price_opt: None,
+            min_net_quote_profit_opt: None,
+            liquidation_cost: 0,
+            max_health_improvement_opt: None,
+        };
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+        let group_key = perp_market.group;
+        let liqor = account_loader
+            .load_mango_account(&self.liqor)
+            .await
+            .unwrap();
+        let liqee = account_loader
+            .load_mango_account(&self.liqee)
+            .await
+            .unwrap();
+        let health_check_metas = derive_liquidation_remaining_account_metas(
+            account_loader,
+            &liqee,
+            &liqor,
+            TokenIndex::MAX,
+            0,
+            TokenIndex::MAX,
+            0,
+        )
+        .await;
+
+        let settle_mint_info =
+            get_mint_info_by_token_index(account_loader, &liqee, perp_market.settle_token_index)
+                .await;
+
+        let accounts = Self::Accounts {
+            group: group_key,
+            perp_market: self.perp_market,
+            liqor: self.liqor,
+            liqee: self.liqee,
+            settle_bank: settle_mint_info.first_bank(),
+        };
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction.accounts.extend(health_check_metas);
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
 pub struct PerpLiqNegativePnlOrBankruptcyInstruction {
     pub liqor: Pubkey,
     pub liqor_owner: TestKeypair,
@@ -4454,6 +5159,110 @@ impl ClientInstruction for PerpLiqNegativePnlOrBankruptcyInstruction {
     }
 }
 
+#[derive(Clone)]
+pub struct PerpAdlInstruction {
+    pub admin: TestKeypair,
+    pub liqee: Pubkey,
+    pub perp_market: Pubkey,
+    pub counterparties: Vec<Pubkey>,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpAdlInstruction {
+    type Accounts = mango_v4::accounts::PerpAdl;
+    type Instruction = mango_v4::instruction::PerpAdl;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            num_counterparties: self.counterparties.len() as u8,
+        };
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+        let liqee = account_loader
+            .load_mango_account(&self.liqee)
+            .await
+            .unwrap();
+        let group_key = liqee.fixed.group;
+        let group: Group = account_loader.load(&group_key).await.unwrap();
+
+        let mut counterparties = vec![];
+        for counterparty in &self.counterparties {
+            counterparties.push(
+                account_loader
+                    .load_mango_account(counterparty)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        // The health accounts must cover the liqee and all counterparties
+        let accounts_iter = || std::iter::once(&liqee).chain(counterparties.iter());
+        let mut banks = vec![];
+        let mut oracles = vec![];
+        let token_indexes = accounts_iter()
+            .flat_map(|a| a.active_token_positions().map(|ta| ta.token_index))
+            .unique();
+        for token_index in token_indexes {
+            let mint_info = get_mint_info_by_token_index(account_loader, &liqee, token_index).await;
+            banks.push(mint_info.first_bank());
+            oracles.push(mint_info.oracle);
+        }
+
+        let perp_markets: Vec<Pubkey> = accounts_iter()
+            .flat_map(|a| a.active_perp_positions().map(|perp| perp.market_index))
+            .unique()
+            .map(|market_index| get_perp_market_address_by_index(group_key, market_index))
+            .collect();
+        let mut perp_oracles = vec![];
+        for &perp in &perp_markets {
+            perp_oracles
+                .push(get_oracle_address_from_perp_market_address(account_loader, &perp).await)
+        }
+
+        let serum_oos: Vec<Pubkey> = accounts_iter()
+            .flat_map(|a| a.active_serum3_orders().map(|s| s.open_orders))
+            .collect();
+
+        let health_check_metas = banks
+            .into_iter()
+            .chain(oracles)
+            .chain(perp_markets)
+            .chain(perp_oracles)
+            .chain(serum_oos)
+            .map(|pubkey| AccountMeta {
+                pubkey,
+                is_writable: false,
+                is_signer: false,
+            });
+
+        let accounts = Self::Accounts {
+            group: group_key,
+            admin: self.admin.pubkey(),
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+            liqee: self.liqee,
+            insurance_vault: group.insurance_vault,
+        };
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction.accounts.extend(health_check_metas);
+        instruction
+            .accounts
+            .extend(self.counterparties.iter().map(|&pubkey| AccountMeta {
+                pubkey,
+                is_writable: true,
+                is_signer: false,
+            }));
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
 pub struct BenchmarkInstruction {}
 #[async_trait::async_trait(?Send)]
 impl ClientInstruction for BenchmarkInstruction {
@@ -4607,6 +5416,140 @@ impl ClientInstruction for ComputeAccountDataInstruction {
     }
 }
 
+pub struct CacheAccountHealthInstruction {
+    pub account: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for CacheAccountHealthInstruction {
+    type Accounts = mango_v4::accounts::CacheAccountHealth;
+    type Instruction = mango_v4::instruction::CacheAccountHealth;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let health_check_metas = derive_health_check_remaining_account_metas(
+            account_loader,
+            &account,
+            None,
+            false,
+            None,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction.accounts.extend(health_check_metas.into_iter());
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
+pub struct RecoverFromBeingLiquidatedInstruction {
+    pub account: Pubkey,
+    pub owner: TestKeypair,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for RecoverFromBeingLiquidatedInstruction {
+    type Accounts = mango_v4::accounts::RecoverFromBeingLiquidated;
+    type Instruction = mango_v4::instruction::RecoverFromBeingLiquidated;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let health_check_metas = derive_health_check_remaining_account_metas(
+            account_loader,
+            &account,
+            None,
+            false,
+            None,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+            owner: self.owner.pubkey(),
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction.accounts.extend(health_check_metas.into_iter());
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner]
+    }
+}
+
+pub struct ComputeAccountHealthInstruction {
+    pub account: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for ComputeAccountHealthInstruction {
+    type Accounts = mango_v4::accounts::ComputeAccountHealth;
+    type Instruction = mango_v4::instruction::ComputeAccountHealth;
+    async fn to_instruction(
+        &self,
+        account_loader: &(impl ClientAccountLoader + 'async_trait),
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        let health_check_metas = derive_health_check_remaining_account_metas(
+            account_loader,
+            &account,
+            None,
+            false,
+            None,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, &instruction);
+        instruction.accounts.extend(health_check_metas.into_iter());
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
 pub struct HealthRegionBeginInstruction {
     pub account: Pubkey,
 }
@@ -321,6 +321,9 @@ export class MangoClient {
     return await this.sendAndConfirmTransaction([ix]);
   }
 
+  /**
+   * admin must be undefined, the admin is changed with groupSetPendingAdmin
+   */
   public async groupEdit(
     group: Group,
     admin?: PublicKey,
@@ -336,7 +339,19 @@ export class MangoClient {
     feesExpiryInterval?: BN,
     allowedFastListingsPerInterval?: number,
     collateralFeeInterval?: BN,
+    maxLiquidationOvershootHealth?: BN,
+    maxDustAbsorbQuote?: BN,
+    initAssetWeightFloor?: number,
+    liqorFeeWaiverMinMngo?: BN,
+    minWeightChangeIntervalSlots?: BN,
+    protocolLiquidator?: PublicKey,
+    maxLiquidationFee?: number,
+    sweepBankruptPerpPnl?: boolean,
+    liqorFeeWaiverRegistrar?: PublicKey,
   ): Promise<MangoSignatureStatus> {
+    if (admin !== undefined) {
+      throw new Error('The admin can only be changed with groupSetPendingAdmin');
+    }
     const ix = await this.program.methods
       .groupEdit(
         admin ?? null,
@@ -352,6 +367,15 @@ export class MangoClient {
         feesExpiryInterval ?? null,
         allowedFastListingsPerInterval ?? null,
         collateralFeeInterval ?? null,
+        maxLiquidationOvershootHealth ?? null,
+        maxDustAbsorbQuote ?? null,
+        initAssetWeightFloor ?? null,
+        liqorFeeWaiverMinMngo ?? null,
+        minWeightChangeIntervalSlots ?? null,
+        protocolLiquidator ?? null,
+        maxLiquidationFee ?? null,
+        sweepBankruptPerpPnl ?? null,
+        liqorFeeWaiverRegistrar ?? null,
       )
       .accounts({
         group: group.publicKey,
@@ -361,6 +385,36 @@ export class MangoClient {
     return await this.sendAndConfirmTransactionForGroup(group, [ix]);
   }
 
+  /**
+   * Proposes a new admin, which takes over once it calls groupAcceptAdmin.
+   * PublicKey.default withdraws the proposal.
+   */
+  public async groupSetPendingAdmin(
+    group: Group,
+    pendingAdmin: PublicKey,
+  ): Promise<MangoSignatureStatus> {
+    const ix = await this.program.methods
+      .groupSetPendingAdmin(pendingAdmin)
+      .accounts({
+        group: group.publicKey,
+        admin: (this.program.provider as AnchorProvider).wallet.publicKey,
+      })
+      .instruction();
+    return await this.sendAndConfirmTransactionForGroup(group, [ix]);
+  }
+
+  public async groupAcceptAdmin(group: Group): Promise<MangoSignatureStatus> {
+    const ix = await this.program.methods
+      .groupAcceptAdmin()
+      .accounts({
+        group: group.publicKey,
+        pendingAdmin: (this.program.provider as AnchorProvider).wallet
+          .publicKey,
+      })
+      .instruction();
+    return await this.sendAndConfirmTransactionForGroup(group, [ix]);
+  }
+
   public async groupChangeInsuranceFund(
     group: Group,
     withdrawDestination: PublicKey,
@@ -610,6 +664,7 @@ export class MangoClient {
         params.disableAssetLiquidation,
         params.collateralFeePerDay,
         params.forceWithdraw,
+        params.projectBorrowInterestInHealth,
       )
       .accounts({
         group: group.publicKey,
@@ -1088,6 +1143,21 @@ export class MangoClient {
     return await this.sendAndConfirmTransactionForGroup(group, [ix]);
   }
 
+  public async compactMangoAccountTokenPositions(
+    group: Group,
+    mangoAccount: MangoAccount,
+  ): Promise<MangoSignatureStatus> {
+    const ix = await this.program.methods
+      .accountCompactTokenPositions()
+      .accounts({
+        group: group.publicKey,
+        account: mangoAccount.publicKey,
+        admin: (this.program.provider as AnchorProvider).wallet.publicKey,
+      })
+      .instruction();
+    return await this.sendAndConfirmTransactionForGroup(group, [ix]);
+  }
+
   public async sequenceCheckIx(
     group: Group,
     mangoAccount: MangoAccount,
@@ -2877,6 +2947,23 @@ export class MangoClient {
         params.name,
         params.forceClose,
         params.platformLiquidationFee,
+        params.liquidationPriceTolerance,
+        params.baseLiquidationFeeBps,
+        params.useEmaForTrigger,
+        params.emaIntervalSeconds,
+        params.largePositionThresholdLots !== null ? new BN(params.largePositionThresholdLots) : null,
+        params.largePositionWhitelist,
+        params.dustThresholdLots !== null ? new BN(params.dustThresholdLots) : null,
+        params.dustLiquidationFee,
+        params.settleFeeBps,
+        params.maxLiquidationBasePerSlot !== null ? new BN(params.maxLiquidationBasePerSlot) : null,
+        params.liquidationThrottleBypassHealth,
+        params.liquidationFeeInsuranceFraction,
+        params.adlEnabled,
+        params.underlyingId,
+        params.minLiquidationBaseLots !== null ? new BN(params.minLiquidationBaseLots) : null,
+        params.liquidationQuoteRoundingBuffer,
+        params.altSettleTokenIndex,
       )
       .accounts({
         group: group.publicKey,
@@ -118,6 +118,7 @@ export interface TokenEditParams {
   disableAssetLiquidation: boolean | null;
   collateralFeePerDay: number | null;
   forceWithdraw: boolean | null;
+  projectBorrowInterestInHealth: boolean | null;
 }
 
 export const NullTokenEditParams: TokenEditParams = {
@@ -162,6 +163,7 @@ export const NullTokenEditParams: TokenEditParams = {
   disableAssetLiquidation: null,
   collateralFeePerDay: null,
   forceWithdraw: null,
+  projectBorrowInterestInHealth: null,
 };
 
 export interface PerpEditParams {
@@ -196,6 +198,23 @@ export interface PerpEditParams {
   name: string | null;
   forceClose: boolean | null;
   platformLiquidationFee: number | null;
+  liquidationPriceTolerance: number | null;
+  baseLiquidationFeeBps: number | null;
+  useEmaForTrigger: boolean | null;
+  emaIntervalSeconds: number | null;
+  largePositionThresholdLots: number | null;
+  largePositionWhitelist: PublicKey[] | null;
+  dustThresholdLots: number | null;
+  dustLiquidationFee: number | null;
+  settleFeeBps: number | null;
+  maxLiquidationBasePerSlot: number | null;
+  liquidationThrottleBypassHealth: number | null;
+  liquidationFeeInsuranceFraction: number | null;
+  adlEnabled: boolean | null;
+  underlyingId: number | null;
+  minLiquidationBaseLots: number | null;
+  liquidationQuoteRoundingBuffer: number | null;
+  altSettleTokenIndex: number | null;
 }
 
 export const NullPerpEditParams: PerpEditParams = {
@@ -230,6 +249,23 @@ export const NullPerpEditParams: PerpEditParams = {
   name: null,
   forceClose: null,
   platformLiquidationFee: null,
+  liquidationPriceTolerance: null,
+  baseLiquidationFeeBps: null,
+  useEmaForTrigger: null,
+  emaIntervalSeconds: null,
+  largePositionThresholdLots: null,
+  largePositionWhitelist: null,
+  dustThresholdLots: null,
+  dustLiquidationFee: null,
+  settleFeeBps: null,
+  maxLiquidationBasePerSlot: null,
+  liquidationThrottleBypassHealth: null,
+  liquidationFeeInsuranceFraction: null,
+  adlEnabled: null,
+  underlyingId: null,
+  minLiquidationBaseLots: null,
+  liquidationQuoteRoundingBuffer: null,
+  altSettleTokenIndex: null,
 };
 
 // Use with TrueIxGateParams and buildIxGate
@@ -313,6 +349,19 @@ export interface IxGateParams {
   SequenceCheck: boolean;
   HealthCheck: boolean;
   GroupChangeInsuranceFund: boolean;
+  AccountSetOwner: boolean;
+  PerpSettleAllFunding: boolean;
+  TokenLiqWithTokenRelaxedStaleness: boolean;
+  TokenLiqAbsorbDust: boolean;
+  PerpAdl: boolean;
+  AccountSetTokenAllowlist: boolean;
+  ComputeAccountHealth: boolean;
+  CacheAccountHealth: boolean;
+  RecoverFromBeingLiquidated: boolean;
+  AccountSetDelegatePermissions: boolean;
+  GroupStatus: boolean;
+  PerpExplainLiquidation: boolean;
+  AccountCompactTokenPositions: boolean;
 }
 
 // Default with all ixs enabled, use with buildIxGate
@@ -396,6 +445,19 @@ export const TrueIxGateParams: IxGateParams = {
   SequenceCheck: true,
   HealthCheck: true,
   GroupChangeInsuranceFund: true,
+  AccountSetOwner: true,
+  PerpSettleAllFunding: true,
+  TokenLiqWithTokenRelaxedStaleness: true,
+  TokenLiqAbsorbDust: true,
+  PerpAdl: true,
+  AccountSetTokenAllowlist: true,
+  ComputeAccountHealth: true,
+  CacheAccountHealth: true,
+  RecoverFromBeingLiquidated: true,
+  AccountSetDelegatePermissions: true,
+  GroupStatus: true,
+  PerpExplainLiquidation: true,
+  AccountCompactTokenPositions: true,
 };
 
 // build ix gate e.g. buildIxGate(Builder(TrueIxGateParams).TokenDeposit(false).build()).toNumber(),
@@ -489,6 +551,19 @@ export function buildIxGate(p: IxGateParams): BN {
   toggleIx(ixGate, p, 'SequenceCheck', 73);
   toggleIx(ixGate, p, 'HealthCheck', 74);
   toggleIx(ixGate, p, 'GroupChangeInsuranceFund', 76);
+  toggleIx(ixGate, p, 'AccountSetOwner', 77);
+  toggleIx(ixGate, p, 'PerpSettleAllFunding', 78);
+  toggleIx(ixGate, p, 'TokenLiqWithTokenRelaxedStaleness', 79);
+  toggleIx(ixGate, p, 'TokenLiqAbsorbDust', 80);
+  toggleIx(ixGate, p, 'PerpAdl', 81);
+  toggleIx(ixGate, p, 'AccountSetTokenAllowlist', 82);
+  toggleIx(ixGate, p, 'ComputeAccountHealth', 83);
+  toggleIx(ixGate, p, 'CacheAccountHealth', 84);
+  toggleIx(ixGate, p, 'RecoverFromBeingLiquidated', 85);
+  toggleIx(ixGate, p, 'AccountSetDelegatePermissions', 86);
+  toggleIx(ixGate, p, 'GroupStatus', 87);
+  toggleIx(ixGate, p, 'PerpExplainLiquidation', 88);
+  toggleIx(ixGate, p, 'AccountCompactTokenPositions', 89);
 
   return ixGate;
 }
@@ -190,6 +190,9 @@ export type MangoV4 = {
     },
     {
       "name": "groupEdit",
+      "docs": [
+        "admin_opt must be None, the admin is changed with group_set_pending_admin."
+      ],
       "accounts": [
         {
           "name": "group",
@@ -283,9 +286,114 @@ export type MangoV4 = {
           "type": {
             "option": "u64"
           }
+        },
+        {
+          "name": "maxLiquidationOvershootHealthOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "maxDustAbsorbQuoteOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "initAssetWeightFloorOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liqorFeeWaiverMinMngoOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "minWeightChangeIntervalSlotsOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "protocolLiquidatorOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        },
+        {
+          "name": "maxLiquidationFeeOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "sweepBankruptPerpPnlOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "liqorFeeWaiverRegistrarOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        }
+      ]
+    },
+    {
+      "name": "groupSetPendingAdmin",
+      "docs": [
+        "Proposes a new group admin, which takes over once it calls group_accept_admin.",
+        "",
+        "Pubkey::default() withdraws the proposal."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "admin"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": [
+        {
+          "name": "pendingAdmin",
+          "type": "publicKey"
         }
       ]
     },
+    {
+      "name": "groupAcceptAdmin",
+      "docs": [
+        "Makes the pending admin proposed with group_set_pending_admin the group admin"
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "pending_admin"
+          ]
+        },
+        {
+          "name": "pendingAdmin",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": []
+    },
     {
       "name": "groupWithdrawInsuranceFund",
       "accounts": [
@@ -427,6 +535,17 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "groupStatus",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "groupClose",
       "accounts": [
@@ -467,7 +586,7 @@ export type MangoV4 = {
       "accounts": [
         {
           "name": "group",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "admin"
@@ -1153,6 +1272,12 @@ export type MangoV4 = {
           "type": {
             "option": "bool"
           }
+        },
+        {
+          "name": "projectBorrowInterestInHealthOpt",
+          "type": {
+            "option": "bool"
+          }
         }
       ]
     },
@@ -1290,7 +1415,7 @@ export type MangoV4 = {
       "accounts": [
         {
           "name": "group",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "admin"
@@ -1357,6 +1482,36 @@ export type MangoV4 = {
       ],
       "args": []
     },
+    {
+      "name": "tokenUpdateIndexAndRateResilient",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "mintInfo",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "oracle",
+            "group"
+          ]
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "instructions",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "accountCreate",
       "accounts": [
@@ -1856,6 +2011,40 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "accountSetOwner",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "owner"
+          ]
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": [
+        {
+          "name": "newOwner",
+          "type": "publicKey"
+        },
+        {
+          "name": "clearDelegates",
+          "type": "bool"
+        }
+      ]
+    },
     {
       "name": "accountToggleFreeze",
       "accounts": [
@@ -1885,6 +2074,99 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "accountCompactTokenPositions",
+      "docs": [
+        "Moves the account's active token positions into the front slots, filling the gaps",
+        "that deactivated positions leave. Token indexes and the active order are unchanged."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "accountSetDelegatePermissions",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "owner"
+          ]
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": [
+        {
+          "name": "delegatePermissions",
+          "type": "u8"
+        },
+        {
+          "name": "temporaryDelegatePermissions",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "accountSetTokenAllowlist",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": [
+        {
+          "name": "tokenIndexes",
+          "type": {
+            "vec": "u16"
+          }
+        }
+      ]
+    },
     {
       "name": "accountClose",
       "accounts": [
@@ -2049,13 +2331,86 @@ export type MangoV4 = {
       ]
     },
     {
-      "name": "stubOracleCreate",
+      "name": "computeAccountHealth",
+      "docs": [
+        "Returns init and maint health as return data, see instructions::compute_account_health"
+      ],
       "accounts": [
         {
           "name": "group",
           "isMut": false,
-          "isSigner": false,
-          "relations": [
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "cacheAccountHealth",
+      "docs": [
+        "Stores the account's health for the current slot, see instructions::cache_account_health"
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "recoverFromBeingLiquidated",
+      "docs": [
+        "Lets the owner clear being_liquidated on a healthy account, see",
+        "instructions::recover_from_being_liquidated"
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "owner"
+          ]
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "stubOracleCreate",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
             "admin"
           ]
         },
@@ -3973,6 +4328,69 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "tokenLiqWithTokenRelaxedStaleness",
+      "docs": [
+        "Admin-only variant of token_liq_with_token for emergencies, where oracles may",
+        "be up to extra_staleness_slots staler than usually allowed."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "admin"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "assetTokenIndex",
+          "type": "u16"
+        },
+        {
+          "name": "liabTokenIndex",
+          "type": "u16"
+        },
+        {
+          "name": "maxLiabTransfer",
+          "type": {
+            "defined": "I80F48"
+          }
+        },
+        {
+          "name": "extraStalenessSlots",
+          "type": "u64"
+        }
+      ]
+    },
     {
       "name": "tokenForceCloseBorrowsWithToken",
       "accounts": [
@@ -4018,6 +4436,51 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "tokenLiqAbsorbDust",
+      "docs": [
+        "Lets the liqor take over a healthy account's tiny dust_token_index deposit",
+        "at oracle price, in exchange for quote_token_index."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "dustTokenIndex",
+          "type": "u16"
+        },
+        {
+          "name": "quoteTokenIndex",
+          "type": "u16"
+        }
+      ]
+    },
     {
       "name": "tokenLiqBankruptcy",
       "accounts": [
@@ -4554,6 +5017,110 @@ export type MangoV4 = {
           "type": {
             "option": "f32"
           }
+        },
+        {
+          "name": "liquidationPriceToleranceOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "baseLiquidationFeeBpsOpt",
+          "type": {
+            "option": "u32"
+          }
+        },
+        {
+          "name": "useEmaForTriggerOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "emaIntervalSecondsOpt",
+          "type": {
+            "option": "u32"
+          }
+        },
+        {
+          "name": "largePositionThresholdLotsOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "largePositionWhitelistOpt",
+          "type": {
+            "option": {
+              "vec": "publicKey"
+            }
+          }
+        },
+        {
+          "name": "dustThresholdLotsOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "dustLiquidationFeeOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "settleFeeBpsOpt",
+          "type": {
+            "option": "u32"
+          }
+        },
+        {
+          "name": "maxLiquidationBasePerSlotOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationThrottleBypassHealthOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "liquidationFeeInsuranceFractionOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "adlEnabledOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "underlyingIdOpt",
+          "type": {
+            "option": "u16"
+          }
+        },
+        {
+          "name": "minLiquidationBaseLotsOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationQuoteRoundingBufferOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "altSettleTokenIndexOpt",
+          "type": {
+            "option": "u16"
+          }
         }
       ]
     },
@@ -5366,7 +5933,7 @@ export type MangoV4 = {
         },
         {
           "name": "perpMarket",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "group",
@@ -5452,7 +6019,82 @@ export type MangoV4 = {
       "args": []
     },
     {
-      "name": "perpSettleFees",
+      "name": "perpAdl",
+      "docs": [
+        "Takes the loss of a bankrupt perp position that the insurance fund couldn't cover",
+        "from the last num_counterparties remaining accounts, ranked by descending pnl."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "admin",
+            "insurance_vault"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "oracle"
+          ]
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "insuranceVault",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "numCounterparties",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "perpSettleAllFunding",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "perpSettleFees",
       "accounts": [
         {
           "name": "group",
@@ -5549,6 +6191,11 @@ export type MangoV4 = {
           "name": "settleBank",
           "isMut": true,
           "isSigner": false,
+          "docs": [
+            "The liqor pays for taken over pnl in this token, unless perp_liq_base_or_positive_pnl_v3",
+            "gets a liqor_quote_token_index. Then that token's bank and oracle must be among the",
+            "health accounts."
+          ],
           "relations": [
             "group"
           ]
@@ -5576,59 +6223,36 @@ export type MangoV4 = {
       ]
     },
     {
-      "name": "perpLiqForceCancelOrders",
+      "name": "perpLiqBaseOrPositivePnlV2",
+      "docs": [
+        "Like perp_liq_base_or_positive_pnl, but allows the liqor to supply the price",
+        "(in native quote per native base) that the base position is transferred at.",
+        "",
+        "With min_net_quote_profit_opt the liquidation only goes through if the liqor reward",
+        "minus liquidation_cost (both in native settle token) reaches that minimum.",
+        "",
+        "With max_health_improvement_opt the liqor limits the liqee's health improvement",
+        "(in native quote) in this call."
+      ],
       "accounts": [
         {
           "name": "group",
           "isMut": false,
           "isSigner": false
         },
-        {
-          "name": "account",
-          "isMut": true,
-          "isSigner": false,
-          "relations": [
-            "group"
-          ]
-        },
         {
           "name": "perpMarket",
           "isMut": true,
           "isSigner": false,
           "relations": [
             "group",
-            "bids",
-            "asks"
+            "oracle"
           ]
         },
         {
-          "name": "bids",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "asks",
-          "isMut": true,
-          "isSigner": false
-        }
-      ],
-      "args": [
-        {
-          "name": "limit",
-          "type": "u8"
-        }
-      ]
-    },
-    {
-      "name": "perpLiqNegativePnlOrBankruptcy",
-      "accounts": [
-        {
-          "name": "group",
+          "name": "oracle",
           "isMut": false,
-          "isSigner": false,
-          "relations": [
-            "insurance_vault"
-          ]
+          "isSigner": false
         },
         {
           "name": "liqor",
@@ -5651,24 +6275,15 @@ export type MangoV4 = {
             "group"
           ]
         },
-        {
-          "name": "perpMarket",
-          "isMut": true,
-          "isSigner": false,
-          "relations": [
-            "group",
-            "oracle"
-          ]
-        },
-        {
-          "name": "oracle",
-          "isMut": false,
-          "isSigner": false
-        },
         {
           "name": "settleBank",
           "isMut": true,
           "isSigner": false,
+          "docs": [
+            "The liqor pays for taken over pnl in this token, unless perp_liq_base_or_positive_pnl_v3",
+            "gets a liqor_quote_token_index. Then that token's bank and oracle must be among the",
+            "health accounts."
+          ],
           "relations": [
             "group"
           ]
@@ -5682,27 +6297,51 @@ export type MangoV4 = {
           "name": "settleOracle",
           "isMut": false,
           "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBaseTransfer",
+          "type": "i64"
         },
         {
-          "name": "insuranceVault",
-          "isMut": true,
-          "isSigner": false
+          "name": "maxPnlTransfer",
+          "type": "u64"
         },
         {
-          "name": "tokenProgram",
-          "isMut": false,
-          "isSigner": false
-        }
-      ],
-      "args": [
+          "name": "liqorPriceOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
         {
-          "name": "maxLiabTransfer",
+          "name": "minNetQuoteProfitOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationCost",
           "type": "u64"
+        },
+        {
+          "name": "maxHealthImprovementOpt",
+          "type": {
+            "option": "u64"
+          }
         }
       ]
     },
     {
-      "name": "perpLiqNegativePnlOrBankruptcyV2",
+      "name": "perpLiqBaseOrPositivePnlV3",
+      "docs": [
+        "Like perp_liq_base_or_positive_pnl_v2. With settle_bad_debt_first the group insurance",
+        "fund first covers the liqee's bad debt in the market, before the base position is",
+        "liquidated against the improved health.",
+        "",
+        "With liqor_quote_token_index the liqor pays for taken over positive pnl in the market's",
+        "alternative settle token instead, converted at oracle prices."
+      ],
       "accounts": [
         {
           "name": "group",
@@ -5713,20 +6352,21 @@ export type MangoV4 = {
           ]
         },
         {
-          "name": "liqor",
+          "name": "perpMarket",
           "isMut": true,
           "isSigner": false,
           "relations": [
-            "group"
+            "group",
+            "oracle"
           ]
         },
         {
-          "name": "liqorOwner",
+          "name": "oracle",
           "isMut": false,
-          "isSigner": true
+          "isSigner": false
         },
         {
-          "name": "liqee",
+          "name": "liqor",
           "isMut": true,
           "isSigner": false,
           "relations": [
@@ -5734,19 +6374,18 @@ export type MangoV4 = {
           ]
         },
         {
-          "name": "perpMarket",
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
           "isMut": true,
           "isSigner": false,
           "relations": [
-            "group",
-            "oracle"
+            "group"
           ]
         },
-        {
-          "name": "oracle",
-          "isMut": false,
-          "isSigner": false
-        },
         {
           "name": "settleBank",
           "isMut": true,
@@ -5770,24 +6409,6 @@ export type MangoV4 = {
           "isMut": true,
           "isSigner": false
         },
-        {
-          "name": "insuranceBank",
-          "isMut": true,
-          "isSigner": false,
-          "relations": [
-            "group"
-          ]
-        },
-        {
-          "name": "insuranceBankVault",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "insuranceOracle",
-          "isMut": false,
-          "isSigner": false
-        },
         {
           "name": "tokenProgram",
           "isMut": false,
@@ -5796,13 +6417,53 @@ export type MangoV4 = {
       ],
       "args": [
         {
-          "name": "maxLiabTransfer",
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
+          "type": "u64"
+        },
+        {
+          "name": "liqorPriceOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "minNetQuoteProfitOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationCost",
           "type": "u64"
+        },
+        {
+          "name": "maxHealthImprovementOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "settleBadDebtFirst",
+          "type": "bool"
+        },
+        {
+          "name": "liqorQuoteTokenIndex",
+          "type": {
+            "option": "u16"
+          }
         }
       ]
     },
     {
-      "name": "tokenConditionalSwapCreate",
+      "name": "perpExplainLiquidation",
+      "docs": [
+        "Emits what perp_liq_base_or_positive_pnl_v2 would do with these arguments,",
+        "without changing any accounts."
+      ],
       "accounts": [
         {
           "name": "group",
@@ -5810,20 +6471,23 @@ export type MangoV4 = {
           "isSigner": false
         },
         {
-          "name": "account",
-          "isMut": true,
+          "name": "perpMarket",
+          "isMut": false,
           "isSigner": false,
           "relations": [
             "group"
           ]
         },
         {
-          "name": "authority",
+          "name": "liqor",
           "isMut": false,
-          "isSigner": true
-        },
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
         {
-          "name": "buyBank",
+          "name": "liqee",
           "isMut": false,
           "isSigner": false,
           "relations": [
@@ -5831,7 +6495,7 @@ export type MangoV4 = {
           ]
         },
         {
-          "name": "sellBank",
+          "name": "settleBank",
           "isMut": false,
           "isSigner": false,
           "relations": [
@@ -5841,49 +6505,94 @@ export type MangoV4 = {
       ],
       "args": [
         {
-          "name": "maxBuy",
-          "type": "u64"
+          "name": "maxBaseTransfer",
+          "type": "i64"
         },
         {
-          "name": "maxSell",
+          "name": "maxPnlTransfer",
           "type": "u64"
         },
         {
-          "name": "expiryTimestamp",
+          "name": "liqorPriceOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "minNetQuoteProfitOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationCost",
           "type": "u64"
         },
         {
-          "name": "priceLowerLimit",
-          "type": "f64"
+          "name": "maxHealthImprovementOpt",
+          "type": {
+            "option": "u64"
+          }
+        }
+      ]
+    },
+    {
+      "name": "perpLiqForceCancelOrders",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
         },
         {
-          "name": "priceUpperLimit",
-          "type": "f64"
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
         },
         {
-          "name": "pricePremiumRate",
-          "type": "f64"
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "bids",
+            "asks"
+          ]
         },
         {
-          "name": "allowCreatingDeposits",
-          "type": "bool"
+          "name": "bids",
+          "isMut": true,
+          "isSigner": false
         },
         {
-          "name": "allowCreatingBorrows",
-          "type": "bool"
+          "name": "asks",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "limit",
+          "type": "u8"
         }
       ]
     },
     {
-      "name": "tokenConditionalSwapCreateV2",
+      "name": "perpLiqNegativePnlOrBankruptcy",
       "accounts": [
         {
           "name": "group",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "relations": [
+            "insurance_vault"
+          ]
         },
         {
-          "name": "account",
+          "name": "liqor",
           "isMut": true,
           "isSigner": false,
           "relations": [
@@ -5891,76 +6600,170 @@ export type MangoV4 = {
           ]
         },
         {
-          "name": "authority",
+          "name": "liqorOwner",
           "isMut": false,
           "isSigner": true
         },
         {
-          "name": "buyBank",
-          "isMut": false,
+          "name": "liqee",
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "group"
           ]
         },
         {
-          "name": "sellBank",
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "oracle"
+          ]
+        },
+        {
+          "name": "oracle",
           "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "group"
           ]
+        },
+        {
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "insuranceVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "maxBuy",
+          "name": "maxLiabTransfer",
           "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqNegativePnlOrBankruptcyV2",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "insurance_vault"
+          ]
         },
         {
-          "name": "maxSell",
-          "type": "u64"
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
         },
         {
-          "name": "expiryTimestamp",
-          "type": "u64"
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
         },
         {
-          "name": "priceLowerLimit",
-          "type": "f64"
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
         },
         {
-          "name": "priceUpperLimit",
-          "type": "f64"
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "oracle"
+          ]
         },
         {
-          "name": "pricePremiumRate",
-          "type": "f64"
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
         },
         {
-          "name": "allowCreatingDeposits",
-          "type": "bool"
+          "name": "settleBank",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
         },
         {
-          "name": "allowCreatingBorrows",
-          "type": "bool"
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
         },
         {
-          "name": "displayPriceStyle",
-          "type": {
-            "defined": "TokenConditionalSwapDisplayPriceStyle"
-          }
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
         },
         {
-          "name": "intention",
-          "type": {
-            "defined": "TokenConditionalSwapIntention"
-          }
+          "name": "insuranceVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "insuranceBank",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "insuranceBankVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "insuranceOracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxLiabTransfer",
+          "type": "u64"
         }
       ]
     },
     {
-      "name": "tokenConditionalSwapCreatePremiumAuction",
+      "name": "tokenConditionalSwapCreate",
       "accounts": [
         {
           "name": "group",
@@ -6019,7 +6822,7 @@ export type MangoV4 = {
           "type": "f64"
         },
         {
-          "name": "maxPricePremiumRate",
+          "name": "pricePremiumRate",
           "type": "f64"
         },
         {
@@ -6029,27 +6832,11 @@ export type MangoV4 = {
         {
           "name": "allowCreatingBorrows",
           "type": "bool"
-        },
-        {
-          "name": "displayPriceStyle",
-          "type": {
-            "defined": "TokenConditionalSwapDisplayPriceStyle"
-          }
-        },
-        {
-          "name": "intention",
-          "type": {
-            "defined": "TokenConditionalSwapIntention"
-          }
-        },
-        {
-          "name": "durationSeconds",
-          "type": "u64"
         }
       ]
     },
     {
-      "name": "tokenConditionalSwapCreateLinearAuction",
+      "name": "tokenConditionalSwapCreateV2",
       "accounts": [
         {
           "name": "group",
@@ -6100,7 +6887,181 @@ export type MangoV4 = {
           "type": "u64"
         },
         {
-          "name": "priceStart",
+          "name": "priceLowerLimit",
+          "type": "f64"
+        },
+        {
+          "name": "priceUpperLimit",
+          "type": "f64"
+        },
+        {
+          "name": "pricePremiumRate",
+          "type": "f64"
+        },
+        {
+          "name": "allowCreatingDeposits",
+          "type": "bool"
+        },
+        {
+          "name": "allowCreatingBorrows",
+          "type": "bool"
+        },
+        {
+          "name": "displayPriceStyle",
+          "type": {
+            "defined": "TokenConditionalSwapDisplayPriceStyle"
+          }
+        },
+        {
+          "name": "intention",
+          "type": {
+            "defined": "TokenConditionalSwapIntention"
+          }
+        }
+      ]
+    },
+    {
+      "name": "tokenConditionalSwapCreatePremiumAuction",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "buyBank",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "sellBank",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBuy",
+          "type": "u64"
+        },
+        {
+          "name": "maxSell",
+          "type": "u64"
+        },
+        {
+          "name": "expiryTimestamp",
+          "type": "u64"
+        },
+        {
+          "name": "priceLowerLimit",
+          "type": "f64"
+        },
+        {
+          "name": "priceUpperLimit",
+          "type": "f64"
+        },
+        {
+          "name": "maxPricePremiumRate",
+          "type": "f64"
+        },
+        {
+          "name": "allowCreatingDeposits",
+          "type": "bool"
+        },
+        {
+          "name": "allowCreatingBorrows",
+          "type": "bool"
+        },
+        {
+          "name": "displayPriceStyle",
+          "type": {
+            "defined": "TokenConditionalSwapDisplayPriceStyle"
+          }
+        },
+        {
+          "name": "intention",
+          "type": {
+            "defined": "TokenConditionalSwapIntention"
+          }
+        },
+        {
+          "name": "durationSeconds",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "tokenConditionalSwapCreateLinearAuction",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "buyBank",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "sellBank",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBuy",
+          "type": "u64"
+        },
+        {
+          "name": "maxSell",
+          "type": "u64"
+        },
+        {
+          "name": "expiryTimestamp",
+          "type": "u64"
+        },
+        {
+          "name": "priceStart",
           "type": "f64"
         },
         {
@@ -6977,7 +7938,7 @@ export type MangoV4 = {
       ]
     },
     {
-      "name": "openbookV2CancelOrder",
+      "name": "openbookV2PlaceTakerOrder",
       "accounts": [
         {
           "name": "group",
@@ -6997,11 +7958,6 @@ export type MangoV4 = {
           "isMut": false,
           "isSigner": true
         },
-        {
-          "name": "openOrders",
-          "isMut": true,
-          "isSigner": false
-        },
         {
           "name": "openbookV2Market",
           "isMut": false,
@@ -7019,11 +7975,12 @@ export type MangoV4 = {
         },
         {
           "name": "openbookV2MarketExternal",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "bids",
-            "asks"
+            "asks",
+            "event_heap"
           ]
         },
         {
@@ -7035,88 +7992,234 @@ export type MangoV4 = {
           "name": "asks",
           "isMut": true,
           "isSigner": false
-        }
-      ],
-      "args": [
-        {
-          "name": "side",
-          "type": {
-            "defined": "OpenbookV2Side"
-          }
         },
         {
-          "name": "orderId",
-          "type": "u128"
-        }
-      ]
-    },
-    {
-      "name": "openbookV2SettleFunds",
-      "accounts": [
-        {
-          "name": "group",
-          "isMut": false,
+          "name": "eventHeap",
+          "isMut": true,
           "isSigner": false
         },
         {
-          "name": "account",
+          "name": "marketRequestQueue",
           "isMut": true,
-          "isSigner": false,
-          "relations": [
-            "group"
-          ]
+          "isSigner": false
         },
         {
-          "name": "authority",
+          "name": "marketBaseVault",
           "isMut": true,
-          "isSigner": true
+          "isSigner": false
         },
         {
-          "name": "openOrders",
+          "name": "marketQuoteVault",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "openbookV2Market",
-          "isMut": false,
-          "isSigner": false,
-          "relations": [
-            "group",
-            "openbook_v2_program",
-            "openbook_v2_market_external"
-          ]
-        },
-        {
-          "name": "openbookV2Program",
+          "name": "marketVaultSigner",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "openbookV2MarketExternal",
+          "name": "payerBank",
           "isMut": true,
           "isSigner": false,
+          "docs": [
+            "The bank that pays for the order, if necessary"
+          ],
           "relations": [
-            "market_base_vault",
-            "market_quote_vault"
+            "group"
           ]
         },
         {
-          "name": "marketBaseVault",
+          "name": "payerVault",
           "isMut": true,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "The bank vault that pays for the order, if necessary"
+          ]
         },
         {
-          "name": "marketQuoteVault",
-          "isMut": true,
+          "name": "payerOracle",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "marketVaultSigner",
+          "name": "tokenProgram",
           "isMut": false,
-          "isSigner": false,
-          "docs": [
-            "needed for the automatic settle_funds call"
-          ]
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "side",
+          "type": "u8"
+        },
+        {
+          "name": "limitPrice",
+          "type": "u64"
+        },
+        {
+          "name": "maxBaseQty",
+          "type": "u64"
+        },
+        {
+          "name": "maxNativeQuoteQtyIncludingFees",
+          "type": "u64"
+        },
+        {
+          "name": "selfTradeBehavior",
+          "type": "u8"
+        },
+        {
+          "name": "clientOrderId",
+          "type": "u64"
+        },
+        {
+          "name": "limit",
+          "type": "u16"
+        }
+      ]
+    },
+    {
+      "name": "openbookV2CancelOrder",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "openOrders",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "openbookV2Market",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "openbook_v2_program",
+            "openbook_v2_market_external"
+          ]
+        },
+        {
+          "name": "openbookV2Program",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "openbookV2MarketExternal",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "bids",
+            "asks"
+          ]
+        },
+        {
+          "name": "bids",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "asks",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "side",
+          "type": {
+            "defined": "OpenbookV2Side"
+          }
+        },
+        {
+          "name": "orderId",
+          "type": "u128"
+        }
+      ]
+    },
+    {
+      "name": "openbookV2SettleFunds",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "authority",
+          "isMut": true,
+          "isSigner": true
+        },
+        {
+          "name": "openOrders",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "openbookV2Market",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "openbook_v2_program",
+            "openbook_v2_market_external"
+          ]
+        },
+        {
+          "name": "openbookV2Program",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "openbookV2MarketExternal",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "market_base_vault",
+            "market_quote_vault"
+          ]
+        },
+        {
+          "name": "marketBaseVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketQuoteVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketVaultSigner",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "needed for the automatic settle_funds call"
+          ]
         },
         {
           "name": "quoteBank",
@@ -7872,6 +8975,14 @@ export type MangoV4 = {
             ],
             "type": "f32"
           },
+          {
+            "name": "projectBorrowInterestInHealth",
+            "docs": [
+              "If set to 1, health computations value borrows with the interest that accrued",
+              "since index_last_updated, instead of waiting for the next index update."
+            ],
+            "type": "u8"
+          },
           {
             "name": "padding2",
             "type": {
@@ -7881,6 +8992,15 @@ export type MangoV4 = {
               ]
             }
           },
+          {
+            "name": "lastWeightChangeSlot",
+            "docs": [
+              "Slot of the last token_edit that changed this token's weights, 0 if never.",
+              "",
+              "See Group::min_weight_change_interval_slots."
+            ],
+            "type": "u64"
+          },
           {
             "name": "potentialOpenbookTokens",
             "docs": [
@@ -7894,7 +9014,7 @@ export type MangoV4 = {
             "type": {
               "array": [
                 "u8",
-                1888
+                1876
               ]
             }
           }
@@ -8039,73 +9159,196 @@ export type MangoV4 = {
             "type": "u64"
           },
           {
-            "name": "reserved",
-            "type": {
-              "array": [
-                "u8",
-                1800
-              ]
-            }
-          }
-        ]
-      }
-    },
-    {
-      "name": "mangoAccount",
-      "type": {
-        "kind": "struct",
-        "fields": [
-          {
-            "name": "group",
-            "type": "publicKey"
+            "name": "maxLiquidationOvershootHealth",
+            "docs": [
+              "Perp base liquidation avoids pushing the liqee's liquidation end health above",
+              "this value (in quote native), as long as it can still make progress.",
+              "",
+              "When set to 0, there's no limit."
+            ],
+            "type": "u64"
           },
           {
-            "name": "owner",
-            "type": "publicKey"
+            "name": "maxDustAbsorbQuote",
+            "docs": [
+              "Token positions worth at most this much (in quote native) can be taken over",
+              "by anyone with token_liq_absorb_dust, to allow closing accounts with dust left.",
+              "",
+              "When set to 0, dust absorption is disabled."
+            ],
+            "type": "u64"
           },
           {
-            "name": "name",
-            "type": {
-              "array": [
-                "u8",
-                32
-              ]
-            }
+            "name": "initAssetWeightFloor",
+            "docs": [
+              "Tokens that aren't reduce-only must have an init_asset_weight of at least this value.",
+              "",
+              "Guards against accidentally listing or editing tokens to provide no init collateral."
+            ],
+            "type": "f32"
           },
           {
-            "name": "delegate",
-            "type": "publicKey"
+            "name": "maxLiquidationFee",
+            "docs": [
+              "Upper limit on the base liquidation fee of every perp market, see",
+              "capped_liquidation_fee().",
+              "",
+              "When set to 0, market fees are not limited."
+            ],
+            "type": "f32"
           },
           {
-            "name": "accountNum",
-            "type": "u32"
+            "name": "liqorFeeWaiverMinMngo",
+            "docs": [
+              "Liqors whose owner has at least this much native mngo locked in the governance",
+              "stake registry liqor_fee_waiver_registrar receive the platform liquidation fee on",
+              "perp base liquidations, instead of the group.",
+              "",
+              "When set to 0, no liqor receives the platform fee."
+            ],
+            "type": "u64"
           },
           {
-            "name": "beingLiquidated",
+            "name": "minWeightChangeIntervalSlots",
             "docs": [
-              "Tracks that this account should be liquidated until init_health >= 0.",
-              "",
-              "Normally accounts can not be liquidated while maint_health >= 0. But when an account",
-              "reaches maint_health < 0, liquidators will call a liquidation instruction and thereby",
-              "set this flag. Now the account may be liquidated until init_health >= 0.",
+              "Minimum number of slots between two token_edit calls that change a token's weights.",
               "",
-              "Many actions should be disabled while the account is being liquidated, even if",
-              "its maint health has recovered to positive. Creating new open orders would, for example,",
-              "confuse liquidators."
+              "When set to 0, weights can be changed at any time."
             ],
-            "type": "u8"
+            "type": "u64"
           },
           {
-            "name": "inHealthRegion",
+            "name": "protocolLiquidator",
             "docs": [
-              "The account is currently inside a health region marked by HealthRegionBegin...HealthRegionEnd.",
+              "Group-operated mango account that liquidates on behalf of the protocol.",
               "",
-              "Must never be set after a transaction ends."
+              "Its health isn't checked after perp liquidations, since the protocol backstops it.",
+              "Unset if Pubkey::default()."
             ],
-            "type": "u8"
+            "type": "publicKey"
           },
           {
-            "name": "bump",
+            "name": "sweepBankruptPerpPnl",
+            "docs": [
+              "If set, perp_liq_negative_pnl_or_bankruptcy sells positive perp pnl that is left",
+              "on a bankrupt account to the liqor and pays the proceeds into the insurance fund,",
+              "see sweep_bankrupt_positive_pnl()."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding3",
+            "type": {
+              "array": [
+                "u8",
+                1
+              ]
+            }
+          },
+          {
+            "name": "tokenCount",
+            "docs": [
+              "Number of registered tokens, maintained by token_register(_trustless) and",
+              "token_deregister.",
+              "",
+              "Groups that listed tokens before this was tracked undercount them."
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "padding4",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "pendingAdmin",
+            "docs": [
+              "Admin proposed with group_set_pending_admin, becomes the admin once it calls group_accept_admin.",
+              "",
+              "Unset if Pubkey::default()."
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "liqorFeeWaiverRegistrar",
+            "docs": [
+              "Voter stake registry Registrar of the mngo governance realm, see",
+              "liqor_fee_waiver_min_mngo. Unset if Pubkey::default()."
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "reserved",
+            "type": {
+              "array": [
+                "u8",
+                1656
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "mangoAccount",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "group",
+            "type": "publicKey"
+          },
+          {
+            "name": "owner",
+            "type": "publicKey"
+          },
+          {
+            "name": "name",
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "delegate",
+            "type": "publicKey"
+          },
+          {
+            "name": "accountNum",
+            "type": "u32"
+          },
+          {
+            "name": "beingLiquidated",
+            "docs": [
+              "Tracks that this account should be liquidated until init_health >= 0.",
+              "",
+              "Normally accounts can not be liquidated while maint_health >= 0. But when an account",
+              "reaches maint_health < 0, liquidators will call a liquidation instruction and thereby",
+              "set this flag. Now the account may be liquidated until init_health >= 0.",
+              "",
+              "Many actions should be disabled while the account is being liquidated, even if",
+              "its maint health has recovered to positive. Creating new open orders would, for example,",
+              "confuse liquidators."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "inHealthRegion",
+            "docs": [
+              "The account is currently inside a health region marked by HealthRegionBegin...HealthRegionEnd.",
+              "",
+              "Must never be set after a transaction ends."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "bump",
             "type": "u8"
           },
           {
@@ -8175,12 +9418,115 @@ export type MangoV4 = {
             ],
             "type": "u64"
           },
+          {
+            "name": "liquidationBaseAccumulated",
+            "docs": [
+              "Perp base lots taken over from this account during the current liquidation.",
+              "Reset when the account stops being liquidated."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "liquidationQuoteAccumulated",
+            "docs": [
+              "Perp quote (native, absolute) exchanged for liquidation_base_accumulated."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "perpTradingFeesPaid",
+            "docs": [
+              "Perp trading fees paid over the account's lifetime, in native quote.",
+              "Maker rebates reduce it."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "perpFundingPaidDeactivated",
+            "docs": [
+              "Perp funding paid by perp positions that have been deactivated, in native quote.",
+              "See perp_funding_paid() for the lifetime total."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "perpLiquidationFeesPaid",
+            "docs": [
+              "Perp liquidation fees paid as a liqee over the account's lifetime, in native quote:",
+              "base liquidation fees and the discount on positive pnl taken over by liqors."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "tokenAllowlist",
+            "docs": [
+              "Tokens the account may deposit into or borrow, set by the group admin.",
+              "Only the first token_allowlist_len entries are used. All tokens are allowed if empty."
+            ],
+            "type": {
+              "array": [
+                "u16",
+                8
+              ]
+            }
+          },
+          {
+            "name": "tokenAllowlistLen",
+            "type": "u8"
+          },
+          {
+            "name": "delegatePermissions",
+            "docs": [
+              "DelegatePermission bits for delegate and temporary_delegate, see",
+              "MangoAccountFixed::has_permission(). Zero means unrestricted."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "temporaryDelegatePermissions",
+            "type": "u8"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                5
+              ]
+            }
+          },
+          {
+            "name": "cachedInitHealth",
+            "docs": [
+              "Health stored by cache_account_health and health_region_end, see",
+              "MangoAccountFixed::cached_health()"
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "cachedMaintHealth",
+            "type": "i64"
+          },
+          {
+            "name": "cachedHealthSlot",
+            "docs": [
+              "Slot the cached health was computed in. Zero if it's invalid."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "beingLiquidatedSlot",
+            "docs": [
+              "Slot in which being_liquidated was last set. Zero if the account isn't being liquidated."
+            ],
+            "type": "u64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                152
+                56
               ]
             }
           },
@@ -9000,74 +10346,305 @@ export type MangoV4 = {
             }
           },
           {
-            "name": "reserved",
+            "name": "liquidationPriceTolerance",
+            "docs": [
+              "Max relative deviation of a liqor-supplied liquidation price from the oracle price.",
+              "",
+              "Allows liquidators to use an externally computed (e.g. index) price for the base",
+              "transfer. With 0 only the oracle price itself is accepted."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "useEmaForTrigger",
+            "docs": [
+              "If true, maint health (the liquidation trigger) values the base position at",
+              "ema_price instead of the oracle price. Liquidation transfers still use the oracle price."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding5",
             "type": {
               "array": [
                 "u8",
-                1848
+                3
               ]
             }
-          }
-        ]
-      }
-    },
-    {
-      "name": "serum3Market",
-      "type": {
-        "kind": "struct",
-        "fields": [
-          {
-            "name": "group",
-            "type": "publicKey"
-          },
-          {
-            "name": "baseTokenIndex",
-            "type": "u16"
           },
           {
-            "name": "quoteTokenIndex",
-            "type": "u16"
+            "name": "emaPrice",
+            "docs": [
+              "Exponential moving average of the oracle price, updated with funding"
+            ],
+            "type": "f64"
           },
           {
-            "name": "reduceOnly",
-            "type": "u8"
+            "name": "emaLastUpdated",
+            "docs": [
+              "Timestamp of the last ema_price update"
+            ],
+            "type": "u64"
           },
           {
-            "name": "forceClose",
-            "type": "u8"
+            "name": "emaIntervalSeconds",
+            "docs": [
+              "Time constant of the ema_price in seconds"
+            ],
+            "type": "u32"
           },
           {
-            "name": "padding1",
+            "name": "padding6",
             "type": {
               "array": [
                 "u8",
-                2
+                4
               ]
             }
           },
           {
-            "name": "name",
+            "name": "largePositionThresholdLots",
+            "docs": [
+              "Liqors may only grow their base position above this many lots in liquidations",
+              "if they are in large_position_whitelist. Disabled if 0."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "largePositionWhitelist",
+            "docs": [
+              "Liqor mango accounts that may exceed large_position_threshold_lots.",
+              "Unused entries are Pubkey::default()."
+            ],
             "type": {
               "array": [
-                "u8",
-                16
+                "publicKey",
+                4
               ]
             }
           },
           {
-            "name": "serumProgram",
-            "type": "publicKey"
-          },
-          {
-            "name": "serumMarketExternal",
-            "type": "publicKey"
+            "name": "dustThresholdLots",
+            "docs": [
+              "Liqee positions of at most this many lots are dust: base liquidation of these",
+              "last lots pays dust_liquidation_fee instead of base_liquidation_fee, to give liqors",
+              "a reason to fully close positions. Disabled if 0."
+            ],
+            "type": "i64"
           },
           {
-            "name": "marketIndex",
-            "type": "u16"
+            "name": "dustLiquidationFee",
+            "docs": [
+              "Liquidation fee for the lots in the dust region, see dust_threshold_lots.",
+              "",
+              "Only applies if it's larger than base_liquidation_fee."
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
           },
           {
-            "name": "bump",
+            "name": "accruedSettleFees",
+            "docs": [
+              "Settle fees collected by the protocol (in native settle token)",
+              "",
+              "They are added to the settle bank's collected_fees_native."
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "settleFeeBps",
+            "docs": [
+              "Fee taken by the protocol from the positive side of pnl settlements, in basis points"
+            ],
+            "type": "u32"
+          },
+          {
+            "name": "padding7",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "maxLiquidationBasePerSlot",
+            "docs": [
+              "Max number of base lots that may be liquidated in a single slot, to slow down",
+              "liquidation cascades. Disabled if 0."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "liquidationBaseInSlot",
+            "docs": [
+              "Base lots liquidated in liquidation_base_slot"
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "liquidationBaseSlot",
+            "docs": [
+              "Slot that liquidation_base_in_slot refers to"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "liquidationThrottleBypassHealth",
+            "docs": [
+              "Liqees with a maint health below minus this value (in quote native) are so far",
+              "underwater that they're liquidated regardless of max_liquidation_base_per_slot.",
+              "No liqee bypasses the limit if 0."
+            ],
+            "type": "f64"
+          },
+          {
+            "name": "liquidationFeeInsuranceFraction",
+            "docs": [
+              "Fraction of base_liquidation_fee that goes to the market's fees instead of the liqor.",
+              "",
+              "The liqee always pays the full fee. Between 0 and 1."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "adlEnabled",
+            "docs": [
+              "If set, losses of bankrupt accounts that the insurance fund can't cover are not",
+              "socialized through funding but left for perp_adl to take from profitable accounts."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding8",
+            "type": {
+              "array": [
+                "u8",
+                1
+              ]
+            }
+          },
+          {
+            "name": "underlyingId",
+            "docs": [
+              "Markets with the same non-zero underlying_id track the same underlying asset,",
+              "like a perpetual and a dated future on it. Zero means not grouped with other markets.",
+              "",
+              "Only used for reporting net exposure, see HealthCache::net_delta_for_underlying()."
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "minLiquidationBaseLots",
+            "docs": [
+              "Base liquidations must transfer at least this many lots, unless they close the",
+              "liqee's base position. Stops dust liquidations that each cost the liqee a fee.",
+              "Disabled if 0."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "liquidationQuoteRoundingBuffer",
+            "docs": [
+              "Quote (in native settle token) kept back from the liqor on every base liquidation",
+              "and added to fees_accrued, so rounding in the transfer can't cost the protocol.",
+              "Disabled if 0."
+            ],
+            "type": "f64"
+          },
+          {
+            "name": "altSettleTokenIndex",
+            "docs": [
+              "Token that liqors may pay for taken over positive pnl in instead of the settle token,",
+              "see perp_liq_base_or_positive_pnl_v3. Only used if alt_settle_token_enabled is set."
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "altSettleTokenEnabled",
+            "type": "u8"
+          },
+          {
+            "name": "padding9",
+            "type": {
+              "array": [
+                "u8",
+                5
+              ]
+            }
+          },
+          {
+            "name": "reserved",
+            "type": {
+              "array": [
+                "u8",
+                1568
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "serum3Market",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "group",
+            "type": "publicKey"
+          },
+          {
+            "name": "baseTokenIndex",
+            "type": "u16"
+          },
+          {
+            "name": "quoteTokenIndex",
+            "type": "u16"
+          },
+          {
+            "name": "reduceOnly",
+            "type": "u8"
+          },
+          {
+            "name": "forceClose",
+            "type": "u8"
+          },
+          {
+            "name": "padding1",
+            "type": {
+              "array": [
+                "u8",
+                2
+              ]
+            }
+          },
+          {
+            "name": "name",
+            "type": {
+              "array": [
+                "u8",
+                16
+              ]
+            }
+          },
+          {
+            "name": "serumProgram",
+            "type": "publicKey"
+          },
+          {
+            "name": "serumMarketExternal",
+            "type": "publicKey"
+          },
+          {
+            "name": "marketIndex",
+            "type": "u16"
+          },
+          {
+            "name": "bump",
             "type": "u8"
           },
           {
@@ -9392,6 +10969,22 @@ export type MangoV4 = {
         ]
       }
     },
+    {
+      "name": "PerpSettleFundingDetail",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "perpMarketIndex",
+            "type": "u16"
+          },
+          {
+            "name": "funding",
+            "type": "i128"
+          }
+        ]
+      }
+    },
     {
       "name": "TokenPosition",
       "type": {
@@ -9924,89 +11517,196 @@ export type MangoV4 = {
       }
     },
     {
-      "name": "PerpOpenOrder",
+      "name": "PerpPositionWire",
+      "docs": [
+        "Stable serialization of PerpPosition for clients and indexers.",
+        "",
+        "Unlike PerpPosition it has no padding or reserved bytes and stores I80F48 values",
+        "as their i128 bits, so it doesn't change when the zero-copy layout is rearranged.",
+        "Fields may only ever be appended, together with a bump of the version."
+      ],
       "type": {
         "kind": "struct",
         "fields": [
           {
-            "name": "sideAndTree",
+            "name": "version",
             "type": "u8"
           },
           {
-            "name": "padding1",
-            "type": {
-              "array": [
-                "u8",
-                1
-              ]
-            }
+            "name": "marketIndex",
+            "type": "u16"
           },
           {
-            "name": "market",
-            "type": "u16"
+            "name": "settlePnlLimitWindow",
+            "type": "u32"
           },
           {
-            "name": "padding2",
-            "type": {
-              "array": [
-                "u8",
-                4
-              ]
-            }
+            "name": "settlePnlLimitSettledInCurrentWindowNative",
+            "type": "i64"
           },
           {
-            "name": "clientId",
-            "type": "u64"
+            "name": "basePositionLots",
+            "type": "i64"
           },
           {
-            "name": "id",
-            "type": "u128"
+            "name": "quotePositionNative",
+            "type": "i128"
           },
           {
-            "name": "quantity",
+            "name": "quoteRunningNative",
             "type": "i64"
           },
           {
-            "name": "reserved",
-            "type": {
-              "array": [
-                "u8",
-                56
-              ]
-            }
-          }
-        ]
-      }
-    },
-    {
-      "name": "MangoAccountFixed",
-      "type": {
-        "kind": "struct",
-        "fields": [
-          {
-            "name": "group",
-            "type": "publicKey"
+            "name": "longSettledFunding",
+            "type": "i128"
           },
           {
-            "name": "owner",
-            "type": "publicKey"
+            "name": "shortSettledFunding",
+            "type": "i128"
           },
           {
-            "name": "name",
-            "type": {
-              "array": [
-                "u8",
-                32
-              ]
-            }
+            "name": "bidsBaseLots",
+            "type": "i64"
           },
           {
-            "name": "delegate",
-            "type": "publicKey"
+            "name": "asksBaseLots",
+            "type": "i64"
           },
           {
-            "name": "accountNum",
-            "type": "u32"
+            "name": "takerBaseLots",
+            "type": "i64"
+          },
+          {
+            "name": "takerQuoteLots",
+            "type": "i64"
+          },
+          {
+            "name": "cumulativeLongFunding",
+            "type": "f64"
+          },
+          {
+            "name": "cumulativeShortFunding",
+            "type": "f64"
+          },
+          {
+            "name": "makerVolume",
+            "type": "u64"
+          },
+          {
+            "name": "takerVolume",
+            "type": "u64"
+          },
+          {
+            "name": "perpSpotTransfers",
+            "type": "i64"
+          },
+          {
+            "name": "avgEntryPricePerBaseLot",
+            "type": "f64"
+          },
+          {
+            "name": "deprecatedRealizedTradePnlNative",
+            "type": "i128"
+          },
+          {
+            "name": "oneshotSettlePnlAllowance",
+            "type": "i128"
+          },
+          {
+            "name": "recurringSettlePnlAllowance",
+            "type": "i64"
+          },
+          {
+            "name": "realizedPnlForPositionNative",
+            "type": "i128"
+          }
+        ]
+      }
+    },
+    {
+      "name": "PerpOpenOrder",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "sideAndTree",
+            "type": "u8"
+          },
+          {
+            "name": "padding1",
+            "type": {
+              "array": [
+                "u8",
+                1
+              ]
+            }
+          },
+          {
+            "name": "market",
+            "type": "u16"
+          },
+          {
+            "name": "padding2",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "clientId",
+            "type": "u64"
+          },
+          {
+            "name": "id",
+            "type": "u128"
+          },
+          {
+            "name": "quantity",
+            "type": "i64"
+          },
+          {
+            "name": "reserved",
+            "type": {
+              "array": [
+                "u8",
+                56
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "MangoAccountFixed",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "group",
+            "type": "publicKey"
+          },
+          {
+            "name": "owner",
+            "type": "publicKey"
+          },
+          {
+            "name": "name",
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "delegate",
+            "type": "publicKey"
+          },
+          {
+            "name": "accountNum",
+            "type": "u32"
           },
           {
             "name": "beingLiquidated",
@@ -10068,12 +11768,78 @@ export type MangoV4 = {
             "name": "lastCollateralFeeCharge",
             "type": "u64"
           },
+          {
+            "name": "liquidationBaseAccumulated",
+            "type": "i64"
+          },
+          {
+            "name": "liquidationQuoteAccumulated",
+            "type": "i64"
+          },
+          {
+            "name": "perpTradingFeesPaid",
+            "type": "i64"
+          },
+          {
+            "name": "perpFundingPaidDeactivated",
+            "type": "i64"
+          },
+          {
+            "name": "perpLiquidationFeesPaid",
+            "type": "i64"
+          },
+          {
+            "name": "tokenAllowlist",
+            "type": {
+              "array": [
+                "u16",
+                8
+              ]
+            }
+          },
+          {
+            "name": "tokenAllowlistLen",
+            "type": "u8"
+          },
+          {
+            "name": "delegatePermissions",
+            "type": "u8"
+          },
+          {
+            "name": "temporaryDelegatePermissions",
+            "type": "u8"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                5
+              ]
+            }
+          },
+          {
+            "name": "cachedInitHealth",
+            "type": "i64"
+          },
+          {
+            "name": "cachedMaintHealth",
+            "type": "i64"
+          },
+          {
+            "name": "cachedHealthSlot",
+            "type": "u64"
+          },
+          {
+            "name": "beingLiquidatedSlot",
+            "type": "u64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                152
+                56
               ]
             }
           }
@@ -11182,6 +12948,23 @@ export type MangoV4 = {
         ]
       }
     },
+    {
+      "name": "LiquidationScenarioAsset",
+      "docs": [
+        "The asset whose price moves in a LiquidationScenario"
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Token"
+          },
+          {
+            "name": "Perp"
+          }
+        ]
+      }
+    },
     {
       "name": "SpotMarketIndex",
       "type": {
@@ -11243,6 +13026,38 @@ export type MangoV4 = {
         ]
       }
     },
+    {
+      "name": "LiquidationConstraint",
+      "docs": [
+        "The limit that determined the size of a base reduction step"
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "None"
+          },
+          {
+            "name": "Health"
+          },
+          {
+            "name": "PnlLimit"
+          },
+          {
+            "name": "LiqeeBasePosition"
+          },
+          {
+            "name": "MaxBaseTransfer"
+          },
+          {
+            "name": "MaxOvershootHealth"
+          },
+          {
+            "name": "MaxHealthImprovement"
+          }
+        ]
+      }
+    },
     {
       "name": "IxGate",
       "docs": [
@@ -11490,29 +13305,88 @@ export type MangoV4 = {
           },
           {
             "name": "GroupChangeInsuranceFund"
+          },
+          {
+            "name": "AccountSetOwner"
+          },
+          {
+            "name": "PerpSettleAllFunding"
+          },
+          {
+            "name": "TokenLiqWithTokenRelaxedStaleness"
+          },
+          {
+            "name": "TokenLiqAbsorbDust"
+          },
+          {
+            "name": "PerpAdl"
+          },
+          {
+            "name": "AccountSetTokenAllowlist"
+          },
+          {
+            "name": "ComputeAccountHealth"
+          },
+          {
+            "name": "CacheAccountHealth"
+          },
+          {
+            "name": "RecoverFromBeingLiquidated"
+          },
+          {
+            "name": "AccountSetDelegatePermissions"
+          },
+          {
+            "name": "GroupStatus"
+          },
+          {
+            "name": "PerpExplainLiquidation"
+          },
+          {
+            "name": "AccountCompactTokenPositions"
           }
         ]
       }
     },
     {
-      "name": "CheckLiquidatable",
+      "name": "DelegatePermission",
+      "docs": [
+        "Actions a delegate can be restricted to, see MangoAccountFixed::has_permission()"
+      ],
       "type": {
         "kind": "enum",
         "variants": [
           {
-            "name": "NotLiquidatable"
+            "name": "Liquidate"
           },
           {
-            "name": "Liquidatable"
+            "name": "Trade"
           },
           {
-            "name": "BecameNotLiquidatable"
+            "name": "Withdraw"
           }
         ]
       }
     },
     {
-      "name": "OracleType",
+      "name": "CheckLiquidatable",
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "NotLiquidatable"
+          },
+          {
+            "name": "Liquidatable"
+          },
+          {
+            "name": "BecameNotLiquidatable"
+          }
+        ]
+      }
+    },
+    {
+      "name": "OracleType",
       "type": {
         "kind": "enum",
         "variants": [
@@ -11855,6 +13729,182 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "MangoAccountDataV2",
+      "fields": [
+        {
+          "name": "initHealth",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "maintHealth",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "equity",
+          "type": {
+            "defined": "Equity"
+          },
+          "index": false
+        },
+        {
+          "name": "perpTradingFeesPaid",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "perpFundingPaid",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "perpLiquidationFeesPaid",
+          "type": "i64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "GroupStatusData",
+      "fields": [
+        {
+          "name": "group",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "admin",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "securityAdmin",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "ixGate",
+          "type": "u128",
+          "index": false
+        },
+        {
+          "name": "tokenCount",
+          "type": "u16",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiquidationExplanation",
+      "fields": [
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "oraclePrice",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "pricePerLot",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "healthPerLot",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "baseTransferForZero",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "bindingConstraint",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "baseTransferLiqee",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqee",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqor",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "quotePlatformFee",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "pnlTransfer",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "liqeeHealthBefore",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "liqeeHealthAfter",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        }
+      ]
+    },
     {
       "name": "PerpBalanceLog",
       "fields": [
@@ -13101,6 +15151,28 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "AccountCompactTokenPositionsLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "tokenIndexes",
+          "type": {
+            "vec": "u16"
+          },
+          "index": false
+        }
+      ]
+    },
     {
       "name": "DeactivatePerpPositionLog",
       "fields": [
@@ -13517,7 +15589,7 @@ export type MangoV4 = {
       ]
     },
     {
-      "name": "PerpLiqBankruptcyLog",
+      "name": "PerpLiqBaseOrPositivePnlLogV4",
       "fields": [
         {
           "name": "mangoGroup",
@@ -13525,8 +15597,8 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "liqee",
-          "type": "publicKey",
+          "name": "perpMarketIndex",
+          "type": "u16",
           "index": false
         },
         {
@@ -13535,71 +15607,291 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "perpMarketIndex",
-          "type": "u16",
+          "name": "liqee",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "insuranceTransfer",
-          "type": "i128",
+          "name": "baseTransferLiqee",
+          "type": "i64",
           "index": false
         },
         {
-          "name": "socializedLoss",
+          "name": "quoteTransferLiqee",
           "type": "i128",
           "index": false
         },
         {
-          "name": "startingLongFunding",
+          "name": "quoteTransferLiqor",
           "type": "i128",
           "index": false
         },
         {
-          "name": "startingShortFunding",
+          "name": "quotePlatformFee",
           "type": "i128",
           "index": false
         },
         {
-          "name": "endingLongFunding",
+          "name": "pnlTransfer",
           "type": "i128",
           "index": false
         },
         {
-          "name": "endingShortFunding",
-          "type": "i128",
+          "name": "pnlSettleLimitTransferRecurring",
+          "type": "i64",
           "index": false
-        }
-      ]
-    },
-    {
-      "name": "PerpLiqNegativePnlOrBankruptcyLog",
-      "fields": [
+        },
         {
-          "name": "mangoGroup",
-          "type": "publicKey",
+          "name": "pnlSettleLimitTransferOneshot",
+          "type": "i64",
           "index": false
         },
         {
-          "name": "liqee",
-          "type": "publicKey",
+          "name": "price",
+          "type": "i128",
           "index": false
         },
         {
-          "name": "liqor",
-          "type": "publicKey",
+          "name": "liqeeStateHashBefore",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
           "index": false
         },
         {
-          "name": "perpMarketIndex",
-          "type": "u16",
+          "name": "liqeeStateHashAfter",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
           "index": false
         },
         {
-          "name": "settlement",
-          "type": "i128",
+          "name": "liqorStateHashBefore",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
           "index": false
-        }
-      ]
+        },
+        {
+          "name": "liqorStateHashAfter",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
+          "index": false
+        },
+        {
+          "name": "liqeeAvgEntryPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqeeBreakEvenPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqorAvgEntryPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqorBreakEvenPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqeeLiquidationCost",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "oraclePrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqeeInitHealthBefore",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqeeInitHealthAfter",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqBankruptcyLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "insuranceTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "socializedLoss",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "startingLongFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "startingShortFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "endingLongFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "endingShortFunding",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqBadDebtInsuranceLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "badDebt",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "insuranceTransfer",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqPnlTakeoverTokenLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "tokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "settleTokenTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "tokenTransfer",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqNegativePnlOrBankruptcyLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "settlement",
+          "type": "i128",
+          "index": false
+        }
+      ]
     },
     {
       "name": "PerpSettlePnlLog",
@@ -13666,6 +15958,30 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "PerpSettleAllFundingLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "settledFunding",
+          "type": {
+            "vec": {
+              "defined": "PerpSettleFundingDetail"
+            }
+          },
+          "index": false
+        }
+      ]
+    },
     {
       "name": "AccountBuybackFeesWithMngoLog",
       "fields": [
@@ -14575,47 +16891,252 @@ export type MangoV4 = {
           "index": false
         }
       ]
-    }
-  ],
-  "errors": [
-    {
-      "code": 6000,
-      "name": "SomeError",
-      "msg": ""
     },
     {
-      "code": 6001,
-      "name": "NotImplementedError",
-      "msg": ""
-    },
-    {
-      "code": 6002,
-      "name": "MathError",
-      "msg": "checked math error"
-    },
-    {
-      "code": 6003,
-      "name": "UnexpectedOracle",
-      "msg": ""
-    },
-    {
-      "code": 6004,
-      "name": "UnknownOracleType",
-      "msg": "oracle type cannot be determined"
-    },
-    {
-      "code": 6005,
-      "name": "InvalidFlashLoanTargetCpiProgram",
-      "msg": ""
-    },
-    {
-      "code": 6006,
-      "name": "HealthMustBePositive",
-      "msg": "health must be positive"
-    },
-    {
-      "code": 6007,
-      "name": "HealthMustBePositiveOrIncrease",
+      "name": "TokenLiqAbsorbDustLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "dustTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "quoteTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "dustTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "quoteTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "dustPrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "quotePrice",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqHealthLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "initHealth",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "maintHealth",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqEndHealth",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "GroupRiskDeltaLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "openInterestBefore",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "openInterestAfter",
+          "type": "i64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpAdlCounterpartyLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "counterparty",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "baseClosed",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "pnlAbsorbed",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpAdlLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "oraclePrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqeeBaseLotsRemaining",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "deficit",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "remainingDeficit",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "numCounterparties",
+          "type": "u8",
+          "index": false
+        }
+      ]
+    }
+  ],
+  "errors": [
+    {
+      "code": 6000,
+      "name": "SomeError",
+      "msg": ""
+    },
+    {
+      "code": 6001,
+      "name": "NotImplementedError",
+      "msg": ""
+    },
+    {
+      "code": 6002,
+      "name": "MathError",
+      "msg": "checked math error"
+    },
+    {
+      "code": 6003,
+      "name": "UnexpectedOracle",
+      "msg": ""
+    },
+    {
+      "code": 6004,
+      "name": "UnknownOracleType",
+      "msg": "oracle type cannot be determined"
+    },
+    {
+      "code": 6005,
+      "name": "InvalidFlashLoanTargetCpiProgram",
+      "msg": ""
+    },
+    {
+      "code": 6006,
+      "name": "HealthMustBePositive",
+      "msg": "health must be positive"
+    },
+    {
+      "code": 6007,
+      "name": "HealthMustBePositiveOrIncrease",
       "msg": "health must be positive or not decrease"
     },
     {
@@ -14748,6 +17269,11 @@ export type MangoV4 = {
       "name": "PerpHasBaseLots",
       "msg": "the perp position has non-zero base lots"
     },
+    {
+      "code": 6034,
+      "name": "HasOpenOrUnsettledSerum3Orders",
+      "msg": "there are open or unsettled serum3 orders"
+    },
     {
       "code": 6034,
       "name": "HasOpenOrUnsettledSpotOrders",
@@ -14883,6 +17409,11 @@ export type MangoV4 = {
       "name": "HealthAccountBankNotWritable",
       "msg": "a bank in the health account list should be writable but is not"
     },
+    {
+      "code": 6061,
+      "name": "Serum3PriceBandExceeded",
+      "msg": "the market does not allow limit orders too far from the current oracle value"
+    },
     {
       "code": 6061,
       "name": "SpotPriceBandExceeded",
@@ -14945,34 +17476,94 @@ export type MangoV4 = {
     },
     {
       "code": 6073,
-      "name": "NoFreeOpenbookV2OpenOrdersIndex",
-      "msg": "no free openbook v2 open orders index"
+      "name": "LiquidationPriceOutOfTolerance",
+      "msg": "liquidation price is not within tolerance of the oracle price"
     },
     {
       "code": 6074,
-      "name": "OpenbookV2OpenOrdersExistAlready",
-      "msg": "openbook v2 open orders exist already"
-    }
-  ]
-};
-
-export const IDL: MangoV4 = {
-  "version": "0.25.0",
-  "name": "mango_v4",
-  "instructions": [
+      "name": "LiquidationNotProfitable",
+      "msg": "liquidation reward does not cover the liqor's minimum net profit"
+    },
     {
-      "name": "adminTokenWithdrawFees",
-      "accounts": [
-        {
-          "name": "group",
-          "isMut": false,
-          "isSigner": false,
-          "relations": [
-            "admin"
-          ]
-        },
-        {
-          "name": "bank",
+      "code": 6075,
+      "name": "InvalidDelegate",
+      "msg": "the delegate can't be the account owner or the account itself"
+    },
+    {
+      "code": 6076,
+      "name": "InitAssetWeightBelowFloor",
+      "msg": "the init asset weight is below the group's floor"
+    },
+    {
+      "code": 6077,
+      "name": "LiqorNotWhitelistedForLargePosition",
+      "msg": "the liqor is not whitelisted for perp positions this large"
+    },
+    {
+      "code": 6078,
+      "name": "WeightChangeTooSoon",
+      "msg": "the token's weights were changed too recently"
+    },
+    {
+      "code": 6079,
+      "name": "LiquidationThrottled",
+      "msg": "the perp market's liquidation volume for this slot is exhausted"
+    },
+    {
+      "code": 6080,
+      "name": "LiquidationWrongDirection",
+      "msg": "max_base_transfer has the wrong sign for the liqee's base position"
+    },
+    {
+      "code": 6081,
+      "name": "UnauthorizedLiqor",
+      "msg": "the liqor_owner is neither the owner nor a delegate allowed to liquidate for the liqor account"
+    },
+    {
+      "code": 6082,
+      "name": "AdlDisabled",
+      "msg": "auto-deleveraging is not enabled for this perp market"
+    },
+    {
+      "code": 6083,
+      "name": "TokenNotAllowedForAccount",
+      "msg": "the token is not on the account's token allowlist"
+    },
+    {
+      "code": 6084,
+      "name": "LiquidationTooSmall",
+      "msg": "the liquidation transfers too few base lots without closing the liqee position"
+    },
+    {
+      "code": 6085,
+      "name": "DelegatePermissionMissing",
+      "msg": "the delegate doesn't have the permission for this action"
+    },
+    {
+      "code": 6086,
+      "name": "MathOverflow",
+      "msg": "math overflow"
+    }
+  ]
+};
+
+export const IDL: MangoV4 = {
+  "version": "0.25.0",
+  "name": "mango_v4",
+  "instructions": [
+    {
+      "name": "adminTokenWithdrawFees",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "admin"
+          ]
+        },
+        {
+          "name": "bank",
           "isMut": true,
           "isSigner": false,
           "relations": [
@@ -15148,6 +17739,9 @@ export const IDL: MangoV4 = {
     },
     {
       "name": "groupEdit",
+      "docs": [
+        "admin_opt must be None, the admin is changed with group_set_pending_admin."
+      ],
       "accounts": [
         {
           "name": "group",
@@ -15241,9 +17835,114 @@ export const IDL: MangoV4 = {
           "type": {
             "option": "u64"
           }
+        },
+        {
+          "name": "maxLiquidationOvershootHealthOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "maxDustAbsorbQuoteOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "initAssetWeightFloorOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liqorFeeWaiverMinMngoOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "minWeightChangeIntervalSlotsOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "protocolLiquidatorOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        },
+        {
+          "name": "maxLiquidationFeeOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "sweepBankruptPerpPnlOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "liqorFeeWaiverRegistrarOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        }
+      ]
+    },
+    {
+      "name": "groupSetPendingAdmin",
+      "docs": [
+        "Proposes a new group admin, which takes over once it calls group_accept_admin.",
+        "",
+        "Pubkey::default() withdraws the proposal."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "admin"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": [
+        {
+          "name": "pendingAdmin",
+          "type": "publicKey"
         }
       ]
     },
+    {
+      "name": "groupAcceptAdmin",
+      "docs": [
+        "Makes the pending admin proposed with group_set_pending_admin the group admin"
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "pending_admin"
+          ]
+        },
+        {
+          "name": "pendingAdmin",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": []
+    },
     {
       "name": "groupWithdrawInsuranceFund",
       "accounts": [
@@ -15385,6 +18084,17 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "groupStatus",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "groupClose",
       "accounts": [
@@ -15425,7 +18135,7 @@ export const IDL: MangoV4 = {
       "accounts": [
         {
           "name": "group",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "admin"
@@ -16111,6 +18821,12 @@ export const IDL: MangoV4 = {
           "type": {
             "option": "bool"
           }
+        },
+        {
+          "name": "projectBorrowInterestInHealthOpt",
+          "type": {
+            "option": "bool"
+          }
         }
       ]
     },
@@ -16248,7 +18964,7 @@ export const IDL: MangoV4 = {
       "accounts": [
         {
           "name": "group",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "admin"
@@ -16315,6 +19031,36 @@ export const IDL: MangoV4 = {
       ],
       "args": []
     },
+    {
+      "name": "tokenUpdateIndexAndRateResilient",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "mintInfo",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "oracle",
+            "group"
+          ]
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "instructions",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "accountCreate",
       "accounts": [
@@ -16815,7 +19561,7 @@ export const IDL: MangoV4 = {
       ]
     },
     {
-      "name": "accountToggleFreeze",
+      "name": "accountSetOwner",
       "accounts": [
         {
           "name": "group",
@@ -16827,24 +19573,29 @@ export const IDL: MangoV4 = {
           "isMut": true,
           "isSigner": false,
           "relations": [
-            "group"
+            "group",
+            "owner"
           ]
         },
         {
-          "name": "admin",
+          "name": "owner",
           "isMut": false,
           "isSigner": true
         }
       ],
       "args": [
         {
-          "name": "freeze",
+          "name": "newOwner",
+          "type": "publicKey"
+        },
+        {
+          "name": "clearDelegates",
           "type": "bool"
         }
       ]
     },
     {
-      "name": "accountClose",
+      "name": "accountToggleFreeze",
       "accounts": [
         {
           "name": "group",
@@ -16856,35 +19607,28 @@ export const IDL: MangoV4 = {
           "isMut": true,
           "isSigner": false,
           "relations": [
-            "group",
-            "owner"
+            "group"
           ]
         },
         {
-          "name": "owner",
+          "name": "admin",
           "isMut": false,
           "isSigner": true
-        },
-        {
-          "name": "solDestination",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "tokenProgram",
-          "isMut": false,
-          "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "forceClose",
+          "name": "freeze",
           "type": "bool"
         }
       ]
     },
     {
-      "name": "accountBuybackFeesWithMngo",
+      "name": "accountCompactTokenPositions",
+      "docs": [
+        "Moves the account's active token positions into the front slots, filling the gaps",
+        "that deactivated positions leave. Token indexes and the active order are unchanged."
+      ],
       "accounts": [
         {
           "name": "group",
@@ -16900,54 +19644,49 @@ export const IDL: MangoV4 = {
           ]
         },
         {
-          "name": "owner",
+          "name": "admin",
           "isMut": false,
           "isSigner": true
-        },
-        {
-          "name": "daoAccount",
-          "isMut": true,
-          "isSigner": false,
-          "relations": [
-            "group"
-          ]
-        },
-        {
-          "name": "mngoBank",
-          "isMut": true,
-          "isSigner": false,
-          "relations": [
-            "group"
-          ]
-        },
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "accountSetDelegatePermissions",
+      "accounts": [
         {
-          "name": "mngoOracle",
+          "name": "group",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "feesBank",
+          "name": "account",
           "isMut": true,
           "isSigner": false,
           "relations": [
-            "group"
+            "group",
+            "owner"
           ]
         },
         {
-          "name": "feesOracle",
+          "name": "owner",
           "isMut": false,
-          "isSigner": false
+          "isSigner": true
         }
       ],
       "args": [
         {
-          "name": "maxBuybackUsd",
-          "type": "u64"
+          "name": "delegatePermissions",
+          "type": "u8"
+        },
+        {
+          "name": "temporaryDelegatePermissions",
+          "type": "u8"
         }
       ]
     },
     {
-      "name": "sequenceCheck",
+      "name": "accountSetTokenAllowlist",
       "accounts": [
         {
           "name": "group",
@@ -16959,8 +19698,142 @@ export const IDL: MangoV4 = {
           "isMut": true,
           "isSigner": false,
           "relations": [
-            "group",
-            "owner"
+            "group"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": [
+        {
+          "name": "tokenIndexes",
+          "type": {
+            "vec": "u16"
+          }
+        }
+      ]
+    },
+    {
+      "name": "accountClose",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "owner"
+          ]
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "solDestination",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "forceClose",
+          "type": "bool"
+        }
+      ]
+    },
+    {
+      "name": "accountBuybackFeesWithMngo",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "daoAccount",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "mngoBank",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "mngoOracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "feesBank",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "feesOracle",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBuybackUsd",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "sequenceCheck",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "owner"
           ]
         },
         {
@@ -17006,6 +19879,79 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "computeAccountHealth",
+      "docs": [
+        "Returns init and maint health as return data, see instructions::compute_account_health"
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "cacheAccountHealth",
+      "docs": [
+        "Stores the account's health for the current slot, see instructions::cache_account_health"
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "recoverFromBeingLiquidated",
+      "docs": [
+        "Lets the owner clear being_liquidated on a healthy account, see",
+        "instructions::recover_from_being_liquidated"
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "owner"
+          ]
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        }
+      ],
+      "args": []
+    },
     {
       "name": "stubOracleCreate",
       "accounts": [
@@ -18932,12 +21878,24 @@ export const IDL: MangoV4 = {
       ]
     },
     {
-      "name": "tokenForceCloseBorrowsWithToken",
+      "name": "tokenLiqWithTokenRelaxedStaleness",
+      "docs": [
+        "Admin-only variant of token_liq_with_token for emergencies, where oracles may",
+        "be up to extra_staleness_slots staler than usually allowed."
+      ],
       "accounts": [
         {
           "name": "group",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "relations": [
+            "admin"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
         },
         {
           "name": "liqor",
@@ -18972,20 +21930,23 @@ export const IDL: MangoV4 = {
         },
         {
           "name": "maxLiabTransfer",
+          "type": {
+            "defined": "I80F48"
+          }
+        },
+        {
+          "name": "extraStalenessSlots",
           "type": "u64"
         }
       ]
     },
     {
-      "name": "tokenLiqBankruptcy",
+      "name": "tokenForceCloseBorrowsWithToken",
       "accounts": [
         {
           "name": "group",
           "isMut": false,
-          "isSigner": false,
-          "relations": [
-            "insurance_vault"
-          ]
+          "isSigner": false
         },
         {
           "name": "liqor",
@@ -19007,20 +21968,113 @@ export const IDL: MangoV4 = {
           "relations": [
             "group"
           ]
+        }
+      ],
+      "args": [
+        {
+          "name": "assetTokenIndex",
+          "type": "u16"
         },
         {
-          "name": "liabMintInfo",
+          "name": "liabTokenIndex",
+          "type": "u16"
+        },
+        {
+          "name": "maxLiabTransfer",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "tokenLiqAbsorbDust",
+      "docs": [
+        "Lets the liqor take over a healthy account's tiny dust_token_index deposit",
+        "at oracle price, in exchange for quote_token_index."
+      ],
+      "accounts": [
+        {
+          "name": "group",
           "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "group"
           ]
         },
         {
-          "name": "quoteVault",
-          "isMut": true,
-          "isSigner": false
-        },
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "dustTokenIndex",
+          "type": "u16"
+        },
+        {
+          "name": "quoteTokenIndex",
+          "type": "u16"
+        }
+      ]
+    },
+    {
+      "name": "tokenLiqBankruptcy",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "insurance_vault"
+          ]
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liabMintInfo",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "quoteVault",
+          "isMut": true,
+          "isSigner": false
+        },
         {
           "name": "insuranceVault",
           "isMut": true,
@@ -19512,6 +22566,110 @@ export const IDL: MangoV4 = {
           "type": {
             "option": "f32"
           }
+        },
+        {
+          "name": "liquidationPriceToleranceOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "baseLiquidationFeeBpsOpt",
+          "type": {
+            "option": "u32"
+          }
+        },
+        {
+          "name": "useEmaForTriggerOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "emaIntervalSecondsOpt",
+          "type": {
+            "option": "u32"
+          }
+        },
+        {
+          "name": "largePositionThresholdLotsOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "largePositionWhitelistOpt",
+          "type": {
+            "option": {
+              "vec": "publicKey"
+            }
+          }
+        },
+        {
+          "name": "dustThresholdLotsOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "dustLiquidationFeeOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "settleFeeBpsOpt",
+          "type": {
+            "option": "u32"
+          }
+        },
+        {
+          "name": "maxLiquidationBasePerSlotOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationThrottleBypassHealthOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "liquidationFeeInsuranceFractionOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "adlEnabledOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "underlyingIdOpt",
+          "type": {
+            "option": "u16"
+          }
+        },
+        {
+          "name": "minLiquidationBaseLotsOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationQuoteRoundingBufferOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "altSettleTokenIndexOpt",
+          "type": {
+            "option": "u16"
+          }
         }
       ]
     },
@@ -20324,7 +23482,7 @@ export const IDL: MangoV4 = {
         },
         {
           "name": "perpMarket",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false,
           "relations": [
             "group",
@@ -20409,6 +23567,81 @@ export const IDL: MangoV4 = {
       ],
       "args": []
     },
+    {
+      "name": "perpAdl",
+      "docs": [
+        "Takes the loss of a bankrupt perp position that the insurance fund couldn't cover",
+        "from the last num_counterparties remaining accounts, ranked by descending pnl."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "admin",
+            "insurance_vault"
+          ]
+        },
+        {
+          "name": "admin",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "oracle"
+          ]
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "insuranceVault",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "numCounterparties",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "perpSettleAllFunding",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        }
+      ],
+      "args": []
+    },
     {
       "name": "perpSettleFees",
       "accounts": [
@@ -20505,21 +23738,318 @@ export const IDL: MangoV4 = {
         },
         {
           "name": "settleBank",
-          "isMut": true,
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "The liqor pays for taken over pnl in this token, unless perp_liq_base_or_positive_pnl_v3",
+            "gets a liqor_quote_token_index. Then that token's bank and oracle must be among the",
+            "health accounts."
+          ],
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqBaseOrPositivePnlV2",
+      "docs": [
+        "Like perp_liq_base_or_positive_pnl, but allows the liqor to supply the price",
+        "(in native quote per native base) that the base position is transferred at.",
+        "",
+        "With min_net_quote_profit_opt the liquidation only goes through if the liqor reward",
+        "minus liquidation_cost (both in native settle token) reaches that minimum.",
+        "",
+        "With max_health_improvement_opt the liqor limits the liqee's health improvement",
+        "(in native quote) in this call."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "oracle"
+          ]
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "The liqor pays for taken over pnl in this token, unless perp_liq_base_or_positive_pnl_v3",
+            "gets a liqor_quote_token_index. Then that token's bank and oracle must be among the",
+            "health accounts."
+          ],
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
+          "type": "u64"
+        },
+        {
+          "name": "liqorPriceOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "minNetQuoteProfitOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationCost",
+          "type": "u64"
+        },
+        {
+          "name": "maxHealthImprovementOpt",
+          "type": {
+            "option": "u64"
+          }
+        }
+      ]
+    },
+    {
+      "name": "perpLiqBaseOrPositivePnlV3",
+      "docs": [
+        "Like perp_liq_base_or_positive_pnl_v2. With settle_bad_debt_first the group insurance",
+        "fund first covers the liqee's bad debt in the market, before the base position is",
+        "liquidated against the improved health.",
+        "",
+        "With liqor_quote_token_index the liqor pays for taken over positive pnl in the market's",
+        "alternative settle token instead, converted at oracle prices."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "insurance_vault"
+          ]
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "oracle"
+          ]
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "insuranceVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
+          "type": "u64"
+        },
+        {
+          "name": "liqorPriceOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "minNetQuoteProfitOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationCost",
+          "type": "u64"
+        },
+        {
+          "name": "maxHealthImprovementOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "settleBadDebtFirst",
+          "type": "bool"
+        },
+        {
+          "name": "liqorQuoteTokenIndex",
+          "type": {
+            "option": "u16"
+          }
+        }
+      ]
+    },
+    {
+      "name": "perpExplainLiquidation",
+      "docs": [
+        "Emits what perp_liq_base_or_positive_pnl_v2 would do with these arguments,",
+        "without changing any accounts."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liqor",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "liqee",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "settleBank",
+          "isMut": false,
           "isSigner": false,
           "relations": [
             "group"
           ]
-        },
-        {
-          "name": "settleVault",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "settleOracle",
-          "isMut": false,
-          "isSigner": false
         }
       ],
       "args": [
@@ -20530,6 +24060,28 @@ export const IDL: MangoV4 = {
         {
           "name": "maxPnlTransfer",
           "type": "u64"
+        },
+        {
+          "name": "liqorPriceOpt",
+          "type": {
+            "option": "f64"
+          }
+        },
+        {
+          "name": "minNetQuoteProfitOpt",
+          "type": {
+            "option": "i64"
+          }
+        },
+        {
+          "name": "liquidationCost",
+          "type": "u64"
+        },
+        {
+          "name": "maxHealthImprovementOpt",
+          "type": {
+            "option": "u64"
+          }
         }
       ]
     },
@@ -21934,6 +25486,148 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "openbookV2PlaceTakerOrder",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "openbookV2Market",
+          "isMut": false,
+          "isSigner": false,
+          "relations": [
+            "group",
+            "openbook_v2_program",
+            "openbook_v2_market_external"
+          ]
+        },
+        {
+          "name": "openbookV2Program",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "openbookV2MarketExternal",
+          "isMut": true,
+          "isSigner": false,
+          "relations": [
+            "bids",
+            "asks",
+            "event_heap"
+          ]
+        },
+        {
+          "name": "bids",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "asks",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "eventHeap",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketRequestQueue",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketBaseVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketQuoteVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketVaultSigner",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "payerBank",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "The bank that pays for the order, if necessary"
+          ],
+          "relations": [
+            "group"
+          ]
+        },
+        {
+          "name": "payerVault",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "The bank vault that pays for the order, if necessary"
+          ]
+        },
+        {
+          "name": "payerOracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "side",
+          "type": "u8"
+        },
+        {
+          "name": "limitPrice",
+          "type": "u64"
+        },
+        {
+          "name": "maxBaseQty",
+          "type": "u64"
+        },
+        {
+          "name": "maxNativeQuoteQtyIncludingFees",
+          "type": "u64"
+        },
+        {
+          "name": "selfTradeBehavior",
+          "type": "u8"
+        },
+        {
+          "name": "clientOrderId",
+          "type": "u64"
+        },
+        {
+          "name": "limit",
+          "type": "u16"
+        }
+      ]
+    },
     {
       "name": "openbookV2CancelOrder",
       "accounts": [
@@ -22830,6 +26524,14 @@ export const IDL: MangoV4 = {
             ],
             "type": "f32"
           },
+          {
+            "name": "projectBorrowInterestInHealth",
+            "docs": [
+              "If set to 1, health computations value borrows with the interest that accrued",
+              "since index_last_updated, instead of waiting for the next index update."
+            ],
+            "type": "u8"
+          },
           {
             "name": "padding2",
             "type": {
@@ -22839,6 +26541,15 @@ export const IDL: MangoV4 = {
               ]
             }
           },
+          {
+            "name": "lastWeightChangeSlot",
+            "docs": [
+              "Slot of the last token_edit that changed this token's weights, 0 if never.",
+              "",
+              "See Group::min_weight_change_interval_slots."
+            ],
+            "type": "u64"
+          },
           {
             "name": "potentialOpenbookTokens",
             "docs": [
@@ -22852,7 +26563,7 @@ export const IDL: MangoV4 = {
             "type": {
               "array": [
                 "u8",
-                1888
+                1876
               ]
             }
           }
@@ -22974,14 +26685,127 @@ export const IDL: MangoV4 = {
             "type": "u16"
           },
           {
-            "name": "allowedFastListingsPerInterval",
+            "name": "allowedFastListingsPerInterval",
+            "docs": [
+              "Number of fast listings that are allowed per interval"
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "padding2",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "collateralFeeInterval",
+            "docs": [
+              "Intervals in which collateral fee is applied"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "maxLiquidationOvershootHealth",
+            "docs": [
+              "Perp base liquidation avoids pushing the liqee's liquidation end health above",
+              "this value (in quote native), as long as it can still make progress.",
+              "",
+              "When set to 0, there's no limit."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "maxDustAbsorbQuote",
+            "docs": [
+              "Token positions worth at most this much (in quote native) can be taken over",
+              "by anyone with token_liq_absorb_dust, to allow closing accounts with dust left.",
+              "",
+              "When set to 0, dust absorption is disabled."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "initAssetWeightFloor",
+            "docs": [
+              "Tokens that aren't reduce-only must have an init_asset_weight of at least this value.",
+              "",
+              "Guards against accidentally listing or editing tokens to provide no init collateral."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "maxLiquidationFee",
+            "docs": [
+              "Upper limit on the base liquidation fee of every perp market, see",
+              "capped_liquidation_fee().",
+              "",
+              "When set to 0, market fees are not limited."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "liqorFeeWaiverMinMngo",
+            "docs": [
+              "Liqors whose owner has at least this much native mngo locked in the governance",
+              "stake registry liqor_fee_waiver_registrar receive the platform liquidation fee on",
+              "perp base liquidations, instead of the group.",
+              "",
+              "When set to 0, no liqor receives the platform fee."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "minWeightChangeIntervalSlots",
+            "docs": [
+              "Minimum number of slots between two token_edit calls that change a token's weights.",
+              "",
+              "When set to 0, weights can be changed at any time."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "protocolLiquidator",
+            "docs": [
+              "Group-operated mango account that liquidates on behalf of the protocol.",
+              "",
+              "Its health isn't checked after perp liquidations, since the protocol backstops it.",
+              "Unset if Pubkey::default()."
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "sweepBankruptPerpPnl",
+            "docs": [
+              "If set, perp_liq_negative_pnl_or_bankruptcy sells positive perp pnl that is left",
+              "on a bankrupt account to the liqor and pays the proceeds into the insurance fund,",
+              "see sweep_bankrupt_positive_pnl()."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding3",
+            "type": {
+              "array": [
+                "u8",
+                1
+              ]
+            }
+          },
+          {
+            "name": "tokenCount",
             "docs": [
-              "Number of fast listings that are allowed per interval"
+              "Number of registered tokens, maintained by token_register(_trustless) and",
+              "token_deregister.",
+              "",
+              "Groups that listed tokens before this was tracked undercount them."
             ],
             "type": "u16"
           },
           {
-            "name": "padding2",
+            "name": "padding4",
             "type": {
               "array": [
                 "u8",
@@ -22990,18 +26814,28 @@ export const IDL: MangoV4 = {
             }
           },
           {
-            "name": "collateralFeeInterval",
+            "name": "pendingAdmin",
             "docs": [
-              "Intervals in which collateral fee is applied"
+              "Admin proposed with group_set_pending_admin, becomes the admin once it calls group_accept_admin.",
+              "",
+              "Unset if Pubkey::default()."
             ],
-            "type": "u64"
+            "type": "publicKey"
+          },
+          {
+            "name": "liqorFeeWaiverRegistrar",
+            "docs": [
+              "Voter stake registry Registrar of the mngo governance realm, see",
+              "liqor_fee_waiver_min_mngo. Unset if Pubkey::default()."
+            ],
+            "type": "publicKey"
           },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                1800
+                1656
               ]
             }
           }
@@ -23133,12 +26967,115 @@ export const IDL: MangoV4 = {
             ],
             "type": "u64"
           },
+          {
+            "name": "liquidationBaseAccumulated",
+            "docs": [
+              "Perp base lots taken over from this account during the current liquidation.",
+              "Reset when the account stops being liquidated."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "liquidationQuoteAccumulated",
+            "docs": [
+              "Perp quote (native, absolute) exchanged for liquidation_base_accumulated."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "perpTradingFeesPaid",
+            "docs": [
+              "Perp trading fees paid over the account's lifetime, in native quote.",
+              "Maker rebates reduce it."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "perpFundingPaidDeactivated",
+            "docs": [
+              "Perp funding paid by perp positions that have been deactivated, in native quote.",
+              "See perp_funding_paid() for the lifetime total."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "perpLiquidationFeesPaid",
+            "docs": [
+              "Perp liquidation fees paid as a liqee over the account's lifetime, in native quote:",
+              "base liquidation fees and the discount on positive pnl taken over by liqors."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "tokenAllowlist",
+            "docs": [
+              "Tokens the account may deposit into or borrow, set by the group admin.",
+              "Only the first token_allowlist_len entries are used. All tokens are allowed if empty."
+            ],
+            "type": {
+              "array": [
+                "u16",
+                8
+              ]
+            }
+          },
+          {
+            "name": "tokenAllowlistLen",
+            "type": "u8"
+          },
+          {
+            "name": "delegatePermissions",
+            "docs": [
+              "DelegatePermission bits for delegate and temporary_delegate, see",
+              "MangoAccountFixed::has_permission(). Zero means unrestricted."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "temporaryDelegatePermissions",
+            "type": "u8"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                5
+              ]
+            }
+          },
+          {
+            "name": "cachedInitHealth",
+            "docs": [
+              "Health stored by cache_account_health and health_region_end, see",
+              "MangoAccountFixed::cached_health()"
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "cachedMaintHealth",
+            "type": "i64"
+          },
+          {
+            "name": "cachedHealthSlot",
+            "docs": [
+              "Slot the cached health was computed in. Zero if it's invalid."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "beingLiquidatedSlot",
+            "docs": [
+              "Slot in which being_liquidated was last set. Zero if the account isn't being liquidated."
+            ],
+            "type": "u64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                152
+                56
               ]
             }
           },
@@ -23872,10 +27809,209 @@ export const IDL: MangoV4 = {
               "",
               "See also PerpPosition::settle_pnl_limit_realized_trade"
             ],
-            "type": "f32"
+            "type": "f32"
+          },
+          {
+            "name": "padding3",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "settlePnlLimitWindowSizeTs",
+            "docs": [
+              "Window size in seconds for the perp settlement limit"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "reduceOnly",
+            "docs": [
+              "If true, users may no longer increase their market exposure. Only actions",
+              "that reduce their position are still allowed."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "forceClose",
+            "type": "u8"
+          },
+          {
+            "name": "padding4",
+            "type": {
+              "array": [
+                "u8",
+                6
+              ]
+            }
+          },
+          {
+            "name": "maintOverallAssetWeight",
+            "docs": [
+              "Weights for full perp market health, if positive"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "initOverallAssetWeight",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "positivePnlLiquidationFee",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "feesWithdrawn",
+            "type": "u64"
+          },
+          {
+            "name": "platformLiquidationFee",
+            "docs": [
+              "Additional to liquidation_fee, but goes to the group owner instead of the liqor"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "accruedLiquidationFees",
+            "docs": [
+              "Platform fees that were accrued during liquidation (in native tokens)",
+              "",
+              "These fees are also added to fees_accrued, this is just for bookkeeping the total",
+              "liquidation fees that happened. So never decreases (different to fees_accrued)."
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "liquidationPriceTolerance",
+            "docs": [
+              "Max relative deviation of a liqor-supplied liquidation price from the oracle price.",
+              "",
+              "Allows liquidators to use an externally computed (e.g. index) price for the base",
+              "transfer. With 0 only the oracle price itself is accepted."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "useEmaForTrigger",
+            "docs": [
+              "If true, maint health (the liquidation trigger) values the base position at",
+              "ema_price instead of the oracle price. Liquidation transfers still use the oracle price."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding5",
+            "type": {
+              "array": [
+                "u8",
+                3
+              ]
+            }
+          },
+          {
+            "name": "emaPrice",
+            "docs": [
+              "Exponential moving average of the oracle price, updated with funding"
+            ],
+            "type": "f64"
+          },
+          {
+            "name": "emaLastUpdated",
+            "docs": [
+              "Timestamp of the last ema_price update"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "emaIntervalSeconds",
+            "docs": [
+              "Time constant of the ema_price in seconds"
+            ],
+            "type": "u32"
+          },
+          {
+            "name": "padding6",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "largePositionThresholdLots",
+            "docs": [
+              "Liqors may only grow their base position above this many lots in liquidations",
+              "if they are in large_position_whitelist. Disabled if 0."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "largePositionWhitelist",
+            "docs": [
+              "Liqor mango accounts that may exceed large_position_threshold_lots.",
+              "Unused entries are Pubkey::default()."
+            ],
+            "type": {
+              "array": [
+                "publicKey",
+                4
+              ]
+            }
+          },
+          {
+            "name": "dustThresholdLots",
+            "docs": [
+              "Liqee positions of at most this many lots are dust: base liquidation of these",
+              "last lots pays dust_liquidation_fee instead of base_liquidation_fee, to give liqors",
+              "a reason to fully close positions. Disabled if 0."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "dustLiquidationFee",
+            "docs": [
+              "Liquidation fee for the lots in the dust region, see dust_threshold_lots.",
+              "",
+              "Only applies if it's larger than base_liquidation_fee."
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "accruedSettleFees",
+            "docs": [
+              "Settle fees collected by the protocol (in native settle token)",
+              "",
+              "They are added to the settle bank's collected_fees_native."
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "settleFeeBps",
+            "docs": [
+              "Fee taken by the protocol from the positive side of pnl settlements, in basis points"
+            ],
+            "type": "u32"
           },
           {
-            "name": "padding3",
+            "name": "padding7",
             "type": {
               "array": [
                 "u8",
@@ -23884,77 +28020,109 @@ export const IDL: MangoV4 = {
             }
           },
           {
-            "name": "settlePnlLimitWindowSizeTs",
+            "name": "maxLiquidationBasePerSlot",
             "docs": [
-              "Window size in seconds for the perp settlement limit"
+              "Max number of base lots that may be liquidated in a single slot, to slow down",
+              "liquidation cascades. Disabled if 0."
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "liquidationBaseInSlot",
+            "docs": [
+              "Base lots liquidated in liquidation_base_slot"
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "liquidationBaseSlot",
+            "docs": [
+              "Slot that liquidation_base_in_slot refers to"
             ],
             "type": "u64"
           },
           {
-            "name": "reduceOnly",
+            "name": "liquidationThrottleBypassHealth",
             "docs": [
-              "If true, users may no longer increase their market exposure. Only actions",
-              "that reduce their position are still allowed."
+              "Liqees with a maint health below minus this value (in quote native) are so far",
+              "underwater that they're liquidated regardless of max_liquidation_base_per_slot.",
+              "No liqee bypasses the limit if 0."
             ],
-            "type": "u8"
+            "type": "f64"
           },
           {
-            "name": "forceClose",
+            "name": "liquidationFeeInsuranceFraction",
+            "docs": [
+              "Fraction of base_liquidation_fee that goes to the market's fees instead of the liqor.",
+              "",
+              "The liqee always pays the full fee. Between 0 and 1."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "adlEnabled",
+            "docs": [
+              "If set, losses of bankrupt accounts that the insurance fund can't cover are not",
+              "socialized through funding but left for perp_adl to take from profitable accounts."
+            ],
             "type": "u8"
           },
           {
-            "name": "padding4",
+            "name": "padding8",
             "type": {
               "array": [
                 "u8",
-                6
+                1
               ]
             }
           },
           {
-            "name": "maintOverallAssetWeight",
+            "name": "underlyingId",
             "docs": [
-              "Weights for full perp market health, if positive"
+              "Markets with the same non-zero underlying_id track the same underlying asset,",
+              "like a perpetual and a dated future on it. Zero means not grouped with other markets.",
+              "",
+              "Only used for reporting net exposure, see HealthCache::net_delta_for_underlying()."
             ],
-            "type": {
-              "defined": "I80F48"
-            }
-          },
-          {
-            "name": "initOverallAssetWeight",
-            "type": {
-              "defined": "I80F48"
-            }
-          },
-          {
-            "name": "positivePnlLiquidationFee",
-            "type": {
-              "defined": "I80F48"
-            }
+            "type": "u16"
           },
           {
-            "name": "feesWithdrawn",
-            "type": "u64"
+            "name": "minLiquidationBaseLots",
+            "docs": [
+              "Base liquidations must transfer at least this many lots, unless they close the",
+              "liqee's base position. Stops dust liquidations that each cost the liqee a fee.",
+              "Disabled if 0."
+            ],
+            "type": "i64"
           },
           {
-            "name": "platformLiquidationFee",
+            "name": "liquidationQuoteRoundingBuffer",
             "docs": [
-              "Additional to liquidation_fee, but goes to the group owner instead of the liqor"
+              "Quote (in native settle token) kept back from the liqor on every base liquidation",
+              "and added to fees_accrued, so rounding in the transfer can't cost the protocol.",
+              "Disabled if 0."
             ],
-            "type": {
-              "defined": "I80F48"
-            }
+            "type": "f64"
           },
           {
-            "name": "accruedLiquidationFees",
+            "name": "altSettleTokenIndex",
             "docs": [
-              "Platform fees that were accrued during liquidation (in native tokens)",
-              "",
-              "These fees are also added to fees_accrued, this is just for bookkeeping the total",
-              "liquidation fees that happened. So never decreases (different to fees_accrued)."
+              "Token that liqors may pay for taken over positive pnl in instead of the settle token,",
+              "see perp_liq_base_or_positive_pnl_v3. Only used if alt_settle_token_enabled is set."
             ],
+            "type": "u16"
+          },
+          {
+            "name": "altSettleTokenEnabled",
+            "type": "u8"
+          },
+          {
+            "name": "padding9",
             "type": {
-              "defined": "I80F48"
+              "array": [
+                "u8",
+                5
+              ]
             }
           },
           {
@@ -23962,7 +28130,7 @@ export const IDL: MangoV4 = {
             "type": {
               "array": [
                 "u8",
-                1848
+                1568
               ]
             }
           }
@@ -24350,6 +28518,22 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "PerpSettleFundingDetail",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "perpMarketIndex",
+            "type": "u16"
+          },
+          {
+            "name": "funding",
+            "type": "i128"
+          }
+        ]
+      }
+    },
     {
       "name": "TokenPosition",
       "type": {
@@ -24881,6 +29065,113 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "PerpPositionWire",
+      "docs": [
+        "Stable serialization of PerpPosition for clients and indexers.",
+        "",
+        "Unlike PerpPosition it has no padding or reserved bytes and stores I80F48 values",
+        "as their i128 bits, so it doesn't change when the zero-copy layout is rearranged.",
+        "Fields may only ever be appended, together with a bump of the version."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "version",
+            "type": "u8"
+          },
+          {
+            "name": "marketIndex",
+            "type": "u16"
+          },
+          {
+            "name": "settlePnlLimitWindow",
+            "type": "u32"
+          },
+          {
+            "name": "settlePnlLimitSettledInCurrentWindowNative",
+            "type": "i64"
+          },
+          {
+            "name": "basePositionLots",
+            "type": "i64"
+          },
+          {
+            "name": "quotePositionNative",
+            "type": "i128"
+          },
+          {
+            "name": "quoteRunningNative",
+            "type": "i64"
+          },
+          {
+            "name": "longSettledFunding",
+            "type": "i128"
+          },
+          {
+            "name": "shortSettledFunding",
+            "type": "i128"
+          },
+          {
+            "name": "bidsBaseLots",
+            "type": "i64"
+          },
+          {
+            "name": "asksBaseLots",
+            "type": "i64"
+          },
+          {
+            "name": "takerBaseLots",
+            "type": "i64"
+          },
+          {
+            "name": "takerQuoteLots",
+            "type": "i64"
+          },
+          {
+            "name": "cumulativeLongFunding",
+            "type": "f64"
+          },
+          {
+            "name": "cumulativeShortFunding",
+            "type": "f64"
+          },
+          {
+            "name": "makerVolume",
+            "type": "u64"
+          },
+          {
+            "name": "takerVolume",
+            "type": "u64"
+          },
+          {
+            "name": "perpSpotTransfers",
+            "type": "i64"
+          },
+          {
+            "name": "avgEntryPricePerBaseLot",
+            "type": "f64"
+          },
+          {
+            "name": "deprecatedRealizedTradePnlNative",
+            "type": "i128"
+          },
+          {
+            "name": "oneshotSettlePnlAllowance",
+            "type": "i128"
+          },
+          {
+            "name": "recurringSettlePnlAllowance",
+            "type": "i64"
+          },
+          {
+            "name": "realizedPnlForPositionNative",
+            "type": "i128"
+          }
+        ]
+      }
+    },
     {
       "name": "PerpOpenOrder",
       "type": {
@@ -24999,31 +29290,97 @@ export const IDL: MangoV4 = {
             "type": "u64"
           },
           {
-            "name": "buybackFeesAccruedCurrent",
-            "type": "u64"
+            "name": "buybackFeesAccruedCurrent",
+            "type": "u64"
+          },
+          {
+            "name": "buybackFeesAccruedPrevious",
+            "type": "u64"
+          },
+          {
+            "name": "buybackFeesExpiryTimestamp",
+            "type": "u64"
+          },
+          {
+            "name": "nextTokenConditionalSwapId",
+            "type": "u64"
+          },
+          {
+            "name": "temporaryDelegate",
+            "type": "publicKey"
+          },
+          {
+            "name": "temporaryDelegateExpiry",
+            "type": "u64"
+          },
+          {
+            "name": "lastCollateralFeeCharge",
+            "type": "u64"
+          },
+          {
+            "name": "liquidationBaseAccumulated",
+            "type": "i64"
+          },
+          {
+            "name": "liquidationQuoteAccumulated",
+            "type": "i64"
+          },
+          {
+            "name": "perpTradingFeesPaid",
+            "type": "i64"
+          },
+          {
+            "name": "perpFundingPaidDeactivated",
+            "type": "i64"
+          },
+          {
+            "name": "perpLiquidationFeesPaid",
+            "type": "i64"
+          },
+          {
+            "name": "tokenAllowlist",
+            "type": {
+              "array": [
+                "u16",
+                8
+              ]
+            }
+          },
+          {
+            "name": "tokenAllowlistLen",
+            "type": "u8"
+          },
+          {
+            "name": "delegatePermissions",
+            "type": "u8"
           },
           {
-            "name": "buybackFeesAccruedPrevious",
-            "type": "u64"
+            "name": "temporaryDelegatePermissions",
+            "type": "u8"
           },
           {
-            "name": "buybackFeesExpiryTimestamp",
-            "type": "u64"
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                5
+              ]
+            }
           },
           {
-            "name": "nextTokenConditionalSwapId",
-            "type": "u64"
+            "name": "cachedInitHealth",
+            "type": "i64"
           },
           {
-            "name": "temporaryDelegate",
-            "type": "publicKey"
+            "name": "cachedMaintHealth",
+            "type": "i64"
           },
           {
-            "name": "temporaryDelegateExpiry",
+            "name": "cachedHealthSlot",
             "type": "u64"
           },
           {
-            "name": "lastCollateralFeeCharge",
+            "name": "beingLiquidatedSlot",
             "type": "u64"
           },
           {
@@ -25031,7 +29388,7 @@ export const IDL: MangoV4 = {
             "type": {
               "array": [
                 "u8",
-                152
+                56
               ]
             }
           }
@@ -26140,6 +30497,23 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "LiquidationScenarioAsset",
+      "docs": [
+        "The asset whose price moves in a LiquidationScenario"
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Token"
+          },
+          {
+            "name": "Perp"
+          }
+        ]
+      }
+    },
     {
       "name": "SpotMarketIndex",
       "type": {
@@ -26201,6 +30575,38 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "LiquidationConstraint",
+      "docs": [
+        "The limit that determined the size of a base reduction step"
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "None"
+          },
+          {
+            "name": "Health"
+          },
+          {
+            "name": "PnlLimit"
+          },
+          {
+            "name": "LiqeeBasePosition"
+          },
+          {
+            "name": "MaxBaseTransfer"
+          },
+          {
+            "name": "MaxOvershootHealth"
+          },
+          {
+            "name": "MaxHealthImprovement"
+          }
+        ]
+      }
+    },
     {
       "name": "IxGate",
       "docs": [
@@ -26448,6 +30854,65 @@ export const IDL: MangoV4 = {
           },
           {
             "name": "GroupChangeInsuranceFund"
+          },
+          {
+            "name": "AccountSetOwner"
+          },
+          {
+            "name": "PerpSettleAllFunding"
+          },
+          {
+            "name": "TokenLiqWithTokenRelaxedStaleness"
+          },
+          {
+            "name": "TokenLiqAbsorbDust"
+          },
+          {
+            "name": "PerpAdl"
+          },
+          {
+            "name": "AccountSetTokenAllowlist"
+          },
+          {
+            "name": "ComputeAccountHealth"
+          },
+          {
+            "name": "CacheAccountHealth"
+          },
+          {
+            "name": "RecoverFromBeingLiquidated"
+          },
+          {
+            "name": "AccountSetDelegatePermissions"
+          },
+          {
+            "name": "GroupStatus"
+          },
+          {
+            "name": "PerpExplainLiquidation"
+          },
+          {
+            "name": "AccountCompactTokenPositions"
+          }
+        ]
+      }
+    },
+    {
+      "name": "DelegatePermission",
+      "docs": [
+        "Actions a delegate can be restricted to, see MangoAccountFixed::has_permission()"
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "Liquidate"
+          },
+          {
+            "name": "Trade"
+          },
+          {
+            "name": "Withdraw"
           }
         ]
       }
@@ -26782,38 +31247,214 @@ export const IDL: MangoV4 = {
           {
             "name": "FixedPremium"
           },
-          {
-            "name": "PremiumAuction"
+          {
+            "name": "PremiumAuction"
+          },
+          {
+            "name": "LinearAuction"
+          }
+        ]
+      }
+    }
+  ],
+  "events": [
+    {
+      "name": "MangoAccountData",
+      "fields": [
+        {
+          "name": "initHealth",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "maintHealth",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "equity",
+          "type": {
+            "defined": "Equity"
+          },
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "MangoAccountDataV2",
+      "fields": [
+        {
+          "name": "initHealth",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "maintHealth",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "equity",
+          "type": {
+            "defined": "Equity"
+          },
+          "index": false
+        },
+        {
+          "name": "perpTradingFeesPaid",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "perpFundingPaid",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "perpLiquidationFeesPaid",
+          "type": "i64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "GroupStatusData",
+      "fields": [
+        {
+          "name": "group",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "admin",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "securityAdmin",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "ixGate",
+          "type": "u128",
+          "index": false
+        },
+        {
+          "name": "tokenCount",
+          "type": "u16",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiquidationExplanation",
+      "fields": [
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "oraclePrice",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "pricePerLot",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "healthPerLot",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "baseTransferForZero",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "bindingConstraint",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "baseTransferLiqee",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqee",
+          "type": {
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqor",
+          "type": {
+            "defined": "I80F48"
           },
-          {
-            "name": "LinearAuction"
-          }
-        ]
-      }
-    }
-  ],
-  "events": [
-    {
-      "name": "MangoAccountData",
-      "fields": [
+          "index": false
+        },
         {
-          "name": "initHealth",
+          "name": "quotePlatformFee",
           "type": {
             "defined": "I80F48"
           },
           "index": false
         },
         {
-          "name": "maintHealth",
+          "name": "pnlTransfer",
           "type": {
             "defined": "I80F48"
           },
           "index": false
         },
         {
-          "name": "equity",
+          "name": "liqeeHealthBefore",
           "type": {
-            "defined": "Equity"
+            "defined": "I80F48"
+          },
+          "index": false
+        },
+        {
+          "name": "liqeeHealthAfter",
+          "type": {
+            "defined": "I80F48"
           },
           "index": false
         }
@@ -28010,6 +32651,28 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "AccountCompactTokenPositionsLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "tokenIndexes",
+          "type": {
+            "vec": "u16"
+          },
+          "index": false
+        }
+      ]
+    },
     {
       "name": "DeactivatePerpPositionLog",
       "fields": [
@@ -28385,6 +33048,151 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "PerpLiqBaseOrPositivePnlLogV4",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "baseTransferLiqee",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqee",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqor",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "quotePlatformFee",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "pnlTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "pnlSettleLimitTransferRecurring",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "pnlSettleLimitTransferOneshot",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqeeStateHashBefore",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
+          "index": false
+        },
+        {
+          "name": "liqeeStateHashAfter",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
+          "index": false
+        },
+        {
+          "name": "liqorStateHashBefore",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
+          "index": false
+        },
+        {
+          "name": "liqorStateHashAfter",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
+          "index": false
+        },
+        {
+          "name": "liqeeAvgEntryPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqeeBreakEvenPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqorAvgEntryPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqorBreakEvenPrice",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "liqeeLiquidationCost",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "oraclePrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqeeInitHealthBefore",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqeeInitHealthAfter",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
     {
       "name": "PerpLiqBankruptcyLog",
       "fields": [
@@ -28399,7 +33207,87 @@ export const IDL: MangoV4 = {
           "index": false
         },
         {
-          "name": "liqor",
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "insuranceTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "socializedLoss",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "startingLongFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "startingShortFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "endingLongFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "endingShortFunding",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqBadDebtInsuranceLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "badDebt",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "insuranceTransfer",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqPnlTakeoverTokenLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
           "type": "publicKey",
           "index": false
         },
@@ -28409,32 +33297,27 @@ export const IDL: MangoV4 = {
           "index": false
         },
         {
-          "name": "insuranceTransfer",
-          "type": "i128",
-          "index": false
-        },
-        {
-          "name": "socializedLoss",
-          "type": "i128",
+          "name": "liqor",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "startingLongFunding",
-          "type": "i128",
+          "name": "liqee",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "startingShortFunding",
-          "type": "i128",
+          "name": "tokenIndex",
+          "type": "u16",
           "index": false
         },
         {
-          "name": "endingLongFunding",
+          "name": "settleTokenTransfer",
           "type": "i128",
           "index": false
         },
         {
-          "name": "endingShortFunding",
+          "name": "tokenTransfer",
           "type": "i128",
           "index": false
         }
@@ -28535,6 +33418,30 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "PerpSettleAllFundingLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "settledFunding",
+          "type": {
+            "vec": {
+              "defined": "PerpSettleFundingDetail"
+            }
+          },
+          "index": false
+        }
+      ]
+    },
     {
       "name": "AccountBuybackFeesWithMngoLog",
       "fields": [
@@ -29444,6 +34351,211 @@ export const IDL: MangoV4 = {
           "index": false
         }
       ]
+    },
+    {
+      "name": "TokenLiqAbsorbDustLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "dustTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "quoteTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "dustTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "quoteTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "dustPrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "quotePrice",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqHealthLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "initHealth",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "maintHealth",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqEndHealth",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "GroupRiskDeltaLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "openInterestBefore",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "openInterestAfter",
+          "type": "i64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpAdlCounterpartyLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "counterparty",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "baseClosed",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "pnlAbsorbed",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpAdlLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "oraclePrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqeeBaseLotsRemaining",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "deficit",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "remainingDeficit",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "numCounterparties",
+          "type": "u8",
+          "index": false
+        }
+      ]
     }
   ],
   "errors": [
@@ -29814,13 +34926,73 @@ export const IDL: MangoV4 = {
     },
     {
       "code": 6073,
-      "name": "NoFreeOpenbookV2OpenOrdersIndex",
-      "msg": "no free openbook v2 open orders index"
+      "name": "LiquidationPriceOutOfTolerance",
+      "msg": "liquidation price is not within tolerance of the oracle price"
     },
     {
       "code": 6074,
-      "name": "OpenbookV2OpenOrdersExistAlready",
-      "msg": "openbook v2 open orders exist already"
+      "name": "LiquidationNotProfitable",
+      "msg": "liquidation reward does not cover the liqor's minimum net profit"
+    },
+    {
+      "code": 6075,
+      "name": "InvalidDelegate",
+      "msg": "the delegate can't be the account owner or the account itself"
+    },
+    {
+      "code": 6076,
+      "name": "InitAssetWeightBelowFloor",
+      "msg": "the init asset weight is below the group's floor"
+    },
+    {
+      "code": 6077,
+      "name": "LiqorNotWhitelistedForLargePosition",
+      "msg": "the liqor is not whitelisted for perp positions this large"
+    },
+    {
+      "code": 6078,
+      "name": "WeightChangeTooSoon",
+      "msg": "the token's weights were changed too recently"
+    },
+    {
+      "code": 6079,
+      "name": "LiquidationThrottled",
+      "msg": "the perp market's liquidation volume for this slot is exhausted"
+    },
+    {
+      "code": 6080,
+      "name": "LiquidationWrongDirection",
+      "msg": "max_base_transfer has the wrong sign for the liqee's base position"
+    },
+    {
+      "code": 6081,
+      "name": "UnauthorizedLiqor",
+      "msg": "the liqor_owner is neither the owner nor a delegate allowed to liquidate for the liqor account"
+    },
+    {
+      "code": 6082,
+      "name": "AdlDisabled",
+      "msg": "auto-deleveraging is not enabled for this perp market"
+    },
+    {
+      "code": 6083,
+      "name": "TokenNotAllowedForAccount",
+      "msg": "the token is not on the account's token allowlist"
+    },
+    {
+      "code": 6084,
+      "name": "LiquidationTooSmall",
+      "msg": "the liquidation transfers too few base lots without closing the liqee position"
+    },
+    {
+      "code": 6085,
+      "name": "DelegatePermissionMissing",
+      "msg": "the delegate doesn't have the permission for this action"
+    },
+    {
+      "code": 6086,
+      "name": "MathOverflow",
+      "msg": "math overflow"
     }
   ]
 };